@@ -0,0 +1,184 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tari_common::configuration::Network;
+use tari_common_types::{
+    emoji::EmojiId,
+    tari_address::{TariAddress, TariAddressFeatures},
+    types::PublicKey,
+};
+use tari_core::consensus::consensus_network;
+use tari_crypto::tari_utilities::hex::{to_hex, Hex};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A struct to hold the parts of a parsed address
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct ParsedAddressResult {
+    /// The network of the address, e.g. `mainnet`
+    pub network: Option<String>,
+    /// The features byte of the address: 1 for one-sided, 2 for interactive, 3 for both, plus 4 if it carries a
+    /// payment ID
+    pub features: Option<u8>,
+    /// The public view key (hex value), if the address is a dual address
+    pub view_key: Option<String>,
+    /// The public spend key (hex value)
+    pub spend_key: Option<String>,
+    /// The payment ID of a dual address (hex value), if it carries one
+    pub payment_id: Option<String>,
+    /// The address in base58
+    pub base58: Option<String>,
+    /// The address in emoji
+    pub emoji: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn parsed_address_error(error: &str) -> ParsedAddressResult {
+    ParsedAddressResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// A struct to hold an address built from keys
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BuiltAddressResult {
+    /// The address in base58
+    pub base58: Option<String>,
+    /// The address in emoji
+    pub emoji: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn built_address_error(error: &str) -> BuiltAddressResult {
+    BuiltAddressResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Returns the view and spend public keys of a recipient accepting one-sided payments, given either a Tari address
+/// (base58 or emoji) or a single public key (hex value); the spend key is used as both for a single address or key
+pub(crate) fn one_sided_recipient_keys(address: &str) -> Result<(PublicKey, PublicKey), String> {
+    if let Ok(public_key) = PublicKey::from_hex(address) {
+        return Ok((public_key.clone(), public_key));
+    }
+    let address = TariAddress::from_str(address).map_err(|e| e.to_string())?;
+    if !address.features().contains(TariAddressFeatures::ONE_SIDED) {
+        return Err("The address does not accept one-sided payments".to_string());
+    }
+    let public_spend_key = address.public_spend_key().clone();
+    let public_view_key = address
+        .public_view_key()
+        .cloned()
+        .unwrap_or_else(|| public_spend_key.clone());
+    Ok((public_view_key, public_spend_key))
+}
+
+/// Parses a single or dual-key Tari address, in base58 or emoji, validating its checksum, network byte and keys, and
+/// returns the view and spend public keys and payment ID a sender needs to pay it
+#[wasm_bindgen]
+pub fn parse_address(address: &str) -> ParsedAddressResult {
+    let address = match TariAddress::from_str(address) {
+        Ok(val) => val,
+        Err(e) => return parsed_address_error(&format!("address: {e}")),
+    };
+    ParsedAddressResult {
+        network: Some(address.network().to_string()),
+        features: Some(address.features().as_u8()),
+        view_key: address.public_view_key().map(|key| key.to_hex()),
+        spend_key: Some(address.public_spend_key().to_hex()),
+        payment_id: Some(address.payment_id_user_data())
+            .filter(|payment_id| !payment_id.is_empty())
+            .map(to_hex),
+        base58: Some(address.to_base58()),
+        emoji: Some(address.to_emoji_string()),
+        error: None,
+    }
+}
+
+/// Builds a dual-key Tari address, accepting both interactive and one-sided payments, from the recipient's keys
+/// * `network` - the network of the address, e.g. `mainnet`; the library's network (see `set_network`) if not provided
+#[wasm_bindgen]
+pub fn build_address(view_pk: &str, spend_pk: &str, network: Option<String>) -> BuiltAddressResult {
+    let view_pk = match PublicKey::from_hex(view_pk) {
+        Ok(val) => val,
+        Err(e) => return built_address_error(&format!("view_pk: {e}")),
+    };
+    let spend_pk = match PublicKey::from_hex(spend_pk) {
+        Ok(val) => val,
+        Err(e) => return built_address_error(&format!("spend_pk: {e}")),
+    };
+    let network = match network.as_deref().map(Network::from_str).transpose() {
+        Ok(val) => val.unwrap_or_else(consensus_network),
+        Err(e) => return built_address_error(&format!("network: {e}")),
+    };
+    let address = TariAddress::new_dual_address(view_pk, spend_pk, network, TariAddressFeatures::default());
+    BuiltAddressResult {
+        base58: Some(address.to_base58()),
+        emoji: Some(address.to_emoji_string()),
+        error: None,
+    }
+}
+
+/// A struct to hold an emoji ID
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct EmojiIdResult {
+    /// The emoji ID
+    pub emoji_id: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+/// A struct to hold the public key of an emoji ID
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct EmojiIdPublicKeyResult {
+    /// The public key (hex value)
+    pub public_key: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+/// Encodes a public key as an emoji ID: 32 emoji for the key followed by a checksum emoji
+#[wasm_bindgen]
+pub fn public_key_to_emoji_id(pk_hex: &str) -> EmojiIdResult {
+    match PublicKey::from_hex(pk_hex) {
+        Ok(public_key) => EmojiIdResult {
+            emoji_id: Some(EmojiId::from_public_key(&public_key).to_emoji_string()),
+            error: None,
+        },
+        Err(e) => EmojiIdResult {
+            emoji_id: None,
+            error: Some(format!("pk_hex: {e}")),
+        },
+    }
+}
+
+/// Decodes an emoji ID into its public key, validating its length, emoji and checksum. Addresses in emoji are parsed
+/// with `parse_address` instead.
+#[wasm_bindgen]
+pub fn emoji_id_to_public_key(emoji: &str) -> EmojiIdPublicKeyResult {
+    match EmojiId::from_str(emoji) {
+        Ok(emoji_id) => EmojiIdPublicKeyResult {
+            public_key: Some(emoji_id.as_public_key().to_hex()),
+            error: None,
+        },
+        Err(e) => EmojiIdPublicKeyResult {
+            public_key: None,
+            error: Some(format!("emoji: {e}")),
+        },
+    }
+}
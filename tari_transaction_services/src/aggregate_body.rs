@@ -0,0 +1,194 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tari_common_types::types::PrivateKey;
+use tari_core::transactions::{
+    aggregated_body::AggregateBody,
+    transaction_components::{Transaction, TransactionInput, TransactionKernel, TransactionOutput},
+};
+use tari_crypto::tari_utilities::hex::Hex;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+/// A struct to hold the result of validating a transaction body
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BodyValidationResult {
+    /// Whether the body passed all checks
+    pub valid: bool,
+    /// Whether the inputs, outputs and kernels are sorted
+    pub sorted: bool,
+    /// Whether an input appears more than once
+    pub duplicated_inputs: bool,
+    /// Whether an output appears more than once
+    pub duplicated_outputs: bool,
+    /// Whether an output is spent by an input of the same body, i.e. the body has not been cut through
+    pub cut_through: bool,
+    /// Whether the signatures of all kernels are valid
+    pub kernel_signatures_valid: bool,
+    /// An error message in case of an error or failed validation
+    pub error: Option<String>,
+}
+
+fn from_json<T: DeserializeOwned>(json: &str, name: &str) -> Result<T, JsValue> {
+    serde_json::from_str(json).map_err(|e| JsValue::from_str(&format!("{name}: {e}")))
+}
+
+fn to_json<T: Serialize>(value: &T, name: &str) -> Result<String, JsValue> {
+    serde_json::to_string(value).map_err(|e| JsValue::from_str(&format!("Could not serialize {name}: {e}")))
+}
+
+fn private_key_or_zero(key: Option<String>, name: &str) -> Result<PrivateKey, JsValue> {
+    key.map_or_else(
+        || Ok(PrivateKey::default()),
+        |key| PrivateKey::from_hex(&key).map_err(|e| JsValue::from_str(&format!("{name}: {e}"))),
+    )
+}
+
+/// The inputs, outputs and kernels of a transaction or block, with the transaction offsets, for block-level tooling
+/// that needs to assemble, inspect or check bodies
+#[wasm_bindgen]
+pub struct TransactionBody {
+    body: AggregateBody,
+    offset: PrivateKey,
+    script_offset: PrivateKey,
+}
+
+#[wasm_bindgen]
+impl TransactionBody {
+    /// Creates a body from its parts
+    /// * `inputs` - a JSON array of transaction inputs
+    /// * `outputs` - a JSON array of transaction outputs
+    /// * `kernels` - a JSON array of transaction kernels
+    /// * `offset` - the transaction offset (hex value), zero if not provided
+    /// * `script_offset` - the transaction script offset (hex value), zero if not provided
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        inputs: &str,
+        outputs: &str,
+        kernels: &str,
+        offset: Option<String>,
+        script_offset: Option<String>,
+    ) -> Result<TransactionBody, JsValue> {
+        let inputs: Vec<TransactionInput> = from_json(inputs, "inputs")?;
+        let outputs: Vec<TransactionOutput> = from_json(outputs, "outputs")?;
+        let kernels: Vec<TransactionKernel> = from_json(kernels, "kernels")?;
+        Ok(Self {
+            body: AggregateBody::new(inputs, outputs, kernels),
+            offset: private_key_or_zero(offset, "offset")?,
+            script_offset: private_key_or_zero(script_offset, "script_offset")?,
+        })
+    }
+
+    /// Creates a body from a transaction, as JSON
+    pub fn from_transaction(transaction: &str) -> Result<TransactionBody, JsValue> {
+        let transaction: Transaction = from_json(transaction, "transaction")?;
+        Ok(Self {
+            body: transaction.body,
+            offset: transaction.offset,
+            script_offset: transaction.script_offset,
+        })
+    }
+
+    /// Returns the body as a transaction, as JSON
+    pub fn to_transaction(&self) -> Result<String, JsValue> {
+        let (inputs, outputs, kernels) = self.body.clone().dissolve();
+        let transaction = Transaction::new(
+            inputs,
+            outputs,
+            kernels,
+            self.offset.clone(),
+            self.script_offset.clone(),
+        );
+        to_json(&transaction, "transaction")
+    }
+
+    /// The inputs, as a JSON array
+    pub fn inputs(&self) -> Result<String, JsValue> {
+        to_json(self.body.inputs(), "inputs")
+    }
+
+    /// The outputs, as a JSON array
+    pub fn outputs(&self) -> Result<String, JsValue> {
+        to_json(self.body.outputs(), "outputs")
+    }
+
+    /// The kernels, as a JSON array
+    pub fn kernels(&self) -> Result<String, JsValue> {
+        to_json(self.body.kernels(), "kernels")
+    }
+
+    /// The hashes of the kernels (hex values)
+    pub fn kernel_hashes(&self) -> Vec<String> {
+        self.body
+            .kernels()
+            .iter()
+            .map(|kernel| kernel.hash().to_hex())
+            .collect()
+    }
+
+    /// The transaction offset (hex value)
+    pub fn offset(&self) -> String {
+        self.offset.to_hex()
+    }
+
+    /// The transaction script offset (hex value)
+    pub fn script_offset(&self) -> String {
+        self.script_offset.to_hex()
+    }
+
+    /// The number of inputs, outputs and kernels, e.g. `input(s): 1, output(s): 2, kernel(s): 1`
+    pub fn counts(&self) -> String {
+        self.body.to_counts_string()
+    }
+
+    /// The total fee of the kernels in µT
+    pub fn total_fee(&self) -> Result<u64, JsValue> {
+        self.body
+            .get_total_fee()
+            .map(|fee| fee.as_u64())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// The hash of the body (hex value), which commits to its inputs, outputs and kernels regardless of their order
+    pub fn hash(&self) -> String {
+        self.body.hash().to_hex()
+    }
+
+    /// Sorts the inputs, outputs and kernels into their canonical order
+    pub fn sort(&mut self) {
+        self.body.sort();
+    }
+
+    /// Checks the body for duplicated inputs and outputs, outputs spent in the same body and invalid kernel
+    /// signatures. Sorting is reported, but an unsorted body is not invalid, as it can still be sorted.
+    pub fn validate(&self) -> BodyValidationResult {
+        let duplicated_inputs = self.body.contains_duplicated_inputs();
+        let duplicated_outputs = self.body.contains_duplicated_outputs();
+        let cut_through = self.body.contains_cut_through();
+        let kernel_signatures = self.body.verify_kernel_signatures();
+        let error = if duplicated_inputs {
+            Some("Body contains duplicated inputs".to_string())
+        } else if duplicated_outputs {
+            Some("Body contains duplicated outputs".to_string())
+        } else if cut_through {
+            Some("Body contains an output that is spent by an input in the same body".to_string())
+        } else {
+            kernel_signatures
+                .as_ref()
+                .err()
+                .map(|e| format!("Kernel signature verification failed: {e}"))
+        };
+        BodyValidationResult {
+            valid: error.is_none(),
+            sorted: self.body.is_sorted(),
+            duplicated_inputs,
+            duplicated_outputs,
+            cut_through,
+            kernel_signatures_valid: kernel_signatures.is_ok(),
+            error,
+        }
+    }
+}
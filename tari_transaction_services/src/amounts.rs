@@ -0,0 +1,251 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{cmp::Ordering, str::FromStr};
+
+use js_sys::BigInt;
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::tari_amount::{MicroMinotari, Minotari, T};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+/// A placeholder for the thousands separator while the decimal separator is substituted
+const SEPARATOR_PLACEHOLDER: char = '\u{0}';
+/// The characters used to group thousands in the supported locales
+const GROUPING_CHARS: [char; 4] = ['.', ',', ' ', '\u{a0}'];
+
+/// The unit an amount is formatted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub enum AmountUnit {
+    /// Minotari (T), with six decimals
+    Minotari,
+    /// MicroMinotari (µT)
+    MicroMinotari,
+    /// MicroMinotari for amounts below 1 T, otherwise Minotari
+    Auto,
+}
+
+/// The options of `format_minotari`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatOptions {
+    /// The unit; `auto` if not provided
+    pub unit: Option<AmountUnit>,
+    /// The separator between groups of thousands, e.g. `.` or ` `; `,` if not provided
+    pub thousands_separator: Option<char>,
+    /// The decimal separator, e.g. `,`; `.` if not provided
+    pub decimal_separator: Option<char>,
+    /// Whether to drop trailing zero decimals of Minotari amounts
+    #[serde(default)]
+    pub trim_zeros: bool,
+}
+
+/// A struct to hold a formatted amount
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct FormattedAmountResult {
+    /// The formatted amount, including the unit, e.g. `1,234.5 T`
+    pub formatted: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+/// A struct to hold a parsed amount
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct ParsedAmountResult {
+    /// The amount in µT
+    pub micro_value: Option<u64>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn trim_decimal_zeros(amount: &str) -> &str {
+    if amount.contains('.') {
+        amount.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        amount
+    }
+}
+
+/// Formats an amount with the separators and unit of `options`, without rounding
+pub(crate) fn format_amount(value: MicroMinotari, options: &FormatOptions) -> String {
+    let unit = match options.unit.unwrap_or(AmountUnit::Auto) {
+        AmountUnit::Auto if value < T => AmountUnit::MicroMinotari,
+        AmountUnit::Auto => AmountUnit::Minotari,
+        unit => unit,
+    };
+    let (formatted, suffix) = match unit {
+        AmountUnit::MicroMinotari => (value.to_currency_string(SEPARATOR_PLACEHOLDER), " µT"),
+        _ => (Minotari::from(value).to_currency_string(SEPARATOR_PLACEHOLDER), " T"),
+    };
+    let amount = formatted.trim_end_matches(suffix);
+    let amount = if options.trim_zeros {
+        trim_decimal_zeros(amount)
+    } else {
+        amount
+    };
+    let amount = amount
+        .replace('.', &options.decimal_separator.unwrap_or('.').to_string())
+        .replace(
+            SEPARATOR_PLACEHOLDER,
+            &options.thousands_separator.unwrap_or(',').to_string(),
+        );
+    format!("{amount}{suffix}")
+}
+
+/// Formats an amount in µT for display, e.g. `1,234.500000 T` or `999 µT`, without rounding
+/// * `options` - a JSON `FormatOptions`, e.g. `{ "unit": "minotari", "thousandsSeparator": ".", "decimalSeparator":
+///   ",", "trimZeros": true }`; if not provided, the amount is shown in µT below 1 T and in T otherwise, with `,` and
+///   `.` as separators
+#[wasm_bindgen]
+pub fn format_minotari(micro_value: u64, options: Option<String>) -> FormattedAmountResult {
+    let options = match options
+        .as_deref()
+        .map(serde_json::from_str::<FormatOptions>)
+        .transpose()
+    {
+        Ok(val) => val.unwrap_or_default(),
+        Err(e) => {
+            return FormattedAmountResult {
+                formatted: None,
+                error: Some(format!("options: {e}")),
+            }
+        },
+    };
+    FormattedAmountResult {
+        formatted: Some(format_amount(MicroMinotari::from(micro_value), &options)),
+        error: None,
+    }
+}
+
+/// Parses an amount into µT, e.g. `1.5 T`, `1,500,000 µT` or `1500000uT`. An amount without a unit is read as µT if it
+/// has no decimals and as T otherwise. Amounts in T may have at most six decimals; more are an error, not rounded.
+/// * `decimal_separator` - the decimal separator of the input, e.g. `,` for `1.234,5 T`; `.` if not provided
+#[wasm_bindgen]
+pub fn parse_minotari(value: &str, decimal_separator: Option<char>) -> ParsedAmountResult {
+    let value = match decimal_separator {
+        // Drop the grouping characters, which may include `.`, and read the decimal separator as `.`
+        Some(separator) if separator != '.' => value
+            .chars()
+            .filter(|c| *c == separator || !GROUPING_CHARS.contains(c))
+            .map(|c| if c == separator { '.' } else { c })
+            .collect(),
+        _ => value.replace('\u{a0}', ""),
+    };
+    match MicroMinotari::from_str(&value) {
+        Ok(val) => ParsedAmountResult {
+            micro_value: Some(val.as_u64()),
+            error: None,
+        },
+        Err(e) => ParsedAmountResult {
+            micro_value: None,
+            error: Some(format!("value: {e}")),
+        },
+    }
+}
+
+/// An amount in µT, so that wallet balance math is done on u64 values in the library instead of on JS numbers, which
+/// cannot represent all u64 values exactly. All arithmetic is checked and throws instead of wrapping or rounding.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MinotariAmount {
+    value: MicroMinotari,
+}
+
+impl MinotariAmount {
+    fn checked(value: Option<MicroMinotari>, operation: &str) -> Result<MinotariAmount, JsValue> {
+        value
+            .map(|value| MinotariAmount { value })
+            .ok_or_else(|| JsValue::from_str(&format!("MinotariAmount {operation} overflowed or underflowed")))
+    }
+}
+
+#[wasm_bindgen]
+impl MinotariAmount {
+    /// Creates an amount of `micro_value` µT
+    #[wasm_bindgen(constructor)]
+    pub fn new(micro_value: u64) -> MinotariAmount {
+        Self {
+            value: MicroMinotari::from(micro_value),
+        }
+    }
+
+    /// Creates an amount from a BigInt of µT, which must be between 0 and u64::MAX
+    pub fn from_bigint(micro_value: BigInt) -> Result<MinotariAmount, JsValue> {
+        let decimal = String::from(micro_value.to_string(10).map_err(JsValue::from)?);
+        let micro_value = decimal
+            .parse::<u64>()
+            .map_err(|e| JsValue::from_str(&format!("micro_value {decimal}: {e}")))?;
+        Ok(Self::new(micro_value))
+    }
+
+    /// Parses an amount as per `parse_minotari`
+    pub fn from_string(value: &str, decimal_separator: Option<char>) -> Result<MinotariAmount, JsValue> {
+        let parsed = parse_minotari(value, decimal_separator);
+        match (parsed.micro_value, parsed.error) {
+            (Some(micro_value), None) => Ok(Self::new(micro_value)),
+            (_, error) => Err(JsValue::from_str(&error.unwrap_or_default())),
+        }
+    }
+
+    /// The amount in µT, as a BigInt
+    pub fn to_bigint(&self) -> BigInt {
+        BigInt::from(self.value.as_u64())
+    }
+
+    /// The amount in µT
+    pub fn micro_value(&self) -> u64 {
+        self.value.as_u64()
+    }
+
+    /// Formats the amount as per `format_minotari`
+    pub fn format(&self, options: Option<String>) -> Result<String, JsValue> {
+        let formatted = format_minotari(self.value.as_u64(), options);
+        formatted
+            .formatted
+            .ok_or_else(|| JsValue::from_str(&formatted.error.unwrap_or_default()))
+    }
+
+    /// Returns the sum of the amounts, or throws if it exceeds u64::MAX
+    pub fn checked_add(&self, other: &MinotariAmount) -> Result<MinotariAmount, JsValue> {
+        Self::checked(self.value.checked_add(other.value), "addition")
+    }
+
+    /// Returns the difference of the amounts, or throws if `other` is larger
+    pub fn checked_sub(&self, other: &MinotariAmount) -> Result<MinotariAmount, JsValue> {
+        Self::checked(self.value.checked_sub(other.value), "subtraction")
+    }
+
+    /// Returns the amount multiplied by `factor`, or throws if it exceeds u64::MAX
+    pub fn checked_mul(&self, factor: u64) -> Result<MinotariAmount, JsValue> {
+        Self::checked(self.value.checked_mul(MicroMinotari::from(factor)), "multiplication")
+    }
+
+    /// Returns the amount divided by `divisor`, rounded down, or throws if `divisor` is zero
+    pub fn checked_div(&self, divisor: u64) -> Result<MinotariAmount, JsValue> {
+        Self::checked(self.value.checked_div(MicroMinotari::from(divisor)), "division")
+    }
+
+    /// Compares the amounts, returning -1, 0 or 1 if this amount is less than, equal to or greater than `other`
+    pub fn compare(&self, other: &MinotariAmount) -> i32 {
+        match self.cmp(other) {
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+            Ordering::Greater => 1,
+        }
+    }
+
+    /// Whether the amounts are equal
+    pub fn equals(&self, other: &MinotariAmount) -> bool {
+        self == other
+    }
+
+    /// Whether the amount is zero
+    pub fn is_zero(&self) -> bool {
+        self.value == MicroMinotari::zero()
+    }
+}
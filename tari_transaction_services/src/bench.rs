@@ -0,0 +1,238 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Benchmarks that generate their data internally and time the library's hot paths in the browser, so that
+//! integrators can compare devices and builds, e.g. a build with WebAssembly SIMD enabled against the scalar one. Only
+//! the measured operation is timed, not the generation of its data.
+
+use std::hint::black_box;
+
+use js_sys::{Date, Function, Reflect};
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_core::transactions::{
+    tari_amount::MicroMinotari,
+    transaction_components::{OutputFeatures, RangeProofType, TransactionOutput, TransactionOutputVersion},
+};
+use tari_crypto::{
+    keys::{PublicKey as PK, SecretKey},
+    tari_utilities::hex::Hex,
+};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+
+use crate::{
+    create_output::{build_stealth_output, SenderKeys},
+    deposit_tagging::DepositRules,
+    environment::is_simd_build,
+    factories::crypto_factories,
+    parallel::verify_range_proofs,
+    scan_outputs::{scan_output, ScanKeys},
+    value_promise::MinimumValuePromisePolicy,
+};
+
+/// The largest number of items a benchmark generates
+const MAX_BENCH_ITEMS: u32 = 10_000;
+/// The largest number of range proofs a benchmark generates
+const MAX_RANGE_PROOF_ITEMS: u32 = 256;
+/// The number of distinct outputs the hashing benchmark cycles through
+const HASHED_OUTPUTS: u32 = 16;
+
+/// A struct to hold the timing of a benchmark
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BenchResult {
+    /// The number of items the operation was timed over
+    pub items: u32,
+    /// The total time taken, in milliseconds
+    pub elapsed_ms: f64,
+    /// The mean time per item, in milliseconds
+    pub per_item_ms: f64,
+    /// The number of items processed per second
+    pub items_per_second: f64,
+    /// The number of items the operation matched or accepted, where that applies
+    pub matched: u32,
+    /// Whether the library was built with WebAssembly SIMD
+    pub simd: bool,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn bench_error(error: &str) -> BenchResult {
+    BenchResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// The current time in milliseconds, from `performance.now()` where available for sub-millisecond resolution
+fn now_ms() -> f64 {
+    let global = js_sys::global();
+    Reflect::get(&global, &JsValue::from_str("performance"))
+        .ok()
+        .filter(|performance| performance.is_object())
+        .and_then(|performance| {
+            let now = Reflect::get(&performance, &JsValue::from_str("now")).ok()?;
+            now.dyn_into::<Function>().ok()?.call0(&performance).ok()?.as_f64()
+        })
+        .unwrap_or_else(Date::now)
+}
+
+/// Checks the number of items a benchmark is asked to generate
+fn check_bench_items(items: u32, max_items: u32) -> Result<(), String> {
+    if items == 0 || items > max_items {
+        return Err(format!("The number of items must be between 1 and {max_items}"));
+    }
+    Ok(())
+}
+
+/// Times `f` over `items` items, where `f` returns the number of items it matched
+fn time_items<F: FnOnce() -> u32>(items: u32, f: F) -> BenchResult {
+    let start = now_ms();
+    let matched = f();
+    let elapsed_ms = now_ms() - start;
+    BenchResult {
+        items,
+        elapsed_ms,
+        per_item_ms: elapsed_ms / f64::from(items),
+        items_per_second: if elapsed_ms > 0.0 {
+            f64::from(items) * 1000.0 / elapsed_ms
+        } else {
+            0.0
+        },
+        matched,
+        simd: is_simd_build(),
+        error: None,
+    }
+}
+
+/// Generates stealth one-sided outputs with `range_proof_type`, every `match_every`th one paid to `wallet_pk` and the
+/// rest to random wallets
+async fn stealth_outputs(
+    n_outputs: u32,
+    wallet_pk: &PublicKey,
+    match_every: u32,
+    range_proof_type: RangeProofType,
+) -> Result<Vec<TransactionOutput>, String> {
+    let features = OutputFeatures {
+        range_proof_type,
+        ..Default::default()
+    };
+    let mut outputs = Vec::with_capacity(n_outputs as usize);
+    for i in 0..n_outputs {
+        let recipient_pk = if i % match_every == 0 {
+            wallet_pk.clone()
+        } else {
+            PublicKey::from_secret_key(&PrivateKey::random(&mut rand::thread_rng()))
+        };
+        let sender_keys = SenderKeys {
+            wallet_pk: PublicKey::from_secret_key(&PrivateKey::random(&mut rand::thread_rng())).to_hex(),
+            sender_offset_sk: None,
+            nonce_sk: None,
+        };
+        let (output, _) = build_stealth_output(
+            MicroMinotari::from(1_000_000),
+            sender_keys,
+            &recipient_pk,
+            &recipient_pk,
+            features.clone(),
+            MinimumValuePromisePolicy::default_for(range_proof_type),
+            TransactionOutputVersion::get_current_version(),
+        )
+        .await?;
+        outputs.push(output);
+    }
+    Ok(outputs)
+}
+
+/// Times scanning `n_outputs` stealth one-sided outputs, one in `match_every` of which belong to the wallet
+async fn bench_scan_outputs(n_outputs: u32, match_every: u32) -> BenchResult {
+    if let Err(e) = check_bench_items(n_outputs, MAX_BENCH_ITEMS) {
+        return bench_error(&format!("n_outputs: {e}"));
+    }
+    let wallet_sk = PrivateKey::random(&mut rand::thread_rng());
+    let keys = match ScanKeys::new(Vec::new(), wallet_sk.to_hex()) {
+        Ok(val) => val,
+        Err(e) => return bench_error(&e.message),
+    };
+    let (_, wallet_pk) = keys.wallet_keys();
+    let outputs = match stealth_outputs(n_outputs, wallet_pk, match_every, RangeProofType::RevealedValue).await {
+        Ok(val) => val,
+        Err(e) => return bench_error(&format!("Could not generate outputs: {e}")),
+    };
+    let rules = DepositRules::default();
+    time_items(n_outputs, || {
+        outputs
+            .iter()
+            .filter(|output| matches!(scan_output(&keys, &rules, output), Ok(Some(_))))
+            .count() as u32
+    })
+}
+
+/// Times scanning `n_outputs` stealth one-sided outputs, half of which belong to the wallet, which exercises the
+/// Diffie-Hellman exchanges and domain separated hashing of the scanner's hot loop. At most 10,000 outputs are
+/// generated.
+#[wasm_bindgen]
+pub async fn bench_scan(n_outputs: u32) -> BenchResult {
+    bench_scan_outputs(n_outputs, 2).await
+}
+
+/// Times scanning `n_outputs` stealth one-sided outputs that all belong to the wallet, so that every output is also
+/// decrypted and its commitment opened. At most 10,000 outputs are generated.
+#[wasm_bindgen]
+pub async fn bench_scan_stealth(n_outputs: u32) -> BenchResult {
+    bench_scan_outputs(n_outputs, 1).await
+}
+
+/// Times batch verifying the range proofs of `n_outputs` outputs, as `verify_range_proofs_parallel` does. Generating
+/// the proofs is slow, so at most 256 outputs are generated.
+#[wasm_bindgen]
+pub async fn bench_range_proof_verify(n_outputs: u32) -> BenchResult {
+    if let Err(e) = check_bench_items(n_outputs, MAX_RANGE_PROOF_ITEMS) {
+        return bench_error(&format!("n_outputs: {e}"));
+    }
+    let wallet_pk = PublicKey::from_secret_key(&PrivateKey::random(&mut rand::thread_rng()));
+    let outputs = match stealth_outputs(n_outputs, &wallet_pk, 1, RangeProofType::BulletProofPlus).await {
+        Ok(val) => val,
+        Err(e) => return bench_error(&format!("Could not generate outputs: {e}")),
+    };
+    let range_proof = crypto_factories().range_proof;
+    let mut result = time_items(n_outputs, || match verify_range_proofs(&range_proof, &outputs) {
+        Ok(()) => n_outputs,
+        Err(_) => 0,
+    });
+    if result.matched != n_outputs {
+        result.error = Some("The generated range proofs did not verify".to_string());
+    }
+    result
+}
+
+/// Times hashing `n_outputs` outputs, as their hashes are computed when scanning and validating them. At most 10,000
+/// hashes are computed, cycling through a few generated outputs.
+#[wasm_bindgen]
+pub async fn bench_output_hash(n_outputs: u32) -> BenchResult {
+    if let Err(e) = check_bench_items(n_outputs, MAX_BENCH_ITEMS) {
+        return bench_error(&format!("n_outputs: {e}"));
+    }
+    let wallet_pk = PublicKey::from_secret_key(&PrivateKey::random(&mut rand::thread_rng()));
+    let outputs = match stealth_outputs(
+        n_outputs.min(HASHED_OUTPUTS),
+        &wallet_pk,
+        1,
+        RangeProofType::RevealedValue,
+    )
+    .await
+    {
+        Ok(val) => val,
+        Err(e) => return bench_error(&format!("Could not generate outputs: {e}")),
+    };
+    time_items(n_outputs, || {
+        outputs
+            .iter()
+            .cycle()
+            .take(n_outputs as usize)
+            .map(|output| black_box(output.hash()))
+            .count() as u32
+    })
+}
@@ -0,0 +1,144 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_core::transactions::transaction_components::{self as components, SideChainFeature};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::PublicKey as PK, tari_utilities::hex::Hex};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    factories::crypto_factories,
+    output_encoding::{decode_output, OutputEncoding},
+};
+
+/// A struct to hold what is known about a burn output, and the data needed to claim it on the second layer
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BurnOutputResult {
+    /// Whether the output is a burn output
+    pub is_burned: bool,
+    /// The hash of the output (hex value)
+    pub output_hash: Option<String>,
+    /// The commitment of the burned value, which the claim spends on the second layer (hex value)
+    pub commitment: Option<String>,
+    /// The range proof of the commitment, which the claim carries to the second layer (hex value)
+    pub range_proof: Option<String>,
+    /// The public key the burn can be claimed with on the second layer, if the burn has a confidential output feature
+    /// (hex value)
+    pub claim_public_key: Option<String>,
+    /// Whether `claim_secret` is the secret key of the claim public key, if a claim secret was given
+    pub claim_key_matches: Option<bool>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn burn_output_error(error: &str) -> BurnOutputResult {
+    BurnOutputResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Detects whether an output is a burn output, and if so extracts the data needed to claim the burned value on the
+/// second layer: its commitment, range proof and claim public key. Outputs that are not burns only report `isBurned`
+/// as false. The output is passed in any of the encodings accepted by `scan_output_for_one_sided_payment`.
+/// * `claim_secret` - the claim private key (hex value); if provided, `claimKeyMatches` reports whether the burn can be
+///   claimed with it
+#[wasm_bindgen]
+pub fn scan_burn_output(
+    output: JsValue,
+    claim_secret: Option<String>,
+    encoding: Option<OutputEncoding>,
+) -> BurnOutputResult {
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => return burn_output_error(&e.to_string()),
+    };
+    let claim_secret = match claim_secret.as_deref().map(PrivateKey::from_hex).transpose() {
+        Ok(val) => val,
+        Err(e) => return burn_output_error(&format!("claim_secret: {e}")),
+    };
+    if !output.is_burned() {
+        return BurnOutputResult::default();
+    }
+    let claim_public_key = match &output.features.sidechain_feature {
+        Some(SideChainFeature::ConfidentialOutput(data)) => Some(data.claim_public_key.clone()),
+        _ => None,
+    };
+    let claim_key_matches =
+        claim_secret.map(|claim_secret| claim_public_key.as_ref() == Some(&PublicKey::from_secret_key(&claim_secret)));
+    BurnOutputResult {
+        is_burned: true,
+        output_hash: Some(output.hash().to_hex()),
+        commitment: Some(output.commitment.to_hex()),
+        range_proof: output.proof.as_ref().map(|proof| proof.to_hex()),
+        claim_public_key: claim_public_key.map(|key| key.to_hex()),
+        claim_key_matches,
+        error: None,
+    }
+}
+
+/// A struct to hold a burn commitment proof
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BurnProofResult {
+    /// The commitment of the burned value (hex value)
+    pub commitment: Option<String>,
+    /// The public nonce of the commitment signature (hex value)
+    pub public_nonce: Option<String>,
+    /// The `u` scalar of the commitment signature (hex value)
+    pub u: Option<String>,
+    /// The `v` scalar of the commitment signature (hex value)
+    pub v: Option<String>,
+    /// The commitment signature, serialized as JSON
+    pub proof: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn burn_proof_error(error: &str) -> BurnProofResult {
+    BurnProofResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Creates the commitment signature needed to claim burned funds as a confidential output on the second layer: a proof
+/// of knowledge of the opening of the burned commitment, bound to the claim public key, as the key manager's
+/// `generate_burn_proof` does, but from the spending key itself.
+/// * `spending_key` - the spending key, i.e. the commitment mask, of the burned output (hex value)
+/// * `amount` - the burned value in µT
+/// * `claim_public_key` - the public key the burn is claimed with (hex value)
+#[wasm_bindgen]
+pub fn create_burn_commitment_proof(spending_key: &str, amount: u64, claim_public_key: &str) -> BurnProofResult {
+    let spending_key = match PrivateKey::from_hex(spending_key) {
+        Ok(val) => val,
+        Err(e) => return burn_proof_error(&format!("spending_key: {e}")),
+    };
+    let claim_public_key = match PublicKey::from_hex(claim_public_key) {
+        Ok(val) => val,
+        Err(e) => return burn_proof_error(&format!("claim_public_key: {e}")),
+    };
+    let amount = PrivateKey::from(amount);
+    let factory = crypto_factories().commitment;
+    let proof = match components::create_burn_commitment_proof(&factory, &spending_key, &amount, &claim_public_key) {
+        Ok(val) => val,
+        Err(e) => return burn_proof_error(&format!("Could not create the burn proof: {e}")),
+    };
+    let proof_json = match serde_json::to_string(&proof) {
+        Ok(val) => val,
+        Err(e) => return burn_proof_error(&format!("Could not serialize the burn proof: {e}")),
+    };
+    BurnProofResult {
+        commitment: Some(factory.commit(&spending_key, &amount).to_hex()),
+        public_nonce: Some(proof.public_nonce().to_hex()),
+        u: Some(proof.u().to_hex()),
+        v: Some(proof.v().to_hex()),
+        proof: Some(proof_json),
+        error: None,
+    }
+}
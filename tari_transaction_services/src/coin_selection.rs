@@ -0,0 +1,468 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Coin selection: choosing which recovered outputs to spend to pay an amount and the fee of a one-sided transaction.
+//! The fee grows with every input, so each strategy checks the selection against the fee of a transaction with as
+//! many inputs, with or without a change output, as `build_one_sided_transaction` builds it.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::tari_amount::MicroMinotari;
+use tsify::Tsify;
+
+use crate::{
+    one_sided_transaction::{change_and_fee, one_sided_fee},
+    output_store::StoredOutput,
+};
+
+/// The most subsets the branch and bound strategy visits before settling for the best match found
+const MAX_BNB_TRIES: usize = 100_000;
+/// The most outputs, largest first, the branch and bound strategy searches
+const MAX_BNB_CANDIDATES: usize = 1_000;
+
+/// How the outputs to spend are chosen
+/// * `"largestFirst"` - the largest outputs first, which needs the fewest inputs and so the lowest fee
+/// * `"smallestFirst"` - the smallest outputs first, which consolidates small outputs at the cost of a higher fee
+/// * `"branchAndBound"` - a set of outputs that pays the amount and fee closely enough that no change output is needed,
+///   so that the transaction does not link a change output to the sender; falls back to `largestFirst` if there is none
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(from_wasm_abi)]
+pub enum CoinSelectionStrategy {
+    LargestFirst,
+    SmallestFirst,
+    BranchAndBound,
+}
+
+/// The recovered outputs selected to pay an amount, and the resulting change and fee
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct CoinSelectionResult {
+    /// The hashes of the selected outputs (hex values)
+    pub selected: Option<Vec<String>>,
+    /// The value of the selected outputs, in µT
+    pub total: Option<u64>,
+    /// The change returned to the sender, in µT
+    pub change: Option<u64>,
+    /// The fee of a one-sided transaction spending the selected outputs, in µT
+    pub fee: Option<u64>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+pub(crate) fn coin_selection_error(error: &str) -> CoinSelectionResult {
+    CoinSelectionResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Selects the stored outputs available at `current_height` to pay `amount` with the strategy
+pub(crate) fn select_stored_outputs(
+    unspent: &[StoredOutput],
+    amount: u64,
+    fee_per_gram: u64,
+    current_height: u64,
+    strategy: CoinSelectionStrategy,
+) -> CoinSelectionResult {
+    if amount == 0 {
+        return coin_selection_error("amount: The amount must be greater than 0");
+    }
+    let available = unspent
+        .iter()
+        .filter(|output| output.is_available(current_height))
+        .collect::<Vec<_>>();
+    let values = available
+        .iter()
+        .map(|output| MicroMinotari::from(output.value()))
+        .collect::<Vec<_>>();
+    match select_coins(&values, amount.into(), fee_per_gram, Some(strategy)) {
+        Ok(selection) => CoinSelectionResult {
+            selected: Some(
+                selection
+                    .indexes
+                    .iter()
+                    .map(|index| available[*index].output_hash.clone())
+                    .collect(),
+            ),
+            total: Some(selection.total.as_u64()),
+            change: Some(selection.change.as_u64()),
+            fee: Some(selection.fee.as_u64()),
+            error: None,
+        },
+        Err(e) => coin_selection_error(&format!("Could not select outputs: {e}")),
+    }
+}
+
+/// The outputs selected to pay an amount, and the resulting change and fee
+pub(crate) struct CoinSelection {
+    /// The indexes of the selected outputs, in ascending order
+    pub indexes: Vec<usize>,
+    pub total: MicroMinotari,
+    pub change: MicroMinotari,
+    pub fee: MicroMinotari,
+}
+
+/// Selects outputs of `values` to pay `amount` with the strategy, or all of them if there is none
+pub(crate) fn select_coins(
+    values: &[MicroMinotari],
+    amount: MicroMinotari,
+    fee_per_gram: u64,
+    strategy: Option<CoinSelectionStrategy>,
+) -> Result<CoinSelection, String> {
+    let largest_first = |a: &MicroMinotari, b: &MicroMinotari| b.cmp(a);
+    let indexes = match strategy {
+        None => (0..values.len()).collect(),
+        Some(CoinSelectionStrategy::LargestFirst) => greedy(values, amount, fee_per_gram, largest_first)?,
+        Some(CoinSelectionStrategy::SmallestFirst) => greedy(values, amount, fee_per_gram, |a, b| a.cmp(b))?,
+        Some(CoinSelectionStrategy::BranchAndBound) => match branch_and_bound(values, amount, fee_per_gram)? {
+            Some(indexes) => indexes,
+            None => greedy(values, amount, fee_per_gram, largest_first)?,
+        },
+    };
+    selection(values, indexes, amount, fee_per_gram)
+}
+
+fn selection(
+    values: &[MicroMinotari],
+    mut indexes: Vec<usize>,
+    amount: MicroMinotari,
+    fee_per_gram: u64,
+) -> Result<CoinSelection, String> {
+    indexes.sort_unstable();
+    let total = indexes
+        .iter()
+        .try_fold(MicroMinotari::zero(), |total, index| total.checked_add(values[*index]))
+        .ok_or_else(|| "The value of the outputs overflows".to_string())?;
+    let (change, fee) = change_and_fee(total, amount, indexes.len(), fee_per_gram)?;
+    Ok(CoinSelection {
+        indexes,
+        total,
+        change,
+        fee,
+    })
+}
+
+/// Adds outputs in the order of `compare` until they pay the amount and the fee of a transaction without change,
+/// returning all outputs if even they do not
+fn greedy<F>(
+    values: &[MicroMinotari],
+    amount: MicroMinotari,
+    fee_per_gram: u64,
+    compare: F,
+) -> Result<Vec<usize>, String>
+where
+    F: Fn(&MicroMinotari, &MicroMinotari) -> Ordering,
+{
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| compare(&values[*a], &values[*b]));
+    let mut total = MicroMinotari::zero();
+    for (i, index) in order.iter().enumerate() {
+        total = match total.checked_add(values[*index]) {
+            Some(val) => val,
+            None => break,
+        };
+        let needed = amount.checked_add(one_sided_fee(fee_per_gram, i + 1, 1)?);
+        if needed.map_or(false, |needed| total >= needed) {
+            order.truncate(i + 1);
+            break;
+        }
+    }
+    Ok(order)
+}
+
+/// A depth-first search, largest outputs first, for the set of outputs whose value after the fee of spending them is
+/// within the cost of a change output above the amount and the fee of the rest of the transaction
+struct BranchAndBound<'a> {
+    // The position of each candidate in the values, and its value minus the fee of spending it, largest first
+    candidates: &'a [(usize, u128)],
+    // The sum of the candidates from each position on
+    remaining: Vec<u128>,
+    target: u128,
+    upper: u128,
+    tries: usize,
+    selected: Vec<usize>,
+    // The selection closest above the target, and its excess
+    best: Option<(u128, Vec<usize>)>,
+}
+
+impl BranchAndBound<'_> {
+    fn search(&mut self, position: usize, sum: u128) {
+        self.tries += 1;
+        if self.tries > MAX_BNB_TRIES ||
+            sum > self.upper ||
+            self.best.as_ref().map_or(false, |(excess, _)| *excess == 0)
+        {
+            return;
+        }
+        if sum >= self.target {
+            let excess = sum - self.target;
+            if self.best.as_ref().map_or(true, |(best, _)| excess < *best) {
+                self.best = Some((excess, self.selected.clone()));
+            }
+            return;
+        }
+        if position == self.candidates.len() || sum + self.remaining[position] < self.target {
+            return;
+        }
+        self.selected.push(position);
+        self.search(position + 1, sum + self.candidates[position].1);
+        self.selected.pop();
+        self.search(position + 1, sum);
+    }
+}
+
+fn branch_and_bound(
+    values: &[MicroMinotari],
+    amount: MicroMinotari,
+    fee_per_gram: u64,
+) -> Result<Option<Vec<usize>>, String> {
+    // The fee is linear in the number of inputs and outputs
+    let base_fee = u128::from(one_sided_fee(fee_per_gram, 0, 1)?.as_u64());
+    let input_fee = u128::from(one_sided_fee(fee_per_gram, 1, 1)?.as_u64()).saturating_sub(base_fee);
+    let cost_of_change = u128::from(one_sided_fee(fee_per_gram, 0, 2)?.as_u64()).saturating_sub(base_fee);
+
+    // Outputs worth less than the fee of spending them never help
+    let mut candidates = values
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| {
+            u128::from(value.as_u64())
+                .checked_sub(input_fee)
+                .filter(|value| *value > 0)
+                .map(|value| (index, value))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.1.cmp(&a.1));
+    candidates.truncate(MAX_BNB_CANDIDATES);
+    let mut remaining = vec![0; candidates.len() + 1];
+    for position in (0..candidates.len()).rev() {
+        remaining[position] = remaining[position + 1] + candidates[position].1;
+    }
+
+    let target = u128::from(amount.as_u64()) + base_fee;
+    let mut search = BranchAndBound {
+        candidates: &candidates,
+        remaining,
+        target,
+        upper: target + cost_of_change,
+        tries: 0,
+        selected: Vec::new(),
+        best: None,
+    };
+    search.search(0, 0);
+    Ok(search
+        .best
+        .map(|(_, selected)| selected.into_iter().map(|position| candidates[position].0).collect()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RecoveredOutputResult;
+
+    const FEE_PER_GRAM: u64 = 5;
+    const STRATEGIES: [CoinSelectionStrategy; 3] = [
+        CoinSelectionStrategy::LargestFirst,
+        CoinSelectionStrategy::SmallestFirst,
+        CoinSelectionStrategy::BranchAndBound,
+    ];
+
+    fn fee(num_inputs: usize, num_outputs: usize) -> u64 {
+        one_sided_fee(FEE_PER_GRAM, num_inputs, num_outputs).unwrap().as_u64()
+    }
+
+    fn values(values: &[u64]) -> Vec<MicroMinotari> {
+        values.iter().map(|value| MicroMinotari::from(*value)).collect()
+    }
+
+    fn select(outputs: &[u64], amount: u64, strategy: Option<CoinSelectionStrategy>) -> Result<CoinSelection, String> {
+        select_coins(&values(outputs), amount.into(), FEE_PER_GRAM, strategy)
+    }
+
+    fn stored_output(output_hash: &str, value: u64) -> StoredOutput {
+        StoredOutput {
+            output_hash: output_hash.to_string(),
+            output: RecoveredOutputResult {
+                value: Some(value),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn largest_first_selects_the_fewest_outputs() {
+        let selection = select(
+            &[1_000, 50_000, 10_000, 200_000],
+            100_000,
+            Some(CoinSelectionStrategy::LargestFirst),
+        )
+        .unwrap();
+        assert_eq!(selection.indexes, vec![3]);
+        assert_eq!(selection.total.as_u64(), 200_000);
+        assert_eq!(selection.fee.as_u64(), fee(1, 2));
+        assert_eq!(selection.change.as_u64(), 200_000 - 100_000 - fee(1, 2));
+    }
+
+    #[test]
+    fn smallest_first_consolidates_small_outputs() {
+        let selection = select(
+            &[1_000_000, 30_000, 40_000, 50_000],
+            100_000,
+            Some(CoinSelectionStrategy::SmallestFirst),
+        )
+        .unwrap();
+        assert_eq!(selection.indexes, vec![1, 2, 3]);
+        assert_eq!(selection.total.as_u64(), 120_000);
+        assert_eq!(selection.fee.as_u64(), fee(3, 2));
+        assert_eq!(selection.change.as_u64(), 120_000 - 100_000 - fee(3, 2));
+    }
+
+    #[test]
+    fn greedy_selection_pays_the_fee_of_every_input() {
+        // The first output alone pays the amount, but not the fee of spending it
+        let selection = select(&[100_000, 50_000], 100_000, Some(CoinSelectionStrategy::LargestFirst)).unwrap();
+        assert_eq!(selection.indexes, vec![0, 1]);
+        assert_eq!(selection.fee.as_u64(), fee(2, 2));
+    }
+
+    #[test]
+    fn branch_and_bound_finds_an_exact_match() {
+        let amount = 100_000;
+        let exact = amount + fee(1, 1);
+        let selection = select(
+            &[10 * amount, exact, amount / 2],
+            amount,
+            Some(CoinSelectionStrategy::BranchAndBound),
+        )
+        .unwrap();
+        assert_eq!(selection.indexes, vec![1]);
+        assert_eq!(selection.change.as_u64(), 0);
+        assert_eq!(selection.fee.as_u64(), fee(1, 1));
+
+        // The largest output would pay it as well, with change
+        let selection = select(
+            &[10 * amount, exact, amount / 2],
+            amount,
+            Some(CoinSelectionStrategy::LargestFirst),
+        )
+        .unwrap();
+        assert_eq!(selection.indexes, vec![0]);
+        assert!(selection.change.as_u64() > 0);
+    }
+
+    #[test]
+    fn branch_and_bound_combines_outputs_for_an_exact_match() {
+        let amount = 100_000;
+        let outputs = [60_000, 1_000_000, 40_000 + fee(2, 1)];
+        let selection = select(&outputs, amount, Some(CoinSelectionStrategy::BranchAndBound)).unwrap();
+        assert_eq!(selection.indexes, vec![0, 2]);
+        assert_eq!(selection.total.as_u64(), amount + fee(2, 1));
+        assert_eq!(selection.change.as_u64(), 0);
+        assert_eq!(selection.fee.as_u64(), fee(2, 1));
+    }
+
+    #[test]
+    fn branch_and_bound_falls_back_to_largest_first() {
+        // No set of outputs is within the cost of a change output of the amount
+        let selection = select(
+            &[1_000_000, 30_000, 2_000_000],
+            100_000,
+            Some(CoinSelectionStrategy::BranchAndBound),
+        )
+        .unwrap();
+        assert_eq!(selection.indexes, vec![2]);
+        assert_eq!(selection.fee.as_u64(), fee(1, 2));
+        assert_eq!(selection.change.as_u64(), 2_000_000 - 100_000 - fee(1, 2));
+    }
+
+    #[test]
+    fn change_is_only_created_above_its_cost() {
+        let amount = 100_000;
+        // Something remains after paying for a change output
+        let selection = select(&[amount + fee(1, 2) + 1], amount, None).unwrap();
+        assert_eq!(selection.change.as_u64(), 1);
+        assert_eq!(selection.fee.as_u64(), fee(1, 2));
+
+        // Nothing would remain, so the remainder goes to the fee instead
+        let selection = select(&[amount + fee(1, 2)], amount, None).unwrap();
+        assert_eq!(selection.change.as_u64(), 0);
+        assert_eq!(selection.fee.as_u64(), fee(1, 2));
+        let selection = select(&[amount + fee(1, 1)], amount, None).unwrap();
+        assert_eq!(selection.change.as_u64(), 0);
+        assert_eq!(selection.fee.as_u64(), fee(1, 1));
+
+        let error = select(&[amount + fee(1, 1) - 1], amount, None).err().unwrap();
+        assert!(error.starts_with("Insufficient funds"), "{error}");
+    }
+
+    #[test]
+    fn insufficient_funds_are_an_error() {
+        for strategy in STRATEGIES {
+            let error = select(&[10_000, 20_000], 100_000, Some(strategy)).err().unwrap();
+            assert!(error.starts_with("Insufficient funds"), "{strategy:?}: {error}");
+            let error = select(&[], 100_000, Some(strategy)).err().unwrap();
+            assert!(error.starts_with("Insufficient funds"), "{strategy:?}: {error}");
+        }
+    }
+
+    #[test]
+    fn overflowing_values_are_an_error() {
+        let outputs = [u64::MAX, u64::MAX];
+        // A single output pays the amount, so the sum is not needed
+        for strategy in STRATEGIES {
+            let selection = select(&outputs, 100_000, Some(strategy)).unwrap();
+            assert_eq!(selection.indexes.len(), 1, "{strategy:?}");
+        }
+        let error = select(&outputs, 100_000, None).err().unwrap();
+        assert_eq!(error, "The value of the outputs overflows");
+        for strategy in STRATEGIES {
+            let error = select(&outputs, u64::MAX, Some(strategy)).err().unwrap();
+            assert!(error.contains("overflow"), "{strategy:?}: {error}");
+        }
+    }
+
+    #[test]
+    fn stored_outputs_that_are_not_available_are_not_selected() {
+        let mut spent = stored_output("aa", 1_000_000);
+        spent.spent = true;
+        let mut time_locked = stored_output("bb", 1_000_000);
+        time_locked.output.maturity = Some(200);
+        let mut pending = stored_output("cc", 1_000_000);
+        pending.mined_height = Some(99);
+        let available = stored_output("dd", 500_000);
+        let unspent = [spent, time_locked, pending, available];
+
+        let result = select_stored_outputs(
+            &unspent,
+            100_000,
+            FEE_PER_GRAM,
+            100,
+            CoinSelectionStrategy::LargestFirst,
+        );
+        assert_eq!(result.error, None);
+        assert_eq!(result.selected, Some(vec!["dd".to_string()]));
+        assert_eq!(result.total, Some(500_000));
+        assert_eq!(result.fee, Some(fee(1, 2)));
+
+        let result = select_stored_outputs(&unspent, 0, FEE_PER_GRAM, 100, CoinSelectionStrategy::LargestFirst);
+        assert_eq!(
+            result.error,
+            Some("amount: The amount must be greater than 0".to_string())
+        );
+        let result = select_stored_outputs(
+            &unspent,
+            600_000,
+            FEE_PER_GRAM,
+            100,
+            CoinSelectionStrategy::LargestFirst,
+        );
+        assert!(result
+            .error
+            .unwrap()
+            .starts_with("Could not select outputs: Insufficient funds"));
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec_with_limit};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+// The compression level used for cached chain data, a good trade-off between speed and size for repetitive data
+const COMPRESSION_LEVEL: u8 = 6;
+// Upper limit on the decompressed size, to guard against decompression bombs in persisted data
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+// The prefix of a compressed storage entry. Neither JSON nor the Borsh encoding of an output starts with it, so data
+// written before entries were compressed is told apart and read as is.
+const ENTRY_MAGIC: [u8; 4] = [0x00, b'T', b'C', b'E'];
+// The version of the storage entry format
+const ENTRY_VERSION: u8 = 1;
+
+/// The codec used for a compressed cache entry, stored as the first byte of the entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CompressionCodec {
+    /// The data is stored as is
+    None = 0x00,
+    /// The data is compressed with raw DEFLATE
+    Deflate = 0x01,
+}
+
+impl CompressionCodec {
+    fn from_byte(byte: u8) -> Result<Self, String> {
+        match byte {
+            0x00 => Ok(CompressionCodec::None),
+            0x01 => Ok(CompressionCodec::Deflate),
+            _ => Err(format!("Unknown compression codec `{byte}`")),
+        }
+    }
+}
+
+/// Compresses data for storage, prefixed with the codec used. Data that does not compress well is stored as is.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let compressed = compress_to_vec(data, COMPRESSION_LEVEL);
+    let (codec, payload) = if compressed.len() < data.len() {
+        (CompressionCodec::Deflate, compressed.as_slice())
+    } else {
+        (CompressionCodec::None, data)
+    };
+    let mut result = Vec::with_capacity(payload.len() + 1);
+    result.push(codec as u8);
+    result.extend_from_slice(payload);
+    result
+}
+
+/// Decompresses data produced by `compress`
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let (codec, payload) = match data.split_first() {
+        Some((codec, payload)) => (CompressionCodec::from_byte(*codec)?, payload),
+        None => return Err("Compressed data is empty".to_string()),
+    };
+    match codec {
+        CompressionCodec::None => Ok(payload.to_vec()),
+        CompressionCodec::Deflate => decompress_to_vec_with_limit(payload, MAX_DECOMPRESSED_SIZE)
+            .map_err(|e| format!("Could not decompress data: {:?}", e.status)),
+    }
+}
+
+/// The content of a compressed storage entry, so that it is read back as it was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum EntryContent {
+    /// Binary data, e.g. a Borsh encoded output
+    Bytes = 0x00,
+    /// UTF-8 text, e.g. JSON
+    Text = 0x01,
+}
+
+impl EntryContent {
+    fn from_byte(byte: u8) -> Result<Self, String> {
+        match byte {
+            0x00 => Ok(EntryContent::Bytes),
+            0x01 => Ok(EntryContent::Text),
+            _ => Err(format!("Unknown entry content `{byte}`")),
+        }
+    }
+}
+
+/// Compresses data written through a storage callback into an entry: the entry magic, the format version, the content
+/// and the data as per `compress`
+pub(crate) fn compress_entry(content: EntryContent, data: &[u8]) -> Vec<u8> {
+    let compressed = compress(data);
+    let mut entry = Vec::with_capacity(ENTRY_MAGIC.len() + 2 + compressed.len());
+    entry.extend_from_slice(&ENTRY_MAGIC);
+    entry.push(ENTRY_VERSION);
+    entry.push(content as u8);
+    entry.extend_from_slice(&compressed);
+    entry
+}
+
+/// Decompresses an entry produced by `compress_entry`, or returns `None` if the data is not an entry, e.g. as it was
+/// written before entries were compressed
+pub(crate) fn decompress_entry(data: &[u8]) -> Result<Option<(EntryContent, Vec<u8>)>, String> {
+    let header = match data.strip_prefix(&ENTRY_MAGIC[..]) {
+        Some(val) => val,
+        None => return Ok(None),
+    };
+    match header {
+        [ENTRY_VERSION, content, payload @ ..] => Ok(Some((EntryContent::from_byte(*content)?, decompress(payload)?))),
+        [ENTRY_VERSION] | [] => Err("The storage entry is truncated".to_string()),
+        [version, ..] => Err(format!("Unsupported storage entry version {version}")),
+    }
+}
+
+/// Compresses cached chain data (e.g. checkpointed block ranges or cached outputs) before it is persisted, to reduce
+/// browser storage usage.
+#[wasm_bindgen]
+pub fn compress_cache_data(data: &[u8]) -> Vec<u8> {
+    compress(data)
+}
+
+/// Decompresses cached chain data that was compressed with `compress_cache_data`.
+#[wasm_bindgen]
+pub fn decompress_cache_data(data: &[u8]) -> Result<Vec<u8>, JsValue> {
+    decompress(data).map_err(|e| JsValue::from_str(&e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn data_round_trips() {
+        let repetitive = b"checkpointed block range ".repeat(100);
+        let compressed = compress(&repetitive);
+        assert_eq!(compressed[0], CompressionCodec::Deflate as u8);
+        assert!(compressed.len() < repetitive.len());
+        assert_eq!(decompress(&compressed).unwrap(), repetitive);
+
+        let short = b"x";
+        let stored = compress(short);
+        assert_eq!(stored, vec![CompressionCodec::None as u8, b'x']);
+        assert_eq!(decompress(&stored).unwrap(), short);
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let json = br#"{"version":2,"unspent":{}}"#.repeat(50);
+        let entry = compress_entry(EntryContent::Text, &json);
+        assert!(entry.len() < json.len());
+        assert_eq!(decompress_entry(&entry).unwrap(), Some((EntryContent::Text, json)));
+
+        let bytes = vec![0u8, 1, 2, 3];
+        let entry = compress_entry(EntryContent::Bytes, &bytes);
+        assert_eq!(decompress_entry(&entry).unwrap(), Some((EntryContent::Bytes, bytes)));
+    }
+
+    #[test]
+    fn data_written_before_compression_is_not_an_entry() {
+        assert_eq!(decompress_entry(br#"{"version":2}"#).unwrap(), None);
+        assert_eq!(decompress_entry(&[0x00, 0x00, 0x01]).unwrap(), None);
+        assert_eq!(decompress_entry(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn malformed_entries_are_rejected() {
+        let mut entry = compress_entry(EntryContent::Text, b"data");
+        entry[ENTRY_MAGIC.len()] = ENTRY_VERSION + 1;
+        assert!(decompress_entry(&entry).is_err());
+        assert!(decompress_entry(&ENTRY_MAGIC).is_err());
+        let mut entry = compress_entry(EntryContent::Text, b"data");
+        entry[ENTRY_MAGIC.len() + 1] = 0xff;
+        assert!(decompress_entry(&entry).is_err());
+        assert!(decompress(&[0x02]).is_err());
+        assert!(decompress(&[]).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Canonical Borsh encodings of transaction components, byte-compatible with base node gRPC and the wallet FFI. JS
+//! passes and receives the components as JSON, in the shapes this library returns them in, and their encodings as
+//! `Uint8Array`s.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{de::DeserializeOwned, Serialize};
+use tari_core::{
+    limited_reader::LimitedBytesReader,
+    transactions::transaction_components::{Transaction, TransactionInput, TransactionKernel, TransactionOutput},
+};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{output_encoding::MAX_OUTPUT_BYTES, size_budget::check_output_components};
+
+/// The maximum size of the Borsh bytes of an input, which carries the data of the output it spends
+pub(crate) const MAX_INPUT_BYTES: usize = 2 * MAX_OUTPUT_BYTES;
+/// The maximum size of the Borsh bytes of a kernel
+const MAX_KERNEL_BYTES: usize = 1024;
+/// The maximum size of the Borsh bytes of a transaction
+const MAX_TRANSACTION_BYTES: usize = 4 * 1024 * 1024;
+
+fn to_borsh<T: DeserializeOwned + BorshSerialize>(json: &str, name: &str) -> Result<Vec<u8>, JsValue> {
+    let value: T = serde_json::from_str(json).map_err(|e| JsValue::from_str(&format!("{name}: {e}")))?;
+    borsh::to_vec(&value).map_err(|e| JsValue::from_str(&format!("Could not serialize {name}: {e}")))
+}
+
+/// Deserializes untrusted Borsh bytes through a reader that fails once more than `max_len` bytes are read
+fn from_borsh<T: BorshDeserialize>(bytes: &[u8], max_len: usize, name: &str) -> Result<T, JsValue> {
+    let mut reader = LimitedBytesReader::new(max_len, bytes);
+    borsh::from_reader(&mut reader).map_err(|e| JsValue::from_str(&format!("{name}: Borsh: {e}")))
+}
+
+fn to_json<T: Serialize>(value: &T, name: &str) -> Result<String, JsValue> {
+    serde_json::to_string(value).map_err(|e| JsValue::from_str(&format!("Could not serialize {name}: {e}")))
+}
+
+/// Serializes a transaction output (JSON) to its canonical Borsh bytes
+#[wasm_bindgen]
+pub fn serialize_output(output: &str) -> Result<Vec<u8>, JsValue> {
+    to_borsh::<TransactionOutput>(output, "output")
+}
+
+/// Deserializes a transaction output from its Borsh bytes, checking its components against the consensus limits, and
+/// returns it as JSON
+#[wasm_bindgen]
+pub fn deserialize_output(bytes: &[u8]) -> Result<String, JsValue> {
+    let output: TransactionOutput = from_borsh(bytes, MAX_OUTPUT_BYTES, "output")?;
+    check_output_components(&output.script, &output.covenant, &output.features)
+        .map_err(|e| JsValue::from_str(&format!("output: {e}")))?;
+    to_json(&output, "output")
+}
+
+/// Serializes a transaction input (JSON) to its canonical Borsh bytes
+#[wasm_bindgen]
+pub fn serialize_input(input: &str) -> Result<Vec<u8>, JsValue> {
+    to_borsh::<TransactionInput>(input, "input")
+}
+
+/// Deserializes a transaction input from its Borsh bytes and returns it as JSON
+#[wasm_bindgen]
+pub fn deserialize_input(bytes: &[u8]) -> Result<String, JsValue> {
+    let input: TransactionInput = from_borsh(bytes, MAX_INPUT_BYTES, "input")?;
+    to_json(&input, "input")
+}
+
+/// Serializes a transaction kernel (JSON) to its canonical Borsh bytes
+#[wasm_bindgen]
+pub fn serialize_kernel(kernel: &str) -> Result<Vec<u8>, JsValue> {
+    to_borsh::<TransactionKernel>(kernel, "kernel")
+}
+
+/// Deserializes a transaction kernel from its Borsh bytes and returns it as JSON
+#[wasm_bindgen]
+pub fn deserialize_kernel(bytes: &[u8]) -> Result<String, JsValue> {
+    let kernel: TransactionKernel = from_borsh(bytes, MAX_KERNEL_BYTES, "kernel")?;
+    to_json(&kernel, "kernel")
+}
+
+/// Serializes a transaction (JSON), e.g. as returned by `build_one_sided_transaction`, to its canonical Borsh bytes
+#[wasm_bindgen]
+pub fn serialize_transaction(transaction: &str) -> Result<Vec<u8>, JsValue> {
+    to_borsh::<Transaction>(transaction, "transaction")
+}
+
+/// Deserializes a transaction from its Borsh bytes and returns it as JSON
+#[wasm_bindgen]
+pub fn deserialize_transaction(bytes: &[u8]) -> Result<String, JsValue> {
+    let transaction: Transaction = from_borsh(bytes, MAX_TRANSACTION_BYTES, "transaction")?;
+    to_json(&transaction, "transaction")
+}
@@ -0,0 +1,280 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{Commitment, FixedHash, PublicKey};
+use tari_core::{
+    covenants::{Covenant, CovenantToken, OutputField},
+    transactions::transaction_components::OutputType,
+};
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_script::TariScript;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    covenant_decoder::{covenant_node, expression},
+    size_budget::check_covenant,
+};
+
+/// A struct to hold a built covenant
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BuiltCovenantResult {
+    /// The consensus encoded covenant, as used for the `covenant` field of an output in JSON (hex value)
+    pub bytes: Option<String>,
+    /// The Borsh encoded covenant, as used in a Borsh encoded output (hex value)
+    pub borsh: Option<String>,
+    /// The covenant in `covenant!` macro notation
+    pub expression: Option<String>,
+    /// The number of tokens in the covenant
+    pub num_tokens: usize,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn built_covenant_error(error: &str) -> BuiltCovenantResult {
+    BuiltCovenantResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Parses an output field name, with or without the `field::` prefix, e.g. `features_maturity`
+fn parse_output_field(name: &str) -> Result<OutputField, String> {
+    match name.trim_start_matches("field::") {
+        "commitment" => Ok(OutputField::Commitment),
+        "script" => Ok(OutputField::Script),
+        "sender_offset_public_key" => Ok(OutputField::SenderOffsetPublicKey),
+        "covenant" => Ok(OutputField::Covenant),
+        "features" => Ok(OutputField::Features),
+        "features_output_type" | "features_flags" => Ok(OutputField::FeaturesOutputType),
+        "features_maturity" => Ok(OutputField::FeaturesMaturity),
+        "features_sidechain_feature" => Ok(OutputField::FeaturesSideChainFeatures),
+        "features_range_proof_type" => Ok(OutputField::FeaturesRangeProofType),
+        "minimum_value_promise" => Ok(OutputField::MinimumValuePromise),
+        _ => Err(format!("Unknown output field `{name}`")),
+    }
+}
+
+fn parse_output_fields(names: &[String]) -> Result<Vec<OutputField>, String> {
+    if names.is_empty() {
+        return Err("At least one output field is required".to_string());
+    }
+    names.iter().map(|name| parse_output_field(name)).collect()
+}
+
+fn parse_hash(hash: &str) -> Result<FixedHash, String> {
+    FixedHash::from_hex(hash).map_err(|e| format!("hash: {e}"))
+}
+
+/// Converts the value an output field must equal to the covenant argument for that field. Numbers may be given as
+/// JavaScript numbers or decimal strings, output types by name (e.g. `Burn`) or number, and all other values as hex.
+fn field_value_token(field: OutputField, value: &JsValue) -> Result<CovenantToken, String> {
+    let value = value
+        .as_string()
+        .or_else(|| value.as_f64().map(|value| value.to_string()))
+        .ok_or_else(|| "The value must be a string or a number".to_string())?;
+    match field {
+        OutputField::Commitment => Commitment::from_hex(&value)
+            .map(CovenantToken::commitment)
+            .map_err(|e| format!("commitment: {e}")),
+        OutputField::Script => serde_json::from_value::<TariScript>(serde_json::Value::String(value))
+            .map(CovenantToken::script)
+            .map_err(|e| format!("script: {e}")),
+        OutputField::SenderOffsetPublicKey => PublicKey::from_hex(&value)
+            .map(CovenantToken::public_key)
+            .map_err(|e| format!("sender_offset_public_key: {e}")),
+        OutputField::Covenant => serde_json::from_value::<Covenant>(serde_json::Value::String(value))
+            .map(CovenantToken::covenant)
+            .map_err(|e| format!("covenant: {e}")),
+        OutputField::FeaturesOutputType => OutputType::all()
+            .iter()
+            .find(|output_type| {
+                output_type.to_string().eq_ignore_ascii_case(&value) || output_type.as_byte().to_string() == value
+            })
+            .map(|output_type| CovenantToken::output_type(*output_type))
+            .ok_or_else(|| format!("Unknown output type `{value}`")),
+        OutputField::FeaturesMaturity => value
+            .parse::<u64>()
+            .map(CovenantToken::uint)
+            .map_err(|e| format!("features_maturity: {e}")),
+        field => Err(format!(
+            "{field} cannot be compared to a value, use `fieldsHashedEq` instead"
+        )),
+    }
+}
+
+/// Composes a covenant from JavaScript, e.g.
+/// `new CovenantBuilder().or(new CovenantBuilder().fieldEq("features_maturity", 1000), new
+/// CovenantBuilder().outputHashEq(hash)).build()`. Every filter added to a builder must match, so filters added one
+/// after the other are combined with `and`, and a builder without filters builds the empty covenant, which matches all
+/// outputs. Builder methods consume the builder and return it, for chaining, while builders passed as arguments are
+/// left untouched. Adding a filter fails if the covenant would exceed the consensus limits on its size.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct CovenantBuilder {
+    // The tokens of each filter, in prefix order
+    filters: Vec<Vec<CovenantToken>>,
+}
+
+impl CovenantBuilder {
+    // Adds a filter, failing if the covenant would exceed the consensus size limits
+    fn push(mut self, operation: &str, tokens: Vec<CovenantToken>) -> Result<Self, JsValue> {
+        self.filters.push(tokens);
+        check_covenant(&self.covenant()).map_err(|e| JsValue::from_str(&format!("{operation}: {e}")))?;
+        Ok(self)
+    }
+
+    // The tokens of all filters combined with `and`, or of the identity filter if there are none
+    fn filter_tokens(&self) -> Vec<CovenantToken> {
+        if self.filters.is_empty() {
+            return vec![CovenantToken::identity()];
+        }
+        let mut tokens = Vec::new();
+        for (i, filter) in self.filters.iter().enumerate() {
+            if i + 1 < self.filters.len() {
+                tokens.push(CovenantToken::and());
+            }
+            tokens.extend(filter.iter().cloned());
+        }
+        tokens
+    }
+
+    fn covenant(&self) -> Covenant {
+        if self.filters.is_empty() {
+            return Covenant::new();
+        }
+        self.filter_tokens().into_iter().collect()
+    }
+
+    fn combine(self, operation: &str, filter: CovenantToken, operands: &[&CovenantBuilder]) -> Result<Self, JsValue> {
+        let mut tokens = vec![filter];
+        for operand in operands {
+            tokens.extend(operand.filter_tokens());
+        }
+        self.push(operation, tokens)
+    }
+}
+
+#[wasm_bindgen]
+impl CovenantBuilder {
+    /// Creates a builder without filters
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the `identity` filter, which matches all outputs
+    pub fn identity(self) -> Result<CovenantBuilder, JsValue> {
+        self.push("identity", vec![CovenantToken::identity()])
+    }
+
+    /// Adds a filter that matches the outputs matched by both `left` and `right`
+    pub fn and(self, left: &CovenantBuilder, right: &CovenantBuilder) -> Result<CovenantBuilder, JsValue> {
+        self.combine("and", CovenantToken::and(), &[left, right])
+    }
+
+    /// Adds a filter that matches the outputs matched by `left` or `right`
+    pub fn or(self, left: &CovenantBuilder, right: &CovenantBuilder) -> Result<CovenantBuilder, JsValue> {
+        self.combine("or", CovenantToken::or(), &[left, right])
+    }
+
+    /// Adds a filter that matches the outputs matched by exactly one of `left` and `right`
+    pub fn xor(self, left: &CovenantBuilder, right: &CovenantBuilder) -> Result<CovenantBuilder, JsValue> {
+        self.combine("xor", CovenantToken::xor(), &[left, right])
+    }
+
+    /// Adds a filter that matches the outputs not matched by `inner`
+    pub fn not(self, inner: &CovenantBuilder) -> Result<CovenantBuilder, JsValue> {
+        self.combine("not", CovenantToken::not(), &[inner])
+    }
+
+    /// Adds a filter that matches the output with the given hash (hex value)
+    #[wasm_bindgen(js_name = outputHashEq)]
+    pub fn output_hash_eq(self, hash: &str) -> Result<CovenantBuilder, JsValue> {
+        let hash = parse_hash(hash).map_err(|e| JsValue::from_str(&format!("output_hash_eq: {e}")))?;
+        self.push("output_hash_eq", vec![
+            CovenantToken::output_hash_eq(),
+            CovenantToken::hash(hash),
+        ])
+    }
+
+    /// Adds a filter that matches the outputs that preserve the given fields of the input being spent
+    #[wasm_bindgen(js_name = fieldsPreserved)]
+    pub fn fields_preserved(self, fields: Vec<String>) -> Result<CovenantBuilder, JsValue> {
+        let fields = parse_output_fields(&fields).map_err(|e| JsValue::from_str(&format!("fields_preserved: {e}")))?;
+        self.push("fields_preserved", vec![
+            CovenantToken::fields_preserved(),
+            CovenantToken::fields(fields),
+        ])
+    }
+
+    /// Adds a filter that matches the outputs whose `field` equals `value`, e.g. `fieldEq("features_maturity", 1000)`
+    /// or `fieldEq("features_output_type", "Burn")`
+    #[wasm_bindgen(js_name = fieldEq)]
+    pub fn field_eq(self, field: &str, value: JsValue) -> Result<CovenantBuilder, JsValue> {
+        let (field, value) = parse_output_field(field)
+            .and_then(|field| Ok((field, field_value_token(field, &value)?)))
+            .map_err(|e| JsValue::from_str(&format!("field_eq: {e}")))?;
+        self.push("field_eq", vec![
+            CovenantToken::field_eq(),
+            CovenantToken::field(field),
+            value,
+        ])
+    }
+
+    /// Adds a filter that matches the outputs whose given fields hash to `hash` (hex value)
+    #[wasm_bindgen(js_name = fieldsHashedEq)]
+    pub fn fields_hashed_eq(self, fields: Vec<String>, hash: &str) -> Result<CovenantBuilder, JsValue> {
+        let (fields, hash) = parse_output_fields(&fields)
+            .and_then(|fields| Ok((fields, parse_hash(hash)?)))
+            .map_err(|e| JsValue::from_str(&format!("fields_hashed_eq: {e}")))?;
+        self.push("fields_hashed_eq", vec![
+            CovenantToken::fields_hashed_eq(),
+            CovenantToken::fields(fields),
+            CovenantToken::hash(hash),
+        ])
+    }
+
+    /// Adds a filter that matches all outputs if the block height is at least `height`, and none otherwise
+    #[wasm_bindgen(js_name = absoluteHeight)]
+    pub fn absolute_height(self, height: u64) -> Result<CovenantBuilder, JsValue> {
+        self.push("absolute_height", vec![
+            CovenantToken::absolute_height(),
+            CovenantToken::uint(height),
+        ])
+    }
+
+    /// Returns the consensus encoded covenant bytes, as accepted by `decode_covenant`
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.covenant().to_bytes()
+    }
+
+    /// Builds the covenant, checking that it decodes, and returns it in consensus and Borsh encoding
+    pub fn build(&self) -> BuiltCovenantResult {
+        let bytes = self.covenant().to_bytes();
+        // Decoding the bytes checks that the covenant is well formed
+        let covenant = match Covenant::from_bytes(&mut bytes.as_slice()) {
+            Ok(val) => val,
+            Err(e) => return built_covenant_error(&format!("Invalid covenant: {e}")),
+        };
+        let ast = match covenant_node(&covenant) {
+            Ok(val) => val,
+            Err(e) => return built_covenant_error(&format!("Invalid covenant: {e}")),
+        };
+        let borsh = match borsh::to_vec(&covenant) {
+            Ok(val) => val,
+            Err(e) => return built_covenant_error(&format!("Could not serialize covenant: {e}")),
+        };
+        BuiltCovenantResult {
+            bytes: Some(bytes.to_hex()),
+            borsh: Some(borsh.to_hex()),
+            expression: ast.as_ref().map(expression),
+            num_tokens: covenant.num_tokens(),
+            error: None,
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::slice::Iter;
+
+use serde::{Deserialize, Serialize};
+use tari_core::covenants::{Covenant, CovenantArg, CovenantFilter, CovenantToken};
+use tari_crypto::tari_utilities::hex::Hex;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A node of a decoded covenant. Filters take their arguments, which are either filters or arguments, as children in
+/// the order they appear in the covenant.
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CovenantNode {
+    #[serde(rename_all = "camelCase")]
+    Filter {
+        /// The filter name as used in the `covenant!` macro, e.g. `field_eq`
+        name: String,
+        /// The filter's arguments
+        children: Vec<CovenantNode>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Argument {
+        /// The argument type as used in the `covenant!` macro, e.g. `hash` or `fields`
+        arg_type: String,
+        /// The argument value in human readable form (hex value for hashes, keys, commitments and bytes)
+        value: String,
+        /// The decoded covenant, for a covenant argument
+        covenant: Option<Box<CovenantNode>>,
+    },
+}
+
+/// A struct to hold a decoded covenant
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct DecodedCovenantResult {
+    /// The covenant as a tree of filters and arguments, or none for an empty covenant, which matches all outputs
+    pub ast: Option<CovenantNode>,
+    /// The covenant in `covenant!` macro notation, e.g. `or(absolute_height(@uint(42)), identity())`
+    pub expression: Option<String>,
+    /// The number of tokens in the covenant
+    pub num_tokens: usize,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn decoded_covenant_error(error: &str) -> DecodedCovenantResult {
+    DecodedCovenantResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// The filter name and the number of tokens it takes as arguments
+fn filter_name_and_arity(filter: &CovenantFilter) -> (&'static str, usize) {
+    match filter {
+        CovenantFilter::Identity(_) => ("identity", 0),
+        CovenantFilter::And(_) => ("and", 2),
+        CovenantFilter::Or(_) => ("or", 2),
+        CovenantFilter::Xor(_) => ("xor", 2),
+        CovenantFilter::Not(_) => ("not", 1),
+        CovenantFilter::OutputHashEq(_) => ("output_hash_eq", 1),
+        CovenantFilter::FieldsPreserved(_) => ("fields_preserved", 1),
+        CovenantFilter::FieldEq(_) => ("field_eq", 2),
+        CovenantFilter::FieldsHashedEq(_) => ("fields_hashed_eq", 2),
+        CovenantFilter::AbsoluteHeight(_) => ("absolute_height", 1),
+    }
+}
+
+fn argument_node(arg: &CovenantArg) -> Result<CovenantNode, String> {
+    let (arg_type, value, covenant) = match arg {
+        CovenantArg::Hash(hash) => ("hash", hash.to_hex(), None),
+        CovenantArg::PublicKey(public_key) => ("public_key", public_key.to_hex(), None),
+        CovenantArg::Commitment(commitment) => ("commitment", commitment.to_hex(), None),
+        CovenantArg::TariScript(script) => ("script", script.to_string(), None),
+        CovenantArg::Covenant(covenant) => (
+            "covenant",
+            covenant.to_bytes().to_hex(),
+            covenant_node(covenant)?.map(Box::new),
+        ),
+        CovenantArg::OutputType(output_type) => ("output_type", output_type.to_string(), None),
+        CovenantArg::Uint(int) => ("uint", int.to_string(), None),
+        CovenantArg::OutputField(field) => ("field", field.to_string(), None),
+        CovenantArg::OutputFields(fields) => (
+            "fields",
+            fields.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "),
+            None,
+        ),
+        CovenantArg::Bytes(bytes) => ("bytes", bytes.to_hex(), None),
+    };
+    Ok(CovenantNode::Argument {
+        arg_type: arg_type.to_string(),
+        value,
+        covenant,
+    })
+}
+
+fn next_node(tokens: &mut Iter<'_, CovenantToken>) -> Result<CovenantNode, String> {
+    match tokens.next() {
+        Some(CovenantToken::Filter(filter)) => {
+            let (name, arity) = filter_name_and_arity(filter);
+            let children = (0..arity)
+                .map(|_| next_node(tokens))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("{name}: {e}"))?;
+            Ok(CovenantNode::Filter {
+                name: name.to_string(),
+                children,
+            })
+        },
+        Some(CovenantToken::Arg(arg)) => argument_node(arg),
+        None => Err("Unexpected end of covenant".to_string()),
+    }
+}
+
+/// Builds the tree of a covenant, which starts with a single root filter
+pub(crate) fn covenant_node(covenant: &Covenant) -> Result<Option<CovenantNode>, String> {
+    if covenant.is_empty() {
+        return Ok(None);
+    }
+    let mut tokens = covenant.tokens().iter();
+    let root = next_node(&mut tokens)?;
+    if !matches!(root, CovenantNode::Filter { .. }) {
+        return Err("A covenant must start with a filter".to_string());
+    }
+    if tokens.next().is_some() {
+        return Err("The covenant has tokens remaining after the root filter".to_string());
+    }
+    Ok(Some(root))
+}
+
+/// Writes a node in `covenant!` macro notation
+pub(crate) fn expression(node: &CovenantNode) -> String {
+    match node {
+        CovenantNode::Filter { name, children } => format!(
+            "{name}({})",
+            children.iter().map(expression).collect::<Vec<_>>().join(", ")
+        ),
+        CovenantNode::Argument { arg_type, covenant, .. } if arg_type == "covenant" => format!(
+            "@covenant_lit({})",
+            covenant.as_deref().map(expression).unwrap_or_default()
+        ),
+        CovenantNode::Argument { arg_type, value, .. } if arg_type == "field" => format!("@{value}"),
+        CovenantNode::Argument { arg_type, value, .. } if arg_type == "fields" => {
+            let fields = value
+                .split(", ")
+                .filter(|field| !field.is_empty())
+                .map(|field| format!("@{field}"))
+                .collect::<Vec<_>>();
+            format!("@fields({})", fields.join(", "))
+        },
+        CovenantNode::Argument { arg_type, value, .. } => format!("@{arg_type}({value})"),
+    }
+}
+
+/// Decodes the bytes of a covenant, as found in a transaction output's `covenant` field, into a tree of filters and
+/// arguments and a `covenant!` macro style expression, so that explorers and wallets can display what the covenant
+/// on an output means.
+#[wasm_bindgen]
+pub fn decode_covenant(mut bytes: &[u8]) -> DecodedCovenantResult {
+    let covenant = match Covenant::from_bytes(&mut bytes) {
+        Ok(val) => val,
+        Err(e) => return decoded_covenant_error(&format!("covenant: {e}")),
+    };
+    let ast = match covenant_node(&covenant) {
+        Ok(val) => val,
+        Err(e) => return decoded_covenant_error(&format!("covenant: {e}")),
+    };
+    DecodedCovenantResult {
+        expression: ast.as_ref().map(expression),
+        ast,
+        num_tokens: covenant.num_tokens(),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn covenant_round_trips(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let result = decode_covenant(&bytes);
+            if let Ok(covenant) = Covenant::from_bytes(&mut bytes.as_slice()) {
+                prop_assert!(result.error.is_none());
+                prop_assert_eq!(result.num_tokens, covenant.num_tokens());
+                let decoded = Covenant::from_bytes(&mut covenant.to_bytes().as_slice()).unwrap();
+                prop_assert_eq!(&decoded, &covenant);
+                let borsh_bytes = borsh::to_vec(&covenant).unwrap();
+                prop_assert_eq!(borsh::from_slice::<Covenant>(&borsh_bytes).unwrap(), covenant);
+            } else {
+                prop_assert!(result.error.is_some());
+            }
+        }
+    }
+}
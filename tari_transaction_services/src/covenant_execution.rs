@@ -0,0 +1,79 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_core::{
+    covenants::{Covenant, CovenantError},
+    transactions::transaction_components::{TransactionInput, TransactionOutput},
+};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A struct to hold the result of executing a covenant against a set of outputs
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct CovenantExecutionResult {
+    /// Whether the covenant is satisfied, i.e. it executed without error and matched at least one output
+    pub satisfied: Option<bool>,
+    /// The indexes of the outputs matched by the covenant, in ascending order
+    pub matching_indexes: Vec<usize>,
+    /// The covenant error, if the covenant is not satisfied
+    pub covenant_error: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn covenant_execution_error(error: &str) -> CovenantExecutionResult {
+    CovenantExecutionResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Executes a covenant (consensus encoded bytes, see `decode_covenant`) for the spend of `input_json` at
+/// `block_height` against the outputs in `outputs_json_array`, e.g. the outputs a dApp plans to create, and returns the
+/// indexes of the outputs that match. A covenant that matches no outputs, or that fails to execute, is not satisfied
+/// and its error is returned in `covenantError`, so that dApps can check their outputs before building a transaction.
+#[wasm_bindgen]
+pub fn execute_covenant(
+    covenant_bytes: &[u8],
+    input_json: &str,
+    outputs_json_array: &str,
+    block_height: u64,
+) -> CovenantExecutionResult {
+    let mut covenant_bytes = covenant_bytes;
+    let covenant = match Covenant::from_bytes(&mut covenant_bytes) {
+        Ok(val) => val,
+        Err(e) => return covenant_execution_error(&format!("covenant_bytes: {e}")),
+    };
+    let input: TransactionInput = match serde_json::from_str(input_json) {
+        Ok(val) => val,
+        Err(e) => return covenant_execution_error(&format!("input_json: {e}")),
+    };
+    let outputs: Vec<TransactionOutput> = match serde_json::from_str(outputs_json_array) {
+        Ok(val) => val,
+        Err(e) => return covenant_execution_error(&format!("outputs_json_array: {e}")),
+    };
+
+    match covenant.execute_matching(block_height, &input, &outputs) {
+        Ok(matching_indexes) if matching_indexes.is_empty() => CovenantExecutionResult {
+            satisfied: Some(false),
+            matching_indexes,
+            covenant_error: Some(CovenantError::NoMatchingOutputs.to_string()),
+            error: None,
+        },
+        Ok(matching_indexes) => CovenantExecutionResult {
+            satisfied: Some(true),
+            matching_indexes,
+            covenant_error: None,
+            error: None,
+        },
+        Err(e) => CovenantExecutionResult {
+            satisfied: Some(false),
+            matching_indexes: Vec::new(),
+            covenant_error: Some(e.to_string()),
+            error: None,
+        },
+    }
+}
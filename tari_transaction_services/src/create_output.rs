@@ -0,0 +1,409 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_comms::types::CommsDHKE;
+use tari_core::{
+    covenants::Covenant,
+    one_sided::{
+        diffie_hellman_stealth_domain_hasher,
+        shared_secret_to_output_encryption_key,
+        shared_secret_to_output_spending_key,
+        stealth_address_script_spending_key,
+    },
+    transactions::{
+        key_manager::{create_memory_db_key_manager, TransactionKeyManagerInterface},
+        tari_amount::MicroMinotari,
+        transaction_components::{
+            EncryptedData,
+            OutputFeatures,
+            TransactionOutput,
+            TransactionOutputVersion,
+            WalletOutputBuilder,
+        },
+    },
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{PublicKey as PK, SecretKey},
+    tari_utilities::hex::Hex,
+};
+use tari_key_manager::{cipher_seed::CipherSeed, KeyId, KeyManagerInterface};
+use tari_script::{inputs, script, TariScript};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    address::one_sided_recipient_keys,
+    factories::crypto_factories,
+    script_tools::script_hex,
+    size_budget::check_output_components,
+    value_promise::MinimumValuePromisePolicy,
+    versions::output_version,
+};
+
+/// The sender's keys for a one-sided payment. The sender offset and stealth nonce keys must never be reused, and are
+/// generated randomly when not provided.
+#[derive(Debug, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct SenderKeys {
+    /// The sender's wallet public key (hex value), which is committed to as the output's input data
+    pub wallet_pk: String,
+    /// The sender offset private key (hex value)
+    pub sender_offset_sk: Option<String>,
+    /// The stealth address nonce private key (hex value)
+    pub nonce_sk: Option<String>,
+}
+
+/// A struct to hold a newly created transaction output
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct CreatedOutputResult {
+    /// The hash of the output (hex value)
+    pub hash: Option<String>,
+    /// The output, serialized as JSON
+    pub output: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn created_output_error(error: &str) -> CreatedOutputResult {
+    CreatedOutputResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn private_key_or_random<R: RngCore + CryptoRng>(
+    key: Option<&str>,
+    name: &str,
+    rng: &mut R,
+) -> Result<PrivateKey, String> {
+    match key {
+        Some(key) => PrivateKey::from_hex(key).map_err(|e| format!("{name}: {e}")),
+        None => Ok(PrivateKey::random(rng)),
+    }
+}
+
+/// The stealth address script `PushPubKey(R) Drop PushPubKey(K)` for the nonce `r`, as per RFC-0203, and its script
+/// spending key `K`, which is derived from the Diffie-Hellman of the nonce with the recipient's view key and offsets
+/// the recipient's spend key
+fn stealth_address_script(nonce_sk: &PrivateKey, view_pk: &PublicKey, spend_pk: &PublicKey) -> (TariScript, PublicKey) {
+    let stealth_address_hasher = diffie_hellman_stealth_domain_hasher(nonce_sk, view_pk);
+    let script_spending_key = stealth_address_script_spending_key(&stealth_address_hasher, spend_pk);
+    let script = script!(
+        PushPubKey(Box::new(PublicKey::from_secret_key(nonce_sk)))
+        Drop
+        PushPubKey(Box::new(script_spending_key.clone()))
+    );
+    (script, script_spending_key)
+}
+
+/// Builds a one-sided payment output to the stealth address of the recipient's view and spend keys, as returned by
+/// `one_sided_recipient_keys`
+pub(crate) async fn build_one_sided_output(
+    value: MicroMinotari,
+    sender_keys: SenderKeys,
+    recipient: (PublicKey, PublicKey),
+    features: OutputFeatures,
+    promise_policy: MinimumValuePromisePolicy,
+    version: TransactionOutputVersion,
+) -> Result<TransactionOutput, String> {
+    let (view_pk, spend_pk) = recipient;
+    build_stealth_output(
+        value,
+        sender_keys,
+        &view_pk,
+        &spend_pk,
+        features,
+        promise_policy,
+        version,
+    )
+    .await
+    .map(|(output, _)| output)
+}
+
+/// Builds a one-sided payment output to the stealth address of the recipient's view and spend keys, returning the
+/// output and its commitment mask, which the sender knows from the Diffie-Hellman shared secret
+pub(crate) async fn build_stealth_output(
+    value: MicroMinotari,
+    sender_keys: SenderKeys,
+    view_pk: &PublicKey,
+    spend_pk: &PublicKey,
+    features: OutputFeatures,
+    promise_policy: MinimumValuePromisePolicy,
+    version: TransactionOutputVersion,
+) -> Result<(TransactionOutput, PrivateKey), String> {
+    build_stealth_output_with_rng(
+        value,
+        sender_keys,
+        view_pk,
+        spend_pk,
+        features,
+        promise_policy,
+        version,
+        &mut OsRng,
+    )
+    .await
+}
+
+/// As `build_stealth_output`, drawing the missing sender keys and every nonce of the output from `rng`
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn build_stealth_output_with_rng<R: RngCore + CryptoRng>(
+    value: MicroMinotari,
+    sender_keys: SenderKeys,
+    view_pk: &PublicKey,
+    spend_pk: &PublicKey,
+    features: OutputFeatures,
+    promise_policy: MinimumValuePromisePolicy,
+    version: TransactionOutputVersion,
+    rng: &mut R,
+) -> Result<(TransactionOutput, PrivateKey), String> {
+    let wallet_pk = PublicKey::from_hex(&sender_keys.wallet_pk).map_err(|e| format!("wallet_pk: {e}"))?;
+    let sender_offset_sk = private_key_or_random(sender_keys.sender_offset_sk.as_deref(), "sender_offset_sk", rng)?;
+    let nonce_sk = private_key_or_random(sender_keys.nonce_sk.as_deref(), "nonce_sk", rng)?;
+
+    let (script, _) = stealth_address_script(&nonce_sk, view_pk, spend_pk);
+    build_output_to_script_with_rng(
+        value,
+        &wallet_pk,
+        sender_offset_sk,
+        view_pk,
+        script,
+        features,
+        promise_policy,
+        version,
+        rng,
+    )
+    .await
+}
+
+/// Builds a one-sided payment output locked with `script`, whose value and mask are encrypted with the shared secret
+/// of the sender offset key and `dh_pk`, the key the recipient scans with, returning the output and its commitment
+/// mask
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn build_output_to_script(
+    value: MicroMinotari,
+    wallet_pk: &PublicKey,
+    sender_offset_sk: PrivateKey,
+    dh_pk: &PublicKey,
+    script: TariScript,
+    features: OutputFeatures,
+    promise_policy: MinimumValuePromisePolicy,
+    version: TransactionOutputVersion,
+) -> Result<(TransactionOutput, PrivateKey), String> {
+    build_output_to_script_with_rng(
+        value,
+        wallet_pk,
+        sender_offset_sk,
+        dh_pk,
+        script,
+        features,
+        promise_policy,
+        version,
+        &mut OsRng,
+    )
+    .await
+}
+
+/// As `build_output_to_script`, drawing the seed of the metadata signature nonces and the nonce of the encrypted data
+/// from `rng`, so that a seeded RNG reproduces the output byte-for-byte when its range proof is a `RevealedValue`
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn build_output_to_script_with_rng<R: RngCore + CryptoRng>(
+    value: MicroMinotari,
+    wallet_pk: &PublicKey,
+    sender_offset_sk: PrivateKey,
+    dh_pk: &PublicKey,
+    script: TariScript,
+    features: OutputFeatures,
+    promise_policy: MinimumValuePromisePolicy,
+    version: TransactionOutputVersion,
+    rng: &mut R,
+) -> Result<(TransactionOutput, PrivateKey), String> {
+    let minimum_value_promise = promise_policy.choose(value, features.range_proof_type)?;
+    let covenant = Covenant::default();
+    check_output_components(&script, &covenant, &features)?;
+
+    let key_manager = create_memory_db_key_manager(CipherSeed::new_with_rng(0, rng), crypto_factories())
+        .map_err(|e| e.to_string())?;
+    let sender_offset_key_id = key_manager
+        .import_key(sender_offset_sk)
+        .await
+        .map_err(|e| e.to_string())?;
+    let shared_secret = key_manager
+        .get_diffie_hellman_shared_secret(&sender_offset_key_id, dh_pk)
+        .await
+        .map_err(|e| e.to_string())?;
+    let spending_key = shared_secret_to_output_spending_key(&shared_secret).map_err(|e| e.to_string())?;
+    let encryption_key = shared_secret_to_output_encryption_key(&shared_secret).map_err(|e| e.to_string())?;
+    let spending_key_id = key_manager
+        .import_key(spending_key.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    let commitment = crypto_factories()
+        .commitment
+        .commit_value(&spending_key, value.as_u64());
+    let encrypted_data = EncryptedData::encrypt_data_with_rng(&encryption_key, &commitment, value, &spending_key, rng)
+        .map_err(|e| e.to_string())?;
+
+    let wallet_output = WalletOutputBuilder::new(value, spending_key_id)
+        .with_version(version)
+        .with_features(features)
+        .with_script(script)
+        .with_encrypted_data(encrypted_data)
+        .with_input_data(inputs!(wallet_pk.clone()))
+        .with_covenant(covenant)
+        .with_minimum_value_promise(minimum_value_promise)
+        .with_script_key(KeyId::Zero)
+        .sign_as_sender_and_receiver(&key_manager, &sender_offset_key_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .try_build(&key_manager)
+        .await
+        .map_err(|e| e.to_string())?;
+    let output = wallet_output
+        .to_transaction_output(&key_manager)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((output, spending_key))
+}
+
+/// Creates a one-sided payment output of `value` to the recipient's stealth address, entirely client-side. The output
+/// script, encrypted data, metadata signature and range proof are produced here, so that web wallets do not need to
+/// round-trip through a backend.
+/// * `sender_keys` - the sender's keys as JSON, e.g. `{ "walletPk": "<hex>" }`
+/// * `recipient_address` - the recipient's Tari address (base58 or emoji), which must accept one-sided payments, or
+///   public key (hex value)
+/// * `features_json` - the output features as JSON, or default output features if not provided
+/// * `promise_policy` - the minimum value promise policy (see `choose_minimum_value_promise`), or the default policy
+///   for the output's range proof type if not provided
+/// * `output_version` - the transaction output version (see `max_supported_versions`), or the current version if not
+///   provided
+#[wasm_bindgen]
+pub async fn create_one_sided_output(
+    value: u64,
+    sender_keys: String,
+    recipient_address: String,
+    features_json: Option<String>,
+    promise_policy: Option<MinimumValuePromisePolicy>,
+    output_version: Option<u8>,
+) -> CreatedOutputResult {
+    let sender_keys: SenderKeys = match serde_json::from_str(&sender_keys) {
+        Ok(val) => val,
+        Err(e) => return created_output_error(&format!("sender_keys: {e}")),
+    };
+    let recipient = match one_sided_recipient_keys(&recipient_address) {
+        Ok(val) => val,
+        Err(e) => return created_output_error(&format!("recipient_address: {e}")),
+    };
+    let features = match features_json {
+        Some(features) => match serde_json::from_str(&features) {
+            Ok(val) => val,
+            Err(e) => return created_output_error(&format!("features_json: {e}")),
+        },
+        None => OutputFeatures::default(),
+    };
+    let promise_policy =
+        promise_policy.unwrap_or_else(|| MinimumValuePromisePolicy::default_for(features.range_proof_type));
+    let version = match output_version(output_version) {
+        Ok(val) => val,
+        Err(e) => return created_output_error(&format!("output_version: {e}")),
+    };
+
+    let output =
+        match build_one_sided_output(value.into(), sender_keys, recipient, features, promise_policy, version).await {
+            Ok(val) => val,
+            Err(e) => return created_output_error(&format!("Could not create output: {e}")),
+        };
+    match serde_json::to_string(&output) {
+        Ok(json) => CreatedOutputResult {
+            hash: Some(output.hash().to_hex()),
+            output: Some(json),
+            error: None,
+        },
+        Err(e) => created_output_error(&format!("Could not serialize output: {e}")),
+    }
+}
+
+/// A struct to hold the sender's key material for a payment to a stealth address
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct StealthPaymentKeysResult {
+    /// The output script (hex value)
+    pub script: Option<String>,
+    /// The ephemeral stealth address nonce private key (hex value)
+    pub nonce_sk: Option<String>,
+    /// The stealth address nonce public key `R`, as pushed by the script (hex value)
+    pub nonce_pk: Option<String>,
+    /// The script spending key `K`, as pushed by the script (hex value)
+    pub script_public_key: Option<String>,
+    /// The ephemeral sender offset private key (hex value)
+    pub sender_offset_sk: Option<String>,
+    /// The sender offset public key, to be set on the output (hex value)
+    pub sender_offset_pk: Option<String>,
+    /// The Diffie-Hellman shared secret of the sender offset key and the recipient's view key (hex value)
+    pub shared_secret: Option<String>,
+    /// The commitment mask derived from the shared secret (hex value)
+    pub spending_key: Option<String>,
+    /// The key that encrypts the output's value and mask, derived from the shared secret (hex value)
+    pub encryption_key: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn stealth_payment_keys_error(error: &str) -> StealthPaymentKeysResult {
+    StealthPaymentKeysResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Derives, from the sender side, the key material of a one-sided payment to a stealth address as per RFC-0203, for
+/// JS senders that construct stealth outputs themselves: a fresh nonce and sender offset key, the stealth address
+/// script, and the shared secret with the keys derived from it. Outputs built from this material are recovered by
+/// the scanner of the recipient wallet, which uses the same key as view and spend key.
+/// * `recipient_view_pk` - the recipient's view public key (hex value)
+/// * `recipient_spend_pk` - the recipient's spend public key (hex value)
+#[wasm_bindgen]
+pub fn derive_stealth_payment_keys(recipient_view_pk: &str, recipient_spend_pk: &str) -> StealthPaymentKeysResult {
+    let view_pk = match PublicKey::from_hex(recipient_view_pk) {
+        Ok(val) => val,
+        Err(e) => return stealth_payment_keys_error(&format!("recipient_view_pk: {e}")),
+    };
+    let spend_pk = match PublicKey::from_hex(recipient_spend_pk) {
+        Ok(val) => val,
+        Err(e) => return stealth_payment_keys_error(&format!("recipient_spend_pk: {e}")),
+    };
+    let nonce_sk = PrivateKey::random(&mut OsRng);
+    let sender_offset_sk = PrivateKey::random(&mut OsRng);
+    let (script, script_public_key) = stealth_address_script(&nonce_sk, &view_pk, &spend_pk);
+    let shared_secret = CommsDHKE::new(&sender_offset_sk, &view_pk);
+    let spending_key = match shared_secret_to_output_spending_key(&shared_secret) {
+        Ok(val) => val,
+        Err(e) => return stealth_payment_keys_error(&format!("Could not derive the spending key: {e}")),
+    };
+    let encryption_key = match shared_secret_to_output_encryption_key(&shared_secret) {
+        Ok(val) => val,
+        Err(e) => return stealth_payment_keys_error(&format!("Could not derive the encryption key: {e}")),
+    };
+    let script = match script_hex(&script) {
+        Ok(val) => val,
+        Err(e) => return stealth_payment_keys_error(&format!("Could not serialize script: {e}")),
+    };
+    StealthPaymentKeysResult {
+        script: Some(script),
+        nonce_sk: Some(nonce_sk.to_hex()),
+        nonce_pk: Some(PublicKey::from_secret_key(&nonce_sk).to_hex()),
+        script_public_key: Some(script_public_key.to_hex()),
+        sender_offset_sk: Some(sender_offset_sk.to_hex()),
+        sender_offset_pk: Some(PublicKey::from_secret_key(&sender_offset_sk).to_hex()),
+        shared_secret: Some(shared_secret.as_bytes().to_hex()),
+        spending_key: Some(spending_key.to_hex()),
+        encryption_key: Some(encryption_key.to_hex()),
+        error: None,
+    }
+}
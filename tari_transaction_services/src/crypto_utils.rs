@@ -0,0 +1,78 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Basic Ristretto operations used throughout the protocol, on hex encoded keys and commitments
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{Commitment, CommitmentFactory, PrivateKey, PublicKey};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::PublicKey as PK, tari_utilities::hex::Hex};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A struct to hold the result of a curve operation
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct CryptoResult {
+    /// The resulting scalar, public key or commitment (hex value)
+    pub value: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn crypto_result<T: Hex>(value: Result<T, String>) -> CryptoResult {
+    match value {
+        Ok(value) => CryptoResult {
+            value: Some(value.to_hex()),
+            error: None,
+        },
+        Err(e) => CryptoResult {
+            value: None,
+            error: Some(e),
+        },
+    }
+}
+
+fn parse<T: Hex>(value: &str, name: &str) -> Result<T, String> {
+    T::from_hex(value).map_err(|e| format!("{name}: {e}"))
+}
+
+/// Creates the Pedersen commitment `mask·G + value·H` to `value` with the blinding factor `mask` (hex value)
+#[wasm_bindgen]
+pub fn commit(value: u64, mask: &str) -> CryptoResult {
+    crypto_result(parse::<PrivateKey>(mask, "mask").map(|mask| CommitmentFactory::default().commit_value(&mask, value)))
+}
+
+/// Derives the public key of a private key (hex value)
+#[wasm_bindgen]
+pub fn pk_from_sk(sk: &str) -> CryptoResult {
+    crypto_result(parse::<PrivateKey>(sk, "sk").map(|sk| PublicKey::from_secret_key(&sk)))
+}
+
+/// Adds two commitments (hex values), which commits to the sum of their values with the sum of their masks
+#[wasm_bindgen]
+pub fn add_commitments(a: &str, b: &str) -> CryptoResult {
+    // Commitments only implement addition by reference
+    crypto_result(parse::<Commitment>(a, "a").and_then(|a| {
+        let b = parse::<Commitment>(b, "b")?;
+        Ok(&a + &b)
+    }))
+}
+
+/// Adds two public keys (hex values)
+#[wasm_bindgen]
+pub fn add_public_keys(a: &str, b: &str) -> CryptoResult {
+    crypto_result(parse::<PublicKey>(a, "a").and_then(|a| Ok(a + &parse::<PublicKey>(b, "b")?)))
+}
+
+/// Adds two scalars (hex values) modulo the group order
+#[wasm_bindgen]
+pub fn scalar_add(a: &str, b: &str) -> CryptoResult {
+    crypto_result(parse::<PrivateKey>(a, "a").and_then(|a| Ok(a + &parse::<PrivateKey>(b, "b")?)))
+}
+
+/// Multiplies two scalars (hex values) modulo the group order
+#[wasm_bindgen]
+pub fn scalar_mul(a: &str, b: &str) -> CryptoResult {
+    crypto_result(parse::<PrivateKey>(a, "a").and_then(|a| Ok(a * &parse::<PrivateKey>(b, "b")?)))
+}
@@ -0,0 +1,603 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A tamper-evident log of every change to the wallet's outputs and balance. Each entry is chained to the one before it
+//! with a hash keyed by an audit key derived from the wallet secret key, so that only holders of the audit key can
+//! produce or verify a log: the audit key can be handed to an auditor without giving away the wallet key.
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+};
+
+use blake2::Blake2b;
+use digest::consts::U32;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{FixedHash, PrivateKey};
+use tari_crypto::{
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    tari_utilities::{
+        hex::{from_hex, Hex},
+        ByteArray,
+    },
+};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+use zeroize::Zeroizing;
+
+hash_domain!(CustodyLogHashDomain, "com.tari.transaction_services.custody_log", 1);
+
+type CustodyLogHasher = DomainSeparatedHasher<Blake2b<U32>, CustodyLogHashDomain>;
+
+/// The kind of mutation recorded in a custody log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+pub enum CustodyEventKind {
+    /// An output was found to belong to the wallet, adding its value to the balance
+    OutputRecovered,
+    /// A recovered output was spent, removing its value from the balance
+    OutputSpent,
+    /// A recovered output was locked for a pending spend; the balance is unchanged
+    OutputLocked,
+    /// A locked output was released without being spent; the balance is unchanged
+    OutputUnlocked,
+    /// A recovered output was forgotten by a rewind past the block it was mined in, removing its value from the
+    /// balance
+    RecoveryRewound,
+    /// The spend of an output was forgotten by a rewind past the block it was spent in, adding its value back
+    SpendRewound,
+}
+
+impl CustodyEventKind {
+    fn as_byte(self) -> u8 {
+        match self {
+            CustodyEventKind::OutputRecovered => 0,
+            CustodyEventKind::OutputSpent => 1,
+            CustodyEventKind::OutputLocked => 2,
+            CustodyEventKind::OutputUnlocked => 3,
+            CustodyEventKind::RecoveryRewound => 4,
+            CustodyEventKind::SpendRewound => 5,
+        }
+    }
+
+    /// The balance after a mutation of an output of `value`, or `None` if it overflows
+    fn apply_to_balance(self, balance: u64, value: u64) -> Option<u64> {
+        match self {
+            CustodyEventKind::OutputRecovered | CustodyEventKind::SpendRewound => balance.checked_add(value),
+            CustodyEventKind::OutputSpent | CustodyEventKind::RecoveryRewound => balance.checked_sub(value),
+            CustodyEventKind::OutputLocked | CustodyEventKind::OutputUnlocked => Some(balance),
+        }
+    }
+}
+
+/// A single mutation of the wallet's outputs and balance. `entry_hash` is `H("entry", audit_key, prev_hash, index,
+/// kind, output_hash, value, balance, start_height, end_height)`, so that every entry commits to all entries before it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct CustodyLogEntry {
+    /// The position of the entry in the log, starting at 0
+    pub index: u64,
+    /// The kind of mutation
+    pub kind: CustodyEventKind,
+    /// The hash of the output (hex value)
+    pub output_hash: String,
+    /// The value of the output in µT
+    pub value: u64,
+    /// The balance in µT after the mutation
+    pub balance: u64,
+    /// The first block height of the range the mutation was made for, if known, e.g. the range an output was pushed
+    /// for with `push_outputs_for_range`, or the blocks a rewind removed
+    pub start_height: Option<u64>,
+    /// The last block height of the range the mutation was made for, if known
+    pub end_height: Option<u64>,
+    /// The hash of the previous entry, or zeros for the first entry (hex value)
+    pub prev_hash: String,
+    /// The hash of this entry (hex value)
+    pub entry_hash: String,
+}
+
+/// An exported custody log
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct CustodyLogExport {
+    /// The entries, in the order they were recorded
+    pub entries: Vec<CustodyLogEntry>,
+    /// The balance in µT after the last entry
+    pub balance: u64,
+    /// The hash of the last entry, or zeros if the log is empty (hex value)
+    pub head_hash: String,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn entry_hash(
+    audit_key: &[u8],
+    prev_hash: &FixedHash,
+    index: u64,
+    kind: CustodyEventKind,
+    output_hash: &FixedHash,
+    value: u64,
+    balance: u64,
+    start_height: Option<u64>,
+    end_height: Option<u64>,
+) -> FixedHash {
+    let mut hasher = CustodyLogHasher::new_with_label("entry");
+    hasher.update(audit_key);
+    hasher.update(prev_hash.as_slice());
+    hasher.update(&index.to_le_bytes());
+    hasher.update(&[kind.as_byte()]);
+    hasher.update(output_hash.as_slice());
+    hasher.update(&value.to_le_bytes());
+    hasher.update(&balance.to_le_bytes());
+    for height in &[start_height, end_height] {
+        match height {
+            Some(height) => {
+                hasher.update(&[1]);
+                hasher.update(&height.to_le_bytes());
+            },
+            None => hasher.update(&[0]),
+        }
+    }
+    // A 32 byte digest always converts
+    FixedHash::try_from(hasher.finalize().as_ref()).unwrap_or_default()
+}
+
+/// Derives the audit key of a custody log from the wallet secret key
+pub(crate) fn custody_audit_key(wallet_sk: &PrivateKey) -> Zeroizing<Vec<u8>> {
+    let hash = CustodyLogHasher::new_with_label("audit_key")
+        .chain(wallet_sk.as_bytes())
+        .finalize();
+    Zeroizing::new(hash.as_ref().to_vec())
+}
+
+/// The outputs a custody log accounts for, to check that every mutation follows from the ones before it, e.g. that an
+/// output is only recovered once and only spent while it is unspent
+#[derive(Clone, Default)]
+struct CustodyState {
+    // The values of the recovered outputs that are not spent, by output hash
+    unspent: HashMap<FixedHash, u64>,
+    // The values of the recovered outputs that are spent, by output hash
+    spent: HashMap<FixedHash, u64>,
+    locked: HashSet<FixedHash>,
+}
+
+impl CustodyState {
+    /// Applies a mutation, or returns why it does not follow from the current state
+    fn apply(&mut self, kind: CustodyEventKind, output_hash: &FixedHash, value: u64) -> Result<(), String> {
+        let check_value = |known: u64| {
+            if known == value {
+                Ok(())
+            } else {
+                Err(format!("value {value} does not match the recovered value {known}"))
+            }
+        };
+        match kind {
+            CustodyEventKind::OutputRecovered => {
+                if self.unspent.contains_key(output_hash) || self.spent.contains_key(output_hash) {
+                    return Err("the output was already recovered".to_string());
+                }
+                self.unspent.insert(*output_hash, value);
+            },
+            CustodyEventKind::OutputSpent | CustodyEventKind::RecoveryRewound => {
+                let known = self
+                    .unspent
+                    .get(output_hash)
+                    .copied()
+                    .ok_or("the output is not unspent")?;
+                check_value(known)?;
+                self.unspent.remove(output_hash);
+                self.locked.remove(output_hash);
+                if kind == CustodyEventKind::OutputSpent {
+                    self.spent.insert(*output_hash, value);
+                }
+            },
+            CustodyEventKind::OutputLocked => {
+                check_value(
+                    self.unspent
+                        .get(output_hash)
+                        .copied()
+                        .ok_or("the output is not unspent")?,
+                )?;
+                if !self.locked.insert(*output_hash) {
+                    return Err("the output is already locked".to_string());
+                }
+            },
+            CustodyEventKind::OutputUnlocked => {
+                check_value(
+                    self.unspent
+                        .get(output_hash)
+                        .copied()
+                        .ok_or("the output is not unspent")?,
+                )?;
+                if !self.locked.remove(output_hash) {
+                    return Err("the output is not locked".to_string());
+                }
+            },
+            CustodyEventKind::SpendRewound => {
+                check_value(self.spent.get(output_hash).copied().ok_or("the output is not spent")?)?;
+                self.spent.remove(output_hash);
+                self.unspent.insert(*output_hash, value);
+            },
+        }
+        Ok(())
+    }
+}
+
+/// A tamper-evident, hash-chained log of every mutation to the wallet's outputs and balance. A mutation that does not
+/// follow from the log, e.g. an output recovered again when a range is pushed again, is not recorded.
+#[derive(Clone)]
+pub(crate) struct CustodyLog {
+    audit_key: Zeroizing<Vec<u8>>,
+    entries: Vec<CustodyLogEntry>,
+    balance: u64,
+    head_hash: FixedHash,
+    state: CustodyState,
+}
+
+impl CustodyLog {
+    pub fn new(wallet_sk: &PrivateKey) -> Self {
+        Self {
+            audit_key: custody_audit_key(wallet_sk),
+            entries: Vec::new(),
+            balance: 0,
+            head_hash: FixedHash::zero(),
+            state: CustodyState::default(),
+        }
+    }
+
+    /// Restores an exported log, verifying it with the audit key of `wallet_sk`
+    pub fn restore(wallet_sk: &PrivateKey, log: CustodyLogExport) -> Result<Self, String> {
+        let mut custody = Self::new(wallet_sk);
+        let (head_hash, state) = verify_log(&custody.audit_key, &log).map_err(|(_, e)| e)?;
+        custody.head_hash = head_hash;
+        custody.state = state;
+        custody.balance = log.balance;
+        custody.entries = log.entries;
+        Ok(custody)
+    }
+
+    /// The audit key to verify the log with (hex value)
+    pub fn audit_key(&self) -> String {
+        self.audit_key.to_hex()
+    }
+
+    /// Whether a recovered output is locked
+    pub fn is_locked(&self, output_hash: &FixedHash) -> bool {
+        self.state.locked.contains(output_hash)
+    }
+
+    /// The value of a recovered output that is not spent, if the log accounts for it
+    pub fn unspent_value(&self, output_hash: &FixedHash) -> Option<u64> {
+        self.state.unspent.get(output_hash).copied()
+    }
+
+    /// The value of a recovered output that is spent, if the log accounts for it
+    pub fn spent_value(&self, output_hash: &FixedHash) -> Option<u64> {
+        self.state.spent.get(output_hash).copied()
+    }
+
+    /// Records a mutation of an output, made for the block range `range` if known, returning whether it was recorded
+    pub fn record(
+        &mut self,
+        kind: CustodyEventKind,
+        output_hash: &FixedHash,
+        value: u64,
+        range: Option<(u64, u64)>,
+    ) -> bool {
+        let balance = match kind.apply_to_balance(self.balance, value) {
+            Some(balance) => balance,
+            None => return false,
+        };
+        if self.state.apply(kind, output_hash, value).is_err() {
+            return false;
+        }
+        let index = self.entries.len() as u64;
+        let (start_height, end_height) = (range.map(|r| r.0), range.map(|r| r.1));
+        let hash = entry_hash(
+            &self.audit_key,
+            &self.head_hash,
+            index,
+            kind,
+            output_hash,
+            value,
+            balance,
+            start_height,
+            end_height,
+        );
+        self.entries.push(CustodyLogEntry {
+            index,
+            kind,
+            output_hash: output_hash.to_hex(),
+            value,
+            balance,
+            start_height,
+            end_height,
+            prev_hash: self.head_hash.to_hex(),
+            entry_hash: hash.to_hex(),
+        });
+        self.balance = balance;
+        self.head_hash = hash;
+        true
+    }
+
+    pub fn export(&self) -> CustodyLogExport {
+        CustodyLogExport {
+            entries: self.entries.clone(),
+            balance: self.balance,
+            head_hash: self.head_hash.to_hex(),
+        }
+    }
+}
+
+/// The outcome of verifying an exported custody log
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct CustodyLogVerificationResult {
+    /// Whether every entry links to the one before it, its hash and balance are correct, it follows from the entries
+    /// before it, and the log's head hash and balance match the last entry
+    pub valid: bool,
+    /// The number of entries verified
+    pub entries: usize,
+    /// The balance in µT after the last entry
+    pub balance: Option<u64>,
+    /// The index of the first entry that failed verification
+    pub invalid_index: Option<u64>,
+    /// An error message in case of an error or failed verification
+    pub error: Option<String>,
+}
+
+fn custody_log_error(invalid_index: Option<u64>, error: &str) -> CustodyLogVerificationResult {
+    CustodyLogVerificationResult {
+        invalid_index,
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn verify_entry(
+    audit_key: &[u8],
+    entry: &CustodyLogEntry,
+    index: u64,
+    prev_hash: &FixedHash,
+    balance: u64,
+    state: &mut CustodyState,
+) -> Result<FixedHash, String> {
+    if entry.index != index {
+        return Err(format!("expected index {index}, found {}", entry.index));
+    }
+    if FixedHash::from_hex(&entry.prev_hash).map_err(|e| format!("prev_hash: {e}"))? != *prev_hash {
+        return Err("does not link to the previous entry".to_string());
+    }
+    if entry.kind.apply_to_balance(balance, entry.value) != Some(entry.balance) {
+        return Err(format!(
+            "balance {} does not follow from the previous balance {balance} and value {}",
+            entry.balance, entry.value
+        ));
+    }
+    let output_hash = FixedHash::from_hex(&entry.output_hash).map_err(|e| format!("output_hash: {e}"))?;
+    let hash = entry_hash(
+        audit_key,
+        prev_hash,
+        index,
+        entry.kind,
+        &output_hash,
+        entry.value,
+        entry.balance,
+        entry.start_height,
+        entry.end_height,
+    );
+    if hash.to_hex() != entry.entry_hash.to_lowercase() {
+        return Err("entry hash does not match its contents and the audit key".to_string());
+    }
+    state.apply(entry.kind, &output_hash, entry.value)?;
+    Ok(hash)
+}
+
+/// Verifies every entry of a log from the genesis entry on, returning its head hash and the outputs it accounts for, or
+/// the index of the first invalid entry, if any, and why it is invalid
+fn verify_log(audit_key: &[u8], log: &CustodyLogExport) -> Result<(FixedHash, CustodyState), (Option<u64>, String)> {
+    let mut prev_hash = FixedHash::zero();
+    let mut balance = 0u64;
+    let mut state = CustodyState::default();
+    for (index, entry) in (0u64..).zip(log.entries.iter()) {
+        prev_hash = verify_entry(audit_key, entry, index, &prev_hash, balance, &mut state)
+            .map_err(|e| (Some(index), format!("entry {index}: {e}")))?;
+        balance = entry.balance;
+    }
+    if prev_hash.to_hex() != log.head_hash.to_lowercase() || balance != log.balance {
+        return Err((
+            None,
+            "The head hash or balance does not match the last entry".to_string(),
+        ));
+    }
+    Ok((prev_hash, state))
+}
+
+/// Verifies a custody log, as exported by `ScanSession.custody_log` or `WalletSyncState.custody_log`, recomputing every
+/// entry hash and balance from the genesis entry on with the audit key, so that auditors can check that the log has
+/// not been altered, reordered or truncated anywhere but at its end. Comparing `head_hash` with a previously exported
+/// log also detects truncation.
+/// * `audit_key` - the audit key of the session that exported the log, as returned by its `custody_audit_key` (hex
+///   value)
+#[wasm_bindgen]
+pub fn verify_custody_log(log: &str, audit_key: &str) -> CustodyLogVerificationResult {
+    let log: CustodyLogExport = match serde_json::from_str(log) {
+        Ok(val) => val,
+        Err(e) => return custody_log_error(None, &format!("log: {e}")),
+    };
+    let audit_key = match from_hex(audit_key) {
+        Ok(val) => Zeroizing::new(val),
+        Err(e) => return custody_log_error(None, &format!("audit_key: {e}")),
+    };
+    match verify_log(&audit_key, &log) {
+        Ok(_) => CustodyLogVerificationResult {
+            valid: true,
+            entries: log.entries.len(),
+            balance: Some(log.balance),
+            invalid_index: None,
+            error: None,
+        },
+        Err((invalid_index, e)) => custody_log_error(invalid_index, &e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn wallet_sk() -> PrivateKey {
+        PrivateKey::from(5)
+    }
+
+    fn output_hash(n: u8) -> FixedHash {
+        FixedHash::try_from(&[n; 32][..]).unwrap()
+    }
+
+    // A log of an output recovered, locked, unlocked and spent, the spend rewound, and a second output recovered and
+    // rewound
+    fn custody_log() -> CustodyLog {
+        let mut log = CustodyLog::new(&wallet_sk());
+        let (first, second) = (output_hash(1), output_hash(2));
+        assert!(log.record(CustodyEventKind::OutputRecovered, &first, 1_000, Some((10, 10))));
+        assert!(log.record(CustodyEventKind::OutputRecovered, &second, 500, Some((11, 11))));
+        assert!(log.record(CustodyEventKind::OutputLocked, &first, 1_000, None));
+        assert!(log.record(CustodyEventKind::OutputUnlocked, &first, 1_000, None));
+        assert!(log.record(CustodyEventKind::OutputSpent, &first, 1_000, Some((12, 12))));
+        assert!(log.record(CustodyEventKind::SpendRewound, &first, 1_000, Some((12, 13))));
+        assert!(log.record(CustodyEventKind::RecoveryRewound, &second, 500, Some((11, 13))));
+        log
+    }
+
+    fn verify(log: &CustodyLogExport, audit_key: &str) -> CustodyLogVerificationResult {
+        verify_custody_log(&serde_json::to_string(log).unwrap(), audit_key)
+    }
+
+    fn assert_invalid_at(log: &CustodyLogExport, invalid_index: Option<u64>) -> String {
+        let result = verify(log, &custody_audit_key(&wallet_sk()).to_hex());
+        assert!(!result.valid);
+        assert_eq!(result.invalid_index, invalid_index, "{:?}", result.error);
+        result.error.unwrap()
+    }
+
+    #[test]
+    fn appended_entries_verify() {
+        let log = custody_log();
+        let export = log.export();
+        assert_eq!(
+            export.entries.iter().map(|entry| entry.balance).collect::<Vec<_>>(),
+            vec![1_000, 1_500, 1_500, 1_500, 500, 1_500, 1_000]
+        );
+        assert_eq!(export.balance, 1_000);
+        assert_eq!(export.head_hash, export.entries[6].entry_hash);
+        assert_eq!(export.entries[0].prev_hash, FixedHash::zero().to_hex());
+        for pair in export.entries.windows(2) {
+            assert_eq!(pair[1].prev_hash, pair[0].entry_hash);
+        }
+        assert_eq!(log.unspent_value(&output_hash(1)), Some(1_000));
+        assert_eq!(log.unspent_value(&output_hash(2)), None);
+        assert!(!log.is_locked(&output_hash(1)));
+
+        let result = verify(&export, &log.audit_key());
+        assert!(result.valid, "{:?}", result.error);
+        assert_eq!(result.entries, 7);
+        assert_eq!(result.balance, Some(1_000));
+        assert_eq!(result.invalid_index, None);
+
+        // A restored log carries on from the head of the chain
+        let mut restored = CustodyLog::restore(&wallet_sk(), export.clone()).unwrap();
+        assert_eq!(restored.export(), export);
+        assert!(restored.record(CustodyEventKind::OutputSpent, &output_hash(1), 1_000, Some((14, 14))));
+        let export = restored.export();
+        assert_eq!(export.entries[7].prev_hash, export.entries[6].entry_hash);
+        assert!(verify(&export, &restored.audit_key()).valid);
+    }
+
+    #[test]
+    fn mutations_that_do_not_follow_from_the_log_are_not_recorded() {
+        let mut log = custody_log();
+        assert!(!log.record(CustodyEventKind::OutputRecovered, &output_hash(1), 1_000, None));
+        assert!(!log.record(CustodyEventKind::OutputSpent, &output_hash(2), 500, None));
+        assert!(!log.record(CustodyEventKind::OutputSpent, &output_hash(1), 999, None));
+        assert!(!log.record(CustodyEventKind::OutputUnlocked, &output_hash(1), 1_000, None));
+        assert!(!log.record(CustodyEventKind::SpendRewound, &output_hash(1), 1_000, None));
+        assert!(!log.record(CustodyEventKind::OutputRecovered, &output_hash(3), u64::MAX, None));
+        assert_eq!(log.export().entries.len(), 7);
+        assert!(verify(&log.export(), &log.audit_key()).valid);
+    }
+
+    #[test]
+    fn tampered_entries_are_detected() {
+        let export = custody_log().export();
+
+        let mut tampered = export.clone();
+        tampered.entries[1].value += 1;
+        tampered.entries[1].balance += 1;
+        let error = assert_invalid_at(&tampered, Some(1));
+        assert!(error.contains("entry hash does not match"), "{error}");
+
+        let mut tampered = export.clone();
+        tampered.entries[2].balance = 0;
+        let error = assert_invalid_at(&tampered, Some(2));
+        assert!(error.contains("does not follow"), "{error}");
+
+        let mut tampered = export.clone();
+        tampered.entries[3].output_hash = output_hash(3).to_hex();
+        assert_invalid_at(&tampered, Some(3));
+
+        let mut tampered = export.clone();
+        tampered.entries[4].end_height = Some(99);
+        assert_invalid_at(&tampered, Some(4));
+
+        let mut tampered = export.clone();
+        tampered.entries[5].kind = CustodyEventKind::OutputRecovered;
+        assert_invalid_at(&tampered, Some(5));
+
+        let mut tampered = export.clone();
+        tampered.head_hash = tampered.entries[5].entry_hash.clone();
+        assert_invalid_at(&tampered, None);
+
+        let mut tampered = export;
+        tampered.balance += 1;
+        assert_invalid_at(&tampered, None);
+    }
+
+    #[test]
+    fn reordered_and_removed_entries_are_detected() {
+        let export = custody_log().export();
+
+        let mut reordered = export.clone();
+        reordered.entries.swap(2, 3);
+        let error = assert_invalid_at(&reordered, Some(2));
+        assert!(error.contains("expected index 2"), "{error}");
+        reordered.entries[2].index = 2;
+        reordered.entries[3].index = 3;
+        let error = assert_invalid_at(&reordered, Some(2));
+        assert!(error.contains("does not link"), "{error}");
+
+        let mut removed = export.clone();
+        removed.entries.remove(0);
+        assert_invalid_at(&removed, Some(0));
+
+        // Truncating the end is only detected by the head hash
+        let mut truncated = export;
+        truncated.entries.pop();
+        assert_invalid_at(&truncated, None);
+    }
+
+    #[test]
+    fn wrong_audit_key_is_rejected() {
+        let export = custody_log().export();
+        let other_key = custody_audit_key(&PrivateKey::from(7)).to_hex();
+        let result = verify(&export, &other_key);
+        assert!(!result.valid);
+        assert_eq!(result.invalid_index, Some(0));
+        assert!(result.error.unwrap().contains("audit key"));
+        assert!(CustodyLog::restore(&PrivateKey::from(7), export.clone()).is_err());
+
+        let result = verify(&export, "not hex");
+        assert!(!result.valid);
+        assert!(result.error.unwrap().starts_with("audit_key: "));
+        let result = verify_custody_log("{}", &other_key);
+        assert!(result.error.unwrap().starts_with("log: "));
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::PublicKey;
+use tari_core::transactions::{tari_amount::MicroMinotari, transaction_components::TransactionOutput};
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_script::Opcode;
+use tsify::Tsify;
+
+use crate::error::{ScanErrorCode, WasmError};
+
+/// A rule for classifying deposits during scanning. A recovered output is tagged with `tag` if it meets every
+/// condition given in the rule; a rule without conditions tags every recovered output.
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositRule {
+    /// The tag to attach to matching outputs, e.g. an internal account id
+    pub tag: String,
+    /// The minimum value of a matching output
+    pub min_value: Option<u64>,
+    /// The maximum value of a matching output
+    pub max_value: Option<u64>,
+    /// The sender offset public keys of matching outputs (hex values), or any if empty
+    #[serde(default)]
+    pub sender_offset_public_keys: Vec<String>,
+    /// The script public keys, i.e. the one-sided or stealth address keys, of matching outputs (hex values), or any if
+    /// empty
+    #[serde(default)]
+    pub script_public_keys: Vec<String>,
+}
+
+struct ParsedRule {
+    tag: String,
+    min_value: Option<MicroMinotari>,
+    max_value: Option<MicroMinotari>,
+    sender_offset_public_keys: Vec<PublicKey>,
+    script_public_keys: Vec<PublicKey>,
+}
+
+impl ParsedRule {
+    fn matches(&self, output: &TransactionOutput, value: MicroMinotari) -> bool {
+        if self.min_value.map_or(false, |min_value| value < min_value) ||
+            self.max_value.map_or(false, |max_value| value > max_value)
+        {
+            return false;
+        }
+        if !self.sender_offset_public_keys.is_empty() &&
+            !self
+                .sender_offset_public_keys
+                .contains(&output.sender_offset_public_key)
+        {
+            return false;
+        }
+        if self.script_public_keys.is_empty() {
+            return true;
+        }
+        match output.script.as_slice().last() {
+            Some(Opcode::PushPubKey(script_public_key)) => self.script_public_keys.contains(script_public_key.as_ref()),
+            _ => false,
+        }
+    }
+}
+
+fn parse_public_keys(keys: &[String], name: &str) -> Result<Vec<PublicKey>, WasmError> {
+    keys.iter()
+        .map(|key| {
+            PublicKey::from_hex(key).map_err(|e| WasmError::new(ScanErrorCode::InvalidKey, format!("{name}: {e}")))
+        })
+        .collect()
+}
+
+/// The deposit rules evaluated for every output recovered by a scan
+#[derive(Default)]
+pub(crate) struct DepositRules {
+    rules: Vec<ParsedRule>,
+}
+
+impl DepositRules {
+    /// Parses a JSON array of [DepositRule]s
+    pub fn from_json(rules: &str) -> Result<Self, WasmError> {
+        let rules: Vec<DepositRule> = serde_json::from_str(rules)
+            .map_err(|e| WasmError::new(ScanErrorCode::DeserializeError, format!("deposit_rules: {e}")))?;
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                Ok(ParsedRule {
+                    sender_offset_public_keys: parse_public_keys(
+                        &rule.sender_offset_public_keys,
+                        "sender_offset_public_keys",
+                    )?,
+                    script_public_keys: parse_public_keys(&rule.script_public_keys, "script_public_keys")?,
+                    tag: rule.tag,
+                    min_value: rule.min_value.map(MicroMinotari::from),
+                    max_value: rule.max_value.map(MicroMinotari::from),
+                })
+            })
+            .collect::<Result<Vec<_>, WasmError>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Parses optional deposit rules, where no rules tag nothing
+    pub fn from_optional_json(rules: Option<&str>) -> Result<Self, WasmError> {
+        rules.map_or_else(|| Ok(Self::default()), Self::from_json)
+    }
+
+    /// The tags of all rules matched by a recovered output of `value`, in the order the rules were given
+    pub fn tags(&self, output: &TransactionOutput, value: MicroMinotari) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(output, value))
+            .map(|rule| rule.tag.clone())
+            .collect()
+    }
+}
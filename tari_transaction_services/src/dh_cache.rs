@@ -0,0 +1,92 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A least recently used cache of Diffie-Hellman shared secrets, keyed by the public key of the wallet's secret key
+//! and the output's sender offset public key. Outputs from the same sender often share their sender offset key, so a
+//! bulk scan can skip the scalar multiplication for all but the first of them.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use tari_common_types::types::PublicKey;
+use tari_comms::types::CommsDHKE;
+
+/// The wallet's public key and the sender offset public key a shared secret was computed from
+type DhCacheKey = (PublicKey, PublicKey);
+
+pub(crate) struct DhCache {
+    capacity: usize,
+    entries: HashMap<DhCacheKey, (Arc<CommsDHKE>, u64)>,
+    recency: BTreeMap<u64, DhCacheKey>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl DhCache {
+    /// Creates a cache of at most `capacity` shared secrets
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the cached shared secret of the secret key of `public_key` and `sender_offset_public_key`, if any
+    pub(crate) fn get(
+        &mut self,
+        public_key: &PublicKey,
+        sender_offset_public_key: &PublicKey,
+    ) -> Option<Arc<CommsDHKE>> {
+        self.tick += 1;
+        let key = (public_key.clone(), sender_offset_public_key.clone());
+        match self.entries.get_mut(&key) {
+            Some((shared_secret, last_used)) => {
+                self.recency.remove(last_used);
+                *last_used = self.tick;
+                self.recency.insert(self.tick, key);
+                self.hits += 1;
+                Some(shared_secret.clone())
+            },
+            None => {
+                self.misses += 1;
+                None
+            },
+        }
+    }
+
+    /// Caches the shared secret of the secret key of `public_key` and `sender_offset_public_key`, evicting the least
+    /// recently used secret if the cache is full
+    pub(crate) fn insert(
+        &mut self,
+        public_key: &PublicKey,
+        sender_offset_public_key: &PublicKey,
+        shared_secret: Arc<CommsDHKE>,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        let key = (public_key.clone(), sender_offset_public_key.clone());
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.recency.remove(&last_used);
+        } else if self.entries.len() >= self.capacity {
+            if let Some((_, evicted)) = self.recency.pop_first() {
+                self.entries.remove(&evicted);
+            }
+        }
+        self.entries.insert(key.clone(), (shared_secret, self.tick));
+        self.recency.insert(self.tick, key);
+    }
+
+    /// The number of lookups answered from the cache and the number computed
+    pub(crate) fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+}
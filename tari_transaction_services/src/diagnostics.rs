@@ -0,0 +1,116 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Panic reporting and logging. A panic aborts the call with an opaque `RuntimeError: unreachable`, so the panic hook
+//! writes the panic message and location to the console and keeps them for `last_error_details`. Log messages are
+//! written to the console by a logger that `init` installs.
+
+use std::{cell::RefCell, panic};
+
+use js_sys::{Function, Reflect};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+
+use crate::init::library_config;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Writes a message to the JavaScript console with a console method, e.g. `error`, if there is a console
+fn console_write(method: &str, message: &str) {
+    let global = js_sys::global();
+    let write = Reflect::get(&global, &JsValue::from_str("console"))
+        .and_then(|console| Reflect::get(&console, &JsValue::from_str(method)).map(|write| (console, write)));
+    if let Ok((console, write)) = write {
+        if let Ok(write) = write.dyn_into::<Function>() {
+            let _ = write.call1(&console, &JsValue::from_str(message));
+        }
+    }
+}
+
+/// Writes a message to the JavaScript console as an error, if there is a console
+fn console_error(message: &str) {
+    console_write("error", message);
+}
+
+/// A logger that writes log messages to the JavaScript console, with the console method matching their level
+struct ConsoleLogger;
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let method = match record.level() {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug | Level::Trace => "debug",
+        };
+        console_write(
+            method,
+            &format!("[{}] {}: {}", record.level(), record.target(), record.args()),
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+static CONSOLE_LOGGER: ConsoleLogger = ConsoleLogger;
+
+/// Installs the console logger and sets the maximum level of log messages. A logger installed by the embedder, e.g.
+/// from another crate in the same module, is kept, and only the level is set.
+pub(crate) fn install_logger(level: LevelFilter) {
+    if level != LevelFilter::Off {
+        let _ = log::set_logger(&CONSOLE_LOGGER);
+    }
+    log::set_max_level(level);
+}
+
+/// Appends the JavaScript stack at the point of the panic, which includes the wasm frames, to the panic details
+#[cfg(feature = "debug")]
+fn with_stack(mut details: String) -> String {
+    let stack = Reflect::get(&js_sys::Error::new(""), &JsValue::from_str("stack"))
+        .ok()
+        .and_then(|stack| stack.as_string());
+    if let Some(stack) = stack {
+        details.push_str("\n\nStack:\n");
+        details.push_str(&stack);
+    }
+    details
+}
+
+#[cfg(not(feature = "debug"))]
+fn with_stack(details: String) -> String {
+    details
+}
+
+/// Installs a panic hook that reports panics to the console and records them for `last_error_details`
+pub(crate) fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        let details = with_stack(info.to_string());
+        console_error(&details);
+        LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(details));
+    }));
+}
+
+/// Installs the panic hook and the console logger without the rest of `init`, for integrators that configure the
+/// library themselves. Panics are written to the console and can be read back with `last_error_details` after catching
+/// the `RuntimeError`. Log messages are written at the log level given to `init`, or `warn` if it was not called.
+#[wasm_bindgen]
+pub fn init_diagnostics() {
+    install_logger(library_config().map_or(LevelFilter::Warn, |config| config.log_level));
+    install_panic_hook();
+}
+
+/// Returns the message and location of the last panic, with the stack at the point of the panic when the library is
+/// built with the `debug` feature, or `undefined` if nothing has panicked since the panic hook was installed
+#[wasm_bindgen]
+pub fn last_error_details() -> Option<String> {
+    LAST_ERROR.with(|last_error| last_error.borrow().clone())
+}
@@ -0,0 +1,122 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::Commitment;
+use tari_crypto::tari_utilities::hex::Hex;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+/// Where a scanned input was seen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendContext {
+    /// An identifier of the spending transaction, e.g. its kernel excess signature (hex value)
+    pub transaction: String,
+    /// The hash of the block the transaction was mined in, if mined (hex value)
+    pub block_hash: Option<String>,
+    /// The height of the block the transaction was mined in, if mined
+    pub height: Option<u64>,
+}
+
+impl SpendContext {
+    fn is_same_spend(&self, other: &SpendContext) -> bool {
+        self.transaction == other.transaction &&
+            (self.block_hash.is_none() || other.block_hash.is_none() || self.block_hash == other.block_hash)
+    }
+}
+
+/// An event emitted while tracking scanned inputs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(tag = "event")]
+pub enum SpendEvent {
+    /// An owned output was seen spent in two different transactions or blocks
+    DoubleSpendSuspected {
+        /// The commitment of the owned output (hex value)
+        commitment: String,
+        /// Where the output was first seen spent
+        first: SpendContext,
+        /// Where the output was seen spent again
+        second: SpendContext,
+    },
+}
+
+/// Tracks the commitments of owned outputs across scanned inputs, and flags an output that is seen spent in two
+/// different transactions or blocks. Wallets accepting low-confirmation payments should treat such outputs as at risk.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct DoubleSpendDetector {
+    owned: HashSet<Commitment>,
+    spends: HashMap<Commitment, SpendContext>,
+}
+
+impl DoubleSpendDetector {
+    /// Records a scanned input spending `commitment`, returning an event if an owned output is spent a second time
+    pub fn record_spend(&mut self, commitment: Commitment, context: SpendContext) -> Option<SpendEvent> {
+        if !self.owned.contains(&commitment) {
+            return None;
+        }
+        match self.spends.get_mut(&commitment) {
+            None => {
+                self.spends.insert(commitment, context);
+                None
+            },
+            // The same input is seen again when a block range is rescanned, or when a pending transaction is mined
+            Some(first) if first.is_same_spend(&context) => {
+                if first.block_hash.is_none() {
+                    *first = context;
+                }
+                None
+            },
+            Some(first) => Some(SpendEvent::DoubleSpendSuspected {
+                commitment: commitment.to_hex(),
+                first: first.clone(),
+                second: context,
+            }),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl DoubleSpendDetector {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking an owned output by its commitment (hex value)
+    pub fn track_owned_output(&mut self, commitment: &str) -> Result<(), JsValue> {
+        let commitment = parse_commitment(commitment)?;
+        self.owned.insert(commitment);
+        Ok(())
+    }
+
+    /// Records a scanned input spending `commitment` (hex value) in the transaction and block described by `context`
+    /// (a JSON `SpendContext`). Returns a `DoubleSpendSuspected` event if the owned output was already seen spent in a
+    /// different transaction or block, otherwise `null`.
+    pub fn record_input(&mut self, commitment: &str, context: &str) -> Result<JsValue, JsValue> {
+        let commitment = parse_commitment(commitment)?;
+        let context: SpendContext =
+            serde_json::from_str(context).map_err(|e| JsValue::from_str(&format!("context: {e}")))?;
+        let event = self.record_spend(commitment, context);
+        Ok(serde_wasm_bindgen::to_value(&event)?)
+    }
+
+    /// Forgets all spends seen above `height`, e.g. after a chain reorganization, so that the replacement blocks are
+    /// not flagged as double spends
+    pub fn rewind_to_height(&mut self, height: u64) {
+        self.spends
+            .retain(|_, context| context.height.map_or(true, |spent_at| spent_at <= height));
+    }
+
+    /// The number of owned outputs being tracked
+    pub fn owned_count(&self) -> usize {
+        self.owned.len()
+    }
+}
+
+fn parse_commitment(commitment: &str) -> Result<Commitment, JsValue> {
+    Commitment::from_hex(commitment).map_err(|e| JsValue::from_str(&format!("commitment: {e}")))
+}
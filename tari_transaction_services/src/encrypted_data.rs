@@ -0,0 +1,157 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{Commitment, PrivateKey};
+use tari_core::transactions::{tari_amount::MicroMinotari, transaction_components::EncryptedData};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    tari_utilities::hex::{from_hex, Hex},
+};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::factories::crypto_factories;
+
+/// A struct to hold the encrypted data of an output
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct EncryptedOutputDataResult {
+    /// The encrypted value and spending key (hex value)
+    pub encrypted_data: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn encrypted_output_data_error(error: &str) -> EncryptedOutputDataResult {
+    EncryptedOutputDataResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// A struct to hold the decrypted data of an output
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct DecryptedOutputDataResult {
+    /// The value of the output in µT
+    pub value: Option<u64>,
+    /// The spending key, i.e. the commitment mask (hex value)
+    pub spending_key: Option<String>,
+    /// Whether the value and spending key open the commitment. Decryption is not key committing, so only an opening
+    /// commitment proves that the data was encrypted for this output with this key.
+    pub commitment_opens: Option<bool>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn decrypted_output_data_error(error: &str) -> DecryptedOutputDataResult {
+    DecryptedOutputDataResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Encrypts the value and spending key of an output with XChaCha20-Poly1305, bound to its commitment, as stored in the
+/// output's `encrypted_data`. Unlike one-sided payments, where the key is derived from the Diffie-Hellman shared
+/// secret, any key can be used, e.g. a custodian's recovery key. A random nonce is used, so the result differs on
+/// every call.
+/// * `encryption_key` - the encryption key (hex value)
+/// * `commitment` - the commitment of the output (hex value)
+/// * `spending_key` - the spending key, i.e. the commitment mask (hex value)
+#[wasm_bindgen]
+pub fn encrypt_output_data(
+    encryption_key: &str,
+    commitment: &str,
+    value: u64,
+    spending_key: &str,
+) -> EncryptedOutputDataResult {
+    let encryption_key = match PrivateKey::from_hex(encryption_key) {
+        Ok(val) => val,
+        Err(e) => return encrypted_output_data_error(&format!("encryption_key: {e}")),
+    };
+    let commitment = match Commitment::from_hex(commitment) {
+        Ok(val) => val,
+        Err(e) => return encrypted_output_data_error(&format!("commitment: {e}")),
+    };
+    let spending_key = match PrivateKey::from_hex(spending_key) {
+        Ok(val) => val,
+        Err(e) => return encrypted_output_data_error(&format!("spending_key: {e}")),
+    };
+    match EncryptedData::encrypt_data(&encryption_key, &commitment, MicroMinotari::from(value), &spending_key) {
+        Ok(val) => EncryptedOutputDataResult {
+            encrypted_data: Some(val.to_byte_vec().to_hex()),
+            error: None,
+        },
+        Err(e) => encrypted_output_data_error(&format!("Could not encrypt output data: {e}")),
+    }
+}
+
+/// Decrypts the value and spending key of an output's `encrypted_data`, and checks that they open its commitment
+/// * `encryption_key` - the encryption key (hex value)
+/// * `commitment` - the commitment of the output (hex value)
+/// * `encrypted_data` - the encrypted data of the output (hex value)
+#[wasm_bindgen]
+pub fn decrypt_output_data(encryption_key: &str, commitment: &str, encrypted_data: &str) -> DecryptedOutputDataResult {
+    let encryption_key = match PrivateKey::from_hex(encryption_key) {
+        Ok(val) => val,
+        Err(e) => return decrypted_output_data_error(&format!("encryption_key: {e}")),
+    };
+    let commitment = match Commitment::from_hex(commitment) {
+        Ok(val) => val,
+        Err(e) => return decrypted_output_data_error(&format!("commitment: {e}")),
+    };
+    let encrypted_data = match from_hex(encrypted_data.trim())
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| EncryptedData::from_bytes(&bytes).map_err(|e| e.to_string()))
+    {
+        Ok(val) => val,
+        Err(e) => return decrypted_output_data_error(&format!("encrypted_data: {e}")),
+    };
+    let (value, spending_key) = match EncryptedData::decrypt_data(&encryption_key, &commitment, &encrypted_data) {
+        Ok(val) => val,
+        Err(e) => return decrypted_output_data_error(&format!("Could not decrypt output data: {e}")),
+    };
+    let commitment_opens = crypto_factories()
+        .commitment
+        .open_value(&spending_key, value.as_u64(), &commitment);
+    DecryptedOutputDataResult {
+        value: Some(value.as_u64()),
+        spending_key: Some(spending_key.to_hex()),
+        commitment_opens: Some(commitment_opens),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn output_data_round_trips(encryption_key: u64, spending_key: u64, value: u64) {
+            let encryption_key = PrivateKey::from(encryption_key).to_hex();
+            let spending_key = PrivateKey::from(spending_key);
+            let commitment = crypto_factories().commitment.commit_value(&spending_key, value).to_hex();
+            let encrypted = encrypt_output_data(&encryption_key, &commitment, value, &spending_key.to_hex());
+            let encrypted_data = encrypted.encrypted_data.unwrap();
+            let decrypted = decrypt_output_data(&encryption_key, &commitment, &encrypted_data);
+            prop_assert_eq!(decrypted.error, None);
+            prop_assert_eq!(decrypted.value, Some(value));
+            prop_assert_eq!(decrypted.spending_key, Some(spending_key.to_hex()));
+            prop_assert_eq!(decrypted.commitment_opens, Some(true));
+        }
+
+        #[test]
+        fn arbitrary_encrypted_data_does_not_panic(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let key = PrivateKey::default().to_hex();
+            let commitment = Commitment::default().to_hex();
+            let decrypted = decrypt_output_data(&key, &commitment, &bytes.to_hex());
+            prop_assert!(decrypted.error.is_some());
+        }
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use js_sys::{Reflect, Uint8Array, WebAssembly};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+/// The smallest module using a v128 instruction, used to probe for WebAssembly SIMD support
+const SIMD_PROBE_MODULE: [u8; 31] = [
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7b, 0x03, 0x02, 0x01, 0x00,
+    0x0a, 0x0a, 0x01, 0x08, 0x00, 0x41, 0x00, 0xfd, 0x0f, 0xfd, 0x62, 0x0b,
+];
+
+/// The outcome of a single capability probe
+#[derive(Debug, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentCheck {
+    /// The name of the capability
+    pub name: String,
+    /// Whether the capability is available in this environment
+    pub available: bool,
+    /// Whether the library cannot function without the capability
+    pub required: bool,
+    /// An actionable message if the capability is not available
+    pub message: Option<String>,
+}
+
+/// A report of the capabilities of the current JavaScript environment
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct EnvironmentReport {
+    /// Whether all required capabilities are available
+    pub supported: bool,
+    /// The outcome of each capability probe
+    pub checks: Vec<EnvironmentCheck>,
+    /// An error message listing the missing required capabilities, if any
+    pub error: Option<String>,
+}
+
+impl EnvironmentReport {
+    fn push(&mut self, name: &str, available: bool, required: bool, message: &str) {
+        self.checks.push(EnvironmentCheck {
+            name: name.to_string(),
+            available,
+            required,
+            message: if available { None } else { Some(message.to_string()) },
+        });
+    }
+
+    /// Whether the capability probed by the check `name` is available
+    pub(crate) fn is_available(&self, name: &str) -> bool {
+        self.checks.iter().any(|check| check.name == name && check.available)
+    }
+}
+
+fn has_global(name: &str) -> bool {
+    Reflect::get(&js_sys::global(), &JsValue::from_str(name))
+        .map(|value| !value.is_undefined() && !value.is_null())
+        .unwrap_or(false)
+}
+
+fn has_secure_rng() -> bool {
+    let mut bytes = [0u8; 8];
+    getrandom::getrandom(&mut bytes).is_ok()
+}
+
+fn has_simd() -> bool {
+    WebAssembly::validate(&Uint8Array::from(&SIMD_PROBE_MODULE[..]).into()).unwrap_or(false)
+}
+
+/// Whether this build of the library uses WebAssembly SIMD, i.e. was built with `RUSTFLAGS="-C
+/// target-feature=+simd128"`. The library has no hand-written SIMD paths, so this only lets the compiler
+/// auto-vectorize. A SIMD build fails to compile in an environment without SIMD, so a loader should check
+/// `check_environment` with the scalar build, or probe for SIMD itself, before choosing which build to load.
+#[wasm_bindgen]
+pub fn is_simd_build() -> bool {
+    cfg!(target_feature = "simd128")
+}
+
+fn has_threads() -> bool {
+    let cross_origin_isolated = Reflect::get(&js_sys::global(), &JsValue::from_str("crossOriginIsolated"))
+        .map(|value| value.is_truthy())
+        .unwrap_or(false);
+    has_global("SharedArrayBuffer") && cross_origin_isolated
+}
+
+/// Probes the current JavaScript environment for the capabilities this library relies on, so that integrators can
+/// show an actionable error on unsupported browsers instead of an opaque WebAssembly trap.
+#[wasm_bindgen]
+pub fn check_environment() -> EnvironmentReport {
+    let mut report = EnvironmentReport::default();
+    report.push(
+        "BigInt",
+        has_global("BigInt"),
+        true,
+        "64-bit values are passed as BigInt; upgrade to a browser released after 2020",
+    );
+    report.push(
+        "secure random number generator",
+        has_secure_rng(),
+        true,
+        "crypto.getRandomValues is not available; a secure context (HTTPS) is required",
+    );
+    report.push(
+        "WebAssembly SIMD",
+        has_simd(),
+        is_simd_build(),
+        "WebAssembly SIMD is not supported; scanning will be slower",
+    );
+    report.push(
+        "WebAssembly threads",
+        has_threads(),
+        cfg!(feature = "threads"),
+        "SharedArrayBuffer requires the page to be cross-origin isolated (COOP/COEP headers)",
+    );
+    report.push(
+        "IndexedDB",
+        has_global("indexedDB"),
+        false,
+        "IndexedDB is not available (e.g. private browsing); wallet state cannot be persisted",
+    );
+
+    let missing = report
+        .checks
+        .iter()
+        .filter(|check| check.required && !check.available)
+        .map(|check| check.name.as_str())
+        .collect::<Vec<_>>();
+    report.supported = missing.is_empty();
+    if !report.supported {
+        report.error = Some(format!("Unsupported environment, missing: {}", missing.join(", ")));
+    }
+    report
+}
@@ -0,0 +1,52 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+/// The kind of error encountered while scanning an output, so that JavaScript callers can branch on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+pub enum ScanErrorCode {
+    /// The output could not be deserialized
+    DeserializeError,
+    /// A supplied key is not a valid hex encoded key
+    InvalidKey,
+    /// A key could not be derived from the output's shared secret
+    KeyDerivationError,
+    /// The output's script belongs to this wallet, but its encrypted data could not be decrypted
+    DecryptError,
+    /// The output's commitment could not be verified against the decrypted value and mask
+    MaskVerifyError,
+    /// The output's metadata signature does not verify under any of the networks tried, or a supplied network is not
+    /// known
+    UnknownNetwork,
+}
+
+/// A structured error that converts to a `{ code, message }` JavaScript object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct WasmError {
+    /// The kind of error
+    pub code: ScanErrorCode,
+    /// A human readable description of the error
+    pub message: String,
+}
+
+impl WasmError {
+    pub fn new<T: Into<String>>(code: ScanErrorCode, message: T) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for WasmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for WasmError {}
@@ -0,0 +1,85 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::RANGE_PROOF_BIT_LENGTH;
+use tari_core::transactions::CryptoFactories;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+// The bit length of the range proofs and the factories created for it, by `init_factories` or on first use
+static SIZED_FACTORIES: OnceCell<(usize, CryptoFactories)> = OnceCell::new();
+
+// The factories shared by all threads, including the workers of the thread pool
+static FACTORIES: Lazy<CryptoFactories> = Lazy::new(|| {
+    SIZED_FACTORIES
+        .get_or_init(|| (RANGE_PROOF_BIT_LENGTH, CryptoFactories::default()))
+        .1
+        .clone()
+});
+
+/// Returns the commitment and range proof services shared by all scanning and verification entry points, creating
+/// them for 64 bit range proofs on first use if `init_factories` has not been called. Creating the range proof
+/// service computes its generators, so it is done once per module instance rather than per call or per worker; the
+/// services are reference counted, so the returned clone is cheap.
+pub(crate) fn crypto_factories() -> CryptoFactories {
+    FACTORIES.clone()
+}
+
+/// A struct to hold the outcome of `init_factories`
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct InitFactoriesResult {
+    /// The bit length of the range proofs the factories are created for
+    pub max_range_proof_size: Option<usize>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn init_factories_error(error: &str) -> InitFactoriesResult {
+    InitFactoriesResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Creates the shared commitment and range proof services up front, e.g. while the page loads, instead of on the first
+/// scan. Consensus range proofs are 64 bits; a smaller size only verifies proofs of that size. The factories can be
+/// created once per module instance, so calling this again with a different size, or after the first scan created
+/// them for 64 bits, is an error.
+/// * `max_range_proof_size` - the bit length of the range proofs, a power of two of at most 64
+#[wasm_bindgen]
+pub fn init_factories(max_range_proof_size: usize) -> InitFactoriesResult {
+    match create_factories(max_range_proof_size) {
+        Ok(()) => InitFactoriesResult {
+            max_range_proof_size: Some(max_range_proof_size),
+            error: None,
+        },
+        Err(e) => init_factories_error(&e),
+    }
+}
+
+/// Checks the range proof size, as `init_factories` and `init` take it
+pub(crate) fn check_range_proof_size(max_range_proof_size: usize) -> Result<(), String> {
+    if !max_range_proof_size.is_power_of_two() || max_range_proof_size > RANGE_PROOF_BIT_LENGTH {
+        return Err(format!(
+            "max_range_proof_size must be a power of two of at most {RANGE_PROOF_BIT_LENGTH}, got \
+             {max_range_proof_size}"
+        ));
+    }
+    Ok(())
+}
+
+/// Creates the shared factories for `max_range_proof_size` bit range proofs, unless they already exist for that size
+pub(crate) fn create_factories(max_range_proof_size: usize) -> Result<(), String> {
+    check_range_proof_size(max_range_proof_size)?;
+    let (size, _) = SIZED_FACTORIES.get_or_init(|| (max_range_proof_size, CryptoFactories::new(max_range_proof_size)));
+    if *size != max_range_proof_size {
+        return Err(format!(
+            "The factories are already initialized for {size} bit range proofs"
+        ));
+    }
+    Ok(())
+}
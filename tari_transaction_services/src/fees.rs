@@ -0,0 +1,105 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::{transaction_components::Transaction, weight::TransactionWeight};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A struct to hold an estimated transaction fee
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct FeeEstimateResult {
+    /// The weight of the transaction in grams
+    pub weight: Option<u64>,
+    /// The fee in µT
+    pub fee: Option<u64>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn fee_estimate_error(error: &str) -> FeeEstimateResult {
+    FeeEstimateResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// A struct to hold the weight of a transaction
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct TransactionWeightResult {
+    /// The weight of the transaction in grams
+    pub weight: Option<u64>,
+    /// The total fee of the transaction's kernels in µT
+    pub fee: Option<u64>,
+    /// The fee paid per gram of weight in µT, rounded down
+    pub fee_per_gram: Option<u64>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn transaction_weight_error(error: &str) -> TransactionWeightResult {
+    TransactionWeightResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Estimates the fee of a transaction from its shape, using the latest consensus weights.
+/// * `features_and_scripts_size` - the total size in bytes of the output features and scripts of all outputs; each
+///   output's size is rounded up by consensus, so the sum of the rounded up sizes gives the exact weight, while the
+///   plain sum is rounded up as a whole
+/// * `fee_per_gram` - the fee per gram of weight in µT
+#[wasm_bindgen]
+pub fn estimate_fee(
+    num_inputs: usize,
+    num_outputs: usize,
+    num_kernels: usize,
+    features_and_scripts_size: usize,
+    fee_per_gram: u64,
+) -> FeeEstimateResult {
+    let transaction_weight = TransactionWeight::latest();
+    let weight = transaction_weight.calculate(
+        num_kernels,
+        num_inputs,
+        num_outputs,
+        transaction_weight.round_up_features_and_scripts_size(features_and_scripts_size),
+    );
+    match weight.checked_mul(fee_per_gram) {
+        Some(fee) => FeeEstimateResult {
+            weight: Some(weight),
+            fee: Some(fee),
+            error: None,
+        },
+        None => fee_estimate_error(&format!(
+            "The fee for a weight of {weight} grams at {fee_per_gram} µT per gram exceeds u64::MAX"
+        )),
+    }
+}
+
+/// Calculates the weight of a transaction, as JSON, using the latest consensus weights, as well as the fee per gram it
+/// pays
+#[wasm_bindgen]
+pub fn calculate_tx_weight(tx_json: &str) -> TransactionWeightResult {
+    let transaction: Transaction = match serde_json::from_str(tx_json) {
+        Ok(val) => val,
+        Err(e) => return transaction_weight_error(&format!("tx_json: {e}")),
+    };
+    let weight = match transaction.calculate_weight(&TransactionWeight::latest()) {
+        Ok(val) => val,
+        Err(e) => return transaction_weight_error(&format!("Could not calculate the weight: {e}")),
+    };
+    let fee = match transaction.body.get_total_fee() {
+        Ok(val) => val.as_u64(),
+        Err(e) => return transaction_weight_error(&e.to_string()),
+    };
+    TransactionWeightResult {
+        weight: Some(weight),
+        fee: Some(fee),
+        fee_per_gram: fee.checked_div(weight),
+        error: None,
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Entry points for the `cargo-fuzz` targets in `fuzz/`, which feed arbitrary bytes through the deserializers behind
+//! the wasm entry points. Malformed input must be reported as an error: a panic aborts the whole wasm instance, so any
+//! panic found here is a bug. Input that does deserialize must survive a round trip unchanged.
+
+use tari_common_types::types::{Commitment, PrivateKey};
+use tari_core::{covenants::Covenant, transactions::transaction_components::EncryptedData};
+use tari_crypto::tari_utilities::hex::Hex;
+
+use crate::{
+    covenant_decoder,
+    encrypted_data,
+    output_encoding::{decode_output_str, safe_deserialize_output, OutputEncoding},
+};
+
+/// Deserializes a transaction output from arbitrary bytes in every encoding
+pub fn decode_output(data: &[u8]) {
+    let _result = safe_deserialize_output(data, None);
+    if let Ok(output) = decode_output_str(&data.to_hex(), OutputEncoding::Hex) {
+        let bytes = borsh::to_vec(&output).expect("an output serializes");
+        let decoded = decode_output_str(&bytes.to_hex(), OutputEncoding::Hex).expect("an output round trips");
+        assert_eq!(decoded, output);
+    }
+    if let Ok(output) = std::str::from_utf8(data) {
+        for encoding in &[
+            OutputEncoding::Auto,
+            OutputEncoding::Borsh,
+            OutputEncoding::Base64,
+            OutputEncoding::Json,
+        ] {
+            let _result = decode_output_str(output, *encoding);
+        }
+    }
+}
+
+/// Decodes a covenant from arbitrary bytes
+pub fn decode_covenant(data: &[u8]) {
+    let _result = covenant_decoder::decode_covenant(data);
+    if let Ok(covenant) = Covenant::from_bytes(&mut &data[..]) {
+        let decoded = Covenant::from_bytes(&mut covenant.to_bytes().as_slice()).expect("a covenant round trips");
+        assert_eq!(decoded, covenant);
+        let bytes = borsh::to_vec(&covenant).expect("a covenant serializes");
+        let decoded = borsh::from_slice::<Covenant>(&bytes).expect("a covenant round trips through Borsh");
+        assert_eq!(decoded, covenant);
+    }
+}
+
+/// Decrypts arbitrary bytes as the encrypted data of an output
+pub fn decrypt_output_data(data: &[u8]) {
+    let _result = encrypted_data::decrypt_output_data(
+        &PrivateKey::default().to_hex(),
+        &Commitment::default().to_hex(),
+        &data.to_hex(),
+    );
+    if let Ok(parsed) = EncryptedData::from_bytes(data) {
+        let decoded = EncryptedData::from_bytes(&parsed.to_byte_vec()).expect("encrypted data round trips");
+        assert_eq!(decoded, parsed);
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Scanning with script keys derived from the seed on the fly. The scanner starts with the first `gap_limit` script
+//! keys, and whenever an output matches the key at index `i`, derives the keys up to `i + gap_limit`, as BIP-44 account
+//! discovery does, so that recovery does not need thousands of known script keys computed up front.
+
+use tari_common::configuration::Network;
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_crypto::{keys::PublicKey as PK, tari_utilities::hex::Hex};
+use tari_key_manager::key_manager::KeyManager;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    cipher_seed_from_hex,
+    deposit_tagging::DepositRules,
+    network::{on_network, parse_network},
+    output_encoding::{decode_output, OutputEncoding},
+    scan_error,
+    scan_outputs::{scan_output, scan_result, ScanKeys},
+    secrets::ProtectedScanResult,
+    wallet_keys::{commitment_mask_key_manager, derive_script_key},
+    RecoveredOutputResult,
+};
+
+/// The number of unused script keys derived past the last match, if not provided
+const DEFAULT_GAP_LIMIT: u64 = 100;
+/// The largest gap limit accepted
+const MAX_GAP_LIMIT: u64 = 10_000;
+
+/// A scanner that derives the wallet's script keys from the seed as matches are found
+#[wasm_bindgen]
+pub struct GapLimitScanner {
+    keys: ScanKeys,
+    rules: DepositRules,
+    network: Option<Network>,
+    key_manager: KeyManager<PublicKey>,
+    gap_limit: u64,
+    highest_match: Option<u64>,
+}
+
+impl GapLimitScanner {
+    /// Derives the script keys up to, but excluding, `end_index`
+    fn derive_to(&mut self, end_index: u64) -> Result<(), String> {
+        for index in self.keys.known_key_count() as u64..end_index {
+            let key = derive_script_key(&self.key_manager, index)?;
+            self.keys.add_known_key(key);
+        }
+        Ok(())
+    }
+
+    /// Extends the derived keys past the index of the script key a recovered output matched, if it is a derived key
+    fn extend_past_match(&mut self, result: &RecoveredOutputResult) -> Result<(), String> {
+        let index = match result
+            .script_key
+            .as_deref()
+            .and_then(|key| PrivateKey::from_hex(key).ok())
+            .and_then(|key| self.keys.known_key_index(&PublicKey::from_secret_key(&key)))
+        {
+            Some(index) => index as u64,
+            None => return Ok(()),
+        };
+        self.highest_match = Some(self.highest_match.map_or(index, |highest| highest.max(index)));
+        self.derive_to(index.saturating_add(1).saturating_add(self.gap_limit))
+    }
+}
+
+#[wasm_bindgen]
+impl GapLimitScanner {
+    /// Creates a scanner that derives the script keys of the wallet's outputs from the cipher seed, as returned by
+    /// `create_cipher_seed` or `mnemonic_to_cipher_seed`, and scans for stealth payments with the wallet secret key
+    /// * `gap_limit` - the number of unused script keys to derive past the last match, 100 if not provided
+    /// * `deposit_rules` - a JSON array of `DepositRule`s to tag recovered outputs with, if provided
+    /// * `network` - the network the scanned outputs are hashed for, or the library's network if not provided
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        seed: &str,
+        wallet_sk: String,
+        gap_limit: Option<u64>,
+        deposit_rules: Option<String>,
+        network: Option<String>,
+    ) -> Result<GapLimitScanner, JsValue> {
+        let seed = cipher_seed_from_hex(seed).map_err(|e| JsValue::from_str(&e))?;
+        let gap_limit = gap_limit.unwrap_or(DEFAULT_GAP_LIMIT);
+        if gap_limit == 0 || gap_limit > MAX_GAP_LIMIT {
+            return Err(JsValue::from_str(&format!(
+                "gap_limit: must be between 1 and {MAX_GAP_LIMIT}"
+            )));
+        }
+        let keys = ScanKeys::new(Vec::new(), wallet_sk).map_err(JsValue::from)?;
+        let rules = DepositRules::from_optional_json(deposit_rules.as_deref()).map_err(JsValue::from)?;
+        let network = parse_network(network.as_deref()).map_err(|e| JsValue::from_str(&e))?;
+        let mut scanner = Self {
+            keys,
+            rules,
+            network,
+            key_manager: commitment_mask_key_manager(seed),
+            gap_limit,
+            highest_match: None,
+        };
+        scanner.derive_to(gap_limit).map_err(|e| JsValue::from_str(&e))?;
+        Ok(scanner)
+    }
+
+    /// Scans a transaction output, passed in any of the encodings accepted by `scan_output_for_one_sided_payment`,
+    /// deriving more script keys if it matches one of the derived keys
+    pub fn scan(&mut self, output: JsValue, encoding: Option<OutputEncoding>) -> RecoveredOutputResult {
+        let output = match decode_output(&output, encoding) {
+            Ok(val) => val,
+            Err(e) => return scan_error(e),
+        };
+        let result = scan_result(on_network(self.network, || {
+            scan_output(&self.keys, &self.rules, &output)
+        }));
+        if result.hash.is_some() {
+            if let Err(e) = self.extend_past_match(&result) {
+                return RecoveredOutputResult {
+                    error: Some(e),
+                    ..result
+                };
+            }
+        }
+        result
+    }
+
+    /// Scans a transaction output as `scan` does, but holds the spending and script keys of a recovered output in wasm
+    /// memory as `SecretKeyHandle`s
+    pub fn scan_protected(&mut self, output: JsValue, encoding: Option<OutputEncoding>) -> ProtectedScanResult {
+        ProtectedScanResult::from(self.scan(output, encoding))
+    }
+
+    /// The number of script keys derived so far
+    pub fn derived_keys(&self) -> u64 {
+        self.keys.known_key_count() as u64
+    }
+
+    /// The highest index of a script key an output has matched, if any
+    pub fn highest_match_index(&self) -> Option<u64> {
+        self.highest_match
+    }
+}
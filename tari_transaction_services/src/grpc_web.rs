@@ -0,0 +1,285 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A gRPC-web client for a base node, with the `grpc-web` feature, so that a browser wallet can fetch blocks and scan
+//! them without a proxy service translating formats. The base node's gRPC server must be reachable through a
+//! gRPC-web endpoint, e.g. an Envoy or tonic-web layer, that allows the page's origin.
+
+use std::convert::{TryFrom, TryInto};
+
+use js_sys::Uint8Array;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::transaction_components::TransactionOutput;
+use tari_crypto::tari_utilities::hex::to_hex;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Headers, Request, RequestInit, Response, Window, WorkerGlobalScope};
+
+use crate::{
+    proto::{Empty, GetBlocksRequest, HistoricalBlock, TipInfoResponse},
+    scan_outputs::{scan_result, ScannerContext},
+    RecoveredOutputResult,
+};
+
+/// The gRPC service of the base node
+const BASE_NODE_SERVICE: &str = "tari.rpc.BaseNode";
+/// The number of blocks requested from the base node per call
+const BLOCKS_PER_REQUEST: u64 = 100;
+/// The flag of a gRPC-web frame that carries the trailers instead of a message
+const TRAILERS_FLAG: u8 = 0x80;
+
+/// The outputs of a block fetched from the base node
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchedBlock {
+    /// The height of the block
+    pub height: u64,
+    /// The hash of the block (hex value)
+    pub hash: String,
+    /// The outputs of the block, as hex-encoded Borsh bytes
+    pub outputs: Vec<String>,
+}
+
+/// A struct to hold the results of scanning a range of blocks fetched from the base node
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BlockRangeScanResult {
+    /// The outputs found to belong to the wallet, and the outputs that could not be scanned
+    pub results: Vec<RecoveredOutputResult>,
+    /// The number of blocks scanned
+    pub blocks: u64,
+    /// The number of outputs scanned
+    pub outputs: u64,
+    /// The number of outputs found to belong to the wallet
+    pub matches: u64,
+    /// The height of the last block scanned
+    pub last_height: Option<u64>,
+}
+
+/// Calls `fetch` on the global scope, which is a window on the main thread and a worker global scope in Web Workers
+async fn fetch(request: &Request) -> Result<Response, JsValue> {
+    let global = js_sys::global();
+    let promise = if let Some(window) = global.dyn_ref::<Window>() {
+        window.fetch_with_request(request)
+    } else if let Some(worker) = global.dyn_ref::<WorkerGlobalScope>() {
+        worker.fetch_with_request(request)
+    } else {
+        return Err(JsValue::from_str("fetch is not available in this environment"));
+    };
+    JsFuture::from(promise).await?.dyn_into::<Response>()
+}
+
+/// Checks the `grpc-status` of a response, from its headers or its trailers
+fn check_status<'a, I: Iterator<Item = (String, &'a str)>>(entries: I) -> Result<(), String> {
+    let (mut status, mut message) = (None, None);
+    for (name, value) in entries {
+        match name.as_str() {
+            "grpc-status" => status = Some(value.trim().to_string()),
+            "grpc-message" => message = Some(value.trim().to_string()),
+            _ => {},
+        }
+    }
+    match status.as_deref() {
+        None | Some("0") => Ok(()),
+        Some(code) => Err(format!("gRPC status {code}: {}", message.unwrap_or_default())),
+    }
+}
+
+/// Splits a gRPC-web response body into its messages, checking the status in its trailers
+fn decode_frames<M: Message + Default>(mut body: &[u8]) -> Result<Vec<M>, String> {
+    let mut messages = Vec::new();
+    while !body.is_empty() {
+        if body.len() < 5 {
+            return Err("Truncated gRPC-web frame header".to_string());
+        }
+        let flag = body[0];
+        let len = u32::from_be_bytes(body[1..5].try_into().expect("slice has 4 bytes")) as usize;
+        let end = len
+            .checked_add(5)
+            .ok_or_else(|| "Invalid gRPC-web frame length".to_string())?;
+        let payload = body.get(5..end).ok_or_else(|| "Truncated gRPC-web frame".to_string())?;
+        if flag & TRAILERS_FLAG == 0 {
+            messages.push(M::decode(payload).map_err(|e| format!("Could not decode message: {e}"))?);
+        } else {
+            let trailers = String::from_utf8_lossy(payload);
+            check_status(trailers.split("\r\n").filter_map(|line| {
+                line.split_once(':')
+                    .map(|(name, value)| (name.trim().to_ascii_lowercase(), value))
+            }))?;
+        }
+        body = &body[end..];
+    }
+    Ok(messages)
+}
+
+/// A client for a base node's gRPC interface, spoken as gRPC-web over `fetch`
+#[wasm_bindgen]
+pub struct BaseNodeClient {
+    url: String,
+}
+
+impl BaseNodeClient {
+    /// Calls a method of the base node, returning every message of the (possibly streamed) response
+    async fn call<Req: Message, Resp: Message + Default>(
+        &self,
+        method: &str,
+        request: &Req,
+    ) -> Result<Vec<Resp>, String> {
+        let message = request.encode_to_vec();
+        let mut body = Vec::with_capacity(5 + message.len());
+        body.push(0);
+        body.extend_from_slice(&u32::try_from(message.len()).map_err(|e| e.to_string())?.to_be_bytes());
+        body.extend_from_slice(&message);
+
+        let js_error = |e: JsValue| e.as_string().unwrap_or_else(|| format!("{e:?}"));
+        let headers = Headers::new().map_err(js_error)?;
+        headers
+            .set("content-type", "application/grpc-web+proto")
+            .map_err(js_error)?;
+        headers.set("x-grpc-web", "1").map_err(js_error)?;
+        let init = RequestInit::new();
+        init.set_method("POST");
+        init.set_headers(&headers);
+        init.set_body(&Uint8Array::from(body.as_slice()));
+        let url = format!("{}/{BASE_NODE_SERVICE}/{method}", self.url);
+        let request = Request::new_with_str_and_init(&url, &init).map_err(js_error)?;
+
+        let response = fetch(&request).await.map_err(js_error)?;
+        if !response.ok() {
+            return Err(format!("{method}: HTTP status {}", response.status()));
+        }
+        // A response without messages carries its status in the headers
+        let response_headers = response.headers();
+        let header = |name: &str| response_headers.get(name).ok().flatten();
+        let (status, message) = (header("grpc-status"), header("grpc-message"));
+        let entries = [("grpc-status", status), ("grpc-message", message)];
+        check_status(
+            entries
+                .iter()
+                .filter_map(|(name, value)| value.as_deref().map(|value| (name.to_string(), value))),
+        )
+        .map_err(|e| format!("{method}: {e}"))?;
+        let body = JsFuture::from(response.array_buffer().map_err(js_error)?)
+            .await
+            .map_err(js_error)?;
+        decode_frames(&Uint8Array::new(&body).to_vec()).map_err(|e| format!("{method}: {e}"))
+    }
+
+    /// Fetches the blocks in the height range, inclusive, converting their outputs
+    async fn blocks(
+        &self,
+        start_height: u64,
+        end_height: u64,
+    ) -> Result<Vec<(u64, String, Vec<TransactionOutput>)>, String> {
+        let mut blocks = Vec::new();
+        let mut from = start_height;
+        while from <= end_height {
+            let to = end_height.min(from.saturating_add(BLOCKS_PER_REQUEST - 1));
+            let request = GetBlocksRequest {
+                heights: (from..=to).collect(),
+            };
+            let response: Vec<HistoricalBlock> = self.call("GetBlocks", &request).await?;
+            for block in response.into_iter().filter_map(|historical| historical.block) {
+                let (height, hash) = block
+                    .header
+                    .map(|header| (header.height, to_hex(&header.hash)))
+                    .ok_or_else(|| "GetBlocks: a block has no header".to_string())?;
+                let outputs = block
+                    .body
+                    .map(|body| body.outputs)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|output| {
+                        TransactionOutput::try_from(output).map_err(|e| format!("Block {height}: output: {e}"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                blocks.push((height, hash, outputs));
+            }
+            if to == u64::MAX {
+                break;
+            }
+            from = to + 1;
+        }
+        blocks.sort_by_key(|(height, _, _)| *height);
+        Ok(blocks)
+    }
+}
+
+#[wasm_bindgen]
+impl BaseNodeClient {
+    /// Creates a client for the base node's gRPC-web endpoint, e.g. `https://node.example.com:18142`
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: String) -> BaseNodeClient {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Returns the height of the base node's best block
+    pub async fn get_tip_height(&self) -> Result<u64, JsValue> {
+        let response: Vec<TipInfoResponse> = self
+            .call("GetTipInfo", &Empty {})
+            .await
+            .map_err(|e| JsValue::from_str(&e))?;
+        response
+            .into_iter()
+            .next()
+            .and_then(|tip| tip.metadata)
+            .map(|metadata| metadata.best_block_height)
+            .ok_or_else(|| JsValue::from_str("GetTipInfo: the response has no metadata"))
+    }
+
+    /// Fetches the blocks in the height range, inclusive, returning an array of `FetchedBlock`s with their outputs as
+    /// hex-encoded Borsh bytes, e.g. to push into a `ScanSession`
+    pub async fn fetch_blocks(&self, start_height: u64, end_height: u64) -> Result<JsValue, JsValue> {
+        let blocks = self
+            .blocks(start_height, end_height)
+            .await
+            .map_err(|e| JsValue::from_str(&e))?;
+        let blocks = blocks
+            .into_iter()
+            .map(|(height, hash, outputs)| {
+                let outputs = outputs
+                    .iter()
+                    .map(|output| borsh::to_vec(output).map(|bytes| to_hex(&bytes)))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| JsValue::from_str(&format!("Could not serialize output: {e}")))?;
+                Ok(FetchedBlock { height, hash, outputs })
+            })
+            .collect::<Result<Vec<_>, JsValue>>()?;
+        Ok(serde_wasm_bindgen::to_value(&blocks)?)
+    }
+
+    /// Fetches the blocks in the height range, inclusive, and scans their outputs with the scanner, returning the
+    /// outputs that belong to the wallet and those that could not be scanned
+    pub async fn scan_blocks(
+        &self,
+        scanner: &ScannerContext,
+        start_height: u64,
+        end_height: u64,
+    ) -> Result<BlockRangeScanResult, JsValue> {
+        let blocks = self
+            .blocks(start_height, end_height)
+            .await
+            .map_err(|e| JsValue::from_str(&e))?;
+        let mut result = BlockRangeScanResult::default();
+        for (height, _, outputs) in blocks {
+            for output in &outputs {
+                let scanned = scan_result(scanner.scan_decoded(output));
+                if scanned.hash.is_some() {
+                    result.matches += 1;
+                }
+                if scanned.hash.is_some() || scanned.error.is_some() {
+                    result.results.push(scanned);
+                }
+            }
+            result.blocks += 1;
+            result.outputs += outputs.len() as u64;
+            result.last_height = Some(height);
+        }
+        Ok(result)
+    }
+}
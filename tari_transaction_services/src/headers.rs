@@ -0,0 +1,135 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::FixedHash;
+use tari_core::blocks::BlockHeader;
+use tari_crypto::tari_utilities::hex::{from_hex, Hex};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A struct to hold a decoded block header
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct DecodedHeaderResult {
+    /// The block hash (hex value)
+    pub hash: Option<String>,
+    /// The block height
+    pub height: Option<u64>,
+    /// The hash of the previous block (hex value)
+    pub prev_hash: Option<String>,
+    /// The block timestamp, in seconds since the Unix epoch
+    pub timestamp: Option<u64>,
+    /// The output sparse Merkle tree root (hex value), as used by `verify_output_inclusion`
+    pub output_mr: Option<String>,
+    /// The proof of work algorithm, `RandomX` or `Sha3x`
+    pub pow_algo: Option<String>,
+    /// The header, serialized as JSON
+    pub header: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn decoded_header_error(error: &str) -> DecodedHeaderResult {
+    DecodedHeaderResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// A struct to hold the result of a header chain validation
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct HeaderChainValidationResult {
+    /// Whether every header links to the one before it
+    pub valid: bool,
+    /// The number of headers validated
+    pub count: usize,
+    /// The hash of the last header (hex value)
+    pub tip_hash: Option<String>,
+    /// The height of the last header
+    pub tip_height: Option<u64>,
+    /// The index of the first header that could not be decoded or does not link to the one before it
+    pub invalid_index: Option<usize>,
+    /// An error message in case of an error or failed validation
+    pub error: Option<String>,
+}
+
+fn header_chain_error(invalid_index: Option<usize>, error: &str) -> HeaderChainValidationResult {
+    HeaderChainValidationResult {
+        invalid_index,
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn header_from_borsh(bytes: &[u8]) -> Result<BlockHeader, String> {
+    borsh::from_slice(bytes).map_err(|e| format!("Borsh: {e}"))
+}
+
+/// Deserializes a Borsh encoded block header and computes its hash
+#[wasm_bindgen]
+pub fn decode_block_header(bytes: &[u8]) -> DecodedHeaderResult {
+    let header = match header_from_borsh(bytes) {
+        Ok(val) => val,
+        Err(e) => return decoded_header_error(&format!("header: {e}")),
+    };
+    let json = match serde_json::to_string(&header) {
+        Ok(val) => val,
+        Err(e) => return decoded_header_error(&format!("Could not serialize header: {e}")),
+    };
+    DecodedHeaderResult {
+        hash: Some(header.hash().to_hex()),
+        height: Some(header.height),
+        prev_hash: Some(header.prev_hash.to_hex()),
+        timestamp: Some(header.timestamp.as_u64()),
+        output_mr: Some(header.output_mr.to_hex()),
+        pow_algo: Some(format!("{:?}", header.pow.pow_algo)),
+        header: Some(json),
+        error: None,
+    }
+}
+
+/// Validates the linkage of a chain of Borsh encoded block headers (hex values), in ascending height order: every
+/// header must link to the hash of the header before it and be exactly one block higher. The proof of work is not
+/// validated, so the chain must come from a trusted source or be checked against a known block hash, e.g. a wallet
+/// birthday checkpoint, passed as `anchor_hash`, which the first header must link to.
+#[wasm_bindgen]
+pub fn validate_header_chain(headers: Vec<String>, anchor_hash: Option<String>) -> HeaderChainValidationResult {
+    let anchor_hash = match anchor_hash.as_deref().map(FixedHash::from_hex).transpose() {
+        Ok(val) => val,
+        Err(e) => return header_chain_error(None, &format!("anchor_hash: {e}")),
+    };
+    let mut previous: Option<BlockHeader> = None;
+    for (i, header) in headers.iter().enumerate() {
+        let header = match from_hex(header.trim())
+            .map_err(|e| format!("hex: {e}"))
+            .and_then(|bytes| header_from_borsh(&bytes))
+        {
+            Ok(val) => val,
+            Err(e) => return header_chain_error(Some(i), &format!("header {i}: {e}")),
+        };
+        let linked = match (&previous, &anchor_hash) {
+            (Some(previous), _) => header.validate_linkage(previous).map_err(|e| e.to_string()),
+            (None, Some(anchor_hash)) if header.prev_hash != *anchor_hash => Err(format!(
+                "Header at height {} does not link to the anchor hash {anchor_hash}",
+                header.height
+            )),
+            (None, _) => Ok(()),
+        };
+        if let Err(e) = linked {
+            return header_chain_error(Some(i), &format!("header {i}: {e}"));
+        }
+        previous = Some(header);
+    }
+    HeaderChainValidationResult {
+        valid: true,
+        count: headers.len(),
+        tip_hash: previous.as_ref().map(|header| header.hash().to_hex()),
+        tip_height: previous.as_ref().map(|header| header.height),
+        invalid_index: None,
+        error: None,
+    }
+}
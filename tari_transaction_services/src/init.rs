@@ -0,0 +1,180 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{cell::RefCell, str::FromStr};
+
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use tari_common::configuration::Network;
+use tari_common_types::types::RANGE_PROOF_BIT_LENGTH;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    diagnostics::{install_logger, install_panic_hook},
+    environment::{check_environment, EnvironmentReport},
+    factories::{check_range_proof_size, create_factories},
+};
+
+/// An optional capability that an integrator can require at initialization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub enum Feature {
+    /// WebAssembly SIMD, for faster scanning
+    Simd,
+    /// WebAssembly threads
+    Threads,
+    /// IndexedDB, to persist wallet state
+    IndexedDb,
+}
+
+impl Feature {
+    /// The name of the environment check that probes for the feature
+    fn check_name(self) -> &'static str {
+        match self {
+            Feature::Simd => "WebAssembly SIMD",
+            Feature::Threads => "WebAssembly threads",
+            Feature::IndexedDb => "IndexedDB",
+        }
+    }
+}
+
+/// The configuration passed to `init`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct InitConfig {
+    /// The network, e.g. `mainnet` or `esmeralda`, which selects the network byte of consensus hashes and addresses
+    pub network: String,
+    /// The optional capabilities that must be available, e.g. `["simd", "indexedDb"]`
+    #[serde(default)]
+    pub features: Vec<Feature>,
+    /// The maximum level of log messages, one of `off`, `error`, `warn`, `info`, `debug` or `trace`; `warn` if not
+    /// provided
+    pub log_level: Option<String>,
+    /// The number of bits of range proofs, as for `init_factories`; 64 if not provided
+    pub max_range_proof_size: Option<usize>,
+}
+
+/// The configuration in effect after a successful `init`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LibraryConfig {
+    pub network: Network,
+    pub features: Vec<Feature>,
+    pub log_level: LevelFilter,
+    pub max_range_proof_size: usize,
+}
+
+thread_local! {
+    static CONFIG: RefCell<Option<LibraryConfig>> = RefCell::new(None);
+}
+
+/// Returns the configuration set by `init`, if the library has been initialized
+pub(crate) fn library_config() -> Option<LibraryConfig> {
+    CONFIG.with(|config| config.borrow().clone())
+}
+
+/// A struct to hold the outcome of `init`
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct InitResult {
+    /// Whether the library is initialized with the requested configuration
+    pub initialized: bool,
+    /// The network the library is initialized for
+    pub network: Option<String>,
+    /// The capabilities of the JavaScript environment
+    pub environment: EnvironmentReport,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn init_error(environment: EnvironmentReport, error: &str) -> InitResult {
+    InitResult {
+        environment,
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn parse_config(config: InitConfig, environment: &EnvironmentReport) -> Result<LibraryConfig, String> {
+    let network = Network::from_str(&config.network).map_err(|e| format!("network: {e}"))?;
+    let log_level = match config.log_level {
+        Some(level) => LevelFilter::from_str(&level).map_err(|e| format!("log_level: {e}"))?,
+        None => LevelFilter::Warn,
+    };
+    let max_range_proof_size = config.max_range_proof_size.unwrap_or(RANGE_PROOF_BIT_LENGTH);
+    check_range_proof_size(max_range_proof_size)?;
+    let missing = config
+        .features
+        .iter()
+        .map(|feature| feature.check_name())
+        .filter(|name| !environment.is_available(name))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(format!("Required features are not available: {}", missing.join(", ")));
+    }
+    Ok(LibraryConfig {
+        network,
+        features: config.features,
+        log_level,
+        max_range_proof_size,
+    })
+}
+
+/// Applies a validated configuration. The network can only be set once per page and the factories only created once,
+/// so both are checked before the logger and the panic hook are installed.
+fn apply_config(config: &LibraryConfig) -> Result<(), String> {
+    create_factories(config.max_range_proof_size)?;
+    if Network::set_current(config.network).is_err() {
+        let current = Network::get_current_or_user_setting_or_default();
+        if current != config.network {
+            return Err(format!(
+                "The network is already set to {current} and cannot be changed to {}",
+                config.network
+            ));
+        }
+    }
+    install_logger(config.log_level);
+    install_panic_hook();
+    Ok(())
+}
+
+/// Initializes the library in one step, instead of relying on defaults picked up by individual functions: probes the
+/// environment, validates the whole configuration and only then sets the network, creates the commitment and range
+/// proof services as `init_factories` does, and installs a console logger at the log level and the panic hook of
+/// `init_diagnostics`. Initialization is all or nothing, and may be repeated with the same configuration,
+/// but not with a different one, so that several embedders on a page cannot silently reconfigure each other.
+/// * `config` - a JSON `InitConfig`, e.g. `{ "network": "mainnet", "features": ["simd"], "logLevel": "info" }`
+#[wasm_bindgen]
+pub fn init(config: &str) -> InitResult {
+    let environment = check_environment();
+    if let Some(error) = &environment.error {
+        let error = error.clone();
+        return init_error(environment, &error);
+    }
+    let config = match serde_json::from_str::<InitConfig>(config)
+        .map_err(|e| format!("config: {e}"))
+        .and_then(|config| parse_config(config, &environment))
+    {
+        Ok(val) => val,
+        Err(e) => return init_error(environment, &e),
+    };
+    if let Some(current) = library_config() {
+        if current != config {
+            return init_error(
+                environment,
+                "The library is already initialized with a different configuration",
+            );
+        }
+    } else if let Err(e) = apply_config(&config) {
+        return init_error(environment, &e);
+    }
+    let network = config.network.to_string();
+    CONFIG.with(|current| *current.borrow_mut() = Some(config));
+    InitResult {
+        initialized: true,
+        network: Some(network),
+        environment,
+        error: None,
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{Commitment, PrivateKey, PublicKey, Signature};
+use tari_core::transactions::{
+    tari_amount::MicroMinotari,
+    transaction_components::{KernelBuilder, KernelFeatures, TransactionKernel, TransactionKernelVersion},
+};
+use tari_crypto::{keys::PublicKey as PK, tari_utilities::hex::Hex};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A struct to hold a signed transaction kernel
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct KernelSignatureResult {
+    /// The kernel excess, the commitment to zero of the excess private key (hex value)
+    pub excess: Option<String>,
+    /// The public nonce of the excess signature (hex value)
+    pub public_nonce: Option<String>,
+    /// The signature scalar of the excess signature (hex value)
+    pub signature: Option<String>,
+    /// The kernel, serialized as JSON
+    pub kernel: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn kernel_signature_error(error: &str) -> KernelSignatureResult {
+    KernelSignatureResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Signs a transaction kernel for a single-party transaction, where the caller holds the raw excess private key (the
+/// sum of the output masks minus the sum of the input masks, less the transaction offset) and the signing nonce. The
+/// challenge is built for `TransactionKernelVersion::V0`.
+/// * `excess_private_key` - the excess private key (hex value)
+/// * `nonce` - the private signing nonce (hex value), which must never be reused
+/// * `kernel_features` - the kernel features bits: 0 for a plain kernel, 1 for a coinbase and 2 for a burn kernel
+/// * `burn_commitment` - the commitment of the burned output (hex value), required for a burn kernel
+#[wasm_bindgen]
+pub fn sign_kernel(
+    excess_private_key: &str,
+    nonce: &str,
+    fee: u64,
+    lock_height: u64,
+    kernel_features: u8,
+    burn_commitment: Option<String>,
+) -> KernelSignatureResult {
+    let excess_private_key = match PrivateKey::from_hex(excess_private_key) {
+        Ok(val) => val,
+        Err(e) => return kernel_signature_error(&format!("excess_private_key: {e}")),
+    };
+    let nonce = match PrivateKey::from_hex(nonce) {
+        Ok(val) => val,
+        Err(e) => return kernel_signature_error(&format!("nonce: {e}")),
+    };
+    let features = match KernelFeatures::from_bits(kernel_features) {
+        Some(val) => val,
+        None => return kernel_signature_error(&format!("kernel_features: invalid bits {kernel_features}")),
+    };
+    let burn_commitment = match burn_commitment.as_deref().map(Commitment::from_hex).transpose() {
+        Ok(val) => val,
+        Err(e) => return kernel_signature_error(&format!("burn_commitment: {e}")),
+    };
+    if features.is_burned() != burn_commitment.is_some() {
+        return kernel_signature_error(
+            "burn_commitment: A burn commitment is required for, and only for, a burn kernel",
+        );
+    }
+
+    let version = TransactionKernelVersion::V0;
+    let fee = MicroMinotari::from(fee);
+    let excess = PublicKey::from_secret_key(&excess_private_key);
+    let public_nonce = PublicKey::from_secret_key(&nonce);
+    let challenge = TransactionKernel::build_kernel_signature_challenge(
+        &version,
+        &public_nonce,
+        &excess,
+        fee,
+        lock_height,
+        &features,
+        &burn_commitment,
+    );
+    let signature = match Signature::sign_raw_uniform(&excess_private_key, nonce, &challenge) {
+        Ok(val) => val,
+        Err(e) => return kernel_signature_error(&format!("Could not sign kernel: {e}")),
+    };
+
+    let excess = Commitment::from_public_key(&excess);
+    let kernel = match KernelBuilder::new()
+        .with_fee(fee)
+        .with_features(features)
+        .with_lock_height(lock_height)
+        .with_excess(&excess)
+        .with_signature(signature.clone())
+        .with_burn_commitment(burn_commitment)
+        .build()
+    {
+        Ok(val) => val,
+        Err(e) => return kernel_signature_error(&format!("Could not build kernel: {e}")),
+    };
+    match serde_json::to_string(&kernel) {
+        Ok(json) => KernelSignatureResult {
+            excess: Some(excess.to_hex()),
+            public_nonce: Some(signature.get_public_nonce().to_hex()),
+            signature: Some(signature.get_signature().to_hex()),
+            kernel: Some(json),
+            error: None,
+        },
+        Err(e) => kernel_signature_error(&format!("Could not serialize kernel: {e}")),
+    }
+}
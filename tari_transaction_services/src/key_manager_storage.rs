@@ -0,0 +1,599 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A transaction key manager whose branch indices are persisted through JavaScript storage callbacks, e.g. against
+//! IndexedDB or localStorage, so that the keys it hands out are not handed out again in a later session.
+//!
+//! The key manager's backend must be synchronous and shareable across threads, which JavaScript functions are not, so
+//! [`JsStorageBackend`] keeps the state in memory and queues the changed indices. [`PersistentKeyManager`] loads the
+//! indices through the `get` callback before the key manager is created, and writes the queued changes through the
+//! `set` callback after every call that changes them. Calls are serialized, so that the writes of one call are not
+//! interleaved with those of another. Imported keys are secret and are kept in memory only.
+//!
+//! The storage keys are namespaced by a fingerprint of the seed, so that several wallets can share the same storage.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+};
+
+use blake2::Blake2b;
+use digest::consts::U32;
+use js_sys::{Function, Promise, Reflect};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use tari_common_types::{
+    types::{PrivateKey, PublicKey},
+    wallet_types::WalletType,
+};
+use tari_core::transactions::key_manager::{TransactionKeyManagerBranch, TransactionKeyManagerWrapper};
+use tari_crypto::{
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    tari_utilities::hex::{to_hex, Hex},
+};
+use tari_key_manager::{
+    cipher_seed::CipherSeed,
+    key_manager_service::{
+        storage::database::{KeyManagerBackend, KeyManagerDatabase, KeyManagerState},
+        KeyManagerInterface,
+        KeyManagerStorageError,
+    },
+};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{cipher_seed_from_hex, factories::crypto_factories, session_guard::SessionGuard};
+
+hash_domain!(
+    KeyManagerStorageHashDomain,
+    "com.tari.transaction_services.key_manager_storage",
+    1
+);
+
+/// The prefix of the storage key of a branch's index
+const STORAGE_KEY_PREFIX: &str = "tari_key_manager/";
+
+/// The number of bytes of the seed fingerprint in the storage keys
+const FINGERPRINT_SIZE: usize = 8;
+
+/// A fingerprint of the seed (hex value) that identifies the wallet in the storage keys without revealing the seed
+fn seed_fingerprint(seed: &CipherSeed) -> String {
+    let hash = DomainSeparatedHasher::<Blake2b<U32>, KeyManagerStorageHashDomain>::new_with_label("fingerprint")
+        .chain(seed.entropy())
+        .finalize();
+    to_hex(&hash.as_ref()[..FINGERPRINT_SIZE])
+}
+
+/// The storage key of a branch's index, `tari_key_manager/<fingerprint>/<branch>`
+fn storage_key(fingerprint: &str, branch: &str) -> String {
+    format!("{STORAGE_KEY_PREFIX}{fingerprint}/{branch}")
+}
+
+struct BackendState {
+    fingerprint: String,
+    key_managers: HashMap<String, KeyManagerState>,
+    imported_keys: HashMap<PublicKey, PrivateKey>,
+    // The latest index per storage key not yet written, or `None` to delete the key
+    pending: BTreeMap<String, Option<String>>,
+}
+
+impl BackendState {
+    fn queue_index(&mut self, branch: &str, index: u64) {
+        let key = storage_key(&self.fingerprint, branch);
+        self.pending.insert(key, Some(index.to_string()));
+    }
+}
+
+/// A `KeyManagerBackend` that keeps the key manager state in memory and queues the changed branch indices to be
+/// written through the storage callbacks of a [`PersistentKeyManager`]. Clones share the same state.
+#[derive(Clone)]
+pub(crate) struct JsStorageBackend {
+    state: Arc<RwLock<BackendState>>,
+}
+
+fn lock_error<E: ToString>(e: E) -> KeyManagerStorageError {
+    KeyManagerStorageError::UnexpectedResult(format!("Storage backend lock poisoned: {}", e.to_string()))
+}
+
+impl JsStorageBackend {
+    /// Creates a backend whose storage keys are namespaced by the seed fingerprint
+    fn new(fingerprint: String) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(BackendState {
+                fingerprint,
+                key_managers: HashMap::new(),
+                imported_keys: HashMap::new(),
+                pending: BTreeMap::new(),
+            })),
+        }
+    }
+
+    /// Sets the index of a branch as loaded from storage, without queuing a write
+    fn load_index(&self, branch: &str, index: u64) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        state.key_managers.insert(branch.to_string(), KeyManagerState {
+            branch_seed: branch.to_string(),
+            primary_key_index: index,
+        });
+        Ok(())
+    }
+
+    /// Sets the index of a branch as stored, if there is one
+    fn load_stored_index(&self, branch: &str, stored: Option<&str>) -> Result<(), String> {
+        let stored = match stored {
+            Some(val) => val,
+            None => return Ok(()),
+        };
+        let index = stored
+            .parse::<u64>()
+            .map_err(|_| format!("storage: The index of branch `{branch}` is not a number"))?;
+        self.load_index(branch, index).map_err(|e| e.to_string())
+    }
+
+    /// Takes the queued writes
+    fn take_pending(&self) -> Result<BTreeMap<String, Option<String>>, KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        Ok(std::mem::take(&mut state.pending))
+    }
+
+    /// Puts back the writes of a failed flush, unless they have been superseded in the meantime
+    fn requeue(&self, writes: impl Iterator<Item = (String, Option<String>)>) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        for (key, value) in writes {
+            state.pending.entry(key).or_insert(value);
+        }
+        Ok(())
+    }
+
+    /// Queues the deletion of the stored index of every branch
+    fn queue_delete_all(&self) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        let keys = state
+            .key_managers
+            .keys()
+            .map(|branch| storage_key(&state.fingerprint, branch))
+            .collect::<Vec<_>>();
+        for key in keys {
+            state.pending.insert(key, None);
+        }
+        Ok(())
+    }
+
+    fn key_index(&self, branch: &str) -> Result<Option<u64>, KeyManagerStorageError> {
+        let state = self.state.read().map_err(lock_error)?;
+        Ok(state.key_managers.get(branch).map(|state| state.primary_key_index))
+    }
+}
+
+impl KeyManagerBackend<PublicKey> for JsStorageBackend {
+    fn get_key_manager(&self, branch: &str) -> Result<Option<KeyManagerState>, KeyManagerStorageError> {
+        let state = self.state.read().map_err(lock_error)?;
+        Ok(state.key_managers.get(branch).cloned())
+    }
+
+    fn add_key_manager(&self, key_manager: KeyManagerState) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        state.queue_index(&key_manager.branch_seed, key_manager.primary_key_index);
+        state.key_managers.insert(key_manager.branch_seed.clone(), key_manager);
+        Ok(())
+    }
+
+    fn increment_key_index(&self, branch: &str) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        let key_manager = state
+            .key_managers
+            .get_mut(branch)
+            .ok_or(KeyManagerStorageError::KeyManagerNotInitialized)?;
+        key_manager.primary_key_index += 1;
+        let index = key_manager.primary_key_index;
+        state.queue_index(branch, index);
+        Ok(())
+    }
+
+    fn set_key_index(&self, branch: &str, index: u64) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        let key_manager = state
+            .key_managers
+            .get_mut(branch)
+            .ok_or(KeyManagerStorageError::KeyManagerNotInitialized)?;
+        key_manager.primary_key_index = index;
+        state.queue_index(branch, index);
+        Ok(())
+    }
+
+    fn insert_imported_key(
+        &self,
+        public_key: PublicKey,
+        private_key: PrivateKey,
+    ) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        state.imported_keys.insert(public_key, private_key);
+        Ok(())
+    }
+
+    fn get_imported_key(&self, public_key: &PublicKey) -> Result<PrivateKey, KeyManagerStorageError> {
+        let state = self.state.read().map_err(lock_error)?;
+        state
+            .imported_keys
+            .get(public_key)
+            .cloned()
+            .ok_or(KeyManagerStorageError::ValueNotFound)
+    }
+}
+
+/// A key handed out by a `PersistentKeyManager`
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct ManagedKey {
+    /// The key branch
+    pub branch: String,
+    /// The key index
+    pub index: u64,
+    /// The key id, as used by the transaction key manager
+    pub key_id: String,
+    /// The public key (hex value)
+    pub public_key: String,
+}
+
+/// Calls a storage callback, awaiting the result if the callback returns a promise
+pub(crate) async fn call_storage(callback: &Function, args: &[&JsValue]) -> Result<JsValue, JsValue> {
+    let result = match args {
+        [key] => callback.call1(&JsValue::NULL, key)?,
+        [key, value] => callback.call2(&JsValue::NULL, key, value)?,
+        _ => return Err(JsValue::from_str("Unexpected storage callback arguments")),
+    };
+    match result.dyn_ref::<Promise>() {
+        Some(promise) => JsFuture::from(promise.clone()).await,
+        None => Ok(result),
+    }
+}
+
+fn storage_callback(storage: &JsValue, name: &str) -> Result<Function, JsValue> {
+    Reflect::get(storage, &JsValue::from_str(name))?
+        .dyn_into::<Function>()
+        .map_err(|_| JsValue::from_str(&format!("storage: `{name}` is not a function")))
+}
+
+fn storage_error(e: KeyManagerStorageError) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+/// A transaction key manager for the wallet seed whose branch indices are persisted through JavaScript storage
+/// callbacks, see the module documentation
+#[wasm_bindgen]
+pub struct PersistentKeyManager {
+    key_manager: TransactionKeyManagerWrapper<JsStorageBackend>,
+    backend: JsStorageBackend,
+    set: Function,
+    delete: Function,
+    guard: SessionGuard,
+}
+
+impl PersistentKeyManager {
+    /// Writes the queued index changes, putting them back if a callback fails
+    async fn flush(&self) -> Result<(), JsValue> {
+        let writes = self
+            .backend
+            .take_pending()
+            .map_err(storage_error)?
+            .into_iter()
+            .collect::<Vec<_>>();
+        for (i, (key, value)) in writes.iter().enumerate() {
+            let key = JsValue::from_str(key);
+            let result = match value {
+                Some(value) => call_storage(&self.set, &[&key, &JsValue::from_str(value)]).await,
+                None => call_storage(&self.delete, &[&key]).await,
+            };
+            if let Err(e) = result {
+                self.backend
+                    .requeue(writes[i..].iter().cloned())
+                    .map_err(storage_error)?;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl PersistentKeyManager {
+    /// Creates a key manager for a cipher seed (hex value), loading the branch indices from storage
+    /// * `storage` - an object with `get(key)`, `set(key, value)` and `delete(key)` functions, each of which may return
+    ///   a promise; `get` returns the stored string or `null` or `undefined` if there is none
+    /// * `branches` - branches to load besides those of the transaction key manager, which are always loaded
+    pub async fn load(
+        seed: String,
+        storage: JsValue,
+        branches: Option<Vec<String>>,
+    ) -> Result<PersistentKeyManager, JsValue> {
+        let seed = cipher_seed_from_hex(&seed).map_err(|e| JsValue::from_str(&e))?;
+        let get = storage_callback(&storage, "get")?;
+        let set = storage_callback(&storage, "set")?;
+        let delete = storage_callback(&storage, "delete")?;
+
+        let fingerprint = seed_fingerprint(&seed);
+        let backend = JsStorageBackend::new(fingerprint.clone());
+        let branches = TransactionKeyManagerBranch::iter()
+            .map(|branch| branch.get_branch_key())
+            .chain(branches.unwrap_or_default());
+        for branch in branches {
+            let stored = call_storage(&get, &[&JsValue::from_str(&storage_key(&fingerprint, &branch))]).await?;
+            let stored = if stored.is_null() || stored.is_undefined() {
+                None
+            } else {
+                Some(stored.as_string().unwrap_or_default())
+            };
+            backend
+                .load_stored_index(&branch, stored.as_deref())
+                .map_err(|e| JsValue::from_str(&e))?;
+        }
+
+        let key_manager = TransactionKeyManagerWrapper::new(
+            seed,
+            KeyManagerDatabase::new(backend.clone()),
+            crypto_factories(),
+            WalletType::default(),
+        )
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let key_manager = Self {
+            key_manager,
+            backend,
+            set,
+            delete,
+            guard: SessionGuard::new(),
+        };
+        // Stores the branches that were not stored yet
+        key_manager.flush().await?;
+        Ok(key_manager)
+    }
+
+    /// Hands out the next key on a branch, storing the branch's new index
+    pub async fn get_next_key(&self, branch: String) -> Result<ManagedKey, JsValue> {
+        let _permit = self.guard.acquire("get_next_key").await;
+        self.key_manager
+            .add_new_branch(branch.as_str())
+            .await
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let (key_id, public_key) = self
+            .key_manager
+            .get_next_key(branch.as_str())
+            .await
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.flush().await?;
+        Ok(ManagedKey {
+            index: key_id.managed_index().unwrap_or_default(),
+            key_id: key_id.to_string(),
+            public_key: public_key.to_hex(),
+            branch,
+        })
+    }
+
+    /// Advances the index of a branch to `index` if it is higher, e.g. after recovering outputs whose keys were handed
+    /// out in another session, storing the branch's new index
+    pub async fn update_key_index_if_higher(&self, branch: String, index: u64) -> Result<(), JsValue> {
+        let _permit = self.guard.acquire("update_key_index_if_higher").await;
+        self.key_manager
+            .add_new_branch(branch.as_str())
+            .await
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.key_manager
+            .update_current_key_index_if_higher(branch.as_str(), index)
+            .await
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.flush().await
+    }
+
+    /// The index of the last key handed out on a branch, if the branch is loaded
+    pub fn key_index(&self, branch: &str) -> Result<Option<u64>, JsValue> {
+        self.backend.key_index(branch).map_err(storage_error)
+    }
+
+    /// Deletes the stored index of every loaded branch, e.g. when the wallet is removed from the browser. The key
+    /// manager keeps its indices in memory, and stores a branch's index again when it changes.
+    pub async fn clear_storage(&self) -> Result<(), JsValue> {
+        let _permit = self.guard.acquire("clear_storage").await;
+        self.backend.queue_delete_all().map_err(storage_error)?;
+        self.flush().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BRANCH: &str = "test_branch";
+
+    // Writes the queued changes of a backend into `store`, as the `set` and `delete` callbacks would
+    fn flush_into(backend: &JsStorageBackend, store: &mut BTreeMap<String, String>) {
+        for (key, value) in backend.take_pending().unwrap() {
+            match value {
+                Some(value) => store.insert(key, value),
+                None => store.remove(&key),
+            };
+        }
+    }
+
+    // Loads the indices of the branches from `store` into a new backend, as `PersistentKeyManager::load` does
+    fn load_from(store: &BTreeMap<String, String>, fingerprint: &str, branches: &[&str]) -> JsStorageBackend {
+        let backend = JsStorageBackend::new(fingerprint.to_string());
+        for branch in branches {
+            let stored = store.get(&storage_key(fingerprint, branch));
+            backend.load_stored_index(branch, stored.map(String::as_str)).unwrap();
+        }
+        backend
+    }
+
+    fn add_branch(db: &KeyManagerDatabase<JsStorageBackend, PublicKey>, branch: &str) {
+        db.set_key_manager_state(KeyManagerState {
+            branch_seed: branch.to_string(),
+            primary_key_index: 0,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn indices_survive_a_reload() {
+        let fingerprint = seed_fingerprint(&CipherSeed::new());
+        let backend = JsStorageBackend::new(fingerprint.clone());
+        let db = KeyManagerDatabase::<_, PublicKey>::new(backend.clone());
+        add_branch(&db, BRANCH);
+        for _ in 0..3 {
+            db.increment_key_index(BRANCH).unwrap();
+        }
+        let mut store = BTreeMap::new();
+        flush_into(&backend, &mut store);
+        assert_eq!(
+            store,
+            BTreeMap::from([(storage_key(&fingerprint, BRANCH), "3".to_string())])
+        );
+        assert!(backend.take_pending().unwrap().is_empty());
+
+        // The next key after a reload is the one the first session would have handed out next
+        let reloaded = load_from(&store, &fingerprint, &[BRANCH, "other_branch"]);
+        assert_eq!(reloaded.key_index(BRANCH).unwrap(), Some(3));
+        assert_eq!(reloaded.key_index("other_branch").unwrap(), None);
+        let reloaded_db = KeyManagerDatabase::<_, PublicKey>::new(reloaded.clone());
+        reloaded_db.increment_key_index(BRANCH).unwrap();
+        db.increment_key_index(BRANCH).unwrap();
+        assert_eq!(reloaded.key_index(BRANCH).unwrap(), Some(4));
+        assert_eq!(backend.key_index(BRANCH).unwrap(), reloaded.key_index(BRANCH).unwrap());
+        flush_into(&reloaded, &mut store);
+        assert_eq!(store[&storage_key(&fingerprint, BRANCH)], "4");
+
+        reloaded.set_key_index(BRANCH, 10).unwrap();
+        flush_into(&reloaded, &mut store);
+        let reloaded = load_from(&store, &fingerprint, &[BRANCH]);
+        assert_eq!(reloaded.key_index(BRANCH).unwrap(), Some(10));
+    }
+
+    #[test]
+    fn loading_does_not_queue_writes() {
+        let fingerprint = seed_fingerprint(&CipherSeed::new());
+        let store = BTreeMap::from([(storage_key(&fingerprint, BRANCH), "7".to_string())]);
+        let backend = load_from(&store, &fingerprint, &[BRANCH]);
+        assert_eq!(backend.key_index(BRANCH).unwrap(), Some(7));
+        assert!(backend.take_pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn cleared_indices_are_not_reloaded() {
+        let fingerprint = seed_fingerprint(&CipherSeed::new());
+        let backend = JsStorageBackend::new(fingerprint.clone());
+        let db = KeyManagerDatabase::<_, PublicKey>::new(backend.clone());
+        add_branch(&db, BRANCH);
+        db.increment_key_index(BRANCH).unwrap();
+        let mut store = BTreeMap::new();
+        flush_into(&backend, &mut store);
+        assert_eq!(store.len(), 1);
+
+        backend.queue_delete_all().unwrap();
+        flush_into(&backend, &mut store);
+        assert!(store.is_empty());
+        let reloaded = load_from(&store, &fingerprint, &[BRANCH]);
+        assert_eq!(reloaded.key_index(BRANCH).unwrap(), None);
+    }
+
+    #[test]
+    fn failed_writes_are_requeued_unless_superseded() {
+        let fingerprint = seed_fingerprint(&CipherSeed::new());
+        let backend = JsStorageBackend::new(fingerprint.clone());
+        let db = KeyManagerDatabase::<_, PublicKey>::new(backend.clone());
+        add_branch(&db, BRANCH);
+        add_branch(&db, "other_branch");
+        let failed = backend.take_pending().unwrap();
+        assert_eq!(failed.len(), 2);
+        db.increment_key_index(BRANCH).unwrap();
+        backend.requeue(failed.into_iter()).unwrap();
+
+        let mut store = BTreeMap::new();
+        flush_into(&backend, &mut store);
+        assert_eq!(store[&storage_key(&fingerprint, BRANCH)], "1");
+        assert_eq!(store[&storage_key(&fingerprint, "other_branch")], "0");
+    }
+
+    #[test]
+    fn wallets_sharing_a_storage_do_not_share_indices() {
+        let first = seed_fingerprint(&CipherSeed::new());
+        let second = seed_fingerprint(&CipherSeed::new());
+        assert_ne!(first, second);
+        assert_eq!(first.len(), FINGERPRINT_SIZE * 2);
+        assert_eq!(
+            storage_key(&first, BRANCH),
+            format!("tari_key_manager/{first}/{BRANCH}")
+        );
+
+        let backend = JsStorageBackend::new(first.clone());
+        add_branch(&KeyManagerDatabase::<_, PublicKey>::new(backend.clone()), BRANCH);
+        let mut store = BTreeMap::new();
+        flush_into(&backend, &mut store);
+        let other = load_from(&store, &second, &[BRANCH]);
+        assert_eq!(other.key_index(BRANCH).unwrap(), None);
+    }
+
+    #[test]
+    fn malformed_stored_indices_are_rejected() {
+        let backend = JsStorageBackend::new("00".to_string());
+        let error = backend.load_stored_index(BRANCH, Some("not a number")).unwrap_err();
+        assert_eq!(
+            error,
+            format!("storage: The index of branch `{BRANCH}` is not a number")
+        );
+        assert!(backend.load_stored_index(BRANCH, Some("-1")).is_err());
+        assert!(backend.load_stored_index(BRANCH, Some("")).is_err());
+        assert_eq!(backend.key_index(BRANCH).unwrap(), None);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn map_storage(map: &js_sys::Map) -> JsValue {
+        use wasm_bindgen::closure::Closure;
+
+        let storage = js_sys::Object::new();
+        let get = {
+            let map = map.clone();
+            Closure::<dyn Fn(JsValue) -> JsValue>::new(move |key: JsValue| map.get(&key))
+        };
+        let set = {
+            let map = map.clone();
+            Closure::<dyn Fn(JsValue, JsValue)>::new(move |key: JsValue, value: JsValue| {
+                map.set(&key, &value);
+            })
+        };
+        let delete = {
+            let map = map.clone();
+            Closure::<dyn Fn(JsValue)>::new(move |key: JsValue| {
+                map.delete(&key);
+            })
+        };
+        Reflect::set(&storage, &JsValue::from_str("get"), get.as_ref()).unwrap();
+        Reflect::set(&storage, &JsValue::from_str("set"), set.as_ref()).unwrap();
+        Reflect::set(&storage, &JsValue::from_str("delete"), delete.as_ref()).unwrap();
+        get.forget();
+        set.forget();
+        delete.forget();
+        storage.into()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    async fn persistent_key_manager_resumes_after_a_reload() {
+        let seed = CipherSeed::new().encipher(None).unwrap().to_hex();
+        let map = js_sys::Map::new();
+        let key_manager = PersistentKeyManager::load(seed.clone(), map_storage(&map), None)
+            .await
+            .unwrap();
+        key_manager.get_next_key(BRANCH.to_string()).await.unwrap();
+        let last = key_manager.get_next_key(BRANCH.to_string()).await.unwrap();
+
+        let branches = Some(vec![BRANCH.to_string()]);
+        let reloaded = PersistentKeyManager::load(seed, map_storage(&map), branches)
+            .await
+            .unwrap();
+        assert_eq!(reloaded.key_index(BRANCH).unwrap(), Some(last.index));
+        let next = reloaded.get_next_key(BRANCH.to_string()).await.unwrap();
+        assert_eq!(next.index, last.index + 1);
+        assert_ne!(next.public_key, last.public_key);
+    }
+}
@@ -6,13 +6,99 @@
 extern crate std;
 
 use serde::{Deserialize, Serialize};
-use wasm_bindgen::JsValue;
+use tari_core::transactions::transaction_components::TransactionOutput;
+use tari_crypto::tari_utilities::hex::from_hex;
+use tari_key_manager::cipher_seed::CipherSeed;
+use tsify::Tsify;
+#[cfg(feature = "threads")]
+pub use wasm_bindgen_rayon::init_thread_pool;
 
+use crate::{
+    error::{ScanErrorCode, WasmError},
+    output_features::RecoveredOutputFeatures,
+    script_tools::script_lock_height,
+};
+
+mod address;
+mod aggregate_body;
+mod amounts;
+mod bench;
+mod burn;
+mod coin_selection;
+mod compression;
+mod consensus_encoding;
+mod covenant_builder;
+mod covenant_decoder;
+mod covenant_execution;
+mod create_output;
+mod crypto_utils;
+mod custody_log;
+mod deposit_tagging;
+mod dh_cache;
+mod diagnostics;
+mod double_spend;
+mod encrypted_data;
+mod environment;
+pub mod error;
+mod factories;
+mod fees;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+mod gap_limit;
+#[cfg(feature = "grpc-web")]
+mod grpc_web;
+mod headers;
+mod init;
+mod kernel_signature;
+mod key_manager_storage;
+mod memory;
+mod mnemonic;
+mod network;
+mod one_sided_transaction;
+mod output_encoding;
+mod output_features;
+mod output_hashing;
+mod output_store;
+mod parallel;
+mod peer_backup;
+mod privacy_analysis;
+mod proto;
+mod result_envelope;
+mod scan_block;
+mod scan_coinbase;
 mod scan_outputs;
 mod scan_outputs_ledger;
+mod scan_receipt;
+mod scan_recovery;
+mod scan_report;
+mod scan_statistics;
+mod script_tools;
+mod secrets;
+pub mod session_guard;
+mod signing_request;
+mod size_budget;
+mod smt_proofs;
+mod spent_outputs;
+mod sweep;
+mod sync;
+#[cfg(feature = "test_vectors")]
+mod test_vectors;
+mod transaction_input;
+mod validation;
+mod validator_node;
+mod value_promise;
+mod verification_queue;
+mod verify_outputs;
+mod versions;
+mod wallet_file;
+mod wallet_keys;
+mod wallet_output;
+mod write_buffer;
 
 /// A struct to hold the parameters for a successful one-sided payment output recovery
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
 pub struct RecoveredOutputResult {
     /// The hash of the output (hex value)
     pub hash: Option<String>,
@@ -20,28 +106,66 @@ pub struct RecoveredOutputResult {
     pub output_source: Option<String>,
     /// The output type
     pub output_type: Option<String>,
+    /// The output features
+    pub features: Option<RecoveredOutputFeatures>,
     /// The output value
     pub value: Option<u64>,
+    /// The minimum value promise of the output
+    pub minimum_value_promise: Option<u64>,
+    /// Whether the minimum value promise exceeds the decrypted value, which marks a malformed or suspicious output
+    /// whose range proof cannot verify
+    pub promise_exceeds_value: Option<bool>,
     /// The output spending private key (hex value)
     pub spending_key: Option<String>,
     /// The script private key (hex value)
     pub script_key: Option<String>,
-    /// The output lock height
+    /// The output lock height, from the output features
     pub maturity: Option<u64>,
+    /// The height lock of the output's script, from its `CheckHeight` or `CompareHeight` opcodes
+    pub lock_height: Option<u64>,
+    /// The height from which the output can be spent, the later of its maturity and script lock height
+    pub spendable_at_height: Option<u64>,
+    /// The tags of the deposit rules matched by the output
+    pub tags: Vec<String>,
+    /// The kind of error, in case of an error
+    pub error_code: Option<ScanErrorCode>,
     /// An error message in cased of an error
     pub error: Option<String>,
 }
 
+impl From<WasmError> for RecoveredOutputResult {
+    fn from(error: WasmError) -> Self {
+        Self {
+            error_code: Some(error.code),
+            error: Some(error.message),
+            ..Default::default()
+        }
+    }
+}
+
+/// Returns the maturity, the script lock height and the height from which an output can be spent
+pub(crate) fn lock_heights(output: &TransactionOutput) -> (u64, Option<u64>, u64) {
+    let maturity = output.features.maturity;
+    let lock_height = script_lock_height(&output.script);
+    (
+        maturity,
+        lock_height,
+        lock_height.map_or(maturity, |height| height.max(maturity)),
+    )
+}
+
 /// Returns a scan error message
-pub fn scan_error(error: &str) -> JsValue {
-    let scan_result = RecoveredOutputResult {
-        error: Some(error.to_string()),
-        ..Default::default()
-    };
-    serde_wasm_bindgen::to_value(&scan_result).unwrap()
+pub fn scan_error(error: WasmError) -> RecoveredOutputResult {
+    RecoveredOutputResult::from(error)
 }
 
 /// Returns a no match message
-pub fn no_match() -> JsValue {
-    serde_wasm_bindgen::to_value(&RecoveredOutputResult::default()).unwrap()
+pub fn no_match() -> RecoveredOutputResult {
+    RecoveredOutputResult::default()
+}
+
+/// Decodes a cipher seed from its hex value, as returned by `create_cipher_seed` or `mnemonic_to_cipher_seed`
+pub fn cipher_seed_from_hex(seed: &str) -> Result<CipherSeed, String> {
+    let bytes = from_hex(seed).map_err(|e| format!("seed: {e}"))?;
+    CipherSeed::from_enciphered_bytes(&bytes, None).map_err(|e| format!("seed: {e}"))
 }
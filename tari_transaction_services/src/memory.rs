@@ -0,0 +1,214 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Heap instrumentation and buffer reuse, so that embedders can tune batch and chunk sizes on low-memory mobile
+//! browsers. With the `memory-stats` feature, the global allocator of wasm builds is the system allocator with counters
+//! of the live and peak heap bytes. WebAssembly linear memory only grows, so its size is the high-water mark of the
+//! heap plus the allocator's overhead.
+//!
+//! Batch scanning decodes every output from hex or base64 into a short-lived byte buffer. [`with_scratch_buffer`]
+//! lends out buffers from a per-thread pool instead, so that a batch reuses the same few allocations, and keeps them
+//! for the next batch up to a limit.
+
+use std::cell::RefCell;
+#[cfg(feature = "memory-stats")]
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// The size of a WebAssembly memory page
+#[cfg(target_arch = "wasm32")]
+const WASM_PAGE_BYTES: usize = 64 * 1024;
+
+/// The most buffers kept in the pool of each thread
+const MAX_POOLED_BUFFERS: usize = 8;
+/// The largest buffer kept in the pool; larger buffers are freed when they are returned
+const MAX_POOLED_BUFFER_BYTES: usize = 64 * 1024;
+
+thread_local! {
+    static BUFFER_POOL: RefCell<Vec<Vec<u8>>> = RefCell::new(Vec::new());
+}
+
+/// Runs `f` with an empty byte buffer from the pool of the calling thread, or a new one if the pool is empty, and
+/// returns the buffer to the pool afterwards unless the pool is full or the buffer grew beyond the pooled size
+pub(crate) fn with_scratch_buffer<R, F: FnOnce(&mut Vec<u8>) -> R>(f: F) -> R {
+    let mut buffer = BUFFER_POOL.with(|pool| pool.borrow_mut().pop()).unwrap_or_default();
+    buffer.clear();
+    let result = f(&mut buffer);
+    if buffer.capacity() <= MAX_POOLED_BUFFER_BYTES {
+        BUFFER_POOL.with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < MAX_POOLED_BUFFERS {
+                pool.push(buffer);
+            }
+        });
+    }
+    result
+}
+
+/// The capacity of the pooled buffers of the calling thread, in bytes
+fn pooled_bytes() -> usize {
+    BUFFER_POOL.with(|pool| pool.borrow().iter().map(Vec::capacity).sum())
+}
+
+/// The system allocator, counting the heap bytes in use. It is only installed in wasm builds with the `memory-stats`
+/// feature, so that native users of the crate and embedders with their own allocator keep their global allocator.
+#[cfg(feature = "memory-stats")]
+struct CountingAllocator {
+    allocated: AtomicUsize,
+    peak: AtomicUsize,
+    allocations: AtomicUsize,
+}
+
+#[cfg(feature = "memory-stats")]
+impl CountingAllocator {
+    fn record_alloc(&self, size: usize) {
+        let allocated = self.allocated.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak.fetch_max(allocated, Ordering::Relaxed);
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_dealloc(&self, size: usize) {
+        self.allocated.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "memory-stats")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            self.record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.record_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            self.record_dealloc(layout.size());
+            self.record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(feature = "memory-stats")]
+#[cfg_attr(target_arch = "wasm32", global_allocator)]
+static ALLOCATOR: CountingAllocator = CountingAllocator {
+    allocated: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+    allocations: AtomicUsize::new(0),
+};
+
+/// A snapshot of the library's memory use
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct MemoryStats {
+    /// The size of the WebAssembly linear memory, in bytes, which never shrinks
+    pub linear_memory_bytes: u64,
+    /// The heap bytes in use, with the `memory-stats` feature
+    pub allocated_bytes: Option<u64>,
+    /// The most heap bytes in use at once since the library was loaded or `reset_peak_memory` was called, with the
+    /// `memory-stats` feature
+    pub peak_allocated_bytes: Option<u64>,
+    /// The number of allocations, including reallocations, since the library was loaded, with the `memory-stats`
+    /// feature
+    pub allocations: Option<u64>,
+    /// The bytes held by the scratch buffers kept for reuse on the calling thread
+    pub pooled_bytes: u64,
+}
+
+#[cfg(target_arch = "wasm32")]
+fn linear_memory_bytes() -> usize {
+    core::arch::wasm32::memory_size(0) * WASM_PAGE_BYTES
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn linear_memory_bytes() -> usize {
+    0
+}
+
+/// Returns the size of the linear memory, the bytes kept in the buffer pool and, with the `memory-stats` feature, the
+/// current and peak heap use, e.g. to measure the peak of a batch call after `reset_peak_memory` and choose a chunk
+/// size that fits the device
+#[wasm_bindgen]
+pub fn get_memory_stats() -> MemoryStats {
+    #[cfg(feature = "memory-stats")]
+    let (allocated_bytes, peak_allocated_bytes, allocations) = (
+        Some(ALLOCATOR.allocated.load(Ordering::Relaxed) as u64),
+        Some(ALLOCATOR.peak.load(Ordering::Relaxed) as u64),
+        Some(ALLOCATOR.allocations.load(Ordering::Relaxed) as u64),
+    );
+    #[cfg(not(feature = "memory-stats"))]
+    let (allocated_bytes, peak_allocated_bytes, allocations) = (None, None, None);
+    MemoryStats {
+        linear_memory_bytes: linear_memory_bytes() as u64,
+        allocated_bytes,
+        peak_allocated_bytes,
+        allocations,
+        pooled_bytes: pooled_bytes() as u64,
+    }
+}
+
+/// Resets the peak heap use to the heap bytes currently in use; does nothing without the `memory-stats` feature
+#[wasm_bindgen]
+pub fn reset_peak_memory() {
+    #[cfg(feature = "memory-stats")]
+    ALLOCATOR
+        .peak
+        .store(ALLOCATOR.allocated.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Frees the scratch buffers kept for reuse on the calling thread, e.g. after a large batch on a low-memory device
+#[wasm_bindgen]
+pub fn release_memory_pool() {
+    BUFFER_POOL.with(|pool| pool.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buffers_are_reused() {
+        release_memory_pool();
+        let ptr = with_scratch_buffer(|buffer| {
+            buffer.extend_from_slice(&[1u8; 100]);
+            buffer.as_ptr()
+        });
+        assert!(pooled_bytes() >= 100);
+        with_scratch_buffer(|buffer| {
+            assert!(buffer.is_empty());
+            assert_eq!(buffer.as_ptr(), ptr);
+        });
+        release_memory_pool();
+        assert_eq!(pooled_bytes(), 0);
+    }
+
+    #[test]
+    fn large_buffers_are_not_kept() {
+        release_memory_pool();
+        with_scratch_buffer(|buffer| buffer.resize(MAX_POOLED_BUFFER_BYTES + 1, 0));
+        assert_eq!(pooled_bytes(), 0);
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tari_crypto::tari_utilities::{hex::Hex, SafePassword};
+use tari_key_manager::{
+    cipher_seed::CipherSeed,
+    mnemonic::{Mnemonic, MnemonicLanguage},
+};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::cipher_seed_from_hex;
+
+/// A struct to hold a cipher seed. The seed is the hex value of the seed enciphered without a passphrase.
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct CipherSeedResult {
+    /// The enciphered seed (hex value)
+    pub seed: Option<String>,
+    /// The seed birthday, in days since 2022-01-01
+    pub birthday: Option<u16>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+/// A struct to hold a mnemonic word sequence
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SeedWordsResult {
+    /// The mnemonic words
+    pub words: Option<Vec<String>>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+pub(crate) fn cipher_seed_result(seed: &CipherSeed) -> CipherSeedResult {
+    match seed.encipher(None) {
+        Ok(bytes) => CipherSeedResult {
+            seed: Some(bytes.to_hex()),
+            birthday: Some(seed.birthday()),
+            error: None,
+        },
+        Err(e) => CipherSeedResult {
+            error: Some(format!("seed: {e}")),
+            ..Default::default()
+        },
+    }
+}
+
+fn seed_words_error(error: &str) -> SeedWordsResult {
+    SeedWordsResult {
+        words: None,
+        error: Some(error.to_string()),
+    }
+}
+
+/// Creates a new random cipher seed with the current date as birthday
+#[wasm_bindgen]
+pub fn create_cipher_seed() -> CipherSeedResult {
+    cipher_seed_result(&CipherSeed::new())
+}
+
+/// Encodes a cipher seed as a 24-word mnemonic sequence in the specified language (e.g. `english`). The seed words
+/// are enciphered with the optional passphrase, which is then also required to restore the seed.
+#[wasm_bindgen]
+pub fn cipher_seed_to_mnemonic(seed: &str, language: &str, passphrase: Option<String>) -> SeedWordsResult {
+    let seed = match cipher_seed_from_hex(seed) {
+        Ok(val) => val,
+        Err(e) => return seed_words_error(&e),
+    };
+    let language = match MnemonicLanguage::from_str(language) {
+        Ok(val) => val,
+        Err(e) => return seed_words_error(&format!("language: {e}")),
+    };
+
+    match seed.to_mnemonic(language, passphrase.map(SafePassword::from)) {
+        Ok(words) => SeedWordsResult {
+            words: Some(words),
+            error: None,
+        },
+        Err(e) => seed_words_error(&e.to_string()),
+    }
+}
+
+/// Restores a cipher seed from a 24-word mnemonic sequence and the optional passphrase it was enciphered with. The
+/// language of the words is detected automatically.
+#[wasm_bindgen]
+pub fn mnemonic_to_cipher_seed(words: Vec<String>, passphrase: Option<String>) -> CipherSeedResult {
+    match CipherSeed::from_mnemonic(&words, passphrase.map(SafePassword::from)) {
+        Ok(seed) => cipher_seed_result(&seed),
+        Err(e) => CipherSeedResult {
+            error: Some(e.to_string()),
+            ..Default::default()
+        },
+    }
+}
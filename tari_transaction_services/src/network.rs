@@ -0,0 +1,183 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! The network that consensus hashes, e.g. output hashes and signature challenges, are separated by. The global
+//! network setting can only be set once per page, so the library keeps its own, which `set_network` changes and
+//! functions taking a `network` override for the duration of the call, so that one bundle can serve several networks.
+//! The override only applies to the calling thread and is restored even if the call panics, so work spread across the
+//! thread pool passes the network on to each worker.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tari_common::configuration::Network;
+use tari_core::{
+    consensus::{consensus_network, set_consensus_network, with_consensus_network},
+    transactions::transaction_components::TransactionOutput,
+};
+use tari_crypto::tari_utilities::hex::Hex;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    error::{ScanErrorCode, WasmError},
+    output_encoding::{decode_output, OutputEncoding},
+    scan_error,
+    scan_outputs::{scan_result, ScannerContext},
+    RecoveredOutputResult,
+};
+
+/// Parses an optional network name, e.g. `mainnet`
+pub(crate) fn parse_network(network: Option<&str>) -> Result<Option<Network>, String> {
+    network
+        .map(Network::from_str)
+        .transpose()
+        .map_err(|e| format!("network: {e}"))
+}
+
+/// Parses an optional network name for a scanning function, reporting an unknown network as a scan error
+pub(crate) fn parse_scan_network(network: Option<&str>) -> Result<Option<Network>, WasmError> {
+    parse_network(network).map_err(|e| WasmError::new(ScanErrorCode::UnknownNetwork, e))
+}
+
+/// Runs `f` with consensus hashes separated by `network`, if provided, or by the library's network
+pub(crate) fn on_network<R, F: FnOnce() -> R>(network: Option<Network>, f: F) -> R {
+    match network {
+        Some(network) => with_consensus_network(network, f),
+        None => f(),
+    }
+}
+
+/// Sets the network that hashing, scanning and validation use when no `network` is passed to them, and that new
+/// addresses default to, e.g. `mainnet`, `nextnet` or `esmeralda`. Unlike the network set by `init`, it can be changed
+/// at any time.
+#[wasm_bindgen]
+pub fn set_network(network: &str) -> Result<(), JsValue> {
+    let network = parse_network(Some(network)).map_err(|e| JsValue::from_str(&e))?;
+    set_consensus_network(network);
+    Ok(())
+}
+
+/// Returns the network that hashing, scanning and validation use when no `network` is passed to them
+#[wasm_bindgen]
+pub fn get_network() -> String {
+    consensus_network().to_string()
+}
+
+/// A struct to hold the network a transaction output verifies under
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct OutputNetworkResult {
+    /// The network the output's metadata signature verifies under, e.g. `mainnet`
+    pub network: Option<String>,
+    /// The hash of the output on that network (hex value)
+    pub hash: Option<String>,
+    /// An error message in case of an error, or if the output verifies under none of the networks
+    pub error: Option<String>,
+}
+
+fn output_network_error(error: &str) -> OutputNetworkResult {
+    OutputNetworkResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Parses a list of network names, e.g. `["mainnet", "nextnet"]`
+fn parse_networks(networks: &[String]) -> Result<Vec<Network>, String> {
+    if networks.is_empty() {
+        return Err("networks: At least one network is required".to_string());
+    }
+    networks
+        .iter()
+        .map(|network| Network::from_str(network).map_err(|e| format!("networks: {e}")))
+        .collect()
+}
+
+/// The first of `networks` whose consensus hashes the output's metadata signature verifies under. The signature
+/// challenge commits to the network byte, so an output verifies under the network it was created for only.
+fn verifying_network(output: &TransactionOutput, networks: &[Network]) -> Result<Network, String> {
+    networks
+        .iter()
+        .copied()
+        .find(|network| with_consensus_network(*network, || output.verify_metadata_signature().is_ok()))
+        .ok_or_else(|| {
+            let names = networks.iter().map(|network| network.to_string()).collect::<Vec<_>>();
+            format!("The output does not verify under any of {}", names.join(", "))
+        })
+}
+
+/// Finds which of `networks` a transaction output of unknown origin was created for, by verifying its metadata
+/// signature under each, and returns its hash on that network. The output is passed in any of the encodings accepted
+/// by `scan_output_for_one_sided_payment`.
+/// * `networks` - the networks to try, in order, e.g. `["mainnet", "nextnet", "esmeralda"]`
+#[wasm_bindgen]
+pub fn detect_output_network(
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    networks: Vec<String>,
+) -> OutputNetworkResult {
+    let networks = match parse_networks(&networks) {
+        Ok(val) => val,
+        Err(e) => return output_network_error(&e),
+    };
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => return output_network_error(&e.to_string()),
+    };
+    match verifying_network(&output, &networks) {
+        Ok(network) => OutputNetworkResult {
+            network: Some(network.to_string()),
+            hash: Some(with_consensus_network(network, || output.hash()).to_hex()),
+            error: None,
+        },
+        Err(e) => output_network_error(&e),
+    }
+}
+
+/// The result of scanning a transaction output of unknown origin, tagged with the network it verifies under
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct NetworkScanResult {
+    /// The network the output's metadata signature verifies under, e.g. `mainnet`
+    pub network: Option<String>,
+    /// The result of scanning the output, with its hash on that network
+    #[serde(flatten)]
+    pub result: RecoveredOutputResult,
+}
+
+#[wasm_bindgen]
+impl ScannerContext {
+    /// Scans a transaction output of unknown origin as `scan` does, after finding which of `networks` it was created
+    /// for as `detect_output_network` does. The output's hash is computed for that network, which is returned with the
+    /// result. An output that verifies under none of the networks is reported with the `UnknownNetwork` error code.
+    pub fn scan_any_network(
+        &self,
+        output: JsValue,
+        encoding: Option<OutputEncoding>,
+        networks: Vec<String>,
+    ) -> Result<NetworkScanResult, JsValue> {
+        let networks = parse_networks(&networks).map_err(|e| JsValue::from_str(&e))?;
+        let output = match decode_output(&output, encoding) {
+            Ok(val) => val,
+            Err(e) => {
+                return Ok(NetworkScanResult {
+                    network: None,
+                    result: scan_error(e),
+                })
+            },
+        };
+        Ok(match verifying_network(&output, &networks) {
+            Ok(network) => NetworkScanResult {
+                network: Some(network.to_string()),
+                result: scan_result(self.scan_on_network(&output, Some(network))),
+            },
+            Err(e) => NetworkScanResult {
+                network: None,
+                result: scan_error(WasmError::new(ScanErrorCode::UnknownNetwork, e)),
+            },
+        })
+    }
+}
@@ -0,0 +1,377 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_core::{
+    borsh::SerializedSize,
+    covenants::Covenant,
+    transactions::{
+        key_manager::{
+            create_memory_db_key_manager,
+            MemoryDbKeyManager,
+            TariKeyId,
+            TransactionKeyManagerInterface,
+            TxoStage,
+        },
+        tari_amount::MicroMinotari,
+        transaction_components::{OutputFeatures, TransactionBuilder, TransactionOutput, TransactionOutputVersion},
+        weight::TransactionWeight,
+    },
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{PublicKey as PK, SecretKey},
+    tari_utilities::hex::Hex,
+};
+use tari_key_manager::{cipher_seed::CipherSeed, KeyManagerInterface};
+use tari_script::script;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    address::one_sided_recipient_keys,
+    coin_selection::{select_coins, CoinSelectionStrategy},
+    create_output::{build_stealth_output, SenderKeys},
+    factories::crypto_factories,
+    output_encoding::{decode_output_str, OutputEncoding},
+    script_tools::spending_input_data,
+    sweep::{import_input, sign_kernel},
+    value_promise::MinimumValuePromisePolicy,
+};
+
+/// A recovered output to spend, with the value and keys reported by the scanner
+#[derive(Debug, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendableOutput {
+    /// The output, as hex-encoded bytes or JSON
+    pub output: String,
+    /// The value of the output
+    pub value: u64,
+    /// The commitment mask of the output (hex value)
+    pub spending_key: String,
+    /// The script private key of the output (hex value)
+    pub script_key: String,
+}
+
+/// A struct to hold a fully-signed one-sided payment transaction
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct OneSidedTransactionResult {
+    /// The transaction, serialized as JSON
+    pub transaction: Option<String>,
+    /// The hash of the payment output (hex value)
+    pub payment_output_hash: Option<String>,
+    /// The hash of the change output, if one was created (hex value)
+    pub change_output_hash: Option<String>,
+    /// The value of the change output, 0 if no change output was created
+    pub change: Option<u64>,
+    /// The fee paid by the transaction
+    pub fee: Option<u64>,
+    /// The indexes of the inputs spent, in ascending order
+    pub selected_inputs: Option<Vec<usize>>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn one_sided_transaction_error(error: &str) -> OneSidedTransactionResult {
+    OneSidedTransactionResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+struct Input {
+    output: TransactionOutput,
+    value: MicroMinotari,
+    spending_key: PrivateKey,
+    script_key: PrivateKey,
+}
+
+fn parse_input(input: SpendableOutput) -> Result<Input, String> {
+    let output = decode_output_str(&input.output, OutputEncoding::Auto).map_err(|e| e.to_string())?;
+    let spending_key = PrivateKey::from_hex(&input.spending_key).map_err(|e| format!("spending_key: {e}"))?;
+    let script_key = PrivateKey::from_hex(&input.script_key).map_err(|e| format!("script_key: {e}"))?;
+    if !crypto_factories()
+        .commitment
+        .open_value(&spending_key, input.value, &output.commitment)
+    {
+        return Err(format!(
+            "The spending key and value do not open the commitment of output {}",
+            output.hash().to_hex()
+        ));
+    }
+    Ok(Input {
+        output,
+        value: input.value.into(),
+        spending_key,
+        script_key,
+    })
+}
+
+/// The fee of a transaction spending `num_inputs` to `num_outputs` stealth address outputs with default features
+pub(crate) fn one_sided_fee(fee_per_gram: u64, num_inputs: usize, num_outputs: usize) -> Result<MicroMinotari, String> {
+    let script = script!(
+        PushPubKey(Box::new(PublicKey::default()))
+        Drop
+        PushPubKey(Box::new(PublicKey::default()))
+    );
+    let size = OutputFeatures::default()
+        .get_serialized_size()
+        .and_then(|features| Ok(features + script.get_serialized_size()?))
+        .and_then(|size| Ok(size + Covenant::default().get_serialized_size()?))
+        .map_err(|e| e.to_string())?;
+    let weight = TransactionWeight::latest();
+    let grams = weight.calculate(
+        1,
+        num_inputs,
+        num_outputs,
+        weight.round_up_features_and_scripts_size(size * num_outputs),
+    );
+    Ok(MicroMinotari::from(fee_per_gram.saturating_mul(grams)))
+}
+
+/// The change and fee of a transaction paying `amount` from `num_inputs` inputs worth `total`. A change output is only
+/// created when something remains after paying for it; otherwise the remainder is added to the fee.
+pub(crate) fn change_and_fee(
+    total: MicroMinotari,
+    amount: MicroMinotari,
+    num_inputs: usize,
+    fee_per_gram: u64,
+) -> Result<(MicroMinotari, MicroMinotari), String> {
+    let fee_with_change = one_sided_fee(fee_per_gram, num_inputs, 2)?;
+    let fee_without_change = one_sided_fee(fee_per_gram, num_inputs, 1)?;
+    let overflow = || "The amount and fee overflow".to_string();
+    let needed_with_change = amount.checked_add(fee_with_change).ok_or_else(overflow)?;
+    let needed_without_change = amount.checked_add(fee_without_change).ok_or_else(overflow)?;
+    match total.checked_sub(needed_with_change) {
+        Some(change) if change > MicroMinotari::from(0) => Ok((change, fee_with_change)),
+        _ => match total.checked_sub(amount) {
+            Some(fee) if fee >= fee_without_change => Ok((MicroMinotari::from(0), fee)),
+            _ => Err(format!(
+                "Insufficient funds: the inputs are worth {total}, but {needed_without_change} is needed"
+            )),
+        },
+    }
+}
+
+/// The view and spend keys to pay the change to: those of `change_address` if provided, otherwise the public key of the
+/// sender's wallet key
+fn change_recipient_keys(wallet_sk: &str, change_address: Option<&str>) -> Result<(PublicKey, PublicKey), String> {
+    match change_address {
+        Some(address) => one_sided_recipient_keys(address),
+        None => {
+            let wallet_sk = PrivateKey::from_hex(wallet_sk).map_err(|e| format!("wallet_sk: {e}"))?;
+            let public_key = PublicKey::from_secret_key(&wallet_sk);
+            Ok((public_key.clone(), public_key))
+        },
+    }
+}
+
+/// Creates a stealth output of `value` to the recipient, importing its commitment mask and sender offset key into the
+/// key manager for signing the kernel and script offset
+async fn add_output(
+    key_manager: &MemoryDbKeyManager,
+    wallet_pk: &PublicKey,
+    recipient: &(PublicKey, PublicKey),
+    value: MicroMinotari,
+) -> Result<(TransactionOutput, TariKeyId, TariKeyId), String> {
+    let sender_offset_sk = PrivateKey::random(&mut OsRng);
+    let sender_keys = SenderKeys {
+        wallet_pk: wallet_pk.to_hex(),
+        sender_offset_sk: Some(sender_offset_sk.to_hex()),
+        nonce_sk: None,
+    };
+    let features = OutputFeatures::default();
+    let promise_policy = MinimumValuePromisePolicy::default_for(features.range_proof_type);
+    let (output, spending_key) = build_stealth_output(
+        value,
+        sender_keys,
+        &recipient.0,
+        &recipient.1,
+        features,
+        promise_policy,
+        TransactionOutputVersion::get_current_version(),
+    )
+    .await?;
+    let spending_key_id = key_manager.import_key(spending_key).await.map_err(|e| e.to_string())?;
+    let sender_offset_key_id = key_manager
+        .import_key(sender_offset_sk)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((output, spending_key_id, sender_offset_key_id))
+}
+
+async fn build_transaction(
+    wallet_sk: &str,
+    inputs: Vec<Input>,
+    recipient: (PublicKey, PublicKey),
+    change_recipient: (PublicKey, PublicKey),
+    amount: MicroMinotari,
+    fee_per_gram: u64,
+) -> Result<OneSidedTransactionResult, String> {
+    let total = inputs
+        .iter()
+        .try_fold(MicroMinotari::from(0), |total, input| total.checked_add(input.value))
+        .ok_or_else(|| "The value of the inputs overflows".to_string())?;
+    let (change, fee) = change_and_fee(total, amount, inputs.len(), fee_per_gram)?;
+    let wallet_pk = PrivateKey::from_hex(wallet_sk)
+        .map(|wallet_sk| PublicKey::from_secret_key(&wallet_sk))
+        .map_err(|e| format!("wallet_sk: {e}"))?;
+
+    let key_manager = create_memory_db_key_manager(CipherSeed::new(), crypto_factories()).map_err(|e| e.to_string())?;
+    let mut transaction_inputs = Vec::with_capacity(inputs.len());
+    let mut kernel_keys = Vec::with_capacity(inputs.len() + 2);
+    let mut script_key_ids = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let input_data = spending_input_data(&input.output.script, &input.script_key)
+            .map_err(|e| format!("input {}: {e}", input.output.hash().to_hex()))?;
+        let (transaction_input, spending_key_id, script_key_id) = import_input(
+            &key_manager,
+            &input.output,
+            input.value,
+            input.spending_key,
+            input.script_key,
+            input_data,
+        )
+        .await?;
+        transaction_inputs.push(transaction_input);
+        kernel_keys.push((spending_key_id, TxoStage::Input));
+        script_key_ids.push(script_key_id);
+    }
+
+    let mut outputs = Vec::with_capacity(2);
+    let mut sender_offset_key_ids = Vec::with_capacity(2);
+    let (payment, spending_key_id, sender_offset_key_id) =
+        add_output(&key_manager, &wallet_pk, &recipient, amount).await?;
+    let payment_output_hash = payment.hash().to_hex();
+    outputs.push(payment);
+    kernel_keys.push((spending_key_id, TxoStage::Output));
+    sender_offset_key_ids.push(sender_offset_key_id);
+    let mut change_output_hash = None;
+    if change > MicroMinotari::from(0) {
+        let (change_output, spending_key_id, sender_offset_key_id) =
+            add_output(&key_manager, &wallet_pk, &change_recipient, change).await?;
+        change_output_hash = Some(change_output.hash().to_hex());
+        outputs.push(change_output);
+        kernel_keys.push((spending_key_id, TxoStage::Output));
+        sender_offset_key_ids.push(sender_offset_key_id);
+    }
+
+    let (kernel, offset) = sign_kernel(&key_manager, &kernel_keys, fee).await?;
+    let script_offset = key_manager
+        .get_script_offset(&script_key_ids, &sender_offset_key_ids)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut builder = TransactionBuilder::new();
+    builder
+        .add_inputs(transaction_inputs)
+        .add_outputs(outputs)
+        .with_kernel(kernel)
+        .add_offset(offset)
+        .add_script_offset(script_offset);
+    let transaction = builder.build().map_err(|e| e.to_string())?;
+    Ok(OneSidedTransactionResult {
+        transaction: Some(serde_json::to_string(&transaction).map_err(|e| e.to_string())?),
+        payment_output_hash: Some(payment_output_hash),
+        change_output_hash,
+        change: Some(change.as_u64()),
+        fee: Some(fee.as_u64()),
+        selected_inputs: None,
+        error: None,
+    })
+}
+
+/// Builds a fully-signed transaction paying `amount` one-sided to the recipient, spending outputs recovered by the
+/// scanner. The inputs are spent with their script keys and the input data their scripts require, any change is paid
+/// one-sided back to the sender, and the kernel and offsets are signed here, so that the returned transaction is ready
+/// for broadcast. Only outputs that are mature and unspent at the current tip should be supplied.
+/// * `wallet_sk` - the sender's wallet secret key (hex value), whose public key is committed to as the input data of
+///   the new outputs
+/// * `inputs` - the outputs to spend as JSON, e.g. `[{ "output": "<hex>", "value": 1000, "spendingKey": "<hex>",
+///   "scriptKey": "<hex>" }]`, as reported by `scan_output_for_one_sided_payment`
+/// * `recipient_address` - the recipient's Tari address (base58 or emoji), or a public key (hex value)
+/// * `change_address` - a Tari address (base58 or emoji), or a public key (hex value), to receive the change; the
+///   public key of `wallet_sk` if not provided, so that the change is found by scanning with `wallet_sk`. Change paid
+///   to a dual-key address is found with its view key, e.g. by `scan_output_for_one_sided_payment_ledger`.
+/// * `strategy` - if provided, the `CoinSelectionStrategy` to choose the inputs to spend among `inputs` with, otherwise
+///   all of them are spent
+/// * `dry_run` - if `true`, only the selected inputs, change and fee are returned, without building the transaction
+#[wasm_bindgen]
+pub async fn build_one_sided_transaction(
+    wallet_sk: String,
+    inputs: String,
+    recipient_address: String,
+    amount: u64,
+    fee_per_gram: u64,
+    change_address: Option<String>,
+    strategy: Option<CoinSelectionStrategy>,
+    dry_run: Option<bool>,
+) -> OneSidedTransactionResult {
+    let inputs: Vec<SpendableOutput> = match serde_json::from_str(&inputs) {
+        Ok(val) => val,
+        Err(e) => return one_sided_transaction_error(&format!("inputs: {e}")),
+    };
+    if inputs.is_empty() {
+        return one_sided_transaction_error("inputs: At least one input is required");
+    }
+    let recipient = match one_sided_recipient_keys(&recipient_address) {
+        Ok(val) => val,
+        Err(e) => return one_sided_transaction_error(&format!("recipient_address: {e}")),
+    };
+    let change_recipient = match change_recipient_keys(&wallet_sk, change_address.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return one_sided_transaction_error(&format!("change_address: {e}")),
+    };
+    if amount == 0 {
+        return one_sided_transaction_error("amount: The amount must be greater than 0");
+    }
+
+    let values = inputs
+        .iter()
+        .map(|input| MicroMinotari::from(input.value))
+        .collect::<Vec<_>>();
+    let selection = match select_coins(&values, amount.into(), fee_per_gram, strategy) {
+        Ok(val) => val,
+        Err(e) => return one_sided_transaction_error(&format!("Could not select inputs: {e}")),
+    };
+    if dry_run.unwrap_or(false) {
+        return OneSidedTransactionResult {
+            change: Some(selection.change.as_u64()),
+            fee: Some(selection.fee.as_u64()),
+            selected_inputs: Some(selection.indexes),
+            ..Default::default()
+        };
+    }
+    let mut inputs = inputs.into_iter().map(Some).collect::<Vec<_>>();
+    let inputs = match selection
+        .indexes
+        .iter()
+        .filter_map(|index| inputs[*index].take())
+        .map(parse_input)
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(val) => val,
+        Err(e) => return one_sided_transaction_error(&format!("inputs: {e}")),
+    };
+
+    match build_transaction(
+        &wallet_sk,
+        inputs,
+        recipient,
+        change_recipient,
+        amount.into(),
+        fee_per_gram,
+    )
+    .await
+    {
+        Ok(result) => OneSidedTransactionResult {
+            selected_inputs: Some(selection.indexes),
+            ..result
+        },
+        Err(e) => one_sided_transaction_error(&format!("Could not build transaction: {e}")),
+    }
+}
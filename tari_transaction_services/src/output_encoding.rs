@@ -0,0 +1,272 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use js_sys::Uint8Array;
+use serde::{Deserialize, Serialize};
+use tari_core::{limited_reader::LimitedBytesReader, transactions::transaction_components::TransactionOutput};
+use tari_crypto::tari_utilities::hex::Hex;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+
+use crate::{
+    error::{ScanErrorCode, WasmError},
+    memory::with_scratch_buffer,
+    size_budget::check_output_components,
+};
+
+/// The default maximum size of the Borsh bytes of an output. Outputs within the consensus limits on their script,
+/// covenant and coinbase extra are well below it.
+pub(crate) const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+/// The maximum length of an output string, which allows for the overhead of the hex, base64 and JSON encodings
+const MAX_OUTPUT_STR_LEN: usize = 4 * MAX_OUTPUT_BYTES;
+
+/// The encoding of a transaction output passed in from JavaScript
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputEncoding {
+    /// Detect the encoding: Borsh bytes for a `Uint8Array`, otherwise JSON, hex or base64 as the string allows
+    Auto,
+    /// Borsh bytes, as a `Uint8Array` (or, for backwards compatibility, as the bytes of a string)
+    Borsh,
+    /// Borsh bytes as a hex string
+    Hex,
+    /// Borsh bytes as a base64 string
+    Base64,
+    /// A JSON string
+    Json,
+}
+
+fn deserialize_error(error: String) -> WasmError {
+    WasmError::new(ScanErrorCode::DeserializeError, error)
+}
+
+/// Checks the variable length components of an untrusted output against the consensus limits
+fn check_limits(output: TransactionOutput) -> Result<TransactionOutput, WasmError> {
+    check_output_components(&output.script, &output.covenant, &output.features).map_err(deserialize_error)?;
+    Ok(output)
+}
+
+/// Deserializes an output from Borsh bytes through a reader that fails once more than `max_len` bytes are read, so
+/// that untrusted length prefixes cannot make the deserializer exhaust memory, then checks its components' limits
+fn from_borsh_limited(bytes: &[u8], max_len: usize) -> Result<TransactionOutput, WasmError> {
+    let mut reader = LimitedBytesReader::new(max_len, bytes);
+    let output = borsh::from_reader(&mut reader).map_err(|e| deserialize_error(format!("Borsh: {e}")))?;
+    check_limits(output)
+}
+
+fn from_borsh(bytes: &[u8]) -> Result<TransactionOutput, WasmError> {
+    from_borsh_limited(bytes, MAX_OUTPUT_BYTES)
+}
+
+fn hex_digit(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decodes a hex string into `buffer`
+fn decode_hex_into(hex: &str, buffer: &mut Vec<u8>) -> Result<(), String> {
+    if hex.len() % 2 != 0 {
+        return Err("odd number of digits".to_string());
+    }
+    buffer.reserve(hex.len() / 2);
+    for pair in hex.as_bytes().chunks(2) {
+        match (hex_digit(pair[0]), hex_digit(pair[1])) {
+            (Some(high), Some(low)) => buffer.push(high << 4 | low),
+            _ => return Err("invalid digit".to_string()),
+        }
+    }
+    Ok(())
+}
+
+// The hex and base64 decoders decode into a pooled buffer, as batch scans decode many outputs in a row
+fn from_hex_str(output: &str) -> Result<TransactionOutput, WasmError> {
+    with_scratch_buffer(|buffer| {
+        decode_hex_into(output.trim(), buffer).map_err(|e| deserialize_error(format!("hex: {e}")))?;
+        from_borsh(buffer)
+    })
+}
+
+fn from_base64_str(output: &str) -> Result<TransactionOutput, WasmError> {
+    with_scratch_buffer(|buffer| {
+        STANDARD
+            .decode_vec(output.trim(), buffer)
+            .map_err(|e| deserialize_error(format!("base64: {e}")))?;
+        from_borsh(buffer)
+    })
+}
+
+fn from_json_str(output: &str) -> Result<TransactionOutput, WasmError> {
+    check_limits(serde_json::from_str(output).map_err(|e| deserialize_error(format!("JSON: {e}")))?)
+}
+
+fn detect_output_str(output: &str) -> Result<TransactionOutput, WasmError> {
+    let trimmed = output.trim();
+    if trimmed.starts_with('{') {
+        return from_json_str(trimmed);
+    }
+    // A hex string is also valid base64, so hex is tried first
+    if trimmed.len() % 2 == 0 && trimmed.bytes().all(|b| b.is_ascii_hexdigit()) {
+        if let Ok(output) = from_hex_str(trimmed) {
+            return Ok(output);
+        }
+    }
+    if let Ok(output) = from_base64_str(trimmed) {
+        return Ok(output);
+    }
+    // Earlier versions of this library took the Borsh bytes as the bytes of a string
+    from_borsh(output.as_bytes())
+        .map_err(|_| deserialize_error("The output is not valid JSON, hex, base64 or Borsh".to_string()))
+}
+
+/// Deserializes a transaction output from a string in the given encoding
+pub(crate) fn decode_output_str(output: &str, encoding: OutputEncoding) -> Result<TransactionOutput, WasmError> {
+    if output.len() > MAX_OUTPUT_STR_LEN {
+        return Err(deserialize_error(format!(
+            "The output is {} characters, exceeding the maximum of {MAX_OUTPUT_STR_LEN}",
+            output.len()
+        )));
+    }
+    match encoding {
+        OutputEncoding::Auto => detect_output_str(output),
+        OutputEncoding::Borsh => from_borsh(output.as_bytes()),
+        OutputEncoding::Hex => from_hex_str(output),
+        OutputEncoding::Base64 => from_base64_str(output),
+        OutputEncoding::Json => from_json_str(output),
+    }
+}
+
+/// Deserializes a transaction output passed in from JavaScript as a `Uint8Array` of Borsh bytes or as a string, in
+/// the given encoding or, if not provided, in the detected encoding
+pub(crate) fn decode_output(
+    output: &JsValue,
+    encoding: Option<OutputEncoding>,
+) -> Result<TransactionOutput, WasmError> {
+    let encoding = encoding.unwrap_or(OutputEncoding::Auto);
+    if let Some(bytes) = output.dyn_ref::<Uint8Array>() {
+        return match encoding {
+            OutputEncoding::Auto | OutputEncoding::Borsh => from_borsh(&bytes.to_vec()),
+            _ => Err(deserialize_error(format!(
+                "A Uint8Array output must be Borsh encoded, not {encoding:?}"
+            ))),
+        };
+    }
+    match output.as_string() {
+        Some(output) => decode_output_str(&output, encoding),
+        None => Err(deserialize_error(
+            "The output must be a Uint8Array or a string".to_string(),
+        )),
+    }
+}
+
+/// A struct to hold an output deserialized from untrusted bytes
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SafeDeserializedOutputResult {
+    /// The hash of the output (hex value)
+    pub hash: Option<String>,
+    /// The output, serialized as JSON
+    pub output: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn safe_deserialized_output_error(error: &str) -> SafeDeserializedOutputResult {
+    SafeDeserializedOutputResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Deserializes an output from untrusted Borsh bytes, e.g. from an RPC response, reading at most `max_len` bytes and
+/// enforcing the consensus limits on the script, covenant and coinbase extra, so that malicious payloads cannot
+/// exhaust the browser's memory. The scanners deserialize every output in the same way.
+/// * `max_len` - the maximum number of bytes to read, 16 KiB if not provided
+#[wasm_bindgen]
+pub fn safe_deserialize_output(bytes: &[u8], max_len: Option<usize>) -> SafeDeserializedOutputResult {
+    let output = match from_borsh_limited(bytes, max_len.unwrap_or(MAX_OUTPUT_BYTES)) {
+        Ok(val) => val,
+        Err(e) => return safe_deserialized_output_error(&e.message),
+    };
+    match serde_json::to_string(&output) {
+        Ok(json) => SafeDeserializedOutputResult {
+            hash: Some(output.hash().to_hex()),
+            output: Some(json),
+            error: None,
+        },
+        Err(e) => safe_deserialized_output_error(&format!("Could not serialize output: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+    use tari_common_types::types::{ComAndPubSignature, PrivateKey, PublicKey};
+    use tari_core::{
+        covenants::Covenant,
+        transactions::{
+            tari_amount::MicroMinotari,
+            transaction_components::{EncryptedData, OutputFeatures},
+        },
+    };
+    use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::PublicKey as PublicKeyTrait};
+    use tari_script::script;
+
+    use super::*;
+    use crate::factories::crypto_factories;
+
+    fn output(value: u64, mask: u64, script_key: u64, maturity: u64) -> TransactionOutput {
+        let mask = PrivateKey::from(mask);
+        let commitment = crypto_factories().commitment.commit_value(&mask, value);
+        let script_public_key = PublicKey::from_secret_key(&PrivateKey::from(script_key));
+        let encrypted_data =
+            EncryptedData::encrypt_data(&mask, &commitment, MicroMinotari::from(value), &mask).unwrap();
+        TransactionOutput::new_current_version(
+            OutputFeatures {
+                maturity,
+                ..Default::default()
+            },
+            commitment,
+            None,
+            script!(PushPubKey(Box::new(script_public_key.clone()))),
+            script_public_key,
+            ComAndPubSignature::default(),
+            Covenant::default(),
+            encrypted_data,
+            MicroMinotari::from(value),
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn output_round_trips(value: u64, mask: u64, script_key: u64, maturity: u64) {
+            let output = output(value, mask, script_key, maturity);
+            let bytes = borsh::to_vec(&output).unwrap();
+            prop_assert_eq!(decode_output_str(&bytes.to_hex(), OutputEncoding::Hex).unwrap(), output.clone());
+            prop_assert_eq!(decode_output_str(&bytes.to_hex(), OutputEncoding::Auto).unwrap(), output.clone());
+            prop_assert_eq!(decode_output_str(&STANDARD.encode(&bytes), OutputEncoding::Base64).unwrap(), output.clone());
+            let json = serde_json::to_string(&output).unwrap();
+            prop_assert_eq!(decode_output_str(&json, OutputEncoding::Json).unwrap(), output.clone());
+            prop_assert_eq!(from_borsh_limited(&bytes, bytes.len()).unwrap(), output);
+        }
+
+        #[test]
+        fn arbitrary_bytes_do_not_panic(bytes in proptest::collection::vec(any::<u8>(), 0..1024)) {
+            let _result = safe_deserialize_output(&bytes, None);
+            let _result = decode_output_str(&bytes.to_hex(), OutputEncoding::Auto);
+            let _result = decode_output_str(&String::from_utf8_lossy(&bytes), OutputEncoding::Auto);
+        }
+    }
+
+    #[test]
+    fn truncated_output_is_an_error() {
+        let bytes = borsh::to_vec(&output(1, 2, 3, 4)).unwrap();
+        let error = from_borsh_limited(&bytes, bytes.len() - 1).unwrap_err();
+        assert_eq!(error.code, ScanErrorCode::DeserializeError);
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::transaction_components::{OutputFeatures, SideChainFeature, TemplateType};
+use tari_crypto::tari_utilities::hex::{to_hex, Hex};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::output_encoding::{decode_output, OutputEncoding};
+
+/// A summary of an output's sidechain feature
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct SideChainFeatureSummary {
+    /// The kind of feature, `ValidatorNodeRegistration`, `CodeTemplateRegistration` or `ConfidentialOutput`
+    pub kind: String,
+    /// The validator node public key, template author public key or burn claim public key (hex value)
+    pub public_key: String,
+    /// The template name, for a code template registration
+    pub template_name: Option<String>,
+    /// The template version, for a code template registration
+    pub template_version: Option<u16>,
+}
+
+impl From<&SideChainFeature> for SideChainFeatureSummary {
+    fn from(feature: &SideChainFeature) -> Self {
+        match feature {
+            SideChainFeature::ValidatorNodeRegistration(registration) => Self {
+                kind: "ValidatorNodeRegistration".to_string(),
+                public_key: registration.public_key().to_hex(),
+                ..Default::default()
+            },
+            SideChainFeature::CodeTemplateRegistration(registration) => Self {
+                kind: "CodeTemplateRegistration".to_string(),
+                public_key: registration.author_public_key.to_hex(),
+                template_name: Some(registration.template_name.to_string()),
+                template_version: Some(registration.template_version),
+            },
+            SideChainFeature::ConfidentialOutput(data) => Self {
+                kind: "ConfidentialOutput".to_string(),
+                public_key: data.claim_public_key.to_hex(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Returns the coinbase extra as text, if it is non-empty, valid UTF-8 without control characters other than whitespace
+pub(crate) fn coinbase_extra_text(extra: &[u8]) -> Option<String> {
+    std::str::from_utf8(extra)
+        .ok()
+        .filter(|text| !text.is_empty() && !text.chars().any(|c| c.is_control() && !c.is_whitespace()))
+        .map(|text| text.to_string())
+}
+
+/// The features of a recovered output, for display without deserializing the output again
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveredOutputFeatures {
+    /// The output features version
+    pub version: String,
+    /// The output type, e.g. `Standard` or `Coinbase`
+    pub output_type: String,
+    /// The output lock height
+    pub maturity: u64,
+    /// The range proof type, `BulletProofPlus` or `RevealedValue`
+    pub range_proof_type: String,
+    /// The extra data of a coinbase output (hex value)
+    pub coinbase_extra: String,
+    /// The extra data of a coinbase output as text, if it is valid UTF-8, e.g. a mining pool's tag
+    pub coinbase_extra_text: Option<String>,
+    /// The sidechain feature, if any
+    pub sidechain_feature: Option<SideChainFeatureSummary>,
+}
+
+impl From<&OutputFeatures> for RecoveredOutputFeatures {
+    fn from(features: &OutputFeatures) -> Self {
+        Self {
+            version: features.version.to_string(),
+            output_type: features.output_type.to_string(),
+            maturity: features.maturity,
+            range_proof_type: features.range_proof_type.to_string(),
+            coinbase_extra: features.coinbase_extra.to_hex(),
+            coinbase_extra_text: coinbase_extra_text(&features.coinbase_extra),
+            sidechain_feature: features.sidechain_feature.as_ref().map(SideChainFeatureSummary::from),
+        }
+    }
+}
+
+/// The full contents of an output's sidechain feature, tagged with its `kind`
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(tag = "kind")]
+pub enum SideChainFeatureDetails {
+    /// A validator node registration
+    #[serde(rename_all = "camelCase")]
+    ValidatorNodeRegistration {
+        /// The validator node public key (hex value)
+        public_key: String,
+        /// The public nonce of the registration signature (hex value)
+        signature_public_nonce: String,
+        /// The scalar of the registration signature (hex value)
+        signature_u: String,
+    },
+    /// A code template registration
+    #[serde(rename_all = "camelCase")]
+    CodeTemplateRegistration {
+        /// The template author's public key (hex value)
+        author_public_key: String,
+        /// The public nonce of the author's signature (hex value)
+        author_signature_public_nonce: String,
+        /// The scalar of the author's signature (hex value)
+        author_signature_u: String,
+        /// The template name
+        template_name: String,
+        /// The template version
+        template_version: u16,
+        /// The template type, `Wasm`, `Flow` or `Manifest`
+        template_type: String,
+        /// The ABI version of a `Wasm` template
+        abi_version: Option<u16>,
+        /// The repository the template is built from
+        repo_url: String,
+        /// The commit the template is built from (hex value)
+        commit_hash: String,
+        /// The SHA hash of the template binary (hex value)
+        binary_sha: String,
+        /// Where the template binary is published
+        binary_url: String,
+    },
+    /// A burn that can be claimed as a confidential output on the second layer
+    #[serde(rename_all = "camelCase")]
+    ConfidentialOutput {
+        /// The public key the burn can be claimed with (hex value)
+        claim_public_key: String,
+    },
+}
+
+impl From<&SideChainFeature> for SideChainFeatureDetails {
+    fn from(feature: &SideChainFeature) -> Self {
+        match feature {
+            SideChainFeature::ValidatorNodeRegistration(registration) => Self::ValidatorNodeRegistration {
+                public_key: registration.public_key().to_hex(),
+                signature_public_nonce: registration.signature().get_public_nonce().to_hex(),
+                signature_u: registration.signature().get_signature().to_hex(),
+            },
+            SideChainFeature::CodeTemplateRegistration(registration) => {
+                let (template_type, abi_version) = match registration.template_type {
+                    TemplateType::Wasm { abi_version } => ("Wasm", Some(abi_version)),
+                    TemplateType::Flow => ("Flow", None),
+                    TemplateType::Manifest => ("Manifest", None),
+                };
+                Self::CodeTemplateRegistration {
+                    author_public_key: registration.author_public_key.to_hex(),
+                    author_signature_public_nonce: registration.author_signature.get_public_nonce().to_hex(),
+                    author_signature_u: registration.author_signature.get_signature().to_hex(),
+                    template_name: registration.template_name.to_string(),
+                    template_version: registration.template_version,
+                    template_type: template_type.to_string(),
+                    abi_version,
+                    repo_url: registration.build_info.repo_url.to_string(),
+                    commit_hash: to_hex(registration.build_info.commit_hash.as_ref()),
+                    binary_sha: to_hex(registration.binary_sha.as_ref()),
+                    binary_url: registration.binary_url.to_string(),
+                }
+            },
+            SideChainFeature::ConfidentialOutput(data) => Self::ConfidentialOutput {
+                claim_public_key: data.claim_public_key.to_hex(),
+            },
+        }
+    }
+}
+
+/// A struct to hold the decoded sidechain feature of an output
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SideChainFeaturesResult {
+    /// The hash of the output (hex value)
+    pub output_hash: Option<String>,
+    /// The output type, e.g. `ValidatorNodeRegistration` or `Burn`
+    pub output_type: Option<String>,
+    /// The sidechain feature, if the output has one
+    pub sidechain_feature: Option<SideChainFeatureDetails>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+/// Decodes the sidechain feature of an output, a validator node registration, code template registration or
+/// confidential output claim, into a structure tagged with its `kind`, so that explorers can render sidechain outputs.
+/// The output is passed in any of the encodings accepted by `scan_output_for_one_sided_payment`.
+#[wasm_bindgen]
+pub fn decode_sidechain_features(output: JsValue, encoding: Option<OutputEncoding>) -> SideChainFeaturesResult {
+    match decode_output(&output, encoding) {
+        Ok(output) => SideChainFeaturesResult {
+            output_hash: Some(output.hash().to_hex()),
+            output_type: Some(output.features.output_type.to_string()),
+            sidechain_feature: output
+                .features
+                .sidechain_feature
+                .as_ref()
+                .map(SideChainFeatureDetails::from),
+            error: None,
+        },
+        Err(e) => SideChainFeaturesResult {
+            error: Some(e.to_string()),
+            ..Default::default()
+        },
+    }
+}
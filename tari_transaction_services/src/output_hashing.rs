@@ -0,0 +1,75 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_crypto::tari_utilities::hex::Hex;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    network::{on_network, parse_network},
+    output_encoding::{decode_output, OutputEncoding},
+};
+
+/// A struct to hold the hashes of a transaction output
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct OutputHashResult {
+    /// The canonical output hash (hex value)
+    pub hash: Option<String>,
+    /// The output's leaf hash in the UTXO sparse Merkle tree, which commits to its mined height (hex value)
+    pub smt_hash: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn output_hash_error(error: &str) -> OutputHashResult {
+    OutputHashResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Computes the canonical hash of a transaction output, passed in any of the encodings accepted by
+/// `scan_output_for_one_sided_payment`
+/// * `network` - the network the output is hashed for, e.g. `mainnet`; the library's network if not provided
+#[wasm_bindgen]
+pub fn output_hash(output: JsValue, encoding: Option<OutputEncoding>, network: Option<String>) -> OutputHashResult {
+    let network = match parse_network(network.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return output_hash_error(&e),
+    };
+    match decode_output(&output, encoding) {
+        Ok(output) => OutputHashResult {
+            hash: Some(on_network(network, || output.hash()).to_hex()),
+            smt_hash: None,
+            error: None,
+        },
+        Err(e) => output_hash_error(&e.to_string()),
+    }
+}
+
+/// Computes the sparse Merkle tree leaf hash of a transaction output mined at `mined_height`, as well as its canonical
+/// hash, so that light clients can check UTXO inclusion proofs client-side
+/// * `network` - the network the output is hashed for, e.g. `mainnet`; the library's network if not provided
+#[wasm_bindgen]
+pub fn output_smt_hash(
+    output: JsValue,
+    mined_height: u64,
+    encoding: Option<OutputEncoding>,
+    network: Option<String>,
+) -> OutputHashResult {
+    let network = match parse_network(network.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return output_hash_error(&e),
+    };
+    match decode_output(&output, encoding) {
+        Ok(output) => OutputHashResult {
+            hash: Some(on_network(network, || output.hash()).to_hex()),
+            smt_hash: Some(on_network(network, || output.smt_hash(mined_height)).to_hex()),
+            error: None,
+        },
+        Err(e) => output_hash_error(&e.to_string()),
+    }
+}
@@ -0,0 +1,355 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Storage of the outputs a scan recovers, so that integrators get the wallet's UTXO bookkeeping from the library
+//! rather than reimplementing it. [`RecoveredOutputStore`] is implemented in memory by [`MemoryOutputStore`], and by
+//! [`JsOutputStore`] over JavaScript callbacks, e.g. to keep the outputs in IndexedDB.
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+use js_sys::{Array, BigInt, Function, Reflect, Uint8Array};
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::tari_amount::MicroMinotari;
+use tsify::Tsify;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{
+    compression::{compress_entry, decompress_entry, EntryContent},
+    RecoveredOutputResult,
+};
+
+/// The number of blocks an output must be mined under before it counts as received, as in the console wallet
+const REQUIRED_CONFIRMATIONS: u64 = 3;
+
+/// A recovered output as it is stored
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredOutput {
+    /// The hash of the output (hex value)
+    pub output_hash: String,
+    /// The height of the block the output was mined in, if known
+    pub mined_height: Option<u64>,
+    /// Whether the output is spent
+    pub spent: bool,
+    /// The height of the block the output was spent in, if spent and known
+    pub spent_height: Option<u64>,
+    /// The recovered output
+    pub output: RecoveredOutputResult,
+}
+
+impl StoredOutput {
+    /// The value of the output
+    pub(crate) fn value(&self) -> u64 {
+        self.output.value.unwrap_or_default()
+    }
+
+    /// The height from which the output can be spent, the later of its maturity and script lock height
+    pub(crate) fn spendable_at_height(&self) -> u64 {
+        self.output.spendable_at_height.unwrap_or_else(|| {
+            self.output
+                .maturity
+                .unwrap_or_default()
+                .max(self.output.lock_height.unwrap_or_default())
+        })
+    }
+
+    /// Whether the output was mined fewer than `REQUIRED_CONFIRMATIONS` blocks below `current_height`. An output whose
+    /// mined height is not known counts as confirmed.
+    pub(crate) fn is_pending(&self, current_height: u64) -> bool {
+        self.mined_height.map_or(false, |mined_height| {
+            current_height < mined_height.saturating_add(REQUIRED_CONFIRMATIONS)
+        })
+    }
+
+    /// Whether the output is unspent, confirmed and spendable at `current_height`
+    pub(crate) fn is_available(&self, current_height: u64) -> bool {
+        !self.spent && !self.is_pending(current_height) && self.spendable_at_height() <= current_height
+    }
+}
+
+/// Serializes a stored output as JSON, compressed in the versioned entry format of the storage callbacks
+pub(crate) fn encode_stored_output(output: &StoredOutput) -> Result<Vec<u8>, String> {
+    let json = serde_json::to_vec(output).map_err(|e| e.to_string())?;
+    Ok(compress_entry(EntryContent::Text, &json))
+}
+
+/// Deserializes a stored output encoded by `encode_stored_output`
+pub(crate) fn decode_stored_output(entry: &[u8]) -> Result<StoredOutput, String> {
+    match decompress_entry(entry)? {
+        Some((EntryContent::Text, json)) => serde_json::from_slice(&json).map_err(|e| e.to_string()),
+        Some((EntryContent::Bytes, _)) => Err("The entry is not JSON".to_string()),
+        None => Err("The entry is not a compressed stored output".to_string()),
+    }
+}
+
+/// The balance of the unspent recovered outputs at a height, as the console wallet reports it
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BalanceResult {
+    /// The height the balance is calculated at
+    pub height: Option<u64>,
+    /// The value of the outputs that can be spent at the height, in µT
+    pub available: Option<u64>,
+    /// The value of the confirmed outputs that cannot be spent before a later height, because of their maturity, e.g.
+    /// immature coinbases, or a script lock, in µT
+    pub time_locked: Option<u64>,
+    /// The value of the outputs mined fewer than 3 blocks below the height, in µT
+    pub pending_incoming: Option<u64>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+pub(crate) fn balance_error(error: &str) -> BalanceResult {
+    BalanceResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Calculates the balance of unspent outputs at `current_height`
+pub(crate) fn calculate_balance(unspent: &[StoredOutput], current_height: u64) -> BalanceResult {
+    let mut available = MicroMinotari::zero();
+    let mut time_locked = MicroMinotari::zero();
+    let mut pending_incoming = MicroMinotari::zero();
+    for output in unspent.iter().filter(|output| !output.spent) {
+        let value = MicroMinotari::from(output.value());
+        let total = if output.is_pending(current_height) {
+            &mut pending_incoming
+        } else if output.spendable_at_height() > current_height {
+            &mut time_locked
+        } else {
+            &mut available
+        };
+        *total = match total.checked_add(value) {
+            Some(val) => val,
+            None => return balance_error("The balance overflows"),
+        };
+    }
+    BalanceResult {
+        height: Some(current_height),
+        available: Some(available.as_u64()),
+        time_locked: Some(time_locked.as_u64()),
+        pending_incoming: Some(pending_incoming.as_u64()),
+        error: None,
+    }
+}
+
+/// A store of recovered outputs. The methods take `&self` so that a store can be called without holding a borrow of
+/// the session that owns it, as a JavaScript store may call back into the session.
+pub(crate) trait RecoveredOutputStore {
+    /// Stores a recovered output, replacing the output with the same hash if there is one
+    fn insert(&self, output: StoredOutput) -> Result<(), String>;
+    /// Marks the output with the hash (hex value) as spent, returning whether it is stored and was not spent yet
+    fn mark_spent(&self, output_hash: &str, spent_height: Option<u64>) -> Result<bool, String>;
+    /// The stored outputs that are not spent
+    fn list_unspent(&self) -> Result<Vec<StoredOutput>, String>;
+    /// The total value of the stored outputs that are not spent
+    fn balance(&self) -> Result<u64, String>;
+}
+
+/// A `RecoveredOutputStore` that keeps the outputs in memory, by output hash
+#[derive(Debug, Default)]
+pub(crate) struct MemoryOutputStore {
+    outputs: RefCell<BTreeMap<String, StoredOutput>>,
+}
+
+impl RecoveredOutputStore for MemoryOutputStore {
+    fn insert(&self, output: StoredOutput) -> Result<(), String> {
+        self.outputs.borrow_mut().insert(output.output_hash.clone(), output);
+        Ok(())
+    }
+
+    fn mark_spent(&self, output_hash: &str, spent_height: Option<u64>) -> Result<bool, String> {
+        match self.outputs.borrow_mut().get_mut(output_hash) {
+            Some(output) if !output.spent => {
+                output.spent = true;
+                output.spent_height = spent_height;
+                Ok(true)
+            },
+            _ => Ok(false),
+        }
+    }
+
+    fn list_unspent(&self) -> Result<Vec<StoredOutput>, String> {
+        Ok(self
+            .outputs
+            .borrow()
+            .values()
+            .filter(|output| !output.spent)
+            .cloned()
+            .collect())
+    }
+
+    fn balance(&self) -> Result<u64, String> {
+        self.outputs
+            .borrow()
+            .values()
+            .filter(|output| !output.spent)
+            .try_fold(0u64, |total, output| total.checked_add(output.value()))
+            .ok_or_else(|| "The balance overflows".to_string())
+    }
+}
+
+/// A `RecoveredOutputStore` over the JavaScript callbacks of an object with the functions
+/// * `insert(output, outputHash)`, called with a `StoredOutput`, or if the store compresses outputs, with the output
+///   compressed into a `Uint8Array` entry, along with the hash of the output (hex value)
+/// * `markSpent(outputHash, spentHeight)`, returning whether the output is stored and was not spent yet
+/// * `listUnspent()`, returning an array of the `StoredOutput`s that are not spent, each as passed to `insert`, so that
+///   outputs stored before compression was enabled are still read
+/// * `balance()`, returning the total value of the outputs that are not spent, as a number or a `BigInt`
+///
+/// The callbacks are called synchronously, so `listUnspent` and `balance` must not return promises, e.g. by answering
+/// from a cache the page keeps in step with IndexedDB. A promise returned by `insert` or `markSpent` is not awaited.
+///
+/// The `StoredOutput`s passed to `insert` hold the spending and script private keys of the outputs as plaintext hex
+/// values, which can spend the outputs; the store must keep them encrypted at rest and must not log them.
+pub(crate) struct JsOutputStore {
+    insert: Function,
+    mark_spent: Function,
+    list_unspent: Function,
+    balance: Function,
+    compress: bool,
+}
+
+fn store_callback(store: &JsValue, name: &str) -> Result<Function, String> {
+    Reflect::get(store, &JsValue::from_str(name))
+        .ok()
+        .and_then(|callback| callback.dyn_into::<Function>().ok())
+        .ok_or_else(|| format!("store: `{name}` is not a function"))
+}
+
+fn js_error(name: &str, e: JsValue) -> String {
+    format!("{name}: {}", e.as_string().unwrap_or_else(|| format!("{e:?}")))
+}
+
+impl JsOutputStore {
+    /// Creates a store over the callbacks of a JavaScript object, which is passed compressed outputs if `compress` is
+    /// set
+    pub(crate) fn new(store: &JsValue, compress: bool) -> Result<Self, String> {
+        Ok(Self {
+            insert: store_callback(store, "insert")?,
+            mark_spent: store_callback(store, "markSpent")?,
+            list_unspent: store_callback(store, "listUnspent")?,
+            balance: store_callback(store, "balance")?,
+            compress,
+        })
+    }
+}
+
+impl RecoveredOutputStore for JsOutputStore {
+    fn insert(&self, output: StoredOutput) -> Result<(), String> {
+        let value = if self.compress {
+            let entry = encode_stored_output(&output).map_err(|e| format!("insert: {e}"))?;
+            Uint8Array::from(entry.as_slice()).into()
+        } else {
+            serde_wasm_bindgen::to_value(&output).map_err(|e| format!("insert: {e}"))?
+        };
+        self.insert
+            .call2(&JsValue::NULL, &value, &JsValue::from_str(&output.output_hash))
+            .map_err(|e| js_error("insert", e))?;
+        Ok(())
+    }
+
+    fn mark_spent(&self, output_hash: &str, spent_height: Option<u64>) -> Result<bool, String> {
+        let spent_height = spent_height.map_or(JsValue::UNDEFINED, JsValue::from);
+        let marked = self
+            .mark_spent
+            .call2(&JsValue::NULL, &JsValue::from_str(output_hash), &spent_height)
+            .map_err(|e| js_error("markSpent", e))?;
+        Ok(marked.as_bool() == Some(true))
+    }
+
+    fn list_unspent(&self) -> Result<Vec<StoredOutput>, String> {
+        let outputs = self
+            .list_unspent
+            .call0(&JsValue::NULL)
+            .map_err(|e| js_error("listUnspent", e))?;
+        let outputs = match outputs.dyn_into::<Array>() {
+            Ok(val) => val,
+            Err(_) => return Err("listUnspent: did not return an array".to_string()),
+        };
+        outputs
+            .iter()
+            .map(|output| match output.dyn_ref::<Uint8Array>() {
+                Some(entry) => decode_stored_output(&entry.to_vec()),
+                None => serde_wasm_bindgen::from_value(output).map_err(|e| e.to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("listUnspent: {e}"))
+    }
+
+    fn balance(&self) -> Result<u64, String> {
+        let balance = self.balance.call0(&JsValue::NULL).map_err(|e| js_error("balance", e))?;
+        let value = match balance.dyn_ref::<BigInt>() {
+            Some(balance) => balance
+                .to_string(10)
+                .ok()
+                .and_then(|balance| String::from(balance).parse::<u64>().ok()),
+            None => balance
+                .as_f64()
+                .filter(|balance| balance.fract() == 0.0 && *balance >= 0.0 && *balance <= u64::MAX as f64)
+                .map(|balance| balance as u64),
+        };
+        value.ok_or_else(|| "balance: did not return a non-negative integer".to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn stored_output(output_hash: &str, value: u64) -> StoredOutput {
+        StoredOutput {
+            output_hash: output_hash.to_string(),
+            output: RecoveredOutputResult {
+                value: Some(value),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn balance_excludes_spent_outputs() {
+        let store = MemoryOutputStore::default();
+        store.insert(stored_output("aa", 5)).unwrap();
+        store.insert(stored_output("bb", 7)).unwrap();
+        assert_eq!(store.balance(), Ok(12));
+        assert_eq!(store.mark_spent("aa", Some(10)), Ok(true));
+        assert_eq!(store.mark_spent("aa", Some(10)), Ok(false));
+        assert_eq!(store.balance(), Ok(7));
+    }
+
+    #[test]
+    fn balance_overflow_is_an_error() {
+        let store = MemoryOutputStore::default();
+        store.insert(stored_output("aa", u64::MAX)).unwrap();
+        store.insert(stored_output("bb", 1)).unwrap();
+        assert_eq!(store.balance(), Err("The balance overflows".to_string()));
+        let balance = calculate_balance(&store.list_unspent().unwrap(), 100);
+        assert_eq!(balance.error, Some("The balance overflows".to_string()));
+    }
+
+    #[test]
+    fn stored_outputs_round_trip_compressed() {
+        let mut output = stored_output("aa", 5);
+        output.mined_height = Some(7);
+        output.spent_height = Some(9);
+        output.spent = true;
+        let entry = encode_stored_output(&output).unwrap();
+        let decoded = decode_stored_output(&entry).unwrap();
+        assert_eq!(
+            serde_json::to_string(&decoded).unwrap(),
+            serde_json::to_string(&output).unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_stored_outputs_are_rejected() {
+        let json = serde_json::to_vec(&stored_output("aa", 5)).unwrap();
+        assert!(decode_stored_output(&json).is_err());
+        assert!(decode_stored_output(&compress_entry(EntryContent::Bytes, &json)).is_err());
+        assert!(decode_stored_output(&compress_entry(EntryContent::Text, b"[]")).is_err());
+    }
+}
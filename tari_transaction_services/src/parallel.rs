@@ -0,0 +1,149 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Batch scanning and range proof verification that is spread across Web Workers with the `threads` feature and runs
+//! on the calling thread without it. A build with the feature needs `SharedArrayBuffer`, i.e. a cross-origin isolated
+//! page, and `init_thread_pool(n)`, re-exported at the crate root, must be awaited once before the batch functions are
+//! called.
+
+use js_sys::Array;
+#[cfg(feature = "threads")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::RangeProofService;
+use tari_core::{
+    consensus::{consensus_network, with_consensus_network},
+    transactions::transaction_components::{transaction_output::batch_verify_range_proofs, TransactionOutput},
+};
+use tari_crypto::errors::RangeProofError;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    deposit_tagging::DepositRules,
+    network::parse_network,
+    output_encoding::{decode_output_str, OutputEncoding},
+    scan_outputs::{scan_output, scan_result, ScanKeys},
+    secrets::protect_results,
+    RecoveredOutputResult,
+};
+
+/// The number of range proofs verified together by each worker
+#[cfg(feature = "threads")]
+const VERIFY_CHUNK_SIZE: usize = 64;
+
+/// A struct to hold the results of a parallel batch scan
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct ParallelScanResult {
+    /// The result of every output, in the order the outputs were passed; outputs that do not belong to the wallet have
+    /// neither a hash nor an error
+    pub results: Vec<RecoveredOutputResult>,
+    /// The number of outputs found to belong to the wallet
+    pub matches: usize,
+    /// Whether the outputs were scanned on a thread pool
+    pub parallel: bool,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn scan_one(keys: &ScanKeys, rules: &DepositRules, output: &str) -> RecoveredOutputResult {
+    scan_result(decode_output_str(output, OutputEncoding::Auto).and_then(|output| scan_output(keys, rules, &output)))
+}
+
+/// Scans a batch of outputs for one-sided payments to this wallet, as `scan_output_for_one_sided_payment` does, spread
+/// across the thread pool with the `threads` feature. The outputs are passed as strings in any of the encodings
+/// accepted by `scan_output_for_one_sided_payment`, which are detected per output, and hashed for `network`, e.g.
+/// `mainnet`, or for the library's network if not provided.
+#[wasm_bindgen]
+pub fn scan_outputs_parallel(
+    known_script_keys: Vec<String>,
+    wallet_sk: String,
+    outputs: Vec<String>,
+    deposit_rules: Option<String>,
+    network: Option<String>,
+) -> ParallelScanResult {
+    let keys = match ScanKeys::new(known_script_keys, wallet_sk) {
+        Ok(val) => val,
+        Err(e) => {
+            return ParallelScanResult {
+                error: Some(e.to_string()),
+                ..Default::default()
+            }
+        },
+    };
+    let rules = match DepositRules::from_optional_json(deposit_rules.as_deref()) {
+        Ok(val) => val,
+        Err(e) => {
+            return ParallelScanResult {
+                error: Some(e.to_string()),
+                ..Default::default()
+            }
+        },
+    };
+    // The network is resolved on the calling thread, as a network override does not carry over to the workers
+    let network = match parse_network(network.as_deref()) {
+        Ok(val) => val.unwrap_or_else(consensus_network),
+        Err(e) => {
+            return ParallelScanResult {
+                error: Some(e),
+                ..Default::default()
+            }
+        },
+    };
+    #[cfg(feature = "threads")]
+    let results = outputs
+        .par_iter()
+        .map(|output| with_consensus_network(network, || scan_one(&keys, &rules, output)))
+        .collect::<Vec<_>>();
+    #[cfg(not(feature = "threads"))]
+    let results = with_consensus_network(network, || {
+        outputs
+            .iter()
+            .map(|output| scan_one(&keys, &rules, output))
+            .collect::<Vec<_>>()
+    });
+    ParallelScanResult {
+        matches: results.iter().filter(|result| result.hash.is_some()).count(),
+        results,
+        parallel: cfg!(feature = "threads"),
+        error: None,
+    }
+}
+
+/// Scans a batch of outputs as `scan_outputs_parallel` does, returning an array of `ProtectedScanResult`s, in the order
+/// the outputs were passed, which hold the spending and script keys of the recovered outputs in wasm memory as
+/// `SecretKeyHandle`s
+#[wasm_bindgen]
+pub fn scan_outputs_parallel_protected(
+    known_script_keys: Vec<String>,
+    wallet_sk: String,
+    outputs: Vec<String>,
+    deposit_rules: Option<String>,
+    network: Option<String>,
+) -> Result<Array, JsValue> {
+    let result = scan_outputs_parallel(known_script_keys, wallet_sk, outputs, deposit_rules, network);
+    match result.error {
+        Some(e) => Err(JsValue::from_str(&e)),
+        None => Ok(protect_results(result.results)),
+    }
+}
+
+/// Batch verifies the range proofs of outputs, in chunks spread across the thread pool with the `threads` feature,
+/// and as a single batch otherwise
+pub(crate) fn verify_range_proofs(
+    range_proof: &RangeProofService,
+    outputs: &[TransactionOutput],
+) -> Result<(), RangeProofError> {
+    #[cfg(feature = "threads")]
+    {
+        outputs
+            .par_chunks(VERIFY_CHUNK_SIZE)
+            .try_for_each(|chunk| batch_verify_range_proofs(range_proof, &chunk.iter().collect::<Vec<_>>()))
+    }
+    #[cfg(not(feature = "threads"))]
+    {
+        batch_verify_range_proofs(range_proof, &outputs.iter().collect::<Vec<_>>())
+    }
+}
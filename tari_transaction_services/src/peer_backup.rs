@@ -0,0 +1,116 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use blake2::Blake2b;
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, Payload},
+    Key,
+    KeyInit,
+    XChaCha20Poly1305,
+    XNonce,
+};
+use digest::consts::U32;
+use rand::rngs::OsRng;
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_comms::types::CommsDHKE;
+use tari_crypto::{
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    keys::PublicKey as PK,
+    tari_utilities::{hex::Hex, ByteArray},
+};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::compression::{compress, decompress};
+
+hash_domain!(PeerBackupHashDomain, "com.tari.transaction_services.peer_backup", 1);
+
+type PeerBackupHasher = DomainSeparatedHasher<Blake2b<U32>, PeerBackupHashDomain>;
+
+// The format version, stored as the first byte of a backup
+const PEER_BACKUP_VERSION: u8 = 0x01;
+const SIZE_PUBLIC_KEY: usize = 32;
+const SIZE_NONCE: usize = 24;
+const SIZE_HEADER: usize = 1 + SIZE_PUBLIC_KEY + SIZE_NONCE;
+
+/// Derives the AEAD key from the Diffie-Hellman shared secret of the ephemeral key and the peer's key. Both public keys
+/// are bound in, so that the key is unique to this backup and this peer.
+fn peer_backup_key(shared_secret: &CommsDHKE, ephemeral_public_key: &PublicKey, peer_public_key: &PublicKey) -> Key {
+    let hash = PeerBackupHasher::new_with_label("aead_key")
+        .chain(shared_secret.as_bytes())
+        .chain(ephemeral_public_key.as_bytes())
+        .chain(peer_public_key.as_bytes())
+        .finalize();
+    *Key::from_slice(hash.as_ref())
+}
+
+// The header and the peer's public key are authenticated along with the ciphertext
+fn associated_data(header: &[u8], peer_public_key: &PublicKey) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.extend_from_slice(peer_public_key.as_bytes());
+    aad
+}
+
+/// Encrypts `state` for the holder of the private key of `peer_public_key`, with a key derived from a Diffie-Hellman
+/// exchange with a fresh ephemeral key. The backup is `version || ephemeral public key || nonce || ciphertext`, where
+/// the ciphertext is the compressed state encrypted with XChaCha20-Poly1305.
+pub fn encrypt_for_peer(state: &[u8], peer_public_key: &PublicKey) -> Result<Vec<u8>, String> {
+    let (ephemeral_secret_key, ephemeral_public_key) = PublicKey::random_keypair(&mut OsRng);
+    let shared_secret = CommsDHKE::new(&ephemeral_secret_key, peer_public_key);
+    let key = peer_backup_key(&shared_secret, &ephemeral_public_key, peer_public_key);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let mut backup = Vec::with_capacity(SIZE_HEADER + state.len());
+    backup.push(PEER_BACKUP_VERSION);
+    backup.extend_from_slice(ephemeral_public_key.as_bytes());
+    backup.extend_from_slice(&nonce);
+    let ciphertext = XChaCha20Poly1305::new(&key)
+        .encrypt(&nonce, Payload {
+            msg: &compress(state),
+            aad: &associated_data(&backup, peer_public_key),
+        })
+        .map_err(|e| format!("Could not encrypt state: {e}"))?;
+    backup.extend_from_slice(&ciphertext);
+    Ok(backup)
+}
+
+/// Decrypts a backup produced by `encrypt_for_peer` with the peer's private key
+pub fn decrypt_from_peer(backup: &[u8], secret_key: &PrivateKey) -> Result<Vec<u8>, String> {
+    if backup.len() < SIZE_HEADER {
+        return Err("The backup is too short".to_string());
+    }
+    let (header, ciphertext) = backup.split_at(SIZE_HEADER);
+    if header[0] != PEER_BACKUP_VERSION {
+        return Err(format!("Unsupported backup version `{}`", header[0]));
+    }
+    let ephemeral_public_key = PublicKey::from_canonical_bytes(&header[1..=SIZE_PUBLIC_KEY])
+        .map_err(|e| format!("Invalid ephemeral public key: {e}"))?;
+    let nonce = XNonce::from_slice(&header[1 + SIZE_PUBLIC_KEY..]);
+    let peer_public_key = PublicKey::from_secret_key(secret_key);
+    let shared_secret = CommsDHKE::new(secret_key, &ephemeral_public_key);
+    let key = peer_backup_key(&shared_secret, &ephemeral_public_key, &peer_public_key);
+    let compressed = XChaCha20Poly1305::new(&key)
+        .decrypt(nonce, Payload {
+            msg: ciphertext,
+            aad: &associated_data(header, &peer_public_key),
+        })
+        .map_err(|_| "Could not decrypt the backup, it is not for this key or has been tampered with".to_string())?;
+    decompress(&compressed)
+}
+
+/// Encrypts scanner state (e.g. a serialized scan session or receipt) for another device of the same user, identified
+/// by its public key (hex value), so that it can be synced through an untrusted relay without exposing balances or
+/// keys. Only the holder of the matching private key can decrypt it with `decrypt_state_from_peer`.
+#[wasm_bindgen]
+pub fn encrypt_state_for_peer(state: &[u8], peer_pk: &str) -> Result<Vec<u8>, JsValue> {
+    let peer_pk = PublicKey::from_hex(peer_pk).map_err(|e| JsValue::from_str(&format!("peer_pk: {e}")))?;
+    encrypt_for_peer(state, &peer_pk).map_err(|e| JsValue::from_str(&e))
+}
+
+/// Decrypts scanner state that was encrypted for this device with `encrypt_state_for_peer`, with the device's private
+/// key (hex value). Fails if the backup was encrypted for another key or has been modified.
+#[wasm_bindgen]
+pub fn decrypt_state_from_peer(blob: &[u8], secret: &str) -> Result<Vec<u8>, JsValue> {
+    let secret = PrivateKey::from_hex(secret).map_err(|e| JsValue::from_str(&format!("secret: {e}")))?;
+    decrypt_from_peer(blob, &secret).map_err(|e| JsValue::from_str(&e))
+}
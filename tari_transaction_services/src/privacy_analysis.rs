@@ -0,0 +1,156 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tari_crypto::tari_utilities::hex::Hex;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    output_encoding::{decode_output_str, OutputEncoding},
+    script_tools::{match_script, ScriptPattern},
+};
+
+/// Change in whole Tari (multiples of 1,000,000 µT) is considered a round amount
+const ROUND_AMOUNT: u64 = 1_000_000;
+
+/// One of the wallet's own outputs, as input to `analyse_output_privacy`
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivacyAnalysisOutput {
+    /// The output, in any of the string encodings accepted by `scan_output_for_one_sided_payment`
+    pub output: String,
+    /// The output value in µT, as recovered when scanning
+    pub value: u64,
+    /// Whether the output is change the wallet paid to itself
+    #[serde(default)]
+    pub change: bool,
+}
+
+/// The kind of privacy-degrading pattern found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+pub enum PrivacyWarningKind {
+    /// Several outputs were paid to the same one-sided address, linking the payments to each other
+    AddressReuse,
+    /// A change output has a round value, which tells observers of the amounts which output is the payment
+    RoundAmountChange,
+    /// Several outputs share a sender offset public key, linking them to the same sender and transaction
+    CommonSenderOffsetKey,
+}
+
+/// A privacy-degrading pattern found in the wallet's outputs
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivacyWarning {
+    /// The kind of pattern
+    pub kind: PrivacyWarningKind,
+    /// The hashes of the outputs involved (hex values)
+    pub outputs: Vec<String>,
+    /// A description of the pattern, for display
+    pub message: String,
+}
+
+/// A struct to hold the privacy warnings for the wallet's outputs
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct PrivacyAnalysisResult {
+    /// The number of outputs analysed
+    pub analysed: usize,
+    /// The patterns found
+    pub warnings: Vec<PrivacyWarning>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn privacy_analysis_error(error: &str) -> PrivacyAnalysisResult {
+    PrivacyAnalysisResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+// Warns about every group of more than one output sharing a key
+fn shared_key_warnings(
+    groups: BTreeMap<String, Vec<String>>,
+    kind: PrivacyWarningKind,
+    describe: impl Fn(&str, usize) -> String,
+) -> impl Iterator<Item = PrivacyWarning> {
+    groups
+        .into_iter()
+        .filter(|(_, outputs)| outputs.len() > 1)
+        .map(move |(key, outputs)| PrivacyWarning {
+            kind,
+            message: describe(&key, outputs.len()),
+            outputs,
+        })
+}
+
+/// Analyses the wallet's own outputs for patterns that degrade its privacy: one-sided addresses that received more
+/// than one payment, change outputs with round values, and outputs sharing a sender offset public key. The analysis
+/// runs locally over the wallet's data only, so that wallets can coach their users.
+/// * `outputs` - a JSON array of `PrivacyAnalysisOutput`s, e.g. `[{ "output": "<hex>", "value": 1000000 }]`, with
+///   `"change": true` for change outputs
+#[wasm_bindgen]
+pub fn analyse_output_privacy(outputs: &str) -> PrivacyAnalysisResult {
+    let outputs: Vec<PrivacyAnalysisOutput> = match serde_json::from_str(outputs) {
+        Ok(val) => val,
+        Err(e) => return privacy_analysis_error(&format!("outputs: {e}")),
+    };
+    let mut addresses: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut sender_offset_keys: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut warnings = Vec::new();
+    for (i, entry) in outputs.iter().enumerate() {
+        let output = match decode_output_str(&entry.output, OutputEncoding::Auto) {
+            Ok(val) => val,
+            Err(e) => return privacy_analysis_error(&format!("output {i}: {e}")),
+        };
+        let hash = output.hash().to_hex();
+        // Stealth addresses are unique per payment, so only plain one-sided and multiparty keys can be reused
+        match match_script(&output.script).map(|matched| matched.pattern) {
+            Some(ScriptPattern::OneSided { script_public_key }) => {
+                addresses
+                    .entry(script_public_key.to_hex())
+                    .or_default()
+                    .push(hash.clone());
+            },
+            Some(ScriptPattern::MultiParty { public_keys }) => {
+                for public_key in public_keys {
+                    addresses.entry(public_key.to_hex()).or_default().push(hash.clone());
+                }
+            },
+            _ => {},
+        }
+        sender_offset_keys
+            .entry(output.sender_offset_public_key.to_hex())
+            .or_default()
+            .push(hash.clone());
+        if entry.change && entry.value > 0 && entry.value % ROUND_AMOUNT == 0 {
+            warnings.push(PrivacyWarning {
+                kind: PrivacyWarningKind::RoundAmountChange,
+                message: format!(
+                    "Change output of {} µT has a round value, which reveals the payment output",
+                    entry.value
+                ),
+                outputs: vec![hash],
+            });
+        }
+    }
+    warnings.extend(shared_key_warnings(
+        addresses,
+        PrivacyWarningKind::AddressReuse,
+        |key, count| format!("{count} outputs were paid to the same address {key}, which links the payments"),
+    ));
+    warnings.extend(shared_key_warnings(
+        sender_offset_keys,
+        PrivacyWarningKind::CommonSenderOffsetKey,
+        |key, count| format!("{count} outputs share the sender offset public key {key}, which links them"),
+    ));
+    PrivacyAnalysisResult {
+        analysed: outputs.len(),
+        warnings,
+        error: None,
+    }
+}
@@ -0,0 +1,572 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! The subset of the base node's protobuf messages (package `tari.rpc`) that this library reads, declared by hand so
+//! that no protobuf compiler is needed to build it, with conversions into the internal types, so that data from gRPC
+//! responses does not need to be transcoded in JS. Fields that are not declared are skipped when decoding.
+
+use std::convert::{TryFrom, TryInto};
+
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{ComAndPubSignature, Commitment, PrivateKey, PublicKey, RangeProof, Signature};
+use tari_core::{
+    covenants::Covenant,
+    transactions::{
+        tari_amount::MicroMinotari,
+        transaction_components::{
+            BuildInfo,
+            CodeTemplateRegistration,
+            ConfidentialOutputData,
+            EncryptedData,
+            KernelFeatures,
+            OutputFeatures,
+            OutputFeaturesVersion,
+            OutputType,
+            RangeProofType,
+            SideChainFeature,
+            TemplateType,
+            TransactionKernel,
+            TransactionKernelVersion,
+            TransactionOutput,
+            TransactionOutputVersion,
+            ValidatorNodeRegistration,
+            ValidatorNodeSignature,
+        },
+    },
+};
+use tari_crypto::tari_utilities::{hex::Hex, ByteArray};
+use tari_script::TariScript;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Empty {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MetaData {
+    #[prost(uint64, tag = "1")]
+    pub best_block_height: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub best_block_hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TipInfoResponse {
+    #[prost(message, optional, tag = "1")]
+    pub metadata: Option<MetaData>,
+    #[prost(bool, tag = "2")]
+    pub initial_sync_achieved: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetBlocksRequest {
+    #[prost(uint64, repeated, tag = "1")]
+    pub heights: Vec<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HistoricalBlock {
+    #[prost(uint64, tag = "1")]
+    pub confirmations: u64,
+    #[prost(message, optional, tag = "2")]
+    pub block: Option<Block>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Block {
+    #[prost(message, optional, tag = "1")]
+    pub header: Option<BlockHeader>,
+    #[prost(message, optional, tag = "2")]
+    pub body: Option<AggregateBody>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BlockHeader {
+    #[prost(bytes = "vec", tag = "1")]
+    pub hash: Vec<u8>,
+    #[prost(uint64, tag = "3")]
+    pub height: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AggregateBody {
+    #[prost(message, repeated, tag = "2")]
+    pub outputs: Vec<ProtoTransactionOutput>,
+    #[prost(message, repeated, tag = "3")]
+    pub kernels: Vec<ProtoTransactionKernel>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoSignature {
+    #[prost(bytes = "vec", tag = "1")]
+    pub public_nonce: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoTransactionKernel {
+    #[prost(uint32, tag = "1")]
+    pub features: u32,
+    #[prost(uint64, tag = "2")]
+    pub fee: u64,
+    #[prost(uint64, tag = "3")]
+    pub lock_height: u64,
+    #[prost(bytes = "vec", tag = "6")]
+    pub excess: Vec<u8>,
+    #[prost(message, optional, tag = "7")]
+    pub excess_sig: Option<ProtoSignature>,
+    #[prost(bytes = "vec", tag = "8")]
+    pub hash: Vec<u8>,
+    #[prost(uint32, tag = "9")]
+    pub version: u32,
+    #[prost(bytes = "vec", tag = "10")]
+    pub burn_commitment: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoRangeProof {
+    #[prost(bytes = "vec", tag = "1")]
+    pub proof_bytes: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoComAndPubSignature {
+    #[prost(bytes = "vec", tag = "1")]
+    pub ephemeral_commitment: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub ephemeral_pubkey: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub u_a: Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub u_x: Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub u_y: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoValidatorNodeRegistration {
+    #[prost(bytes = "vec", tag = "1")]
+    pub public_key: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub signature: Option<ProtoSignature>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoConfidentialOutputData {
+    #[prost(bytes = "vec", tag = "1")]
+    pub claim_public_key: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoWasmInfo {
+    #[prost(uint32, tag = "1")]
+    pub abi_version: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoFlowInfo {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoManifestInfo {}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum ProtoTemplateTypeKind {
+    #[prost(message, tag = "1")]
+    Wasm(ProtoWasmInfo),
+    #[prost(message, tag = "2")]
+    Flow(ProtoFlowInfo),
+    #[prost(message, tag = "3")]
+    Manifest(ProtoManifestInfo),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoTemplateType {
+    #[prost(oneof = "ProtoTemplateTypeKind", tags = "1, 2, 3")]
+    pub template_type: Option<ProtoTemplateTypeKind>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoBuildInfo {
+    #[prost(string, tag = "1")]
+    pub repo_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub commit_hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoTemplateRegistration {
+    #[prost(bytes = "vec", tag = "1")]
+    pub author_public_key: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub author_signature: Option<ProtoSignature>,
+    #[prost(string, tag = "3")]
+    pub template_name: String,
+    #[prost(uint32, tag = "4")]
+    pub template_version: u32,
+    #[prost(message, optional, tag = "5")]
+    pub template_type: Option<ProtoTemplateType>,
+    #[prost(message, optional, tag = "6")]
+    pub build_info: Option<ProtoBuildInfo>,
+    #[prost(bytes = "vec", tag = "7")]
+    pub binary_sha: Vec<u8>,
+    #[prost(string, tag = "8")]
+    pub binary_url: String,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum ProtoSideChainFeatureKind {
+    #[prost(message, tag = "1")]
+    ValidatorNodeRegistration(ProtoValidatorNodeRegistration),
+    #[prost(message, tag = "2")]
+    TemplateRegistration(ProtoTemplateRegistration),
+    #[prost(message, tag = "3")]
+    ConfidentialOutput(ProtoConfidentialOutputData),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoSideChainFeature {
+    #[prost(oneof = "ProtoSideChainFeatureKind", tags = "1, 2, 3")]
+    pub side_chain_feature: Option<ProtoSideChainFeatureKind>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoOutputFeatures {
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+    #[prost(uint32, tag = "2")]
+    pub output_type: u32,
+    #[prost(uint64, tag = "3")]
+    pub maturity: u64,
+    #[prost(bytes = "vec", tag = "4")]
+    pub coinbase_extra: Vec<u8>,
+    #[prost(message, optional, tag = "5")]
+    pub sidechain_feature: Option<ProtoSideChainFeature>,
+    #[prost(uint32, tag = "6")]
+    pub range_proof_type: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoTransactionOutput {
+    #[prost(message, optional, tag = "1")]
+    pub features: Option<ProtoOutputFeatures>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub commitment: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub range_proof: Option<ProtoRangeProof>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub script: Vec<u8>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub sender_offset_public_key: Vec<u8>,
+    #[prost(message, optional, tag = "7")]
+    pub metadata_signature: Option<ProtoComAndPubSignature>,
+    #[prost(bytes = "vec", tag = "8")]
+    pub covenant: Vec<u8>,
+    #[prost(uint32, tag = "9")]
+    pub version: u32,
+    #[prost(bytes = "vec", tag = "10")]
+    pub encrypted_data: Vec<u8>,
+    #[prost(uint64, tag = "11")]
+    pub minimum_value_promise: u64,
+}
+
+fn to_u8(value: u32, name: &str) -> Result<u8, String> {
+    u8::try_from(value).map_err(|_| format!("{name}: {value} is out of range"))
+}
+
+impl TryFrom<ProtoComAndPubSignature> for ComAndPubSignature {
+    type Error = String;
+
+    fn try_from(signature: ProtoComAndPubSignature) -> Result<Self, Self::Error> {
+        let ephemeral_commitment =
+            Commitment::from_canonical_bytes(&signature.ephemeral_commitment).map_err(|e| e.to_string())?;
+        let ephemeral_pubkey =
+            PublicKey::from_canonical_bytes(&signature.ephemeral_pubkey).map_err(|e| e.to_string())?;
+        let u_a = PrivateKey::from_canonical_bytes(&signature.u_a).map_err(|e| e.to_string())?;
+        let u_x = PrivateKey::from_canonical_bytes(&signature.u_x).map_err(|e| e.to_string())?;
+        let u_y = PrivateKey::from_canonical_bytes(&signature.u_y).map_err(|e| e.to_string())?;
+        Ok(ComAndPubSignature::new(
+            ephemeral_commitment,
+            ephemeral_pubkey,
+            u_a,
+            u_x,
+            u_y,
+        ))
+    }
+}
+
+impl TryFrom<ProtoSignature> for Signature {
+    type Error = String;
+
+    fn try_from(signature: ProtoSignature) -> Result<Self, Self::Error> {
+        let public_nonce = PublicKey::from_canonical_bytes(&signature.public_nonce).map_err(|e| e.to_string())?;
+        let signature = PrivateKey::from_canonical_bytes(&signature.signature).map_err(|e| e.to_string())?;
+        Ok(Signature::new(public_nonce, signature))
+    }
+}
+
+impl TryFrom<ProtoTemplateType> for TemplateType {
+    type Error = String;
+
+    fn try_from(template_type: ProtoTemplateType) -> Result<Self, Self::Error> {
+        match template_type.template_type {
+            Some(ProtoTemplateTypeKind::Wasm(info)) => Ok(TemplateType::Wasm {
+                abi_version: u16::try_from(info.abi_version)
+                    .map_err(|_| format!("abi_version: {} is out of range", info.abi_version))?,
+            }),
+            Some(ProtoTemplateTypeKind::Flow(_)) => Ok(TemplateType::Flow),
+            Some(ProtoTemplateTypeKind::Manifest(_)) => Ok(TemplateType::Manifest),
+            None => Err("The template type is empty".to_string()),
+        }
+    }
+}
+
+impl TryFrom<ProtoBuildInfo> for BuildInfo {
+    type Error = String;
+
+    fn try_from(build_info: ProtoBuildInfo) -> Result<Self, Self::Error> {
+        Ok(BuildInfo {
+            repo_url: build_info.repo_url.try_into().map_err(|e| format!("repo_url: {e}"))?,
+            commit_hash: build_info
+                .commit_hash
+                .try_into()
+                .map_err(|e| format!("commit_hash: {e}"))?,
+        })
+    }
+}
+
+impl TryFrom<ProtoTemplateRegistration> for CodeTemplateRegistration {
+    type Error = String;
+
+    fn try_from(registration: ProtoTemplateRegistration) -> Result<Self, Self::Error> {
+        let author_public_key = PublicKey::from_canonical_bytes(&registration.author_public_key)
+            .map_err(|e| format!("author_public_key: {e}"))?;
+        let author_signature = registration
+            .author_signature
+            .ok_or_else(|| "author_signature: missing".to_string())?
+            .try_into()
+            .map_err(|e| format!("author_signature: {e}"))?;
+        Ok(CodeTemplateRegistration {
+            author_public_key,
+            author_signature,
+            template_name: registration
+                .template_name
+                .try_into()
+                .map_err(|e| format!("template_name: {e}"))?,
+            template_version: u16::try_from(registration.template_version)
+                .map_err(|_| format!("template_version: {} is out of range", registration.template_version))?,
+            template_type: registration
+                .template_type
+                .ok_or_else(|| "template_type: missing".to_string())?
+                .try_into()
+                .map_err(|e| format!("template_type: {e}"))?,
+            build_info: registration
+                .build_info
+                .ok_or_else(|| "build_info: missing".to_string())?
+                .try_into()
+                .map_err(|e| format!("build_info: {e}"))?,
+            binary_sha: registration
+                .binary_sha
+                .try_into()
+                .map_err(|e| format!("binary_sha: {e}"))?,
+            binary_url: registration
+                .binary_url
+                .try_into()
+                .map_err(|e| format!("binary_url: {e}"))?,
+        })
+    }
+}
+
+impl TryFrom<ProtoSideChainFeature> for SideChainFeature {
+    type Error = String;
+
+    fn try_from(feature: ProtoSideChainFeature) -> Result<Self, Self::Error> {
+        match feature.side_chain_feature {
+            Some(ProtoSideChainFeatureKind::ValidatorNodeRegistration(registration)) => {
+                let public_key = PublicKey::from_canonical_bytes(&registration.public_key)
+                    .map_err(|e| format!("public_key: {e}"))?;
+                let signature = registration
+                    .signature
+                    .ok_or_else(|| "signature: missing".to_string())?
+                    .try_into()
+                    .map_err(|e| format!("signature: {e}"))?;
+                Ok(SideChainFeature::ValidatorNodeRegistration(
+                    ValidatorNodeRegistration::new(ValidatorNodeSignature::new(public_key, signature)),
+                ))
+            },
+            Some(ProtoSideChainFeatureKind::ConfidentialOutput(data)) => {
+                let claim_public_key = PublicKey::from_canonical_bytes(&data.claim_public_key)
+                    .map_err(|e| format!("claim_public_key: {e}"))?;
+                Ok(SideChainFeature::ConfidentialOutput(ConfidentialOutputData {
+                    claim_public_key,
+                }))
+            },
+            Some(ProtoSideChainFeatureKind::TemplateRegistration(registration)) => {
+                Ok(SideChainFeature::CodeTemplateRegistration(registration.try_into()?))
+            },
+            None => Err("The side-chain feature is empty".to_string()),
+        }
+    }
+}
+
+impl TryFrom<ProtoOutputFeatures> for OutputFeatures {
+    type Error = String;
+
+    fn try_from(features: ProtoOutputFeatures) -> Result<Self, Self::Error> {
+        let sidechain_feature = features
+            .sidechain_feature
+            .map(SideChainFeature::try_from)
+            .transpose()
+            .map_err(|e| format!("sidechain_feature: {e}"))?;
+        let version = OutputFeaturesVersion::try_from(to_u8(features.version, "features version")?)
+            .map_err(|e| format!("features version: {e}"))?;
+        let output_type = OutputType::from_byte(to_u8(features.output_type, "output type")?)
+            .ok_or_else(|| format!("output type: unknown type {}", features.output_type))?;
+        let range_proof_type = RangeProofType::from_byte(to_u8(features.range_proof_type, "range proof type")?)
+            .ok_or_else(|| format!("range proof type: unknown type {}", features.range_proof_type))?;
+        Ok(OutputFeatures::new(
+            version,
+            output_type,
+            features.maturity,
+            features.coinbase_extra,
+            sidechain_feature,
+            range_proof_type,
+        ))
+    }
+}
+
+impl TryFrom<ProtoTransactionOutput> for TransactionOutput {
+    type Error = String;
+
+    fn try_from(output: ProtoTransactionOutput) -> Result<Self, Self::Error> {
+        let features = output
+            .features
+            .ok_or_else(|| "features: missing".to_string())?
+            .try_into()?;
+        let commitment =
+            Commitment::from_canonical_bytes(&output.commitment).map_err(|e| format!("commitment: {e}"))?;
+        let proof = match output.range_proof {
+            Some(proof) if !proof.proof_bytes.is_empty() => Some(RangeProof::from(proof.proof_bytes)),
+            _ => None,
+        };
+        let script = TariScript::from_bytes(&output.script).map_err(|e| format!("script: {e}"))?;
+        let sender_offset_public_key = PublicKey::from_canonical_bytes(&output.sender_offset_public_key)
+            .map_err(|e| format!("sender_offset_public_key: {e}"))?;
+        let metadata_signature = output
+            .metadata_signature
+            .ok_or_else(|| "metadata_signature: missing".to_string())?
+            .try_into()
+            .map_err(|e| format!("metadata_signature: {e}"))?;
+        // The base node sends the covenant Borsh-encoded, with a length prefix, so that an empty covenant is `[0]`
+        let covenant = borsh::from_slice::<Covenant>(&output.covenant).map_err(|e| format!("covenant: {e}"))?;
+        let version = TransactionOutputVersion::try_from(to_u8(output.version, "version")?)
+            .map_err(|e| format!("version: {e}"))?;
+        let encrypted_data =
+            EncryptedData::from_bytes(&output.encrypted_data).map_err(|e| format!("encrypted_data: {e}"))?;
+        Ok(TransactionOutput::new(
+            version,
+            features,
+            commitment,
+            proof,
+            script,
+            sender_offset_public_key,
+            metadata_signature,
+            covenant,
+            encrypted_data,
+            MicroMinotari::from(output.minimum_value_promise),
+        ))
+    }
+}
+
+impl TryFrom<ProtoTransactionKernel> for TransactionKernel {
+    type Error = String;
+
+    fn try_from(kernel: ProtoTransactionKernel) -> Result<Self, Self::Error> {
+        let version = TransactionKernelVersion::try_from(to_u8(kernel.version, "version")?)
+            .map_err(|e| format!("version: {e}"))?;
+        let features = KernelFeatures::from_bits(to_u8(kernel.features, "features")?)
+            .ok_or_else(|| format!("features: invalid bits {}", kernel.features))?;
+        let excess = Commitment::from_canonical_bytes(&kernel.excess).map_err(|e| format!("excess: {e}"))?;
+        let excess_sig = kernel
+            .excess_sig
+            .ok_or_else(|| "excess_sig: missing".to_string())?
+            .try_into()
+            .map_err(|e| format!("excess_sig: {e}"))?;
+        let burn_commitment = if kernel.burn_commitment.is_empty() {
+            None
+        } else {
+            Some(
+                Commitment::from_canonical_bytes(&kernel.burn_commitment)
+                    .map_err(|e| format!("burn_commitment: {e}"))?,
+            )
+        };
+        Ok(TransactionKernel::new(
+            version,
+            features,
+            MicroMinotari::from(kernel.fee),
+            kernel.lock_height,
+            excess,
+            excess_sig,
+            burn_commitment,
+        ))
+    }
+}
+
+/// A struct to hold a transaction component converted from its protobuf encoding
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct ProtoConversionResult {
+    /// The hash of the component (hex value)
+    pub hash: Option<String>,
+    /// The component, serialized as JSON
+    pub json: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn proto_conversion_error(error: &str) -> ProtoConversionResult {
+    ProtoConversionResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn convert<P: Message + Default, T: TryFrom<P, Error = String> + Serialize>(
+    bytes: &[u8],
+    name: &str,
+    hash: impl Fn(&T) -> String,
+) -> ProtoConversionResult {
+    let message = match P::decode(bytes) {
+        Ok(val) => val,
+        Err(e) => return proto_conversion_error(&format!("{name}: {e}")),
+    };
+    let value = match T::try_from(message) {
+        Ok(val) => val,
+        Err(e) => return proto_conversion_error(&format!("{name}: {e}")),
+    };
+    match serde_json::to_string(&value) {
+        Ok(json) => ProtoConversionResult {
+            hash: Some(hash(&value)),
+            json: Some(json),
+            error: None,
+        },
+        Err(e) => proto_conversion_error(&format!("Could not serialize {name}: {e}")),
+    }
+}
+
+/// Converts a `tari.rpc.TransactionOutput` protobuf message, e.g. from a base node gRPC response, into an output,
+/// returned as JSON with its hash. The output's hash is computed, not taken from the message.
+#[wasm_bindgen]
+pub fn output_from_proto_bytes(bytes: &[u8]) -> ProtoConversionResult {
+    convert::<ProtoTransactionOutput, TransactionOutput>(bytes, "output", |output| output.hash().to_hex())
+}
+
+/// Converts a `tari.rpc.TransactionKernel` protobuf message into a kernel, returned as JSON with its hash
+#[wasm_bindgen]
+pub fn kernel_from_proto_bytes(bytes: &[u8]) -> ProtoConversionResult {
+    convert::<ProtoTransactionKernel, TransactionKernel>(bytes, "kernel", |kernel| kernel.hash().to_hex())
+}
@@ -0,0 +1,162 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Scan results sealed to an X25519 session key, so that recovered keys can be relayed through page scripts to a
+//! secure context, e.g. a service worker or a native messaging host, that holds the session's private key. The page
+//! only sees the envelope, although its length tells a match from a miss.
+
+use std::convert::TryFrom;
+
+use blake2::Blake2b;
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, Payload},
+    ChaCha20Poly1305,
+    Key,
+    KeyInit,
+    Nonce,
+};
+use digest::consts::U32;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use tari_crypto::{
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    tari_utilities::hex::{from_hex, Hex},
+};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey, SharedSecret, StaticSecret};
+
+use crate::{output_encoding::OutputEncoding, scan_outputs::ScannerContext, RecoveredOutputResult};
+
+hash_domain!(
+    ResultEnvelopeHashDomain,
+    "com.tari.transaction_services.result_envelope",
+    1
+);
+
+type ResultEnvelopeHasher = DomainSeparatedHasher<Blake2b<U32>, ResultEnvelopeHashDomain>;
+
+// The format version, stored as the first byte of an envelope
+const RESULT_ENVELOPE_VERSION: u8 = 0x01;
+const SIZE_PUBLIC_KEY: usize = 32;
+const SIZE_NONCE: usize = 12;
+const SIZE_HEADER: usize = 1 + SIZE_PUBLIC_KEY + SIZE_NONCE;
+
+/// An X25519 session key pair
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SessionKeyPair {
+    /// The private key, which never needs to leave the secure context (hex value)
+    pub secret_key: String,
+    /// The public key, handed to the scanner (hex value)
+    pub public_key: String,
+}
+
+fn parse_key(key: &str, name: &str) -> Result<[u8; SIZE_PUBLIC_KEY], String> {
+    let bytes = from_hex(key).map_err(|e| format!("{name}: {e}"))?;
+    <[u8; SIZE_PUBLIC_KEY]>::try_from(bytes.as_slice()).map_err(|_| format!("{name}: expected {SIZE_PUBLIC_KEY} bytes"))
+}
+
+/// Derives the AEAD key from the Diffie-Hellman shared secret of the ephemeral key and the session key. Both public
+/// keys are bound in, so that the key is unique to this envelope and this session.
+fn envelope_key(
+    shared_secret: &SharedSecret,
+    ephemeral_public_key: &X25519PublicKey,
+    session_public_key: &X25519PublicKey,
+) -> Key {
+    let hash = ResultEnvelopeHasher::new_with_label("aead_key")
+        .chain(shared_secret.as_bytes())
+        .chain(ephemeral_public_key.as_bytes())
+        .chain(session_public_key.as_bytes())
+        .finalize();
+    *Key::from_slice(hash.as_ref())
+}
+
+/// Encrypts `message` to the session key with a key derived from an exchange with a fresh ephemeral key. The envelope
+/// is `version || ephemeral public key || nonce || ciphertext`, where the ciphertext is encrypted with
+/// ChaCha20-Poly1305 and the header is authenticated along with it.
+fn seal(message: &[u8], session_public_key: &X25519PublicKey) -> Result<Vec<u8>, String> {
+    let ephemeral_secret = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public_key = X25519PublicKey::from(&ephemeral_secret);
+    let shared_secret = ephemeral_secret.diffie_hellman(session_public_key);
+    let key = envelope_key(&shared_secret, &ephemeral_public_key, session_public_key);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let mut envelope = Vec::with_capacity(SIZE_HEADER + message.len());
+    envelope.push(RESULT_ENVELOPE_VERSION);
+    envelope.extend_from_slice(ephemeral_public_key.as_bytes());
+    envelope.extend_from_slice(&nonce);
+    let ciphertext = ChaCha20Poly1305::new(&key)
+        .encrypt(&nonce, Payload {
+            msg: message,
+            aad: &envelope,
+        })
+        .map_err(|e| format!("Could not encrypt result: {e}"))?;
+    envelope.extend_from_slice(&ciphertext);
+    Ok(envelope)
+}
+
+/// Decrypts an envelope produced by `seal` with the session's private key
+fn open(envelope: &[u8], session_secret: &StaticSecret) -> Result<Vec<u8>, String> {
+    if envelope.len() < SIZE_HEADER {
+        return Err("The envelope is too short".to_string());
+    }
+    let (header, ciphertext) = envelope.split_at(SIZE_HEADER);
+    if header[0] != RESULT_ENVELOPE_VERSION {
+        return Err(format!("Unsupported envelope version `{}`", header[0]));
+    }
+    let ephemeral_public_key = X25519PublicKey::from(
+        <[u8; SIZE_PUBLIC_KEY]>::try_from(&header[1..=SIZE_PUBLIC_KEY]).expect("header has a 32 byte public key"),
+    );
+    let nonce = Nonce::from_slice(&header[1 + SIZE_PUBLIC_KEY..]);
+    let session_public_key = X25519PublicKey::from(session_secret);
+    let shared_secret = session_secret.diffie_hellman(&ephemeral_public_key);
+    let key = envelope_key(&shared_secret, &ephemeral_public_key, &session_public_key);
+    ChaCha20Poly1305::new(&key)
+        .decrypt(nonce, Payload {
+            msg: ciphertext,
+            aad: header,
+        })
+        .map_err(|_| "Could not decrypt the envelope, it is not for this key or has been tampered with".to_string())
+}
+
+/// Creates a random X25519 session key pair, e.g. in the secure context that will open the sealed results
+#[wasm_bindgen]
+pub fn generate_session_key() -> SessionKeyPair {
+    let secret = StaticSecret::random_from_rng(OsRng);
+    SessionKeyPair {
+        secret_key: secret.to_bytes().to_hex(),
+        public_key: X25519PublicKey::from(&secret).as_bytes().to_hex(),
+    }
+}
+
+/// Decrypts a sealed scan result with the session's private key (hex value), returning the `RecoveredOutputResult`.
+/// Fails if the envelope was sealed to another session key or has been modified.
+#[wasm_bindgen]
+pub fn open_scan_result(envelope: &[u8], session_sk: &str) -> Result<RecoveredOutputResult, JsValue> {
+    let session_secret = StaticSecret::from(parse_key(session_sk, "session_sk").map_err(|e| JsValue::from_str(&e))?);
+    let json = open(envelope, &session_secret).map_err(|e| JsValue::from_str(&e))?;
+    serde_json::from_slice(&json).map_err(|e| JsValue::from_str(&format!("Could not deserialize result: {e}")))
+}
+
+#[wasm_bindgen]
+impl ScannerContext {
+    /// Scans a transaction output as `scan` does, but returns the result, matched or not, sealed to the X25519 session
+    /// public key (hex value) with ChaCha20-Poly1305, to be opened with `open_scan_result` by the holder of the session
+    /// private key
+    pub fn scan_sealed(
+        &self,
+        output: JsValue,
+        encoding: Option<OutputEncoding>,
+        session_pk: &str,
+    ) -> Result<Vec<u8>, JsValue> {
+        let session_public_key =
+            X25519PublicKey::from(parse_key(session_pk, "session_pk").map_err(|e| JsValue::from_str(&e))?);
+        let result = self.scan(output, encoding);
+        let json =
+            serde_json::to_vec(&result).map_err(|e| JsValue::from_str(&format!("Could not serialize result: {e}")))?;
+        seal(&json, &session_public_key).map_err(|e| JsValue::from_str(&e))
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use js_sys::Array;
+use serde::{Deserialize, Serialize};
+use tari_core::{
+    limited_reader::LimitedBytesReader,
+    transactions::{aggregated_body::AggregateBody, transaction_components::TransactionOutput},
+};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    deposit_tagging::DepositRules,
+    error::{ScanErrorCode, WasmError},
+    network::{on_network, parse_network},
+    scan_coinbase::scan_coinbase_output,
+    scan_error,
+    scan_outputs::{scan_output, scan_result, ScanKeys},
+    secrets::protect_results,
+    size_budget::check_output_components,
+    RecoveredOutputResult,
+};
+
+/// The maximum size of the Borsh bytes of a block body
+const MAX_BLOCK_BYTES: usize = 16 * 1024 * 1024;
+
+/// An output of a block that belongs to the wallet or could not be scanned
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockScannedOutput {
+    /// The index of the output in the block body
+    pub output_index: u64,
+    /// The height of the block the output was mined in
+    pub mined_height: u64,
+    /// The result of scanning the output
+    #[serde(flatten)]
+    pub result: RecoveredOutputResult,
+}
+
+/// A struct to hold the results of scanning the outputs of a block
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BlockScanResult {
+    /// The outputs found to belong to the wallet, and the outputs that could not be scanned
+    pub outputs: Vec<BlockScannedOutput>,
+    /// The number of outputs in the block
+    pub scanned: u64,
+    /// The number of outputs found to belong to the wallet
+    pub matches: u64,
+    /// An error message in case the block could not be deserialized
+    pub error: Option<String>,
+}
+
+fn block_scan_error(error: &str) -> BlockScanResult {
+    BlockScanResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Scans every output of a block body with one call, rather than one call per output. Coinbase outputs are scanned
+/// for a payment to the wallet's address, with the wallet secret key as the view key and its public key as the spend
+/// key, and all outputs for one-sided and stealth one-sided payments, as `scan_output_for_one_sided_payment` does.
+/// * `block_bytes` - the Borsh bytes of the block's aggregate body
+/// * `mined_height` - the height of the block, returned with each recovered output
+/// * `deposit_rules` - a JSON array of `DepositRule`s to tag recovered outputs with, if provided
+/// * `network` - the network the outputs are hashed for, e.g. `mainnet`, or the library's network if not provided
+#[wasm_bindgen]
+pub fn scan_block(
+    block_bytes: &[u8],
+    mined_height: u64,
+    known_script_keys: Vec<String>,
+    wallet_sk: String,
+    deposit_rules: Option<String>,
+    network: Option<String>,
+) -> BlockScanResult {
+    let keys = match ScanKeys::new(known_script_keys, wallet_sk) {
+        Ok(val) => val,
+        Err(e) => return block_scan_error(&e.message),
+    };
+    let rules = match DepositRules::from_optional_json(deposit_rules.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return block_scan_error(&e.message),
+    };
+    let network = match parse_network(network.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return block_scan_error(&e),
+    };
+    on_network(network, || scan_block_body(&keys, &rules, block_bytes, mined_height))
+}
+
+/// Scans every output of a block body as `scan_block` does, returning an array of `ProtectedScanResult`s, which hold
+/// the spending and script keys of the recovered outputs in wasm memory as `SecretKeyHandle`s, for the outputs that
+/// belong to the wallet and those that could not be scanned, identified by their hash
+#[wasm_bindgen]
+pub fn scan_block_protected(
+    block_bytes: &[u8],
+    mined_height: u64,
+    known_script_keys: Vec<String>,
+    wallet_sk: String,
+    deposit_rules: Option<String>,
+    network: Option<String>,
+) -> Result<Array, JsValue> {
+    protected_block_results(scan_block(
+        block_bytes,
+        mined_height,
+        known_script_keys,
+        wallet_sk,
+        deposit_rules,
+        network,
+    ))
+}
+
+/// The outputs of a block scan as an array of `ProtectedScanResult`s, or the error of the scan
+pub(crate) fn protected_block_results(result: BlockScanResult) -> Result<Array, JsValue> {
+    match result.error {
+        Some(e) => Err(JsValue::from_str(&e)),
+        None => Ok(protect_results(result.outputs.into_iter().map(|output| output.result))),
+    }
+}
+
+pub(crate) fn scan_block_body(
+    keys: &ScanKeys,
+    rules: &DepositRules,
+    block_bytes: &[u8],
+    mined_height: u64,
+) -> BlockScanResult {
+    match block_body_from_borsh(block_bytes) {
+        Ok(body) => scan_aggregate_body(keys, rules, &body, mined_height),
+        Err(e) => block_scan_error(&format!("block_bytes: {e}")),
+    }
+}
+
+/// Deserializes the Borsh bytes of a block's aggregate body
+pub(crate) fn block_body_from_borsh(block_bytes: &[u8]) -> Result<AggregateBody, String> {
+    let mut reader = LimitedBytesReader::new(MAX_BLOCK_BYTES, block_bytes);
+    borsh::from_reader(&mut reader).map_err(|e| format!("Borsh: {e}"))
+}
+
+/// Scans every output of a block body that has already been deserialized
+pub(crate) fn scan_aggregate_body(
+    keys: &ScanKeys,
+    rules: &DepositRules,
+    body: &AggregateBody,
+    mined_height: u64,
+) -> BlockScanResult {
+    let mut result = BlockScanResult::default();
+    for (index, output) in body.outputs().iter().enumerate() {
+        let scanned = match check_output_components(&output.script, &output.covenant, &output.features) {
+            Ok(()) => scan_block_output(keys, rules, output),
+            Err(e) => scan_error(WasmError::new(ScanErrorCode::DeserializeError, e)),
+        };
+        if scanned.hash.is_some() {
+            result.matches += 1;
+        }
+        if scanned.hash.is_some() || scanned.error.is_some() {
+            result.outputs.push(BlockScannedOutput {
+                output_index: index as u64,
+                mined_height,
+                result: scanned,
+            });
+        }
+    }
+    result.scanned = body.outputs().len() as u64;
+    result
+}
+
+/// Scans a coinbase output for a payment to the wallet's address, falling back to the one-sided scanner for coinbases
+/// paid to a known script key, and any other output with the one-sided scanner
+fn scan_block_output(keys: &ScanKeys, rules: &DepositRules, output: &TransactionOutput) -> RecoveredOutputResult {
+    if output.is_coinbase() {
+        let (wallet_sk, wallet_pk) = keys.wallet_keys();
+        let scanned = scan_coinbase_output(wallet_sk, wallet_pk, output);
+        if scanned.hash.is_some() || scanned.error.is_some() {
+            return scanned;
+        }
+    }
+    scan_result(scan_output(keys, rules, output))
+}
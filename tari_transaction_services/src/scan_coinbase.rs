@@ -0,0 +1,120 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use minotari_wallet::output_source::OutputSource;
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_comms::types::CommsDHKE;
+use tari_core::{
+    one_sided::{diffie_hellman_stealth_domain_hasher, stealth_address_script_spending_key},
+    transactions::transaction_components::TransactionOutput,
+};
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_script::Opcode;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    error::{ScanErrorCode, WasmError},
+    network::{on_network, parse_scan_network},
+    no_match,
+    output_encoding::{decode_output, OutputEncoding},
+    scan_error,
+    scan_outputs_ledger::verify_onesided_output_ledger,
+    secrets::ProtectedScanResult,
+    RecoveredOutputResult,
+};
+
+/// Scans a coinbase output for a payment to this wallet's address, e.g. a mining pool payout. Coinbase outputs paid to
+/// an address lock the value to the wallet's public spend key, either directly with `PushPubKey` or with a stealth
+/// address script, and encrypt the value and spending key with the shared secret of the wallet's secret view key and
+/// the miner's sender offset public key. Outputs that are not coinbases do not match. A recovered output's
+/// `outputSource` is `Coinbase`, and its `features` carry the coinbase extra, also as text if it is UTF-8, e.g. a
+/// pool's tag. The output is passed in any of the encodings accepted by `scan_output_for_one_sided_payment`, and
+/// hashed for `network`, e.g. `mainnet`, or for the library's network if not provided.
+#[wasm_bindgen]
+pub fn scan_output_for_coinbase(
+    wallet_view_sk: &str,
+    wallet_spend_pk: &str,
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    network: Option<String>,
+) -> RecoveredOutputResult {
+    let wallet_view_sk = match PrivateKey::from_hex(wallet_view_sk) {
+        Ok(val) => val,
+        Err(e) => {
+            return scan_error(WasmError::new(
+                ScanErrorCode::InvalidKey,
+                format!("wallet_view_sk: {e}"),
+            ))
+        },
+    };
+    let wallet_spend_pk = match PublicKey::from_hex(wallet_spend_pk) {
+        Ok(val) => val,
+        Err(e) => {
+            return scan_error(WasmError::new(
+                ScanErrorCode::InvalidKey,
+                format!("wallet_spend_pk: {e}"),
+            ))
+        },
+    };
+    let network = match parse_scan_network(network.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return scan_error(e),
+    };
+
+    match decode_output(&output, encoding) {
+        Ok(output) => on_network(network, || {
+            scan_coinbase_output(&wallet_view_sk, &wallet_spend_pk, &output)
+        }),
+        Err(e) => scan_error(e),
+    }
+}
+
+/// Scans a coinbase output as `scan_output_for_coinbase` does, but holds the spending key of a recovered output in wasm
+/// memory as a `SecretKeyHandle`
+#[wasm_bindgen]
+pub fn scan_output_for_coinbase_protected(
+    wallet_view_sk: &str,
+    wallet_spend_pk: &str,
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    network: Option<String>,
+) -> ProtectedScanResult {
+    ProtectedScanResult::from(scan_output_for_coinbase(
+        wallet_view_sk,
+        wallet_spend_pk,
+        output,
+        encoding,
+        network,
+    ))
+}
+
+/// Scans an output that has already been deserialized for a coinbase paid to the wallet's address
+pub(crate) fn scan_coinbase_output(
+    wallet_view_sk: &PrivateKey,
+    wallet_spend_pk: &PublicKey,
+    output: &TransactionOutput,
+) -> RecoveredOutputResult {
+    if !output.is_coinbase() {
+        return no_match();
+    }
+
+    let script_matches = match output.script.as_slice() {
+        // Coinbase paid to the address's spend key
+        [Opcode::PushPubKey(scanned_pk)] => scanned_pk.as_ref() == wallet_spend_pk,
+        // Coinbase paid to a stealth address of the address
+        [Opcode::PushPubKey(nonce), Opcode::Drop, Opcode::PushPubKey(scanned_pk)] => {
+            let stealth_address_hasher = diffie_hellman_stealth_domain_hasher(wallet_view_sk, nonce.as_ref());
+            &stealth_address_script_spending_key(&stealth_address_hasher, wallet_spend_pk) == scanned_pk.as_ref()
+        },
+        _ => false,
+    };
+    if !script_matches {
+        return no_match();
+    }
+
+    let shared_secret = CommsDHKE::new(wallet_view_sk, &output.sender_offset_public_key);
+    match verify_onesided_output_ledger(output, OutputSource::Coinbase.to_string(), &shared_secret) {
+        Ok(result) => result,
+        Err(e) => scan_error(e),
+    }
+}
@@ -1,9 +1,19 @@
 // Copyright 2022 The Tari Project
 // SPDX-License-Identifier: BSD-3-Clause
 
-use borsh::BorshDeserialize;
-use minotari_wallet::output_source::OutputSource;
-use tari_common_types::types::{PrivateKey, PublicKey};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
+    mem::size_of,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use js_sys::{Array, Function};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use tari_common::configuration::Network;
+use tari_common_types::types::{FixedHash, PrivateKey, PublicKey};
 use tari_comms::types::CommsDHKE;
 use tari_core::{
     one_sided::{
@@ -12,6 +22,7 @@ use tari_core::{
         stealth_address_script_spending_key,
     },
     transactions::{
+        tari_amount::MicroMinotari,
         transaction_components::{EncryptedData, TransactionOutput},
         CryptoFactories,
     },
@@ -20,53 +31,397 @@ use tari_crypto::{
     keys::{PublicKey as PK, SecretKey},
     tari_utilities::hex::Hex,
 };
-use tari_script::Opcode;
+use tsify::Tsify;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use zeroize::Zeroizing;
 
-use crate::{no_match, scan_error, RecoveredOutputResult};
+use crate::{
+    coin_selection::{coin_selection_error, select_stored_outputs, CoinSelectionResult, CoinSelectionStrategy},
+    custody_log::{CustodyEventKind, CustodyLog},
+    deposit_tagging::DepositRules,
+    dh_cache::DhCache,
+    error::{ScanErrorCode, WasmError},
+    factories::crypto_factories,
+    lock_heights,
+    network::{on_network, parse_network, parse_scan_network},
+    no_match,
+    output_encoding::{decode_output, decode_output_str, OutputEncoding},
+    output_features::RecoveredOutputFeatures,
+    output_store::{
+        balance_error,
+        calculate_balance,
+        BalanceResult,
+        JsOutputStore,
+        MemoryOutputStore,
+        RecoveredOutputStore,
+        StoredOutput,
+    },
+    scan_block::{protected_block_results, scan_block_body, BlockScanResult},
+    scan_error,
+    scan_receipt::ScanReceiptBuilder,
+    script_tools::{match_script, ScriptPattern},
+    secrets::{protect_results, ProtectedScanResult},
+    session_guard::SessionGuard,
+    RecoveredOutputResult,
+};
 
-/// Scans a transaction output for a one-sided payment belonging to this wallet. The output is scanned for a one-sided
-/// payment using the provided wallet secret key and known script keys. The output is decrypted and verified using the
-/// shared secret derived from the wallet secret key and the sender's offset public key.
-#[wasm_bindgen]
-pub fn scan_output_for_one_sided_payment(known_script_keys: Vec<String>, wallet_sk: &str, output: &str) -> JsValue {
-    let mut known_keys: Vec<(PublicKey, PrivateKey)> = Vec::new();
-    for script_key in known_script_keys {
-        match PrivateKey::from_hex(&script_key) {
-            Ok(key) => known_keys.push((PublicKey::from_secret_key(&key), key)),
-            Err(e) => return scan_error(&e.to_string()),
+const LOG_TARGET: &str = "tari_transaction_services::scan_outputs";
+const SCAN_BATCH_SIZE: usize = 1_000;
+/// The number of shared secrets a scanner caches, if not provided
+const DEFAULT_DH_CACHE_SIZE: u32 = 1_024;
+
+/// The keys used to scan outputs for one-sided payments to this wallet, with their public keys and the commitment and
+/// range proof services derived once rather than for every output. The known script keys are indexed by their public
+/// keys, so that matching an output's script does not get slower with the number of keys.
+pub(crate) struct ScanKeys {
+    known_keys: Vec<PrivateKey>,
+    known_key_indexes: HashMap<PublicKey, usize>,
+    wallet_sk: PrivateKey,
+    wallet_pk: PublicKey,
+    factories: CryptoFactories,
+    dh_cache: Option<Mutex<DhCache>>,
+}
+
+impl ScanKeys {
+    /// Parses the hex keys, which are wiped from wasm memory afterwards, whether or not they are valid
+    pub(crate) fn new(known_script_keys: Vec<String>, wallet_sk: String) -> Result<Self, WasmError> {
+        let known_script_keys = Zeroizing::new(known_script_keys);
+        let wallet_sk = Zeroizing::new(wallet_sk);
+        let wallet_sk = PrivateKey::from_hex(&wallet_sk)
+            .map_err(|e| WasmError::new(ScanErrorCode::InvalidKey, format!("wallet_sk: {e}")))?;
+        let wallet_pk = PublicKey::from_secret_key(&wallet_sk);
+        let mut keys = Self {
+            known_keys: Vec::with_capacity(known_script_keys.len()),
+            known_key_indexes: HashMap::with_capacity(known_script_keys.len()),
+            wallet_sk,
+            wallet_pk,
+            factories: crypto_factories(),
+            dh_cache: None,
+        };
+        for script_key in known_script_keys.iter() {
+            let key = PrivateKey::from_hex(script_key)
+                .map_err(|e| WasmError::new(ScanErrorCode::InvalidKey, format!("known_script_keys: {e}")))?;
+            keys.add_known_key(key);
+        }
+        Ok(keys)
+    }
+
+    /// The wallet's secret key and its public key
+    pub(crate) fn wallet_keys(&self) -> (&PrivateKey, &PublicKey) {
+        (&self.wallet_sk, &self.wallet_pk)
+    }
+
+    /// Caches the shared secrets of up to `capacity` pairs of keys, for scanners that are used for many outputs
+    pub(crate) fn with_dh_cache(mut self, capacity: usize) -> Self {
+        self.dh_cache = Some(Mutex::new(DhCache::new(capacity)));
+        self
+    }
+
+    /// The shared secret of `secret_key`, whose public key is `public_key`, and an output's sender offset public key,
+    /// from the cache if there is one
+    fn shared_secret(
+        &self,
+        secret_key: &PrivateKey,
+        public_key: &PublicKey,
+        sender_offset_public_key: &PublicKey,
+    ) -> Arc<CommsDHKE> {
+        let cache = match self.dh_cache.as_ref() {
+            Some(cache) => cache,
+            None => return Arc::new(CommsDHKE::new(secret_key, sender_offset_public_key)),
         };
+        // The lock is not held while computing a shared secret, so that parallel scans do not wait on each other
+        let cached = cache
+            .lock()
+            .ok()
+            .and_then(|mut cache| cache.get(public_key, sender_offset_public_key));
+        if let Some(shared_secret) = cached {
+            return shared_secret;
+        }
+        let shared_secret = Arc::new(CommsDHKE::new(secret_key, sender_offset_public_key));
+        if let Ok(mut cache) = cache.lock() {
+            cache.insert(public_key, sender_offset_public_key, shared_secret.clone());
+        }
+        shared_secret
+    }
+
+    /// The number of shared secrets answered from the cache and the number computed, if there is a cache
+    pub(crate) fn dh_cache_stats(&self) -> Option<(u64, u64)> {
+        self.dh_cache
+            .as_ref()
+            .and_then(|cache| cache.lock().ok().map(|cache| cache.stats()))
+    }
+
+    /// The known script private key for a script public key, if any
+    fn known_key(&self, public_key: &PublicKey) -> Option<&PrivateKey> {
+        self.known_key_indexes
+            .get(public_key)
+            .map(|index| &self.known_keys[*index])
+    }
+
+    /// Adds a known script key to scan with
+    pub(crate) fn add_known_key(&mut self, key: PrivateKey) {
+        let index = self.known_keys.len();
+        self.known_key_indexes
+            .entry(PublicKey::from_secret_key(&key))
+            .or_insert(index);
+        self.known_keys.push(key);
     }
 
-    let wallet_sk = match PrivateKey::from_hex(wallet_sk) {
+    /// The number of known script keys
+    pub(crate) fn known_key_count(&self) -> usize {
+        self.known_keys.len()
+    }
+
+    /// The position of a known script public key in the order the keys were provided and added, if any
+    pub(crate) fn known_key_index(&self, public_key: &PublicKey) -> Option<usize> {
+        self.known_key_indexes.get(public_key).copied()
+    }
+
+    /// An estimate of the wasm memory held by the known script keys and their index, in bytes
+    pub(crate) fn known_keys_memory_usage(&self) -> usize {
+        self.known_keys.capacity() * size_of::<PrivateKey>() +
+            self.known_key_indexes.capacity() * (size_of::<PublicKey>() + size_of::<usize>() + 1)
+    }
+}
+
+pub(crate) fn scan_result(result: Result<Option<RecoveredOutputResult>, WasmError>) -> RecoveredOutputResult {
+    match result {
+        Ok(Some(result)) => result,
+        Ok(None) => no_match(),
+        Err(e) => scan_error(e),
+    }
+}
+
+/// Scans a transaction output for a one-sided payment belonging to this wallet. The output is scanned for a one-sided
+/// payment using the provided wallet secret key and known script keys. The output is decrypted and verified using the
+/// shared secret derived from the wallet secret key and the sender's offset public key. The output is passed as a
+/// `Uint8Array` of Borsh bytes, or as a hex, base64 or JSON string; the encoding is detected if not provided. A
+/// recovered output is tagged with the tags of the matching `deposit_rules`, a JSON array of `DepositRule`s, if
+/// provided. The output is hashed for `network`, e.g. `mainnet`, or for the library's network (see `set_network`) if
+/// not provided.
+#[wasm_bindgen]
+pub fn scan_output_for_one_sided_payment(
+    known_script_keys: Vec<String>,
+    wallet_sk: String,
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    deposit_rules: Option<String>,
+    network: Option<String>,
+) -> RecoveredOutputResult {
+    let keys = match ScanKeys::new(known_script_keys, wallet_sk) {
         Ok(val) => val,
-        Err(e) => return scan_error(&format!("wallet_sk: {e}")),
+        Err(e) => return scan_error(e),
     };
-    let wallet_pk = PublicKey::from_secret_key(&wallet_sk);
-
-    let output: TransactionOutput = match BorshDeserialize::deserialize(&mut output.as_bytes()) {
+    let rules = match DepositRules::from_optional_json(deposit_rules.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return scan_error(e),
+    };
+    let network = match parse_scan_network(network.as_deref()) {
         Ok(val) => val,
-        Err(e) => return scan_error(&e.to_string()),
+        Err(e) => return scan_error(e),
     };
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => return scan_error(e),
+    };
+    scan_result(on_network(network, || scan_output(&keys, &rules, &output)))
+}
 
-    let (output, output_source, script_private_key, shared_secret) = match output.script.as_slice() {
+/// Scans a transaction output as `scan_output_for_one_sided_payment` does, but holds the spending and script keys of
+/// a recovered output in wasm memory as `SecretKeyHandle`s
+#[wasm_bindgen]
+pub fn scan_output_for_one_sided_payment_protected(
+    known_script_keys: Vec<String>,
+    wallet_sk: String,
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    deposit_rules: Option<String>,
+    network: Option<String>,
+) -> ProtectedScanResult {
+    ProtectedScanResult::from(scan_output_for_one_sided_payment(
+        known_script_keys,
+        wallet_sk,
+        output,
+        encoding,
+        deposit_rules,
+        network,
+    ))
+}
+
+/// The shared secret cache statistics of a `ScannerContext`
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct DhCacheStats {
+    /// The number of shared secrets found in the cache
+    pub hits: u64,
+    /// The number of shared secrets computed
+    pub misses: u64,
+}
+
+/// A scanner for one-sided payments to this wallet, constructed once with the wallet keys, so that the public keys of
+/// the known script keys and the wallet key, the deposit rules and the commitment and range proof services are set up
+/// once instead of for every call to `scan_output_for_one_sided_payment`. The shared secrets with the outputs' sender
+/// offset public keys are cached, so that outputs from the same sender only need one Diffie-Hellman exchange.
+#[wasm_bindgen]
+pub struct ScannerContext {
+    keys: ScanKeys,
+    rules: DepositRules,
+    network: Option<Network>,
+}
+
+#[wasm_bindgen]
+impl ScannerContext {
+    /// Creates a scanner with the wallet secret key, the known script keys and, optionally, the deposit rules, as for
+    /// `scan_output_for_one_sided_payment`, and the network the scanned outputs are hashed for, e.g. `mainnet`, or the
+    /// library's network (see `set_network`) if not provided
+    /// * `dh_cache_size` - the number of shared secrets to cache, 1024 if not provided, or 0 to not cache them
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        known_script_keys: Vec<String>,
+        wallet_sk: String,
+        deposit_rules: Option<String>,
+        network: Option<String>,
+        dh_cache_size: Option<u32>,
+    ) -> Result<ScannerContext, JsValue> {
+        let keys = ScanKeys::new(known_script_keys, wallet_sk)
+            .map_err(JsValue::from)?
+            .with_dh_cache(dh_cache_size.unwrap_or(DEFAULT_DH_CACHE_SIZE) as usize);
+        let rules = DepositRules::from_optional_json(deposit_rules.as_deref()).map_err(JsValue::from)?;
+        let network = parse_network(network.as_deref()).map_err(|e| JsValue::from_str(&e))?;
+        Ok(Self { keys, rules, network })
+    }
+
+    /// Scans a transaction output, passed in any of the encodings accepted by `scan_output_for_one_sided_payment`
+    pub fn scan(&self, output: JsValue, encoding: Option<OutputEncoding>) -> RecoveredOutputResult {
+        match decode_output(&output, encoding) {
+            Ok(output) => scan_result(self.scan_decoded(&output)),
+            Err(e) => scan_error(e),
+        }
+    }
+
+    /// Scans every output of a block, as `scan_block` does
+    pub fn scan_block(&self, block_bytes: &[u8], mined_height: u64) -> BlockScanResult {
+        on_network(self.network, || {
+            scan_block_body(&self.keys, &self.rules, block_bytes, mined_height)
+        })
+    }
+
+    /// The number of known script keys the scanner matches scripts against
+    pub fn key_count(&self) -> u64 {
+        self.keys.known_key_count() as u64
+    }
+
+    /// An estimate of the wasm memory held by the known script keys and their index, in bytes
+    pub fn memory_usage(&self) -> u64 {
+        self.keys.known_keys_memory_usage() as u64
+    }
+
+    /// The number of shared secrets the scanner found in its cache and the number it computed, e.g. to size the cache
+    pub fn dh_cache_stats(&self) -> DhCacheStats {
+        let (hits, misses) = self.keys.dh_cache_stats().unwrap_or_default();
+        DhCacheStats { hits, misses }
+    }
+
+    /// Scans a transaction output as `scan` does, but holds the spending and script keys of a recovered output in wasm
+    /// memory as `SecretKeyHandle`s
+    pub fn scan_protected(&self, output: JsValue, encoding: Option<OutputEncoding>) -> ProtectedScanResult {
+        ProtectedScanResult::from(self.scan(output, encoding))
+    }
+
+    /// Scans every output of a block as `scan_block` does, returning an array of `ProtectedScanResult`s for the outputs
+    /// that belong to the wallet and those that could not be scanned, identified by their hash
+    pub fn scan_block_protected(&self, block_bytes: &[u8], mined_height: u64) -> Result<Array, JsValue> {
+        protected_block_results(self.scan_block(block_bytes, mined_height))
+    }
+}
+
+impl ScannerContext {
+    /// Scans an output that has already been deserialized
+    pub(crate) fn scan_decoded(&self, output: &TransactionOutput) -> Result<Option<RecoveredOutputResult>, WasmError> {
+        self.scan_on_network(output, self.network)
+    }
+
+    /// Scans an output that has already been deserialized, hashing it for `network` instead of the scanner's network
+    pub(crate) fn scan_on_network(
+        &self,
+        output: &TransactionOutput,
+        network: Option<Network>,
+    ) -> Result<Option<RecoveredOutputResult>, WasmError> {
+        on_network(network, || scan_output(&self.keys, &self.rules, output))
+    }
+}
+
+/// A one-sided payment output recovered by scanning
+pub(crate) struct RecoveredOutput {
+    /// The script pattern the output matched, e.g. `OneSided` or `TimeLockedMultiPartyOneSided`
+    pub output_source: String,
+    pub value: MicroMinotari,
+    pub spending_key: PrivateKey,
+    pub script_key: PrivateKey,
+}
+
+pub(crate) fn scan_output(
+    keys: &ScanKeys,
+    rules: &DepositRules,
+    output: &TransactionOutput,
+) -> Result<Option<RecoveredOutputResult>, WasmError> {
+    let (maturity, lock_height, spendable_at_height) = lock_heights(output);
+    Ok(recover_output(keys, output)?.map(|recovered| RecoveredOutputResult {
+        hash: Some(output.hash().to_hex()),
+        output_source: Some(recovered.output_source),
+        output_type: Some(output.features.output_type.to_string()),
+        features: Some(RecoveredOutputFeatures::from(&output.features)),
+        value: Some(recovered.value.as_u64()),
+        minimum_value_promise: Some(output.minimum_value_promise.as_u64()),
+        promise_exceeds_value: Some(output.minimum_value_promise > recovered.value),
+        spending_key: Some(recovered.spending_key.to_hex()),
+        script_key: Some(recovered.script_key.to_hex()),
+        error: None,
+        maturity: Some(maturity),
+        lock_height,
+        spendable_at_height: Some(spendable_at_height),
+        tags: rules.tags(output, recovered.value),
+        error_code: None,
+    }))
+}
+
+/// Scans a transaction output for a one-sided payment belonging to this wallet, returning the recovered value and keys.
+/// The output's script must match one of the patterns recognised by `match_script`.
+pub(crate) fn recover_output(
+    keys: &ScanKeys,
+    output: &TransactionOutput,
+) -> Result<Option<RecoveredOutput>, WasmError> {
+    let matched = match match_script(&output.script) {
+        Some(matched) => matched,
+        None => return Ok(None),
+    };
+    let (script_private_key, shared_secret) = match matched.pattern {
         // ----------------------------------------------------------------------------
         // simple one-sided address
-        [Opcode::PushPubKey(scanned_pk)] => {
-            match known_keys.iter().find(|x| &x.0 == scanned_pk.as_ref()) {
-                // none of the keys match, skipping
-                None => return no_match(),
-
-                // match found
-                Some(matched_key) => {
-                    let shared_secret = CommsDHKE::new(&matched_key.1, &output.sender_offset_public_key);
-                    (
-                        output.clone(),
-                        OutputSource::OneSided,
-                        matched_key.1.clone(),
-                        shared_secret,
-                    )
-                },
+        ScriptPattern::OneSided { script_public_key } => match keys.known_key(script_public_key) {
+            // none of the keys match, skipping
+            None => return Ok(None),
+
+            // match found
+            Some(matched_key) => (
+                matched_key.clone(),
+                keys.shared_secret(matched_key, script_public_key, &output.sender_offset_public_key),
+            ),
+        },
+
+        // ----------------------------------------------------------------------------
+        // multi-party one-sided address, where one of the parties' keys is a known key
+        ScriptPattern::MultiParty { public_keys } => {
+            match public_keys
+                .iter()
+                .find_map(|public_key| keys.known_key(public_key).map(|key| (key, public_key)))
+            {
+                None => return Ok(None),
+                Some((matched_key, public_key)) => (
+                    matched_key.clone(),
+                    keys.shared_secret(matched_key, public_key, &output.sender_offset_public_key),
+                ),
             }
         },
 
@@ -74,69 +429,562 @@ pub fn scan_output_for_one_sided_payment(known_script_keys: Vec<String>, wallet_
         // one-sided stealth address
         // NOTE: Extracting the nonce R and a spending (public aka scan_key) key from the script
         // NOTE: [RFC 203 on Stealth Addresses](https://rfc.tari.com/RFC-0203_StealthAddresses.html)
-        [Opcode::PushPubKey(nonce), Opcode::Drop, Opcode::PushPubKey(scanned_pk)] => {
+        ScriptPattern::StealthOneSided {
+            nonce,
+            script_public_key,
+        } => {
             // matching spending (public) keys
-            let stealth_address_hasher = diffie_hellman_stealth_domain_hasher(&wallet_sk, nonce.as_ref());
-            let script_spending_key = stealth_address_script_spending_key(&stealth_address_hasher, &wallet_pk);
-            if &script_spending_key != scanned_pk.as_ref() {
-                return no_match();
+            let stealth_address_hasher = diffie_hellman_stealth_domain_hasher(&keys.wallet_sk, nonce);
+            let script_spending_key = stealth_address_script_spending_key(&stealth_address_hasher, &keys.wallet_pk);
+            if &script_spending_key != script_public_key {
+                return Ok(None);
             }
 
             // Compute the stealth address offset
             let stealth_address_offset = PrivateKey::from_uniform_bytes(stealth_address_hasher.as_ref())
                 .expect("'DomainSeparatedHash<Blake2b<U64>>' has correct size");
-            let script_private_key = wallet_sk.clone() + stealth_address_offset;
-
-            let shared_secret = CommsDHKE::new(&wallet_sk, &output.sender_offset_public_key);
-            (
-                output.clone(),
-                OutputSource::StealthOneSided,
-                script_private_key,
-                shared_secret,
-            )
-        },
+            let script_private_key = keys.wallet_sk.clone() + stealth_address_offset;
 
-        _ => return no_match(),
+            let shared_secret = keys.shared_secret(&keys.wallet_sk, &keys.wallet_pk, &output.sender_offset_public_key);
+            (script_private_key, shared_secret)
+        },
     };
 
-    verify_onesided_output(&output, output_source, &script_private_key, &shared_secret)
+    match verify_onesided_output(output, &shared_secret, &keys.factories) {
+        Ok((value, spending_key)) => Ok(Some(RecoveredOutput {
+            output_source: matched.name(),
+            value,
+            spending_key,
+            script_key: script_private_key,
+        })),
+        // Anyone can send an output with the wallet's script and data that does not decrypt, so it is not a match
+        Err(e) if matches!(e.code, ScanErrorCode::DecryptError | ScanErrorCode::MaskVerifyError) => {
+            debug!(target: LOG_TARGET, "Skipping output {} with the wallet's script: {e}", output.hash());
+            Ok(None)
+        },
+        Err(e) => Err(e),
+    }
 }
 
+/// Decrypts and verifies an output whose script belongs to this wallet, returning its value and spending key
 fn verify_onesided_output(
     output: &TransactionOutput,
-    output_source: OutputSource,
-    script_private_key: &PrivateKey,
     shared_secret: &CommsDHKE,
-) -> JsValue {
-    let encryption_key = match shared_secret_to_output_encryption_key(shared_secret) {
-        Ok(key) => key,
-        Err(e) => return scan_error(&format!("Could not derive encryption key: {e}")),
-    };
-    let crypto_factories = CryptoFactories::default();
-    if let Ok((committed_value, spending_key)) =
-        EncryptedData::decrypt_data(&encryption_key, &output.commitment, &output.encrypted_data)
-    {
-        match output.verify_mask(&crypto_factories.range_proof, &spending_key, committed_value.into()) {
-            Ok(verified) => {
-                if verified {
-                    let result = RecoveredOutputResult {
-                        hash: Some(output.hash().to_hex()),
-                        output_source: Some(output_source.to_string()),
-                        output_type: Some(output.features.output_type.to_string()),
-                        value: Some(committed_value.as_u64()),
-                        spending_key: Some(spending_key.to_hex()),
-                        script_key: Some(script_private_key.to_hex()),
-                        error: None,
-                        maturity: None,
-                    };
-                    serde_wasm_bindgen::to_value(&result).unwrap()
-                } else {
-                    no_match()
+    factories: &CryptoFactories,
+) -> Result<(MicroMinotari, PrivateKey), WasmError> {
+    let encryption_key = shared_secret_to_output_encryption_key(shared_secret).map_err(|e| {
+        WasmError::new(
+            ScanErrorCode::KeyDerivationError,
+            format!("Could not derive encryption key: {e}"),
+        )
+    })?;
+    let (committed_value, spending_key) =
+        EncryptedData::decrypt_data(&encryption_key, &output.commitment, &output.encrypted_data).map_err(|e| {
+            WasmError::new(
+                ScanErrorCode::DecryptError,
+                format!("Could not decrypt output data: {e}"),
+            )
+        })?;
+    let verified = output
+        .verify_mask(&factories.range_proof, &spending_key, committed_value.into())
+        .map_err(|e| WasmError::new(ScanErrorCode::MaskVerifyError, format!("Could not verify output: {e}")))?;
+    if !verified {
+        return Err(WasmError::new(
+            ScanErrorCode::MaskVerifyError,
+            "The decrypted value and mask do not open the output's commitment",
+        ));
+    }
+    Ok((committed_value, spending_key))
+}
+
+/// The progress of a [ScanSession], as passed to its progress callback
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct ScanProgress {
+    /// The number of outputs scanned so far
+    pub scanned: u64,
+    /// The number of outputs found to belong to this wallet so far
+    pub matches: u64,
+    /// The number of outputs pushed but not yet scanned
+    pub pending: usize,
+    /// The number of outputs skipped because the session had already scanned them
+    pub skipped: u64,
+    /// Whether the session is paused
+    pub paused: bool,
+}
+
+/// The hashes of the most recently scanned outputs, so that outputs pushed again, e.g. when a caller re-feeds an
+/// overlapping block range after a reconnect, are not scanned twice. The oldest hash is forgotten once it is full.
+struct ScannedOutputs {
+    capacity: usize,
+    hashes: HashSet<FixedHash>,
+    order: VecDeque<FixedHash>,
+}
+
+impl ScannedOutputs {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            hashes: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Whether an output was scanned before or earlier in the batch, whose hashes are collected in `batch_hashes`
+    fn is_duplicate(&self, hash: &FixedHash, batch_hashes: &mut HashSet<FixedHash>) -> bool {
+        self.capacity > 0 && (self.hashes.contains(hash) || !batch_hashes.insert(*hash))
+    }
+
+    /// Remembers an output hash, returning whether it was new
+    fn insert(&mut self, hash: FixedHash) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if !self.hashes.insert(hash) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+        self.order.push_back(hash);
+        true
+    }
+}
+
+/// An output queued for scanning
+#[derive(Clone)]
+struct PendingOutput {
+    // The receipt range the output was pushed for, if any
+    range_index: Option<usize>,
+    // The height of the block the output was mined in, if known
+    mined_height: Option<u64>,
+    output: String,
+}
+
+struct ScanState {
+    pending: VecDeque<PendingOutput>,
+    scanned: u64,
+    matches: u64,
+    skipped: u64,
+    scanned_outputs: Option<ScannedOutputs>,
+    receipt: ScanReceiptBuilder,
+    custody: CustodyLog,
+}
+
+/// The changes a scanned batch makes to the state of a session. They are only committed once the progress callback
+/// has returned, so that a batch whose callback throws stays queued, and is neither counted, recorded nor remembered as
+/// scanned.
+#[derive(Default)]
+struct BatchOutcome {
+    // The number of outputs at the front of the queue the batch consists of
+    len: usize,
+    matches: u64,
+    skipped: u64,
+    receipt_records: Vec<(usize, Option<FixedHash>)>,
+    custody_records: Vec<(FixedHash, u64, Option<usize>)>,
+    // The outputs that were scanned without an error and, if they match, stored
+    delivered: Vec<FixedHash>,
+}
+
+impl BatchOutcome {
+    /// The progress of the session once the batch is committed
+    fn progress(&self, current: &ScanProgress) -> ScanProgress {
+        ScanProgress {
+            scanned: current.scanned + self.len as u64 - self.skipped,
+            matches: current.matches + self.matches,
+            pending: current.pending.saturating_sub(self.len),
+            skipped: current.skipped + self.skipped,
+            paused: current.paused,
+        }
+    }
+
+    fn commit(self, state: &mut ScanState) {
+        state.pending.drain(..self.len);
+        for (range_index, output_hash) in self.receipt_records {
+            state.receipt.record(range_index, output_hash.as_ref());
+        }
+        for (output_hash, value, range_index) in self.custody_records {
+            let range = range_index.and_then(|range_index| state.receipt.range(range_index));
+            state
+                .custody
+                .record(CustodyEventKind::OutputRecovered, &output_hash, value, range);
+        }
+        // An output that failed is scanned again if it is pushed again
+        if let Some(scanned_outputs) = state.scanned_outputs.as_mut() {
+            for output_hash in self.delivered {
+                scanned_outputs.insert(output_hash);
+            }
+        }
+        state.scanned += self.len as u64 - self.skipped;
+        state.matches += self.matches;
+        state.skipped += self.skipped;
+    }
+}
+
+/// A streaming scanner for recovery scans of large UTXO sets. Outputs are pushed in chunks with `push_outputs` and
+/// scanned in batches, with the progress callback invoked after every batch so that the UI can stay responsive. The
+/// session can be paused and resumed at any time, including from within the progress callback.
+#[wasm_bindgen]
+pub struct ScanSession {
+    keys: ScanKeys,
+    rules: RefCell<DepositRules>,
+    state: RefCell<ScanState>,
+    paused: Cell<bool>,
+    on_progress: Option<Function>,
+    store: RefCell<Rc<dyn RecoveredOutputStore>>,
+    guard: SessionGuard,
+    protect_results: Cell<bool>,
+}
+
+impl ScanSession {
+    // The store is called through its own handle, so that a JavaScript store may call back into the session
+    fn store(&self) -> Rc<dyn RecoveredOutputStore> {
+        self.store.borrow().clone()
+    }
+
+    // Records a lock or unlock of every output the custody log accounts for, returning the number recorded
+    fn record_locks(&self, kind: CustodyEventKind, output_hashes: &[String]) -> Result<u32, JsValue> {
+        let mut state = self.state.borrow_mut();
+        let mut recorded = 0;
+        for output_hash in output_hashes {
+            let hash = FixedHash::from_hex(output_hash.trim())
+                .map_err(|e| JsValue::from_str(&format!("output_hash {output_hash}: {e}")))?;
+            if let Some(value) = state.custody.unspent_value(&hash) {
+                if state.custody.record(kind, &hash, value, None) {
+                    recorded += 1;
                 }
-            },
-            Err(e) => scan_error(&format!("Could not verify output: {e}")),
+            }
+        }
+        Ok(recorded)
+    }
+
+    fn progress(&self) -> ScanProgress {
+        let state = self.state.borrow();
+        ScanProgress {
+            scanned: state.scanned,
+            matches: state.matches,
+            pending: state.pending.len(),
+            skipped: state.skipped,
+            paused: self.paused.get(),
         }
-    } else {
-        no_match()
+    }
+
+    // Scans pending outputs until the queue is empty or the session is paused, returning the matches and errors as
+    // plain results or `ProtectedScanResult`s
+    fn scan_pending(&self) -> Result<JsValue, JsValue> {
+        let results = self.scan_pending_results()?;
+        if self.protect_results.get() {
+            Ok(protect_results(results).into())
+        } else {
+            Ok(serde_wasm_bindgen::to_value(&results)?)
+        }
+    }
+
+    fn scan_pending_results(&self) -> Result<Vec<RecoveredOutputResult>, JsValue> {
+        let mut results = Vec::new();
+        // Outputs pushed while a scan is in progress (e.g. from the progress callback) are picked up by that scan
+        let _permit = match self.guard.try_acquire("scan") {
+            Ok(permit) => permit,
+            Err(_) => return Ok(results),
+        };
+        while !self.paused.get() {
+            // The batch stays queued until it is committed, so that it is scanned again if the progress callback throws
+            let batch = {
+                let state = self.state.borrow();
+                state.pending.iter().take(SCAN_BATCH_SIZE).cloned().collect::<Vec<_>>()
+            };
+            if batch.is_empty() {
+                break;
+            }
+            let mut outcome = BatchOutcome {
+                len: batch.len(),
+                ..Default::default()
+            };
+            let mut batch_results = Vec::new();
+            // The recovered outputs are stored once the batch is scanned, as the store may call back into the session
+            let mut inserts = Vec::new();
+            let mut batch_hashes = HashSet::new();
+            for pending in &batch {
+                let range_index = pending.range_index;
+                let output = decode_output_str(&pending.output, OutputEncoding::Auto);
+                let output_hash = output.as_ref().ok().map(|output| output.hash());
+                // A duplicate is still part of the range it was pushed for, so it is recorded before it is skipped
+                if let Some(range_index) = range_index {
+                    outcome.receipt_records.push((range_index, output_hash));
+                }
+                if let Some(output_hash) = output_hash {
+                    let state = self.state.borrow();
+                    if let Some(scanned_outputs) = state.scanned_outputs.as_ref() {
+                        if scanned_outputs.is_duplicate(&output_hash, &mut batch_hashes) {
+                            outcome.skipped += 1;
+                            continue;
+                        }
+                    }
+                }
+                match output.and_then(|output| scan_output(&self.keys, &self.rules.borrow(), &output)) {
+                    Ok(Some(result)) => {
+                        if let Some(output_hash) = output_hash {
+                            outcome
+                                .custody_records
+                                .push((output_hash, result.value.unwrap_or_default(), range_index));
+                            let stored = StoredOutput {
+                                output_hash: output_hash.to_hex(),
+                                mined_height: pending.mined_height,
+                                output: result.clone(),
+                                ..Default::default()
+                            };
+                            inserts.push((batch_results.len(), output_hash, stored));
+                        }
+                        outcome.matches += 1;
+                        batch_results.push(result);
+                    },
+                    Ok(None) => outcome.delivered.extend(output_hash),
+                    Err(e) => batch_results.push(RecoveredOutputResult::from(e)),
+                }
+            }
+            // Storing is idempotent, as the outputs are keyed by hash, so a batch that is scanned again is stored again
+            let store = self.store();
+            for (index, output_hash, stored) in inserts {
+                match store.insert(stored) {
+                    Ok(()) => outcome.delivered.push(output_hash),
+                    Err(e) => batch_results[index].error = Some(format!("Could not store the recovered output: {e}")),
+                }
+            }
+            if let Some(on_progress) = &self.on_progress {
+                let progress = serde_wasm_bindgen::to_value(&outcome.progress(&self.progress()))?;
+                on_progress.call1(&JsValue::NULL, &progress)?;
+            }
+            outcome.commit(&mut self.state.borrow_mut());
+            results.extend(batch_results);
+        }
+        Ok(results)
+    }
+}
+
+#[wasm_bindgen]
+impl ScanSession {
+    /// Creates a new scan session with the same keys as `scan_output_for_one_sided_payment`. The optional
+    /// `on_progress` callback is called with a `ScanProgress` object after every batch of scanned outputs.
+    /// * `dedup_capacity` - if provided, the number of output hashes the session remembers to skip outputs it has
+    ///   already scanned, e.g. when overlapping block ranges are pushed again after a reconnect
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        known_script_keys: Vec<String>,
+        wallet_sk: String,
+        on_progress: Option<Function>,
+        dedup_capacity: Option<u32>,
+    ) -> Result<ScanSession, JsValue> {
+        let keys = ScanKeys::new(known_script_keys, wallet_sk).map_err(JsValue::from)?;
+        let state = ScanState {
+            pending: VecDeque::new(),
+            scanned: 0,
+            matches: 0,
+            skipped: 0,
+            scanned_outputs: dedup_capacity.map(|capacity| ScannedOutputs::new(capacity as usize)),
+            receipt: ScanReceiptBuilder::default(),
+            custody: CustodyLog::new(keys.wallet_keys().0),
+        };
+        Ok(Self {
+            keys,
+            rules: RefCell::new(DepositRules::default()),
+            state: RefCell::new(state),
+            paused: Cell::new(false),
+            on_progress,
+            store: RefCell::new(Rc::new(MemoryOutputStore::default())),
+            guard: SessionGuard::new(),
+            protect_results: Cell::new(false),
+        })
+    }
+
+    /// Sets whether `push_outputs`, `push_outputs_for_range` and `resume` return an array of `ProtectedScanResult`s,
+    /// which hold the spending and script keys of the recovered outputs in wasm memory as `SecretKeyHandle`s, instead
+    /// of plain results with the keys as hex values
+    pub fn set_protected_results(&self, protected: bool) {
+        self.protect_results.set(protected);
+    }
+
+    /// Writes the outputs recovered from now on into a store implemented by JavaScript callbacks instead of the
+    /// in-memory store, e.g. over IndexedDB. `store` is an object with `insert(output)`, `markSpent(outputHash,
+    /// spentHeight)`, `listUnspent()` and `balance()` functions, of which `listUnspent` and `balance` must return
+    /// synchronously. The outputs passed to `insert` hold their spending and script private keys as plaintext hex
+    /// values, so the store must keep them encrypted at rest and must not log them. If `compress` is set, `insert` is
+    /// called with the output compressed into a `Uint8Array` entry and the output hash, and `listUnspent` may return
+    /// such entries.
+    pub fn set_output_store(&self, store: JsValue, compress: Option<bool>) -> Result<(), JsValue> {
+        let store = JsOutputStore::new(&store, compress.unwrap_or(false)).map_err(|e| JsValue::from_str(&e))?;
+        *self.store.borrow_mut() = Rc::new(store);
+        Ok(())
+    }
+
+    /// Marks a recovered output (hash as hex value) as spent, e.g. as reported by `match_inputs_to_outputs`, returning
+    /// whether it was stored and not spent yet. The spend is recorded in the custody log.
+    pub fn mark_spent(&self, output_hash: &str, spent_height: Option<u64>) -> Result<bool, JsValue> {
+        let output_hash = output_hash.trim();
+        let marked = self
+            .store()
+            .mark_spent(output_hash, spent_height)
+            .map_err(|e| JsValue::from_str(&e))?;
+        if let (true, Ok(hash)) = (marked, FixedHash::from_hex(output_hash)) {
+            let mut state = self.state.borrow_mut();
+            if let Some(value) = state.custody.unspent_value(&hash) {
+                let range = spent_height.map(|height| (height, height));
+                state.custody.record(CustodyEventKind::OutputSpent, &hash, value, range);
+            }
+        }
+        Ok(marked)
+    }
+
+    /// Locks recovered outputs (hashes as hex values) for a pending spend, e.g. the inputs of a transaction built with
+    /// `build_one_sided_transaction` that is not mined yet, so that `select_coins` does not select them again. Returns
+    /// the number of outputs that were locked; outputs that are not unspent or are already locked are ignored. Every
+    /// lock is recorded in the custody log.
+    pub fn lock_outputs(&self, output_hashes: Vec<String>) -> Result<u32, JsValue> {
+        self.record_locks(CustodyEventKind::OutputLocked, &output_hashes)
+    }
+
+    /// Releases outputs locked with `lock_outputs`, e.g. when the pending spend was cancelled, returning the number of
+    /// outputs that were unlocked. Every release is recorded in the custody log.
+    pub fn unlock_outputs(&self, output_hashes: Vec<String>) -> Result<u32, JsValue> {
+        self.record_locks(CustodyEventKind::OutputUnlocked, &output_hashes)
+    }
+
+    /// The recovered outputs that are not spent, as an array of `StoredOutput`s
+    pub fn unspent_outputs(&self) -> Result<JsValue, JsValue> {
+        let outputs = self.store().list_unspent().map_err(|e| JsValue::from_str(&e))?;
+        Ok(serde_wasm_bindgen::to_value(&outputs)?)
+    }
+
+    /// The total value of the recovered outputs that are not spent
+    pub fn balance(&self) -> Result<u64, JsValue> {
+        self.store().balance().map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// The balance of the recovered outputs that are not spent at `current_height`, split into the available,
+    /// time-locked and pending incoming values as the console wallet reports them, so that immature coinbases and
+    /// script-locked outputs are not shown as spendable
+    pub fn get_balance(&self, current_height: u64) -> BalanceResult {
+        match self.store().list_unspent() {
+            Ok(unspent) => calculate_balance(&unspent, current_height),
+            Err(e) => balance_error(&e),
+        }
+    }
+
+    /// Selects the recovered outputs to spend to pay `amount` with a one-sided transaction at `fee_per_gram`, among
+    /// those that are not spent and are confirmed and spendable at `current_height`. Nothing is marked as spent; the
+    /// selected outputs are to be passed as the inputs of `build_one_sided_transaction`.
+    pub fn select_coins(
+        &self,
+        amount: u64,
+        fee_per_gram: u64,
+        current_height: u64,
+        strategy: CoinSelectionStrategy,
+    ) -> CoinSelectionResult {
+        let unspent = match self.store().list_unspent() {
+            Ok(val) => val,
+            Err(e) => return coin_selection_error(&e),
+        };
+        let state = self.state.borrow();
+        let unlocked = unspent
+            .into_iter()
+            .filter(|output| match FixedHash::from_hex(&output.output_hash) {
+                Ok(hash) => !state.custody.is_locked(&hash),
+                Err(_) => true,
+            })
+            .collect::<Vec<_>>();
+        select_stored_outputs(&unlocked, amount, fee_per_gram, current_height, strategy)
+    }
+
+    /// Queues a chunk of outputs for scanning and, unless the session is paused, scans all pending outputs. Returns
+    /// the outputs found to belong to this wallet, as well as an error result for every output that could not be
+    /// scanned.
+    /// * `mined_height` - the height of the block the outputs were mined in, if known, which is stored with the
+    ///   recovered outputs so that `get_balance` reports outputs with too few confirmations as pending
+    pub fn push_outputs(&self, chunk: Vec<String>, mined_height: Option<u64>) -> Result<JsValue, JsValue> {
+        self.state
+            .borrow_mut()
+            .pending
+            .extend(chunk.into_iter().map(|output| PendingOutput {
+                range_index: None,
+                mined_height,
+                output,
+            }));
+        self.scan_pending()
+    }
+
+    /// Queues a chunk of outputs served by the indexer for the block range `start_height..=end_height` and scans them
+    /// as per `push_outputs`. The outputs are also accounted for in the scan receipt; a range may be pushed in several
+    /// chunks, in the order the indexer served them.
+    /// * `mined_height` - the height of the block the outputs were mined in, if known; otherwise the outputs are stored
+    ///   as mined at `end_height`, so that they are not counted as confirmed before every block of the range is
+    pub fn push_outputs_for_range(
+        &self,
+        start_height: u64,
+        end_height: u64,
+        chunk: Vec<String>,
+        mined_height: Option<u64>,
+    ) -> Result<JsValue, JsValue> {
+        {
+            let mut state = self.state.borrow_mut();
+            let range_index = state.receipt.range_index(start_height, end_height);
+            let mined_height = mined_height.unwrap_or(end_height);
+            state.pending.extend(chunk.into_iter().map(|output| PendingOutput {
+                range_index: Some(range_index),
+                mined_height: Some(mined_height),
+                output,
+            }));
+        }
+        self.scan_pending()
+    }
+
+    /// A receipt of all outputs scanned so far per block range, as JSON, for outputs pushed with
+    /// `push_outputs_for_range`. The receipt can be exported and checked against the indexer totals with
+    /// `verify_scan_receipt`. Outputs still pending are not yet part of the receipt.
+    pub fn receipt(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.state.borrow().receipt.build()).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// The custody log of the session, as JSON: a hash-chained `CustodyLogExport` of every output recovered, spent,
+    /// locked or unlocked and the balance after it, in the order the mutations were made, so that custodial
+    /// integrators can reconstruct which scanned data led to which balance. An output scanned again is only logged
+    /// once. The log can be checked with `verify_custody_log` and the session's `custody_audit_key`.
+    pub fn custody_log(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.state.borrow().custody.export()).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// The key the entries of the custody log are keyed with (hex value), derived from the wallet secret key, which
+    /// auditors need to verify the log with `verify_custody_log` but which does not give away the wallet key
+    pub fn custody_audit_key(&self) -> String {
+        self.state.borrow().custody.audit_key()
+    }
+
+    /// Sets the deposit rules, a JSON array of `DepositRule`s, whose tags are attached to the outputs recovered from
+    /// now on, so that deposits can be routed to internal accounts during the scan itself
+    pub fn set_deposit_rules(&self, rules: &str) -> Result<(), JsValue> {
+        *self.rules.borrow_mut() = DepositRules::from_json(rules).map_err(JsValue::from)?;
+        Ok(())
+    }
+
+    /// Pauses scanning after the current batch. Outputs pushed while paused are queued until the session is resumed.
+    pub fn pause(&self) {
+        self.paused.set(true);
+    }
+
+    /// Resumes scanning, scanning all pending outputs. Returns the results as per `push_outputs`.
+    pub fn resume(&self) -> Result<JsValue, JsValue> {
+        self.paused.set(false);
+        self.scan_pending()
+    }
+
+    /// Whether the session is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    /// Returns the current `ScanProgress`
+    pub fn progress_report(&self) -> ScanProgress {
+        self.progress()
     }
 }
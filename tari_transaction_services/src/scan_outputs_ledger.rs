@@ -1,8 +1,8 @@
 // Copyright 2022 The Tari Project
 // SPDX-License-Identifier: BSD-3-Clause
 
-use borsh::BorshDeserialize;
 use minotari_wallet::output_source::OutputSource;
+use serde::{Deserialize, Serialize};
 use tari_common_types::types::{PrivateKey, PublicKey};
 use tari_comms::types::CommsDHKE;
 use tari_core::{
@@ -11,93 +11,411 @@ use tari_core::{
         shared_secret_to_output_encryption_key,
         stealth_address_script_spending_key,
     },
-    transactions::{
-        transaction_components::{EncryptedData, TransactionOutput},
-        CryptoFactories,
-    },
+    transactions::transaction_components::{EncryptedData, TransactionOutput},
 };
-use tari_crypto::tari_utilities::hex::Hex;
+use tari_crypto::tari_utilities::hex::{from_hex, Hex};
 use tari_script::Opcode;
+use tsify::Tsify;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
-use crate::{no_match, scan_error, RecoveredOutputResult};
+use crate::{
+    error::{ScanErrorCode, WasmError},
+    factories::crypto_factories,
+    lock_heights,
+    network::{on_network, parse_scan_network},
+    no_match,
+    output_encoding::{decode_output, OutputEncoding},
+    output_features::RecoveredOutputFeatures,
+    scan_error,
+    script_tools::{match_script, ScriptPattern},
+    secrets::ProtectedScanResult,
+    RecoveredOutputResult,
+};
 
 /// Scans a transaction output for a one-sided payment belonging to this ledger wallet. The output is scanned for a
 /// one-sided payment using the provided wallet secret view key and wallet public spend key. The output is decrypted
-/// and verified using the shared secret derived from the wallet secret key and the sender's offset public key.
+/// and verified using the shared secret derived from the wallet secret key and the sender's offset public key. The
+/// output is passed in any of the encodings accepted by `scan_output_for_one_sided_payment`; Borsh bytes should be
+/// passed as a `Uint8Array`, or hex encoded, as a string cannot hold arbitrary bytes.
+/// The output is hashed for `network`, e.g. `mainnet`, or for the library's network if not provided.
 #[wasm_bindgen]
-pub fn scan_output_for_one_sided_payment_ledger(wallet_view_sk: &str, wallet_spend_pk: &str, output: &str) -> JsValue {
+pub fn scan_output_for_one_sided_payment_ledger(
+    wallet_view_sk: &str,
+    wallet_spend_pk: &str,
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    network: Option<String>,
+) -> RecoveredOutputResult {
     let wallet_view_sk = match PrivateKey::from_hex(wallet_view_sk) {
         Ok(val) => val,
-        Err(e) => return scan_error(&format!("wallet_sk: {e}")),
+        Err(e) => {
+            return scan_error(WasmError::new(
+                ScanErrorCode::InvalidKey,
+                format!("wallet_view_sk: {e}"),
+            ))
+        },
     };
     let wallet_spend_pk = match PublicKey::from_hex(wallet_spend_pk) {
         Ok(val) => val,
-        Err(e) => return scan_error(&format!("wallet_sk: {e}")),
+        Err(e) => {
+            return scan_error(WasmError::new(
+                ScanErrorCode::InvalidKey,
+                format!("wallet_spend_pk: {e}"),
+            ))
+        },
+    };
+    let network = match parse_scan_network(network.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return scan_error(e),
     };
 
-    let output: TransactionOutput = match BorshDeserialize::deserialize(&mut output.as_bytes()) {
+    let output = match decode_output(&output, encoding) {
         Ok(val) => val,
-        Err(e) => return scan_error(&e.to_string()),
+        Err(e) => return scan_error(e),
     };
+    on_network(network, || {
+        scan_ledger_output(&wallet_view_sk, &wallet_spend_pk, &output)
+    })
+}
 
-    let (output, output_source, shared_secret) = match output.script.as_slice() {
+/// Scans a transaction output as `scan_output_for_one_sided_payment_ledger` does, but holds the spending key of a
+/// recovered output in wasm memory as a `SecretKeyHandle`
+#[wasm_bindgen]
+pub fn scan_output_for_one_sided_payment_ledger_protected(
+    wallet_view_sk: &str,
+    wallet_spend_pk: &str,
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    network: Option<String>,
+) -> ProtectedScanResult {
+    ProtectedScanResult::from(scan_output_for_one_sided_payment_ledger(
+        wallet_view_sk,
+        wallet_spend_pk,
+        output,
+        encoding,
+        network,
+    ))
+}
+
+/// Scans an output that has already been deserialized for a one-sided payment to this ledger wallet
+pub(crate) fn scan_ledger_output(
+    wallet_view_sk: &PrivateKey,
+    wallet_spend_pk: &PublicKey,
+    output: &TransactionOutput,
+) -> RecoveredOutputResult {
+    let (output_source, shared_secret) = match output.script.as_slice() {
         // ----------------------------------------------------------------------------
         // one-sided stealth address
         // NOTE: Extracting the nonce R and a spending (public aka scan_key) key from the script
         // NOTE: [RFC 203 on Stealth Addresses](https://rfc.tari.com/RFC-0203_StealthAddresses.html)
         [Opcode::PushPubKey(nonce), Opcode::Drop, Opcode::PushPubKey(scanned_pk)] => {
             // matching spending (public) keys
-            let stealth_address_hasher = diffie_hellman_stealth_domain_hasher(&wallet_view_sk, nonce.as_ref());
-            let script_spending_key = stealth_address_script_spending_key(&stealth_address_hasher, &wallet_spend_pk);
+            let stealth_address_hasher = diffie_hellman_stealth_domain_hasher(wallet_view_sk, nonce.as_ref());
+            let script_spending_key = stealth_address_script_spending_key(&stealth_address_hasher, wallet_spend_pk);
             if &script_spending_key != scanned_pk.as_ref() {
                 return no_match();
             }
 
-            let shared_secret = CommsDHKE::new(&wallet_view_sk, &output.sender_offset_public_key);
-            (output.clone(), OutputSource::StealthOneSided, shared_secret)
+            let shared_secret = CommsDHKE::new(wallet_view_sk, &output.sender_offset_public_key);
+            (OutputSource::StealthOneSided, shared_secret)
         },
 
         _ => return no_match(),
     };
 
-    verify_onesided_output_ledger(&output, output_source, &shared_secret)
+    match verify_onesided_output_ledger(output, output_source.to_string(), &shared_secret) {
+        Ok(result) => result,
+        Err(e) => scan_error(e),
+    }
 }
 
-fn verify_onesided_output_ledger(
+/// Decrypts and verifies an output whose script belongs to this ledger wallet, returning the recovered output
+pub(crate) fn verify_onesided_output_ledger(
     output: &TransactionOutput,
-    output_source: OutputSource,
+    output_source: String,
     shared_secret: &CommsDHKE,
-) -> JsValue {
-    let encryption_key = match shared_secret_to_output_encryption_key(shared_secret) {
-        Ok(key) => key,
-        Err(e) => return scan_error(&format!("Could not derive encryption key: {e}")),
+) -> Result<RecoveredOutputResult, WasmError> {
+    let encryption_key = shared_secret_to_output_encryption_key(shared_secret).map_err(|e| {
+        WasmError::new(
+            ScanErrorCode::KeyDerivationError,
+            format!("Could not derive encryption key: {e}"),
+        )
+    })?;
+    recover_output_with_key(output, output_source, &encryption_key)
+}
+
+/// Decrypts the value and spending key of an output with `encryption_key` and verifies that they open its commitment,
+/// returning the recovered output
+pub(crate) fn recover_output_with_key(
+    output: &TransactionOutput,
+    output_source: String,
+    encryption_key: &PrivateKey,
+) -> Result<RecoveredOutputResult, WasmError> {
+    let (committed_value, spending_key) =
+        EncryptedData::decrypt_data(encryption_key, &output.commitment, &output.encrypted_data).map_err(|e| {
+            WasmError::new(
+                ScanErrorCode::DecryptError,
+                format!("Could not decrypt output data: {e}"),
+            )
+        })?;
+    let crypto_factories = crypto_factories();
+    let verified = output
+        .verify_mask(&crypto_factories.range_proof, &spending_key, committed_value.into())
+        .map_err(|e| WasmError::new(ScanErrorCode::MaskVerifyError, format!("Could not verify output: {e}")))?;
+    if !verified {
+        return Err(WasmError::new(
+            ScanErrorCode::MaskVerifyError,
+            "The decrypted value and mask do not open the output's commitment",
+        ));
+    }
+    let (maturity, lock_height, spendable_at_height) = lock_heights(output);
+    Ok(RecoveredOutputResult {
+        hash: Some(output.hash().to_hex()),
+        output_source: Some(output_source),
+        output_type: Some(output.features.output_type.to_string()),
+        features: Some(RecoveredOutputFeatures::from(&output.features)),
+        value: Some(committed_value.as_u64()),
+        minimum_value_promise: Some(output.minimum_value_promise.as_u64()),
+        promise_exceeds_value: Some(output.minimum_value_promise > committed_value),
+        spending_key: Some(spending_key.to_hex()),
+        script_key: None,
+        maturity: Some(maturity),
+        lock_height,
+        spendable_at_height: Some(spendable_at_height),
+        tags: Vec::new(),
+        error_code: None,
+        error: None,
+    })
+}
+
+/// What a Ledger device needs to scan an output, as extracted by `prepare_ledger_scan`
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct LedgerScanRequest {
+    /// Whether the output's script is one the scanner recognises; if not, the output is not a one-sided payment and
+    /// need not be sent to the device
+    pub matched: bool,
+    /// The hash of the output (hex value)
+    pub output_hash: Option<String>,
+    /// The script pattern, e.g. `StealthOneSided` or `TimeLockedOneSided`
+    pub pattern: Option<String>,
+    /// The sender offset public key, with which the device derives the shared secret (hex value)
+    pub sender_offset_public_key: Option<String>,
+    /// The nonce `R` of a stealth address script, with which the device checks that the script key is its own (hex
+    /// value)
+    pub stealth_nonce: Option<String>,
+    /// The public keys in the script, one of which must be the device's, directly or as a stealth address (hex values)
+    pub script_public_keys: Vec<String>,
+    /// The kind of error, in case of an error
+    pub error_code: Option<ScanErrorCode>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+impl From<WasmError> for LedgerScanRequest {
+    fn from(error: WasmError) -> Self {
+        Self {
+            error_code: Some(error.code),
+            error: Some(error.message),
+            ..Default::default()
+        }
+    }
+}
+
+/// The first phase of a Ledger scan, in which the view key stays on the device: extracts from an output what the
+/// device needs to check the script and derive the shared secret, i.e. the script pattern and keys, the stealth nonce
+/// and the sender offset public key. The device then returns the shared secret of its key and the sender offset public
+/// key, which `complete_ledger_scan` uses to finish the scan. The output is passed in any of the encodings accepted by
+/// `scan_output_for_one_sided_payment`, and hashed for `network`, e.g. `mainnet`, or for the library's network if not
+/// provided.
+#[wasm_bindgen]
+pub fn prepare_ledger_scan(
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    network: Option<String>,
+) -> LedgerScanRequest {
+    let network = match parse_scan_network(network.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return LedgerScanRequest::from(e),
+    };
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => return LedgerScanRequest::from(e),
+    };
+    let matched = match match_script(&output.script) {
+        Some(matched) => matched,
+        None => return LedgerScanRequest::default(),
+    };
+    let (stealth_nonce, script_public_keys) = match matched.pattern {
+        ScriptPattern::OneSided { script_public_key } => (None, vec![script_public_key.to_hex()]),
+        ScriptPattern::StealthOneSided {
+            nonce,
+            script_public_key,
+        } => (Some(nonce.to_hex()), vec![script_public_key.to_hex()]),
+        ScriptPattern::MultiParty { public_keys } => (None, public_keys.iter().map(|key| key.to_hex()).collect()),
     };
-    let crypto_factories = CryptoFactories::default();
-    if let Ok((committed_value, spending_key)) =
-        EncryptedData::decrypt_data(&encryption_key, &output.commitment, &output.encrypted_data)
+    LedgerScanRequest {
+        matched: true,
+        output_hash: Some(on_network(network, || output.hash()).to_hex()),
+        pattern: Some(matched.name()),
+        sender_offset_public_key: Some(output.sender_offset_public_key.to_hex()),
+        stealth_nonce,
+        script_public_keys,
+        error_code: None,
+        error: None,
+    }
+}
+
+/// The second phase of a Ledger scan: decrypts the output's value and spending key with the shared secret derived on
+/// the device, and verifies that they open the output's commitment. The host never handles the view key or script
+/// key, so a recovered output has no `scriptKey`; an output that is not the device's fails to decrypt.
+/// * `device_derived_shared_secret` - the Diffie-Hellman shared secret of the device's key and the output's sender
+///   offset public key, as returned by the Ledger app (hex value)
+/// * `network` - the network the output is hashed for, e.g. `mainnet`, or the library's network if not provided
+#[wasm_bindgen]
+pub fn complete_ledger_scan(
+    output: JsValue,
+    device_derived_shared_secret: &str,
+    encoding: Option<OutputEncoding>,
+    network: Option<String>,
+) -> RecoveredOutputResult {
+    let shared_secret = match from_hex(device_derived_shared_secret.trim())
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| CommsDHKE::from_canonical_bytes(&bytes).map_err(|e| e.to_string()))
     {
-        match output.verify_mask(&crypto_factories.range_proof, &spending_key, committed_value.into()) {
-            Ok(verified) => {
-                if verified {
-                    let result = RecoveredOutputResult {
-                        hash: Some(output.hash().to_hex()),
-                        output_source: Some(output_source.to_string()),
-                        output_type: Some(output.features.output_type.to_string()),
-                        value: Some(committed_value.as_u64()),
-                        spending_key: Some(spending_key.to_hex()),
-                        script_key: None,
-                        maturity: Some(output.features.maturity),
-                        error: None,
-                    };
-                    serde_wasm_bindgen::to_value(&result).unwrap()
-                } else {
-                    no_match()
-                }
-            },
-            Err(e) => scan_error(&format!("Could not verify output: {e}")),
+        Ok(val) => val,
+        Err(e) => {
+            return scan_error(WasmError::new(
+                ScanErrorCode::InvalidKey,
+                format!("device_derived_shared_secret: {e}"),
+            ))
+        },
+    };
+    let network = match parse_scan_network(network.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return scan_error(e),
+    };
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => return scan_error(e),
+    };
+    let output_source = match match_script(&output.script) {
+        Some(matched) => matched.name(),
+        None => return no_match(),
+    };
+    match on_network(network, || {
+        verify_onesided_output_ledger(&output, output_source, &shared_secret)
+    }) {
+        Ok(result) => result,
+        Err(e) => scan_error(e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use tari_common_types::types::ComAndPubSignature;
+    use tari_core::{
+        covenants::Covenant,
+        transactions::{tari_amount::MicroMinotari, transaction_components::OutputFeatures},
+    };
+    use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::PublicKey as PublicKeyTrait};
+    use tari_script::script;
+
+    use super::*;
+    use crate::output_encoding::decode_output_str;
+
+    const VALUE: u64 = 123_456_789;
+
+    fn wallet_keys() -> (PrivateKey, PublicKey) {
+        (PrivateKey::from(3), PublicKey::from_secret_key(&PrivateKey::from(5)))
+    }
+
+    fn spending_key() -> PrivateKey {
+        PrivateKey::from(13)
+    }
+
+    // A stealth one-sided payment of `VALUE` to the wallet, as a sender would create it
+    fn stealth_output(wallet_view_sk: &PrivateKey, wallet_spend_pk: &PublicKey) -> TransactionOutput {
+        let nonce_sk = PrivateKey::from(7);
+        let sender_offset_sk = PrivateKey::from(11);
+        let wallet_view_pk = PublicKey::from_secret_key(wallet_view_sk);
+        let stealth_address_hasher = diffie_hellman_stealth_domain_hasher(&nonce_sk, &wallet_view_pk);
+        let script_spending_key = stealth_address_script_spending_key(&stealth_address_hasher, wallet_spend_pk);
+        let script = script!(
+            PushPubKey(Box::new(PublicKey::from_secret_key(&nonce_sk)))
+            Drop
+            PushPubKey(Box::new(script_spending_key))
+        );
+        let shared_secret = CommsDHKE::new(&sender_offset_sk, &wallet_view_pk);
+        let encryption_key = shared_secret_to_output_encryption_key(&shared_secret).unwrap();
+        let commitment = crypto_factories().commitment.commit_value(&spending_key(), VALUE);
+        let encrypted_data = EncryptedData::encrypt_data(
+            &encryption_key,
+            &commitment,
+            MicroMinotari::from(VALUE),
+            &spending_key(),
+        )
+        .unwrap();
+        TransactionOutput::new_current_version(
+            OutputFeatures::default(),
+            commitment,
+            None,
+            script,
+            PublicKey::from_secret_key(&sender_offset_sk),
+            ComAndPubSignature::default(),
+            Covenant::default(),
+            encrypted_data,
+            MicroMinotari::zero(),
+        )
+    }
+
+    #[test]
+    fn serialized_output_round_trips() {
+        let (wallet_view_sk, wallet_spend_pk) = wallet_keys();
+        let output = stealth_output(&wallet_view_sk, &wallet_spend_pk);
+        let bytes = borsh::to_vec(&output).unwrap();
+        let encodings = [
+            (bytes.to_hex(), OutputEncoding::Hex),
+            (bytes.to_hex(), OutputEncoding::Auto),
+            (STANDARD.encode(&bytes), OutputEncoding::Base64),
+            (serde_json::to_string(&output).unwrap(), OutputEncoding::Json),
+        ];
+        for (encoded, encoding) in &encodings {
+            let decoded = decode_output_str(encoded, *encoding).unwrap();
+            assert_eq!(decoded, output);
+            let result = scan_ledger_output(&wallet_view_sk, &wallet_spend_pk, &decoded);
+            assert_eq!(result.error, None);
+            assert_eq!(result.hash, Some(output.hash().to_hex()));
+            assert_eq!(result.output_source, Some(OutputSource::StealthOneSided.to_string()));
+            assert_eq!(result.value, Some(VALUE));
+            assert_eq!(result.spending_key, Some(spending_key().to_hex()));
         }
-    } else {
-        no_match()
+    }
+
+    #[test]
+    fn output_of_another_wallet_does_not_match() {
+        let (wallet_view_sk, wallet_spend_pk) = wallet_keys();
+        let output = stealth_output(&wallet_view_sk, &wallet_spend_pk);
+        let result = scan_ledger_output(&PrivateKey::from(17), &wallet_spend_pk, &output);
+        assert_eq!(result.hash, None);
+        assert_eq!(result.error, None);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn borsh_bytes_are_accepted_as_uint8array() {
+        let (wallet_view_sk, wallet_spend_pk) = wallet_keys();
+        let bytes = borsh::to_vec(&stealth_output(&wallet_view_sk, &wallet_spend_pk)).unwrap();
+        let output = js_sys::Uint8Array::from(bytes.as_slice());
+        let result = scan_output_for_one_sided_payment_ledger(
+            &wallet_view_sk.to_hex(),
+            &wallet_spend_pk.to_hex(),
+            output.into(),
+            None,
+            None,
+        );
+        assert_eq!(result.value, Some(VALUE));
     }
 }
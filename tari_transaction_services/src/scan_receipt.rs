@@ -0,0 +1,267 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use blake2::Blake2b;
+use digest::consts::U32;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::FixedHash;
+use tari_crypto::{
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    tari_utilities::hex::{from_hex, Hex},
+};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+hash_domain!(ScanReceiptHashDomain, "com.tari.transaction_services.scan_receipt", 1);
+
+type ScanReceiptHasher = DomainSeparatedHasher<Blake2b<U32>, ScanReceiptHashDomain>;
+
+/// The outputs processed for a single block range. `outputs_hash` chains the hashes of all outputs processed in the
+/// range, in the order they were served, as `H("outputs", output_hash_1, output_hash_2, ...)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeReceipt {
+    /// The first block height of the range
+    pub start_height: u64,
+    /// The last block height of the range
+    pub end_height: u64,
+    /// The number of outputs processed in the range
+    pub output_count: u64,
+    /// The number of outputs in the range that could not be deserialized, and are not part of `outputs_hash`
+    pub undecodable_count: u64,
+    /// The hash of all output hashes processed in the range (hex value)
+    pub outputs_hash: String,
+}
+
+/// A receipt of all outputs processed by a scan, per block range, that can be checked against the totals reported by
+/// the indexer to prove that the scan covered everything the indexer served
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanReceipt {
+    /// The outputs processed per block range, in the order the ranges were first pushed
+    pub ranges: Vec<RangeReceipt>,
+    /// The total number of outputs processed across all ranges
+    pub total_outputs: u64,
+    /// A hash committing to all range receipts (hex value)
+    pub receipt_hash: String,
+}
+
+/// The totals the indexer reports for a block range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexerRangeTotal {
+    /// The first block height of the range
+    pub start_height: u64,
+    /// The last block height of the range
+    pub end_height: u64,
+    /// The number of outputs the indexer served for the range
+    pub output_count: u64,
+    /// The hash of all output hashes served for the range, if the indexer provides it (hex value)
+    pub outputs_hash: Option<String>,
+}
+
+/// The outcome of checking a scan receipt against the indexer totals
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct ReceiptVerificationResult {
+    /// Whether every range reported by the indexer was scanned completely
+    pub complete: Option<bool>,
+    /// A description of every range that was not scanned completely
+    pub mismatches: Vec<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+#[derive(Clone)]
+struct RangeAccumulator {
+    start_height: u64,
+    end_height: u64,
+    output_count: u64,
+    undecodable_count: u64,
+    hasher: ScanReceiptHasher,
+}
+
+/// Accumulates the outputs processed by a scan into a [ScanReceipt]
+#[derive(Clone, Default)]
+pub(crate) struct ScanReceiptBuilder {
+    ranges: Vec<RangeAccumulator>,
+}
+
+impl ScanReceiptBuilder {
+    /// Returns the index of the accumulator for the block range, adding it if this range was not seen before
+    pub fn range_index(&mut self, start_height: u64, end_height: u64) -> usize {
+        if let Some(index) = self
+            .ranges
+            .iter()
+            .position(|range| range.start_height == start_height && range.end_height == end_height)
+        {
+            return index;
+        }
+        self.ranges.push(RangeAccumulator {
+            start_height,
+            end_height,
+            output_count: 0,
+            undecodable_count: 0,
+            hasher: ScanReceiptHasher::new_with_label("outputs"),
+        });
+        self.ranges.len() - 1
+    }
+
+    /// Returns the block range of the accumulator at `range_index`
+    pub fn range(&self, range_index: usize) -> Option<(u64, u64)> {
+        self.ranges
+            .get(range_index)
+            .map(|range| (range.start_height, range.end_height))
+    }
+
+    /// Records a processed output, or an undecodable one if its hash is not known
+    pub fn record(&mut self, range_index: usize, output_hash: Option<&FixedHash>) {
+        if let Some(range) = self.ranges.get_mut(range_index) {
+            range.output_count += 1;
+            match output_hash {
+                Some(hash) => range.hasher.update(hash.as_slice()),
+                None => range.undecodable_count += 1,
+            }
+        }
+    }
+
+    pub fn build(&self) -> ScanReceipt {
+        let mut receipt_hasher = ScanReceiptHasher::new_with_label("receipt");
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|range| {
+                let outputs_hash = range.hasher.clone().finalize();
+                update_receipt_hash(
+                    &mut receipt_hasher,
+                    range.start_height,
+                    range.end_height,
+                    range.output_count,
+                    outputs_hash.as_ref(),
+                );
+                RangeReceipt {
+                    start_height: range.start_height,
+                    end_height: range.end_height,
+                    output_count: range.output_count,
+                    undecodable_count: range.undecodable_count,
+                    outputs_hash: outputs_hash.as_ref().to_hex(),
+                }
+            })
+            .collect::<Vec<_>>();
+        ScanReceipt {
+            total_outputs: ranges.iter().map(|range| range.output_count).sum(),
+            ranges,
+            receipt_hash: receipt_hasher.finalize().as_ref().to_hex(),
+        }
+    }
+}
+
+/// Adds a range receipt to the receipt hash
+fn update_receipt_hash(
+    receipt_hasher: &mut ScanReceiptHasher,
+    start_height: u64,
+    end_height: u64,
+    output_count: u64,
+    outputs_hash: &[u8],
+) {
+    receipt_hasher.update(&start_height.to_le_bytes());
+    receipt_hasher.update(&end_height.to_le_bytes());
+    receipt_hasher.update(&output_count.to_le_bytes());
+    receipt_hasher.update(outputs_hash);
+}
+
+/// Recomputes the receipt hash of a receipt from its ranges, to detect a receipt that was altered after it was exported
+fn recompute_receipt_hash(receipt: &ScanReceipt) -> Result<String, String> {
+    let mut receipt_hasher = ScanReceiptHasher::new_with_label("receipt");
+    for range in &receipt.ranges {
+        let outputs_hash = from_hex(&range.outputs_hash).map_err(|e| format!("outputs_hash: {e}"))?;
+        update_receipt_hash(
+            &mut receipt_hasher,
+            range.start_height,
+            range.end_height,
+            range.output_count,
+            &outputs_hash,
+        );
+    }
+    Ok(receipt_hasher.finalize().as_ref().to_hex())
+}
+
+fn receipt_verification_error(error: &str) -> ReceiptVerificationResult {
+    ReceiptVerificationResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn range_mismatch(receipt: &ScanReceipt, total: &IndexerRangeTotal) -> Option<String> {
+    let range = format!("blocks {}..={}", total.start_height, total.end_height);
+    let scanned = match receipt
+        .ranges
+        .iter()
+        .find(|range| range.start_height == total.start_height && range.end_height == total.end_height)
+    {
+        Some(scanned) => scanned,
+        None => return Some(format!("{range}: not scanned")),
+    };
+    if scanned.output_count != total.output_count {
+        return Some(format!(
+            "{range}: scanned {} of {} outputs",
+            scanned.output_count, total.output_count
+        ));
+    }
+    if scanned.undecodable_count > 0 {
+        return Some(format!(
+            "{range}: {} outputs could not be decoded",
+            scanned.undecodable_count
+        ));
+    }
+    match &total.outputs_hash {
+        Some(hash) if !hash.eq_ignore_ascii_case(&scanned.outputs_hash) => {
+            Some(format!("{range}: outputs hash does not match"))
+        },
+        _ => None,
+    }
+}
+
+/// Checks a scan receipt, as returned by `ScanSession.receipt`, against the totals reported by the indexer for each
+/// block range, e.g. `[{ "startHeight": 0, "endHeight": 999, "outputCount": 1234, "outputsHash": "<hex>" }]`. The
+/// scan is complete if the receipt hash and total match its ranges, and every range was scanned with the same output
+/// count and, where provided, the same outputs hash.
+#[wasm_bindgen]
+pub fn verify_scan_receipt(receipt: &str, indexer_totals: &str) -> ReceiptVerificationResult {
+    let receipt: ScanReceipt = match serde_json::from_str(receipt) {
+        Ok(val) => val,
+        Err(e) => return receipt_verification_error(&format!("receipt: {e}")),
+    };
+    let indexer_totals: Vec<IndexerRangeTotal> = match serde_json::from_str(indexer_totals) {
+        Ok(val) => val,
+        Err(e) => return receipt_verification_error(&format!("indexer_totals: {e}")),
+    };
+    let receipt_hash = match recompute_receipt_hash(&receipt) {
+        Ok(val) => val,
+        Err(e) => return receipt_verification_error(&format!("receipt: {e}")),
+    };
+    let mut mismatches = Vec::new();
+    if !receipt_hash.eq_ignore_ascii_case(&receipt.receipt_hash) {
+        mismatches.push("receipt: receipt hash does not match its ranges".to_string());
+    }
+    let total_outputs = receipt
+        .ranges
+        .iter()
+        .try_fold(0u64, |total, range| total.checked_add(range.output_count));
+    if total_outputs != Some(receipt.total_outputs) {
+        mismatches.push("receipt: total outputs do not match its ranges".to_string());
+    }
+    mismatches.extend(
+        indexer_totals
+            .iter()
+            .filter_map(|total| range_mismatch(&receipt, total)),
+    );
+    ReceiptVerificationResult {
+        complete: Some(mismatches.is_empty()),
+        mismatches,
+        error: None,
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use minotari_wallet::output_source::OutputSource;
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_core::transactions::key_manager::TransactionKeyManagerBranch;
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_key_manager::key_manager::KeyManager;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    cipher_seed_from_hex,
+    error::{ScanErrorCode, WasmError},
+    network::{on_network, parse_scan_network},
+    no_match,
+    output_encoding::{decode_output, OutputEncoding},
+    scan_error,
+    scan_outputs_ledger::recover_output_with_key,
+    secrets::ProtectedScanResult,
+    RecoveredOutputResult,
+};
+
+/// The length of a private key in hex
+const PRIVATE_KEY_HEX_LEN: usize = 64;
+
+/// Returns the wallet's recovery key, the key at index 0 of the `data encryption` branch, from a cipher seed, or a
+/// custom recovery key as is
+fn recovery_key(master_entropy_or_recovery_key: &str) -> Result<PrivateKey, String> {
+    let key = master_entropy_or_recovery_key.trim();
+    if key.len() == PRIVATE_KEY_HEX_LEN {
+        return PrivateKey::from_hex(key).map_err(|e| format!("recovery key: {e}"));
+    }
+    let seed = cipher_seed_from_hex(key)?;
+    let branch = TransactionKeyManagerBranch::DataEncryption.get_branch_key();
+    KeyManager::<PublicKey>::from(seed, branch, 0)
+        .derive_key(0)
+        .map(|derived_key| derived_key.key)
+        .map_err(|e| format!("Could not derive the recovery key: {e}"))
+}
+
+/// Scans a transaction output for an output of this wallet's own, e.g. change or a received interactive payment, the
+/// way a wallet restored from seed does: the output's encrypted value and spending key are decrypted with the wallet's
+/// recovery key and checked against the commitment. One-sided payments are encrypted for the receiver's view key
+/// instead, and are found with `scan_output_for_one_sided_payment`. A recovered output has the `outputSource`
+/// `Standard`, or `Coinbase` for coinbases; outputs that do not decrypt do not match. The output is passed in any of
+/// the encodings accepted by `scan_output_for_one_sided_payment`.
+/// * `master_entropy_or_recovery_key` - the wallet cipher seed, as returned by `create_cipher_seed` or
+///   `mnemonic_to_cipher_seed`, from which the recovery key is derived, or a custom recovery private key (hex value)
+/// * `network` - the network the output is hashed for, e.g. `mainnet`, or the library's network if not provided
+#[wasm_bindgen]
+pub fn scan_output_for_recovery(
+    master_entropy_or_recovery_key: &str,
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    network: Option<String>,
+) -> RecoveredOutputResult {
+    let recovery_key = match recovery_key(master_entropy_or_recovery_key) {
+        Ok(val) => val,
+        Err(e) => {
+            return scan_error(WasmError::new(
+                ScanErrorCode::InvalidKey,
+                format!("master_entropy_or_recovery_key: {e}"),
+            ))
+        },
+    };
+    let network = match parse_scan_network(network.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return scan_error(e),
+    };
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => return scan_error(e),
+    };
+    let output_source = if output.is_coinbase() {
+        OutputSource::Coinbase
+    } else {
+        OutputSource::Standard
+    };
+    match on_network(network, || {
+        recover_output_with_key(&output, output_source.to_string(), &recovery_key)
+    }) {
+        Ok(result) => result,
+        // Nothing marks an output as the wallet's own, so an output that does not decrypt is not a match
+        Err(e) if e.code == ScanErrorCode::DecryptError => no_match(),
+        Err(e) => scan_error(e),
+    }
+}
+
+/// Scans a transaction output as `scan_output_for_recovery` does, but holds the spending and script keys of a
+/// recovered output in wasm memory as `SecretKeyHandle`s
+#[wasm_bindgen]
+pub fn scan_output_for_recovery_protected(
+    master_entropy_or_recovery_key: &str,
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+    network: Option<String>,
+) -> ProtectedScanResult {
+    ProtectedScanResult::from(scan_output_for_recovery(
+        master_entropy_or_recovery_key,
+        output,
+        encoding,
+        network,
+    ))
+}
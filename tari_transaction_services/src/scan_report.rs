@@ -0,0 +1,116 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_crypto::tari_utilities::hex::Hex;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    error::ScanErrorCode,
+    output_encoding::{decode_output_str, OutputEncoding},
+    scan_outputs::{recover_output, ScanKeys},
+};
+
+/// The non-secret facts about an output that matched, or could not be scanned, in a scan report
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanReportEntry {
+    /// The index of the output in the scanned outputs
+    pub index: usize,
+    /// The hash of the output (hex value), if it could be deserialized
+    pub hash: Option<String>,
+    /// Whether the output belongs to this wallet
+    pub matched: bool,
+    /// The output source, for a matched output
+    pub output_source: Option<String>,
+    /// The output type, for a matched output
+    pub output_type: Option<String>,
+    /// The output value, for a matched output
+    pub value: Option<u64>,
+    /// The output lock height, for a matched output
+    pub maturity: Option<u64>,
+    /// The kind of error, if the output could not be scanned
+    pub error_code: Option<ScanErrorCode>,
+    /// An error message, if the output could not be scanned
+    pub error: Option<String>,
+}
+
+/// A report of a recovery dry run, without any keys
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct ScanReport {
+    /// The number of outputs scanned
+    pub scanned: usize,
+    /// The number of outputs found to belong to this wallet
+    pub matched: usize,
+    /// The total value of the outputs found to belong to this wallet
+    pub total_value: u64,
+    /// The outputs found to belong to this wallet, and the outputs that could not be scanned
+    pub entries: Vec<ScanReportEntry>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn scan_report_error(error: &str) -> ScanReport {
+    ScanReport {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Performs a full recovery scan of `outputs` with the same keys as `scan_output_for_one_sided_payment`, but reports
+/// only non-secret facts: whether each output matched, and its value, lock height, source and type. Spending and script
+/// keys never appear in the report, so it is safe to share with support tooling and diagnostics.
+#[wasm_bindgen]
+pub fn scan_report_only(outputs: Vec<String>, known_script_keys: Vec<String>, wallet_sk: String) -> ScanReport {
+    let keys = match ScanKeys::new(known_script_keys, wallet_sk) {
+        Ok(val) => val,
+        Err(e) => return scan_report_error(&e.to_string()),
+    };
+    let mut report = ScanReport {
+        scanned: outputs.len(),
+        ..Default::default()
+    };
+    for (index, output) in outputs.iter().enumerate() {
+        let output = match decode_output_str(output, OutputEncoding::Auto) {
+            Ok(val) => val,
+            Err(e) => {
+                report.entries.push(ScanReportEntry {
+                    index,
+                    error_code: Some(e.code),
+                    error: Some(e.message),
+                    ..Default::default()
+                });
+                continue;
+            },
+        };
+        let entry = match recover_output(&keys, &output) {
+            Ok(Some(recovered)) => {
+                report.matched += 1;
+                report.total_value = report.total_value.saturating_add(recovered.value.as_u64());
+                ScanReportEntry {
+                    index,
+                    hash: Some(output.hash().to_hex()),
+                    matched: true,
+                    output_source: Some(recovered.output_source),
+                    output_type: Some(output.features.output_type.to_string()),
+                    value: Some(recovered.value.as_u64()),
+                    maturity: Some(output.features.maturity),
+                    ..Default::default()
+                }
+            },
+            Ok(None) => continue,
+            Err(e) => ScanReportEntry {
+                index,
+                hash: Some(output.hash().to_hex()),
+                error_code: Some(e.code),
+                error: Some(e.message),
+                ..Default::default()
+            },
+        };
+        report.entries.push(entry);
+    }
+    report
+}
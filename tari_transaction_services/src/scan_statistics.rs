@@ -0,0 +1,118 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+const DEFAULT_WINDOW_MS: f64 = 30_000.0;
+
+#[derive(Debug, Clone, Copy)]
+struct ScanSample {
+    timestamp_ms: f64,
+    blocks: u64,
+    outputs: u64,
+}
+
+/// Tracks scanning throughput over a sliding time window so that wallet UIs can show progress and an estimated time
+/// to completion during a restore. Timestamps are supplied by the caller (e.g. `Date.now()`) in milliseconds.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct ScanStatistics {
+    window_ms: f64,
+    samples: VecDeque<ScanSample>,
+    total_blocks: u64,
+    total_outputs: u64,
+}
+
+#[wasm_bindgen]
+impl ScanStatistics {
+    /// Creates new scan statistics with a sliding window of `window_ms` milliseconds, or 30 seconds if not provided
+    #[wasm_bindgen(constructor)]
+    pub fn new(window_ms: Option<f64>) -> Self {
+        Self {
+            window_ms: window_ms.filter(|w| *w > 0.0).unwrap_or(DEFAULT_WINDOW_MS),
+            samples: VecDeque::new(),
+            total_blocks: 0,
+            total_outputs: 0,
+        }
+    }
+
+    /// Records that `blocks` blocks containing `outputs` outputs were scanned at `timestamp_ms`
+    pub fn record(&mut self, blocks: u64, outputs: u64, timestamp_ms: f64) {
+        self.total_blocks = self.total_blocks.saturating_add(blocks);
+        self.total_outputs = self.total_outputs.saturating_add(outputs);
+        self.samples.push_back(ScanSample {
+            timestamp_ms,
+            blocks,
+            outputs,
+        });
+        while let Some(oldest) = self.samples.front() {
+            if timestamp_ms - oldest.timestamp_ms > self.window_ms {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// The number of blocks scanned per second over the sliding window
+    pub fn blocks_per_sec(&self) -> f64 {
+        self.rate_per_sec(|sample| sample.blocks)
+    }
+
+    /// The number of outputs scanned per second over the sliding window
+    pub fn outputs_per_sec(&self) -> f64 {
+        self.rate_per_sec(|sample| sample.outputs)
+    }
+
+    /// The estimated time in milliseconds to scan the remaining blocks at the current throughput, or `undefined` if
+    /// there is not enough data to make an estimate yet
+    pub fn estimated_completion_ms(&self, remaining_blocks: u64) -> Option<f64> {
+        let blocks_per_sec = self.blocks_per_sec();
+        if blocks_per_sec > 0.0 {
+            Some(remaining_blocks as f64 / blocks_per_sec * 1000.0)
+        } else {
+            None
+        }
+    }
+
+    /// The total number of blocks scanned
+    pub fn total_blocks(&self) -> u64 {
+        self.total_blocks
+    }
+
+    /// The total number of outputs scanned
+    pub fn total_outputs(&self) -> u64 {
+        self.total_outputs
+    }
+
+    /// Clears all recorded samples and totals
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.total_blocks = 0;
+        self.total_outputs = 0;
+    }
+}
+
+impl ScanStatistics {
+    // The first sample in the window only marks the start time, its work was done before the window started
+    fn rate_per_sec<F: Fn(&ScanSample) -> u64>(&self, count: F) -> f64 {
+        let (first, last) = match (self.samples.front(), self.samples.back()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 0.0,
+        };
+        let elapsed_ms = last.timestamp_ms - first.timestamp_ms;
+        if elapsed_ms <= 0.0 {
+            return 0.0;
+        }
+        let total: u64 = self.samples.iter().skip(1).map(count).sum();
+        total as f64 / elapsed_ms * 1000.0
+    }
+}
+
+impl Default for ScanStatistics {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::convert::{TryFrom, TryInto};
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_crypto::{
+    keys::PublicKey as PK,
+    tari_utilities::hex::{from_hex, Hex},
+};
+use tari_script::{inputs, ExecutionStack, Opcode, TariScript};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::size_budget::check_script;
+
+/// A struct to hold a built script
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BuiltScriptResult {
+    /// The serialized script, as used for the `script` field of an output in JSON (hex value)
+    pub script: Option<String>,
+    /// The script in assembly notation
+    pub asm: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn built_script_error(error: &str) -> BuiltScriptResult {
+    BuiltScriptResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// A struct to hold a disassembled script
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct DisassembledScriptResult {
+    /// The opcodes of the script, in the notation accepted by `build_script`
+    pub opcodes: Vec<String>,
+    /// The script in assembly notation
+    pub asm: Option<String>,
+    /// The script pattern recognised by the one-sided scanner, e.g. `OneSided` or `TimeLockedStealthOneSided`, if any
+    pub pattern: Option<String>,
+    /// The lock height of a time-locked script recognised by the one-sided scanner
+    pub lock_height: Option<u64>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn disassembled_script_error(error: &str) -> DisassembledScriptResult {
+    DisassembledScriptResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// The spending condition of a script recognised by the one-sided scanner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScriptPattern<'a> {
+    /// `PushPubKey(K)`, a one-sided payment to the script key `K`
+    OneSided { script_public_key: &'a PublicKey },
+    /// `PushPubKey(R) Drop PushPubKey(K)`, a one-sided payment to a stealth address with nonce `R`, as per RFC-0203
+    StealthOneSided {
+        nonce: &'a PublicKey,
+        script_public_key: &'a PublicKey,
+    },
+    /// `CheckMultiSig(m, n, [K_1, ..., K_n], msg)` or one of its verify variants, a one-sided payment to `m` of `n`
+    /// parties
+    MultiParty { public_keys: &'a [PublicKey] },
+}
+
+/// A script recognised by the one-sided scanner: a spending condition, optionally preceded by a time lock of
+/// `CheckHeightVerify(h)` or `PushInt(h) CompareHeightVerify`, and optionally followed by `Nop`s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MatchedScript<'a> {
+    pub pattern: ScriptPattern<'a>,
+    pub lock_height: Option<u64>,
+}
+
+impl MatchedScript<'_> {
+    /// The name of the pattern, as reported in the output source of a recovered output, e.g. `StealthOneSided` or
+    /// `TimeLockedMultiPartyOneSided`
+    pub fn name(&self) -> String {
+        let pattern = match self.pattern {
+            ScriptPattern::OneSided { .. } => "OneSided",
+            ScriptPattern::StealthOneSided { .. } => "StealthOneSided",
+            ScriptPattern::MultiParty { .. } => "MultiPartyOneSided",
+        };
+        match self.lock_height {
+            Some(_) => format!("TimeLocked{pattern}"),
+            None => pattern.to_string(),
+        }
+    }
+}
+
+/// Matches a script against the patterns recognised by the one-sided scanner
+pub(crate) fn match_script(script: &TariScript) -> Option<MatchedScript<'_>> {
+    let mut opcodes = script.as_slice();
+    while let [rest @ .., Opcode::Nop] = opcodes {
+        opcodes = rest;
+    }
+    let (lock_height, opcodes) = match opcodes {
+        [Opcode::CheckHeightVerify(height), rest @ ..] => (Some(*height), rest),
+        [Opcode::PushInt(height), Opcode::CompareHeightVerify, rest @ ..] => (Some(u64::try_from(*height).ok()?), rest),
+        _ => (None, opcodes),
+    };
+    let pattern = match opcodes {
+        [Opcode::PushPubKey(script_public_key)] => ScriptPattern::OneSided {
+            script_public_key: script_public_key.as_ref(),
+        },
+        [Opcode::PushPubKey(nonce), Opcode::Drop, Opcode::PushPubKey(script_public_key)] => {
+            ScriptPattern::StealthOneSided {
+                nonce: nonce.as_ref(),
+                script_public_key: script_public_key.as_ref(),
+            }
+        },
+        [Opcode::CheckMultiSig(_, _, public_keys, _)] |
+        [Opcode::CheckMultiSigVerify(_, _, public_keys, _)] |
+        [Opcode::CheckMultiSigVerifyAggregatePubKey(_, _, public_keys, _)] => ScriptPattern::MultiParty {
+            public_keys: public_keys.as_slice(),
+        },
+        _ => return None,
+    };
+    Some(MatchedScript { pattern, lock_height })
+}
+
+/// The input data that spends an output locked with `script` with its script key alone: the script public key for a
+/// script of only `Nop`s, which leaves the input data as the result, and nothing for a (time-locked) one-sided or
+/// stealth one-sided script, which pushes the script public key itself. Other scripts, e.g. multi-party scripts that
+/// need signatures, cannot be spent with the script key alone.
+pub(crate) fn spending_input_data(script: &TariScript, script_key: &PrivateKey) -> Result<ExecutionStack, String> {
+    let script_public_key = PublicKey::from_secret_key(script_key);
+    if script.as_slice().iter().all(|opcode| *opcode == Opcode::Nop) {
+        return Ok(inputs!(script_public_key));
+    }
+    match match_script(script).map(|matched| matched.pattern) {
+        Some(ScriptPattern::OneSided {
+            script_public_key: expected,
+        }) |
+        Some(ScriptPattern::StealthOneSided {
+            script_public_key: expected,
+            ..
+        }) => {
+            if *expected != script_public_key {
+                return Err("The script key does not match the script public key of the script".to_string());
+            }
+            Ok(ExecutionStack::default())
+        },
+        Some(ScriptPattern::MultiParty { .. }) => {
+            Err("A multi-party script cannot be spent with a single script key".to_string())
+        },
+        None => Err(format!("The script `{script}` cannot be spent with a script key alone")),
+    }
+}
+
+/// The height lock of a script: the highest height required by a `CheckHeightVerify(h)` or `CheckHeight(h)`, or by a
+/// `PushInt(h)` followed by `CompareHeightVerify` or `CompareHeight`, anywhere in the script
+pub(crate) fn script_lock_height(script: &TariScript) -> Option<u64> {
+    let opcodes = script.as_slice();
+    opcodes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, opcode)| match (opcode, opcodes.get(i + 1)) {
+            (Opcode::CheckHeightVerify(height) | Opcode::CheckHeight(height), _) => Some(*height),
+            (Opcode::PushInt(height), Some(Opcode::CompareHeightVerify | Opcode::CompareHeight)) => {
+                u64::try_from(*height).ok()
+            },
+            _ => None,
+        })
+        .max()
+}
+
+fn parse_hash(arg: &str) -> Result<[u8; 32], String> {
+    let bytes = from_hex(arg).map_err(|e| e.to_string())?;
+    bytes.try_into().map_err(|_| "Expected a 32 byte hex value".to_string())
+}
+
+fn parse_number<T: std::str::FromStr>(arg: &str) -> Result<T, String>
+where T::Err: std::fmt::Display {
+    arg.parse::<T>().map_err(|e| e.to_string())
+}
+
+/// Parses an opcode in the notation used by its `Display` implementation, e.g. `Drop`, `CheckHeightVerify(100)` or
+/// `PushPubKey(<hex>)`
+fn parse_opcode(opcode: &str) -> Result<Opcode, String> {
+    let opcode = opcode.trim();
+    let (name, arg) = match opcode.find('(') {
+        Some(start) if opcode.ends_with(')') => (&opcode[..start], Some(opcode[start + 1..opcode.len() - 1].trim())),
+        Some(_) => return Err(format!("`{opcode}`: missing closing parenthesis")),
+        None => (opcode, None),
+    };
+    let opcode = match (name, arg) {
+        ("CheckHeightVerify", Some(arg)) => Opcode::CheckHeightVerify(parse_number(arg)?),
+        ("CheckHeight", Some(arg)) => Opcode::CheckHeight(parse_number(arg)?),
+        ("CompareHeightVerify", None) => Opcode::CompareHeightVerify,
+        ("CompareHeight", None) => Opcode::CompareHeight,
+        ("Nop", None) => Opcode::Nop,
+        ("PushZero", None) => Opcode::PushZero,
+        ("PushOne", None) => Opcode::PushOne,
+        ("PushHash", Some(arg)) => Opcode::PushHash(Box::new(parse_hash(arg)?)),
+        ("PushInt", Some(arg)) => Opcode::PushInt(parse_number(arg)?),
+        ("PushPubKey", Some(arg)) => Opcode::PushPubKey(Box::new(PublicKey::from_hex(arg).map_err(|e| e.to_string())?)),
+        ("Drop", None) => Opcode::Drop,
+        ("Dup", None) => Opcode::Dup,
+        ("RevRot", None) => Opcode::RevRot,
+        ("GeZero", None) => Opcode::GeZero,
+        ("GtZero", None) => Opcode::GtZero,
+        ("LeZero", None) => Opcode::LeZero,
+        ("LtZero", None) => Opcode::LtZero,
+        ("Add", None) => Opcode::Add,
+        ("Sub", None) => Opcode::Sub,
+        ("Equal", None) => Opcode::Equal,
+        ("EqualVerify", None) => Opcode::EqualVerify,
+        ("Or", Some(arg)) => Opcode::Or(parse_number(arg)?),
+        ("OrVerify", Some(arg)) => Opcode::OrVerify(parse_number(arg)?),
+        ("HashBlake256", None) => Opcode::HashBlake256,
+        ("HashSha256", None) => Opcode::HashSha256,
+        ("HashSha3", None) => Opcode::HashSha3,
+        ("CheckSig", Some(arg)) => Opcode::CheckSig(Box::new(parse_hash(arg)?)),
+        ("CheckSigVerify", Some(arg)) => Opcode::CheckSigVerify(Box::new(parse_hash(arg)?)),
+        ("ToRistrettoPoint", None) => Opcode::ToRistrettoPoint,
+        ("Return", None) => Opcode::Return,
+        ("IfThen", None) => Opcode::IfThen,
+        ("Else", None) => Opcode::Else,
+        ("EndIf", None) => Opcode::EndIf,
+        _ => return Err(format!("`{opcode}`: unknown or unsupported opcode")),
+    };
+    Ok(opcode)
+}
+
+pub(crate) fn script_hex(script: &TariScript) -> Result<String, String> {
+    match serde_json::to_value(script).map_err(|e| e.to_string())? {
+        serde_json::Value::String(hex) => Ok(hex),
+        _ => Err("The script did not serialize to a hex string".to_string()),
+    }
+}
+
+/// Builds a script from a JSON array of opcodes, in the notation returned by `disassemble_script`, e.g.
+/// `["PushPubKey(<nonce hex>)", "Drop", "PushPubKey(<script spending key hex>)"]` for a stealth one-sided payment.
+/// Multisig opcodes are not supported.
+#[wasm_bindgen]
+pub fn build_script(opcodes_json: &str) -> BuiltScriptResult {
+    let opcodes: Vec<String> = match serde_json::from_str(opcodes_json) {
+        Ok(val) => val,
+        Err(e) => return built_script_error(&format!("opcodes_json: {e}")),
+    };
+    let opcodes = match opcodes
+        .iter()
+        .map(|opcode| parse_opcode(opcode))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(val) => val,
+        Err(e) => return built_script_error(&format!("opcodes_json: {e}")),
+    };
+    let script = TariScript::new(opcodes);
+    if let Err(e) = check_script(&script) {
+        return built_script_error(&e);
+    }
+    match script_hex(&script) {
+        Ok(hex) => BuiltScriptResult {
+            script: Some(hex),
+            asm: Some(script.to_string()),
+            error: None,
+        },
+        Err(e) => built_script_error(&format!("Could not serialize script: {e}")),
+    }
+}
+
+/// Disassembles a serialized script (hex value), e.g. the `script` field of a scanned output, into its opcodes and
+/// recognises the script patterns that the one-sided scanner matches.
+#[wasm_bindgen]
+pub fn disassemble_script(script_hex: &str) -> DisassembledScriptResult {
+    let script: TariScript = match serde_json::from_value(serde_json::Value::String(script_hex.to_string())) {
+        Ok(val) => val,
+        Err(e) => return disassembled_script_error(&format!("script_hex: {e}")),
+    };
+    let matched = match_script(&script);
+    DisassembledScriptResult {
+        opcodes: script.as_slice().iter().map(ToString::to_string).collect(),
+        asm: Some(script.to_string()),
+        pattern: matched.map(|matched| matched.name()),
+        lock_height: matched.and_then(|matched| matched.lock_height),
+        error: None,
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Secret keys that stay in wasm memory until JavaScript explicitly asks for them. Private keys are wiped when they
+//! are dropped, and hex copies of secrets made inside the library are wiped once parsed. Hex strings passed in from
+//! JavaScript as owned `String`s, e.g. `wallet_sk` and `known_script_keys` of the scanners, are copied into wasm
+//! memory by the bindings and wiped there after parsing; the JavaScript strings themselves cannot be wiped.
+
+use js_sys::Array;
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_crypto::{keys::PublicKey as PK, tari_utilities::hex::Hex};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use zeroize::Zeroize;
+
+use crate::RecoveredOutputResult;
+
+/// An opaque handle to a private key held in wasm memory. The key is only copied to JavaScript by `reveal`, and is
+/// wiped by `dispose`, or when the handle is freed.
+#[wasm_bindgen]
+pub struct SecretKeyHandle {
+    key: Option<PrivateKey>,
+}
+
+impl SecretKeyHandle {
+    pub(crate) fn new(key: PrivateKey) -> Self {
+        Self { key: Some(key) }
+    }
+
+    /// Parses a hex private key into a handle, wiping the hex string
+    fn from_hex_string(mut hex: String) -> Option<Self> {
+        let key = PrivateKey::from_hex(&hex).ok();
+        hex.zeroize();
+        key.map(Self::new)
+    }
+
+    fn key(&self) -> Result<&PrivateKey, JsValue> {
+        self.key
+            .as_ref()
+            .ok_or_else(|| JsValue::from_str("The secret key has been disposed"))
+    }
+}
+
+#[wasm_bindgen]
+impl SecretKeyHandle {
+    /// Returns the private key (hex value). The returned string is a JavaScript copy that the library cannot wipe, so
+    /// it should be used and dropped immediately.
+    pub fn reveal(&self) -> Result<String, JsValue> {
+        Ok(self.key()?.to_hex())
+    }
+
+    /// Returns the public key of the private key (hex value)
+    pub fn public_key(&self) -> Result<String, JsValue> {
+        Ok(PublicKey::from_secret_key(self.key()?).to_hex())
+    }
+
+    /// Wipes the private key; `reveal` throws afterwards
+    pub fn dispose(&mut self) {
+        // `PrivateKey` is wiped when dropped
+        self.key = None;
+    }
+
+    /// Whether the private key has been wiped
+    pub fn is_disposed(&self) -> bool {
+        self.key.is_none()
+    }
+}
+
+/// The result of a scan with the spending and script keys of a recovered output held as `SecretKeyHandle`s, instead of
+/// as hex strings in the result
+#[wasm_bindgen]
+pub struct ProtectedScanResult {
+    result: RecoveredOutputResult,
+    spending_key: Option<SecretKeyHandle>,
+    script_key: Option<SecretKeyHandle>,
+}
+
+impl From<RecoveredOutputResult> for ProtectedScanResult {
+    fn from(mut result: RecoveredOutputResult) -> Self {
+        let spending_key = result.spending_key.take().and_then(SecretKeyHandle::from_hex_string);
+        let script_key = result.script_key.take().and_then(SecretKeyHandle::from_hex_string);
+        Self {
+            result,
+            spending_key,
+            script_key,
+        }
+    }
+}
+
+/// Moves the results of a batch scan into an array of `ProtectedScanResult`s
+pub(crate) fn protect_results<I: IntoIterator<Item = RecoveredOutputResult>>(results: I) -> Array {
+    results
+        .into_iter()
+        .map(|result| JsValue::from(ProtectedScanResult::from(result)))
+        .collect()
+}
+
+#[wasm_bindgen]
+impl ProtectedScanResult {
+    /// The scan result, without the spending and script keys
+    pub fn result(&self) -> RecoveredOutputResult {
+        self.result.clone()
+    }
+
+    /// Moves the spending key of the recovered output out of the result, if there is one and it has not been taken
+    pub fn take_spending_key(&mut self) -> Option<SecretKeyHandle> {
+        self.spending_key.take()
+    }
+
+    /// Moves the script key of the recovered output out of the result, if there is one and it has not been taken
+    pub fn take_script_key(&mut self) -> Option<SecretKeyHandle> {
+        self.script_key.take()
+    }
+}
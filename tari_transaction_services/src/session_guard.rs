@@ -0,0 +1,248 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Concurrency guard for long-lived WASM session objects.
+//!
+//! JavaScript may call into a session object again while a previous asynchronous call on the same object is still
+//! pending. A [`SessionGuard`] serializes such calls: [`SessionGuard::try_acquire`] fails fast with a [`Busy`] error,
+//! while [`SessionGuard::acquire`] queues the operation so that operations run, and their promises resolve, in the
+//! order in which they were called.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, VecDeque},
+    fmt,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::JsValue;
+
+/// The error returned when an operation is attempted on a session object that is busy with another operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct Busy {
+    /// The operation that was attempted
+    pub operation: String,
+    /// The operation that is in progress, if known
+    pub in_progress: Option<String>,
+}
+
+impl fmt::Display for Busy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.in_progress {
+            Some(in_progress) => write!(
+                f,
+                "Busy: cannot start `{}` while `{}` is in progress",
+                self.operation, in_progress
+            ),
+            None => write!(
+                f,
+                "Busy: cannot start `{}` while another operation is in progress",
+                self.operation
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Busy {}
+
+impl From<Busy> for JsValue {
+    fn from(busy: Busy) -> Self {
+        #[derive(Serialize)]
+        struct BusyError {
+            error: String,
+            busy: Busy,
+        }
+        let message = busy.to_string();
+        let error = BusyError {
+            error: message.clone(),
+            busy,
+        };
+        serde_wasm_bindgen::to_value(&error).unwrap_or_else(|_| JsValue::from_str(&message))
+    }
+}
+
+#[derive(Debug, Default)]
+struct GuardState {
+    next_ticket: u64,
+    now_serving: u64,
+    in_progress: Option<&'static str>,
+    cancelled: BTreeSet<u64>,
+    waiters: VecDeque<(u64, Waker)>,
+}
+
+impl GuardState {
+    fn is_idle(&self) -> bool {
+        self.next_ticket == self.now_serving
+    }
+
+    // Moves on to the next ticket that has not been cancelled and wakes its waiter
+    fn advance(&mut self) {
+        self.in_progress = None;
+        self.now_serving += 1;
+        while self.cancelled.remove(&self.now_serving) {
+            self.now_serving += 1;
+        }
+        let now_serving = self.now_serving;
+        if let Some(position) = self.waiters.iter().position(|(ticket, _)| *ticket == now_serving) {
+            if let Some((_, waker)) = self.waiters.remove(position) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Serializes operations on a long-lived session object. Cloning the guard shares the underlying state.
+#[derive(Debug, Clone, Default)]
+pub struct SessionGuard {
+    state: Rc<RefCell<GuardState>>,
+}
+
+impl SessionGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether an operation is in progress or queued
+    pub fn is_busy(&self) -> bool {
+        !self.state.borrow().is_idle()
+    }
+
+    /// Starts `operation` immediately, or returns a [`Busy`] error if another operation is in progress or queued
+    pub fn try_acquire(&self, operation: &'static str) -> Result<SessionPermit, Busy> {
+        let mut state = self.state.borrow_mut();
+        if !state.is_idle() {
+            return Err(Busy {
+                operation: operation.to_string(),
+                in_progress: state.in_progress.map(|op| op.to_string()),
+            });
+        }
+        state.next_ticket += 1;
+        state.in_progress = Some(operation);
+        Ok(SessionPermit {
+            state: self.state.clone(),
+        })
+    }
+
+    /// Queues `operation` behind any operations already in progress or queued. The returned future resolves once it is
+    /// this operation's turn, so operations run in the order in which they were queued.
+    pub fn acquire(&self, operation: &'static str) -> SessionAcquire {
+        let mut state = self.state.borrow_mut();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        SessionAcquire {
+            state: self.state.clone(),
+            ticket,
+            operation,
+            acquired: false,
+        }
+    }
+}
+
+/// Grants exclusive access to a session object for one operation. Access is released when the permit is dropped.
+#[derive(Debug)]
+pub struct SessionPermit {
+    state: Rc<RefCell<GuardState>>,
+}
+
+impl Drop for SessionPermit {
+    fn drop(&mut self) {
+        self.state.borrow_mut().advance();
+    }
+}
+
+/// A queued operation waiting for its turn, see [`SessionGuard::acquire`]
+#[derive(Debug)]
+pub struct SessionAcquire {
+    state: Rc<RefCell<GuardState>>,
+    ticket: u64,
+    operation: &'static str,
+    acquired: bool,
+}
+
+impl Future for SessionAcquire {
+    type Output = SessionPermit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        if state.now_serving == self.ticket {
+            state.in_progress = Some(self.operation);
+            drop(state);
+            self.acquired = true;
+            return Poll::Ready(SessionPermit {
+                state: self.state.clone(),
+            });
+        }
+        let ticket = self.ticket;
+        match state.waiters.iter_mut().find(|(t, _)| *t == ticket) {
+            Some((_, waker)) => waker.clone_from(cx.waker()),
+            None => state.waiters.push_back((ticket, cx.waker().clone())),
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for SessionAcquire {
+    // A queued operation that is abandoned must not block the operations queued after it
+    fn drop(&mut self) {
+        if self.acquired {
+            return;
+        }
+        let mut state = self.state.borrow_mut();
+        let ticket = self.ticket;
+        state.waiters.retain(|(t, _)| *t != ticket);
+        if state.now_serving == ticket {
+            state.advance();
+        } else {
+            state.cancelled.insert(ticket);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn busy_while_a_permit_is_held() {
+        let guard = SessionGuard::new();
+        let _permit = guard.try_acquire("persist").unwrap();
+        assert!(guard.is_busy());
+        let busy = guard.try_acquire("accept_block").unwrap_err();
+        assert_eq!(busy, Busy {
+            operation: "accept_block".to_string(),
+            in_progress: Some("persist".to_string()),
+        });
+        assert_eq!(
+            busy.to_string(),
+            "Busy: cannot start `accept_block` while `persist` is in progress"
+        );
+    }
+
+    #[test]
+    fn permit_is_released_on_drop() {
+        let guard = SessionGuard::new();
+        let permit = guard.try_acquire("persist").unwrap();
+        drop(permit);
+        assert!(!guard.is_busy());
+        let _permit = guard.try_acquire("accept_block").unwrap();
+        assert!(guard.try_acquire("persist").is_err());
+    }
+
+    #[test]
+    fn abandoned_acquire_does_not_block_the_guard() {
+        let guard = SessionGuard::new();
+        let permit = guard.try_acquire("persist").unwrap();
+        let queued = guard.acquire("persist");
+        drop(queued);
+        assert!(guard.is_busy());
+        drop(permit);
+        assert!(!guard.is_busy());
+        assert!(guard.try_acquire("accept_block").is_ok());
+    }
+}
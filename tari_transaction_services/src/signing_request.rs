@@ -0,0 +1,482 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Request/response protocol for dApps asking an embedded web wallet to sign.
+//!
+//! A dApp sends a JSON request `{ "version": 1, "id": "<id>", "origin": "<dApp origin>", "method": "<method>",
+//! "params": { ... } }` with one of the methods below. Every request is validated and then passed to the wallet's
+//! approval callback, and keys are only derived from the wallet seed once the user has approved the request.
+//! * `get_public_key` - `{ "key": { "branch": "<branch>", "index": <index> } }`
+//! * `sign_message` - `{ "key": { ... }, "message": "<message>" }`
+//! * `prove_ownership` - `{ "key": { ... }, "challenge": "<verifier challenge>" }`
+//! * `create_one_sided_output` - `{ "key": { ... }, "value": <value>, "recipientAddress": "<address>", "features": {
+//!   ... }, "promisePolicy": ... }`, where the key is the sender's wallet key, the address is a Tari address or public
+//!   key (hex value), and `features` and `promisePolicy` are optional
+
+use blake2::Blake2b;
+use digest::consts::U64;
+use js_sys::{Function, Promise};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey, Signature};
+use tari_core::transactions::transaction_components::{OutputFeatures, TransactionOutputVersion};
+use tari_crypto::{
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    keys::PublicKey as PK,
+    tari_utilities::{hex::Hex, ByteArray},
+};
+use tari_key_manager::key_manager::KeyManager;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{
+    address::one_sided_recipient_keys,
+    cipher_seed_from_hex,
+    create_output::{build_one_sided_output, SenderKeys},
+    value_promise::MinimumValuePromisePolicy,
+};
+
+/// The version of the signing request protocol implemented here
+pub const SIGNING_PROTOCOL_VERSION: u32 = 1;
+
+// Upper limit on the size of a message or challenge to sign
+const MAX_MESSAGE_BYTES: usize = 4096;
+
+hash_domain!(
+    SigningRequestHashDomain,
+    "com.tari.transaction_services.signing_request",
+    1
+);
+
+type SigningRequestHasher = DomainSeparatedHasher<Blake2b<U64>, SigningRequestHashDomain>;
+
+/// A key derived from the wallet seed, at `index` on `branch`
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyPath {
+    /// The key branch
+    pub branch: String,
+    /// The key index
+    pub index: u64,
+}
+
+/// A method requested by a dApp, with its parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum SigningMethod {
+    GetPublicKey {
+        key: KeyPath,
+    },
+    SignMessage {
+        key: KeyPath,
+        message: String,
+    },
+    ProveOwnership {
+        key: KeyPath,
+        challenge: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    CreateOneSidedOutput {
+        key: KeyPath,
+        value: u64,
+        recipient_address: String,
+        features: Option<OutputFeatures>,
+        promise_policy: Option<MinimumValuePromisePolicy>,
+    },
+}
+
+/// A signing request sent by a dApp
+#[derive(Debug, Clone, Deserialize)]
+pub struct SigningRequest {
+    /// The protocol version the request was written for
+    pub version: u32,
+    /// An identifier chosen by the dApp, echoed in the response
+    pub id: String,
+    /// The origin of the dApp, which is shown to the user and bound into signatures
+    pub origin: String,
+    /// The requested method and its parameters
+    #[serde(flatten)]
+    pub method: SigningMethod,
+}
+
+// Used to echo the request id when a request cannot be parsed
+#[derive(Deserialize)]
+struct RequestId {
+    id: Option<String>,
+}
+
+/// The request passed to the wallet's approval callback before any key is derived
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningApproval {
+    /// The request id
+    pub id: String,
+    /// The origin of the dApp
+    pub origin: String,
+    /// The requested method
+    pub method: String,
+    /// The key the dApp asks to use
+    pub key: KeyPath,
+    /// A human readable description of the request, to show to the user
+    pub summary: String,
+}
+
+/// The kind of error returned for a signing request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+pub enum SigningErrorCode {
+    /// The request is malformed, of an unsupported version or method, or has invalid parameters
+    InvalidRequest,
+    /// The user, or the approval callback, did not approve the request
+    Rejected,
+    /// The key could not be derived from the wallet seed
+    KeyDerivationError,
+    /// The signature or output could not be created
+    SigningError,
+}
+
+/// The response to a signing request
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SigningResponse {
+    /// The request id
+    pub id: Option<String>,
+    /// The requested method
+    pub method: Option<String>,
+    /// The public key of the requested key (hex value)
+    pub public_key: Option<String>,
+    /// The public nonce of the signature (hex value)
+    pub public_nonce: Option<String>,
+    /// The signature (hex value)
+    pub signature: Option<String>,
+    /// The created output, serialized as JSON
+    pub output: Option<String>,
+    /// The hash of the created output (hex value)
+    pub output_hash: Option<String>,
+    /// The kind of error, in case of an error
+    pub error_code: Option<SigningErrorCode>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn signing_error(id: Option<String>, code: SigningErrorCode, error: &str) -> SigningResponse {
+    SigningResponse {
+        id,
+        error_code: Some(code),
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// A struct to hold the result of a signature verification
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SignatureVerificationResult {
+    /// Whether the signature is valid
+    pub valid: Option<bool>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn signature_verification_error(error: &str) -> SignatureVerificationResult {
+    SignatureVerificationResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+impl SigningMethod {
+    fn name(&self) -> &'static str {
+        match self {
+            SigningMethod::GetPublicKey { .. } => "get_public_key",
+            SigningMethod::SignMessage { .. } => "sign_message",
+            SigningMethod::ProveOwnership { .. } => "prove_ownership",
+            SigningMethod::CreateOneSidedOutput { .. } => "create_one_sided_output",
+        }
+    }
+
+    fn key(&self) -> &KeyPath {
+        match self {
+            SigningMethod::GetPublicKey { key } |
+            SigningMethod::SignMessage { key, .. } |
+            SigningMethod::ProveOwnership { key, .. } |
+            SigningMethod::CreateOneSidedOutput { key, .. } => key,
+        }
+    }
+
+    fn summary(&self) -> String {
+        let key = self.key();
+        match self {
+            SigningMethod::GetPublicKey { .. } => {
+                format!("Share the public key at index {} on branch `{}`", key.index, key.branch)
+            },
+            SigningMethod::SignMessage { message, .. } => format!("Sign the message \"{message}\""),
+            SigningMethod::ProveOwnership { .. } => format!(
+                "Prove ownership of the key at index {} on branch `{}`",
+                key.index, key.branch
+            ),
+            SigningMethod::CreateOneSidedOutput {
+                value,
+                recipient_address,
+                ..
+            } => format!("Send {value} µT to {recipient_address}"),
+        }
+    }
+}
+
+impl SigningRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.version != SIGNING_PROTOCOL_VERSION {
+            return Err(format!(
+                "Unsupported protocol version {}, expected {SIGNING_PROTOCOL_VERSION}",
+                self.version
+            ));
+        }
+        if self.id.is_empty() {
+            return Err("id: must not be empty".to_string());
+        }
+        if self.origin.is_empty() {
+            return Err("origin: must not be empty".to_string());
+        }
+        if self.method.key().branch.is_empty() {
+            return Err("key.branch: must not be empty".to_string());
+        }
+        match &self.method {
+            SigningMethod::SignMessage { message: text, .. } |
+            SigningMethod::ProveOwnership { challenge: text, .. }
+                if text.is_empty() || text.len() > MAX_MESSAGE_BYTES =>
+            {
+                Err(format!(
+                    "{}: the message must be between 1 and {MAX_MESSAGE_BYTES} bytes",
+                    self.method.name()
+                ))
+            },
+            SigningMethod::CreateOneSidedOutput { value: 0, .. } => Err("value: must not be zero".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The challenge for a signature by `public_key` over `message` for the dApp at `origin`. The method is part of the
+/// domain, so that a signed message can never pass as an ownership proof or vice versa, and the origin is bound in so
+/// that a signature obtained by one dApp cannot be replayed to another.
+fn signing_challenge(
+    method: &'static str,
+    public_key: &PublicKey,
+    public_nonce: &PublicKey,
+    origin: &str,
+    message: &[u8],
+) -> [u8; 64] {
+    let mut hasher = SigningRequestHasher::new_with_label(method);
+    hasher.update(public_key.as_bytes());
+    hasher.update(public_nonce.as_bytes());
+    hasher.update(&(origin.len() as u64).to_le_bytes());
+    hasher.update(origin.as_bytes());
+    hasher.update(message);
+    let mut challenge = [0u8; 64];
+    challenge.copy_from_slice(hasher.finalize().as_ref());
+    challenge
+}
+
+fn sign(method: &'static str, private_key: &PrivateKey, origin: &str, message: &[u8]) -> Result<Signature, String> {
+    let public_key = PublicKey::from_secret_key(private_key);
+    let (secret_nonce, public_nonce) = PublicKey::random_keypair(&mut OsRng);
+    let challenge = signing_challenge(method, &public_key, &public_nonce, origin, message);
+    Signature::sign_raw_uniform(private_key, secret_nonce, &challenge).map_err(|e| e.to_string())
+}
+
+/// Calls the approval callback, which may return a boolean or a promise of a boolean. Anything but `true`, including
+/// an exception or a rejected promise, is treated as a rejection.
+async fn request_approval(approve: &Function, approval: &SigningApproval) -> bool {
+    let approval = match serde_wasm_bindgen::to_value(approval) {
+        Ok(val) => val,
+        Err(_) => return false,
+    };
+    let mut answer = match approve.call1(&JsValue::NULL, &approval) {
+        Ok(val) => val,
+        Err(_) => return false,
+    };
+    if let Some(promise) = answer.dyn_ref::<Promise>() {
+        answer = match JsFuture::from(promise.clone()).await {
+            Ok(val) => val,
+            Err(_) => return false,
+        };
+    }
+    answer.as_bool() == Some(true)
+}
+
+async fn create_output(
+    value: u64,
+    sender_keys: SenderKeys,
+    recipient_address: &str,
+    features: Option<OutputFeatures>,
+    promise_policy: Option<MinimumValuePromisePolicy>,
+) -> Result<(String, String), (SigningErrorCode, String)> {
+    let recipient = one_sided_recipient_keys(recipient_address)
+        .map_err(|e| (SigningErrorCode::InvalidRequest, format!("recipient_address: {e}")))?;
+    let features = features.unwrap_or_default();
+    let promise_policy =
+        promise_policy.unwrap_or_else(|| MinimumValuePromisePolicy::default_for(features.range_proof_type));
+    let output = build_one_sided_output(
+        value.into(),
+        sender_keys,
+        recipient,
+        features,
+        promise_policy,
+        TransactionOutputVersion::get_current_version(),
+    )
+    .await
+    .map_err(|e| (SigningErrorCode::SigningError, format!("Could not create output: {e}")))?;
+    let json = serde_json::to_string(&output).map_err(|e| {
+        (
+            SigningErrorCode::SigningError,
+            format!("Could not serialize output: {e}"),
+        )
+    })?;
+    Ok((output.hash().to_hex(), json))
+}
+
+async fn execute(seed: &str, request: SigningRequest) -> SigningResponse {
+    let id = Some(request.id.clone());
+    let key = request.method.key();
+    let seed = match cipher_seed_from_hex(seed) {
+        Ok(val) => val,
+        Err(e) => return signing_error(id, SigningErrorCode::KeyDerivationError, &e),
+    };
+    let private_key = match KeyManager::<PublicKey>::from(seed, key.branch.clone(), 0).derive_key(key.index) {
+        Ok(derived_key) => derived_key.key,
+        Err(e) => {
+            return signing_error(
+                id,
+                SigningErrorCode::KeyDerivationError,
+                &format!("Could not derive key: {e}"),
+            )
+        },
+    };
+
+    let mut response = SigningResponse {
+        id: id.clone(),
+        method: Some(request.method.name().to_string()),
+        public_key: Some(PublicKey::from_secret_key(&private_key).to_hex()),
+        ..Default::default()
+    };
+    let signed = match request.method {
+        SigningMethod::GetPublicKey { .. } => return response,
+        SigningMethod::SignMessage { message, .. } => {
+            sign("sign_message", &private_key, &request.origin, message.as_bytes())
+        },
+        SigningMethod::ProveOwnership { challenge, .. } => {
+            sign("prove_ownership", &private_key, &request.origin, challenge.as_bytes())
+        },
+        SigningMethod::CreateOneSidedOutput {
+            value,
+            recipient_address,
+            features,
+            promise_policy,
+            ..
+        } => {
+            let sender_keys = SenderKeys {
+                wallet_pk: PublicKey::from_secret_key(&private_key).to_hex(),
+                sender_offset_sk: None,
+                nonce_sk: None,
+            };
+            return match create_output(value, sender_keys, &recipient_address, features, promise_policy).await {
+                Ok((hash, output)) => SigningResponse {
+                    output_hash: Some(hash),
+                    output: Some(output),
+                    ..response
+                },
+                Err((code, e)) => signing_error(id, code, &e),
+            };
+        },
+    };
+    match signed {
+        Ok(signature) => {
+            response.public_nonce = Some(signature.get_public_nonce().to_hex());
+            response.signature = Some(signature.get_signature().to_hex());
+            response
+        },
+        Err(e) => signing_error(id, SigningErrorCode::SigningError, &format!("Could not sign: {e}")),
+    }
+}
+
+/// Handles a signing request from a dApp (see the module documentation for the request schema) on behalf of the wallet
+/// with cipher seed `seed`. The request is validated and passed to `approve` as a `SigningApproval`; keys are only
+/// derived, and the request only executed, if `approve` returns `true` or a promise that resolves to `true`. Errors
+/// are returned in the response with an error code, and the response always echoes the request id when it is known.
+#[wasm_bindgen]
+pub async fn handle_signing_request(seed: String, request: String, approve: Function) -> SigningResponse {
+    let request: SigningRequest = match serde_json::from_str(&request) {
+        Ok(val) => val,
+        Err(e) => {
+            let id = serde_json::from_str::<RequestId>(&request)
+                .ok()
+                .and_then(|request| request.id);
+            return signing_error(id, SigningErrorCode::InvalidRequest, &format!("request: {e}"));
+        },
+    };
+    if let Err(e) = request.validate() {
+        return signing_error(Some(request.id), SigningErrorCode::InvalidRequest, &e);
+    }
+
+    let approval = SigningApproval {
+        id: request.id.clone(),
+        origin: request.origin.clone(),
+        method: request.method.name().to_string(),
+        key: request.method.key().clone(),
+        summary: request.method.summary(),
+    };
+    if !request_approval(&approve, &approval).await {
+        return signing_error(
+            Some(request.id),
+            SigningErrorCode::Rejected,
+            "The request was not approved",
+        );
+    }
+    execute(&seed, request).await
+}
+
+/// Verifies a signature returned for a `sign_message` or `prove_ownership` request (`method`), as made for the dApp at
+/// `origin` over `message` (the message or the challenge). The public key, public nonce and signature are hex values
+/// as returned in the `SigningResponse`.
+#[wasm_bindgen]
+pub fn verify_signing_response(
+    method: &str,
+    origin: &str,
+    message: &str,
+    public_key: &str,
+    public_nonce: &str,
+    signature: &str,
+) -> SignatureVerificationResult {
+    let method = match method {
+        "sign_message" => "sign_message",
+        "prove_ownership" => "prove_ownership",
+        _ => return signature_verification_error(&format!("Unsupported method `{method}`")),
+    };
+    let public_key = match PublicKey::from_hex(public_key) {
+        Ok(val) => val,
+        Err(e) => return signature_verification_error(&format!("public_key: {e}")),
+    };
+    let public_nonce = match PublicKey::from_hex(public_nonce) {
+        Ok(val) => val,
+        Err(e) => return signature_verification_error(&format!("public_nonce: {e}")),
+    };
+    let signature = match PrivateKey::from_hex(signature) {
+        Ok(val) => Signature::new(public_nonce, val),
+        Err(e) => return signature_verification_error(&format!("signature: {e}")),
+    };
+    let challenge = signing_challenge(
+        method,
+        &public_key,
+        signature.get_public_nonce(),
+        origin,
+        message.as_bytes(),
+    );
+    SignatureVerificationResult {
+        valid: Some(signature.verify_raw_uniform(&public_key, &challenge)),
+        error: None,
+    }
+}
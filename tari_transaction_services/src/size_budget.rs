@@ -0,0 +1,59 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Size budgets of the output components that builders assemble, checked against the consensus limits as components
+//! are added, so that builders fail early, naming the component and the limit it exceeds, instead of producing
+//! outputs that base nodes reject.
+
+use tari_core::{covenants::Covenant, transactions::transaction_components::OutputFeatures};
+use tari_script::TariScript;
+
+/// The maximum size of an output script in bytes
+pub(crate) const MAX_SCRIPT_BYTES: usize = 2048;
+/// The maximum number of tokens in an output covenant
+pub(crate) const MAX_COVENANT_TOKENS: usize = 100;
+/// The maximum size of an output covenant in bytes
+pub(crate) const MAX_COVENANT_BYTES: usize = 4096;
+/// The maximum size of the extra data of a coinbase output in bytes
+pub(crate) const MAX_COINBASE_EXTRA_BYTES: usize = 64;
+
+fn check_limit(component: &str, size: usize, limit: usize, unit: &str) -> Result<(), String> {
+    if size > limit {
+        return Err(format!(
+            "The {component} is {size} {unit}, exceeding the consensus limit of {limit} {unit}"
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the size of an output script
+pub(crate) fn check_script(script: &TariScript) -> Result<(), String> {
+    check_limit("script", script.to_bytes().len(), MAX_SCRIPT_BYTES, "bytes")
+}
+
+/// Checks the number of tokens and size of an output covenant
+pub(crate) fn check_covenant(covenant: &Covenant) -> Result<(), String> {
+    check_limit("covenant", covenant.num_tokens(), MAX_COVENANT_TOKENS, "tokens")?;
+    check_limit("covenant", covenant.to_bytes().len(), MAX_COVENANT_BYTES, "bytes")
+}
+
+/// Checks the size of the variable length output features
+pub(crate) fn check_features(features: &OutputFeatures) -> Result<(), String> {
+    check_limit(
+        "coinbase extra",
+        features.coinbase_extra.len(),
+        MAX_COINBASE_EXTRA_BYTES,
+        "bytes",
+    )
+}
+
+/// Checks all variable length components of an output, in the order script, covenant, features
+pub(crate) fn check_output_components(
+    script: &TariScript,
+    covenant: &Covenant,
+    features: &OutputFeatures,
+) -> Result<(), String> {
+    check_script(script)?;
+    check_covenant(covenant)?;
+    check_features(features)
+}
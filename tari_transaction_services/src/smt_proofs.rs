@@ -0,0 +1,100 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Verification of output inclusion proofs against the output sparse Merkle tree (SMT) root of a block header, so
+//! that light clients can check that an output is unspent as of a header without trusting the node that served it.
+
+use std::convert::TryFrom;
+
+use blake2::Blake2b;
+use digest::consts::U32;
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::transaction_components::TransactionOutput;
+use tari_crypto::{
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    tari_utilities::{
+        hex::{from_hex, Hex},
+        ByteArray,
+    },
+};
+use tari_mmr::sparse_merkle_tree::{MerkleProof, NodeHash, NodeKey, ValueHash};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::output_encoding::decode_output;
+
+// The hash domain of the output SMT, as used by base nodes
+hash_domain!(OutputSmtHashDomain, "com.tari.base_layer.core.output_smt", 1);
+
+type OutputSmtHasherBlake256 = DomainSeparatedHasher<Blake2b<U32>, OutputSmtHashDomain>;
+
+/// A struct to hold the result of an output inclusion proof verification
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct InclusionVerificationResult {
+    /// The hash of the output (hex value)
+    pub hash: Option<String>,
+    /// The output's SMT leaf hash at the mined height (hex value)
+    pub smt_hash: Option<String>,
+    /// Whether the proof shows the output to be included in the tree with the given root
+    pub included: bool,
+    /// An error message in case of an error or failed verification
+    pub error: Option<String>,
+}
+
+fn inclusion_error(error: &str) -> InclusionVerificationResult {
+    InclusionVerificationResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Verifies a Borsh encoded SMT membership proof of `output`, mined at `mined_height`, against an output SMT root
+pub(crate) fn verify_output_proof(
+    root: &[u8],
+    proof_bytes: &[u8],
+    output: &TransactionOutput,
+    mined_height: u64,
+) -> Result<(), String> {
+    let root = NodeHash::try_from(root).map_err(|e| format!("root: {e}"))?;
+    let proof: MerkleProof<OutputSmtHasherBlake256> =
+        borsh::from_slice(proof_bytes).map_err(|e| format!("proof_bytes: {e}"))?;
+    let key = NodeKey::try_from(output.commitment.as_bytes()).map_err(|e| format!("commitment: {e}"))?;
+    let value = ValueHash::try_from(output.smt_hash(mined_height).as_slice()).map_err(|e| format!("smt_hash: {e}"))?;
+    proof
+        .validate(&key, &value, &root)
+        .map_err(|e| format!("Inclusion proof verification failed: {e}"))
+}
+
+/// Verifies a Borsh encoded SMT membership proof of a transaction output against a header's output SMT root. The
+/// output's leaf is keyed by its commitment and holds its `smt_hash` at `mined_height`, so the proof only verifies
+/// for the height at which the output was mined.
+/// * `root` - the `output_mr` of the block header (hex value)
+/// * `proof_bytes` - the Borsh encoded SMT membership proof, as served by the base node
+/// * `output` - the output, in any of the encodings accepted by `scan_output_for_one_sided_payment`
+/// * `mined_height` - the height of the block in which the output was mined
+#[wasm_bindgen]
+pub fn verify_output_inclusion(
+    root: &str,
+    proof_bytes: &[u8],
+    output: JsValue,
+    mined_height: u64,
+) -> InclusionVerificationResult {
+    let root = match from_hex(root) {
+        Ok(val) => val,
+        Err(e) => return inclusion_error(&format!("root: {e}")),
+    };
+    let output = match decode_output(&output, None) {
+        Ok(val) => val,
+        Err(e) => return inclusion_error(&e.to_string()),
+    };
+    let verified = verify_output_proof(&root, proof_bytes, &output, mined_height);
+    InclusionVerificationResult {
+        hash: Some(output.hash().to_hex()),
+        smt_hash: Some(output.smt_hash(mined_height).to_hex()),
+        included: verified.is_ok(),
+        error: verified.err(),
+    }
+}
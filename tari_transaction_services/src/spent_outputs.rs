@@ -0,0 +1,89 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::FixedHash;
+use tari_core::{limited_reader::LimitedBytesReader, transactions::transaction_components::TransactionInput};
+use tari_crypto::tari_utilities::hex::{from_hex, Hex};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::consensus_encoding::MAX_INPUT_BYTES;
+
+/// A recovered output that is spent by one of the scanned inputs
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct SpentRecoveredOutput {
+    /// The hash of the recovered output (hex value)
+    pub output_hash: String,
+    /// The index of the input that spends it
+    pub input_index: usize,
+}
+
+/// A struct to hold the recovered outputs spent by a set of inputs
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SpentOutputsResult {
+    /// The recovered outputs that are spent, in the order of the inputs that spend them
+    pub spent: Vec<SpentRecoveredOutput>,
+    /// The number of inputs checked
+    pub inputs: usize,
+    /// The index of the input that could not be decoded, in case of an error
+    pub failed_index: Option<usize>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn spent_outputs_error(failed_index: Option<usize>, error: &str) -> SpentOutputsResult {
+    SpentOutputsResult {
+        failed_index,
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn input_from_borsh(bytes: &[u8]) -> Result<TransactionInput, String> {
+    let mut reader = LimitedBytesReader::new(MAX_INPUT_BYTES, bytes);
+    borsh::from_reader(&mut reader).map_err(|e| format!("Borsh: {e}"))
+}
+
+/// Reports which previously recovered outputs are spent by a set of inputs, e.g. those of a scanned block, so that a
+/// light wallet can keep its balance without tracking the full chain state. The output hash of a compact input is
+/// read from it, and that of a full input is computed from the output data it carries.
+/// * `inputs` - the Borsh encoded inputs (hex values)
+/// * `recovered_output_hashes` - the hashes of the wallet's recovered outputs (hex values)
+#[wasm_bindgen]
+pub fn match_inputs_to_outputs(inputs: Vec<String>, recovered_output_hashes: Vec<String>) -> SpentOutputsResult {
+    let mut recovered = HashMap::with_capacity(recovered_output_hashes.len());
+    for hash in &recovered_output_hashes {
+        match FixedHash::from_hex(hash.trim()) {
+            Ok(val) => recovered.insert(val, hash.trim()),
+            Err(e) => return spent_outputs_error(None, &format!("recovered_output_hashes: {e}")),
+        };
+    }
+    let mut spent = Vec::new();
+    for (i, input) in inputs.iter().enumerate() {
+        let input = match from_hex(input.trim())
+            .map_err(|e| format!("hex: {e}"))
+            .and_then(|bytes| input_from_borsh(&bytes))
+        {
+            Ok(val) => val,
+            Err(e) => return spent_outputs_error(Some(i), &format!("input {i}: {e}")),
+        };
+        if let Some(hash) = recovered.get(&input.output_hash()) {
+            spent.push(SpentRecoveredOutput {
+                output_hash: hash.to_string(),
+                input_index: i,
+            });
+        }
+    }
+    SpentOutputsResult {
+        spent,
+        inputs: inputs.len(),
+        failed_index: None,
+        error: None,
+    }
+}
@@ -0,0 +1,667 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::{
+    types::{Commitment, PrivateKey, PublicKey, Signature},
+    wallet_types::WalletType,
+};
+use tari_core::{
+    borsh::SerializedSize,
+    covenants::Covenant,
+    transactions::{
+        key_manager::{
+            MemoryDbKeyManager,
+            TariKeyId,
+            TransactionKeyManagerBranch,
+            TransactionKeyManagerInterface,
+            TxoStage,
+        },
+        tari_amount::MicroMinotari,
+        transaction_components::{
+            KernelBuilder,
+            KernelFeatures,
+            OutputFeatures,
+            RangeProofType,
+            Transaction,
+            TransactionBuilder,
+            TransactionInput,
+            TransactionKernel,
+            TransactionKernelVersion,
+            TransactionOutput,
+            WalletOutput,
+            WalletOutputBuilder,
+        },
+        weight::TransactionWeight,
+    },
+};
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_key_manager::{
+    key_manager_service::storage::{
+        database::{KeyManagerDatabase, KeyManagerState},
+        memory_db::KeyManagerMemoryDatabase,
+    },
+    KeyManagerInterface,
+};
+use tari_script::{inputs, script, ExecutionStack, TariScript};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    cipher_seed_from_hex,
+    factories::crypto_factories,
+    output_encoding::{decode_output_str, OutputEncoding},
+    scan_outputs::{recover_output, RecoveredOutput, ScanKeys},
+    script_tools::spending_input_data,
+    value_promise::{parse_range_proof_type, MinimumValuePromisePolicy},
+};
+
+/// The default maximum number of inputs spent by a single sweep transaction
+const DEFAULT_MAX_SWEEP_INPUTS: usize = 500;
+
+/// The branches of the new seed from which a sweep hands out keys
+const SWEEP_BRANCHES: [TransactionKeyManagerBranch; 3] = [
+    TransactionKeyManagerBranch::CommitmentMask,
+    TransactionKeyManagerBranch::SenderOffset,
+    TransactionKeyManagerBranch::KernelNonce,
+];
+
+/// A struct to hold the sweep transactions moving the funds of an old wallet to a new seed
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SweepResult {
+    /// The sweep transactions, each serialized as JSON
+    pub transactions: Vec<String>,
+    /// The total value received by the new seed, after fees
+    pub swept_value: Option<u64>,
+    /// The total fees paid by the sweep transactions
+    pub fees: Option<u64>,
+    /// The number of outputs spent by the sweep transactions
+    pub input_count: Option<usize>,
+    /// The hashes of detected outputs that were not swept because they are worth less than the fee to spend them
+    pub skipped: Vec<String>,
+    /// The supplied outputs that could not be decoded or scanned, and so were not swept
+    pub invalid: Vec<SweepOutputError>,
+    /// The index of the last key handed out on each branch of the new seed, to be stored by the caller (e.g. with
+    /// `PersistentKeyManager.update_key_index_if_higher`) so that the keys are not handed out again
+    pub key_indices: BTreeMap<String, u64>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+/// A supplied output that could not be decoded or scanned by a sweep
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepOutputError {
+    /// The index of the output in the supplied outputs
+    pub index: usize,
+    /// The reason the output could not be decoded or scanned
+    pub error: String,
+}
+
+fn sweep_error(error: &str) -> SweepResult {
+    SweepResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+struct SweepInput {
+    output: TransactionOutput,
+    recovered: RecoveredOutput,
+}
+
+struct SweepTransaction {
+    transaction: Transaction,
+    value: MicroMinotari,
+    fee: MicroMinotari,
+}
+
+/// The inputs and output of a sweep transaction, and the key ids needed to sign for them
+struct SweepTxos {
+    inputs: Vec<TransactionInput>,
+    output: TransactionOutput,
+    kernel_keys: Vec<(TariKeyId, TxoStage)>,
+    script_key_ids: Vec<TariKeyId>,
+    sender_offset_key_id: TariKeyId,
+}
+
+fn sweep_output_script_and_size() -> Result<(TariScript, usize), String> {
+    let script = script!(Nop);
+    let size = OutputFeatures::default()
+        .get_serialized_size()
+        .and_then(|features| Ok(features + script.get_serialized_size()?))
+        .and_then(|size| Ok(size + Covenant::default().get_serialized_size()?))
+        .map_err(|e| e.to_string())?;
+    Ok((script, size))
+}
+
+fn sweep_fee(fee_per_gram: u64, num_inputs: usize) -> Result<MicroMinotari, String> {
+    let weight = TransactionWeight::latest();
+    let (_, size) = sweep_output_script_and_size()?;
+    let grams = weight.calculate(1, num_inputs, 1, weight.round_up_features_and_scripts_size(size));
+    Ok(MicroMinotari::from(fee_per_gram.saturating_mul(grams)))
+}
+
+/// Imports the keys of a recovered output into the key manager and creates the input spending it with `input_data`,
+/// returning the input and the key ids of its spending and script keys
+pub(crate) async fn import_input(
+    key_manager: &MemoryDbKeyManager,
+    output: &TransactionOutput,
+    value: MicroMinotari,
+    spending_key: PrivateKey,
+    script_key: PrivateKey,
+    input_data: ExecutionStack,
+) -> Result<(TransactionInput, TariKeyId, TariKeyId), String> {
+    let spending_key_id = key_manager.import_key(spending_key).await.map_err(|e| e.to_string())?;
+    let script_key_id = key_manager.import_key(script_key).await.map_err(|e| e.to_string())?;
+    let wallet_output = WalletOutput::new_with_rangeproof(
+        output.version,
+        value,
+        spending_key_id.clone(),
+        output.features.clone(),
+        output.script.clone(),
+        input_data,
+        script_key_id.clone(),
+        output.sender_offset_public_key.clone(),
+        output.metadata_signature.clone(),
+        0,
+        output.covenant.clone(),
+        output.encrypted_data.clone(),
+        output.minimum_value_promise,
+        output.proof.clone(),
+    );
+    let transaction_input = wallet_output
+        .to_transaction_input(key_manager)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((transaction_input, spending_key_id, script_key_id))
+}
+
+/// Creates an output of `value` spendable by, and recoverable from, the new seed. No change output is ever created. A
+/// `RevealedValue` output carries no range proof and promises its exact value.
+async fn build_sweep_output(
+    key_manager: &MemoryDbKeyManager,
+    value: MicroMinotari,
+    range_proof_type: RangeProofType,
+) -> Result<(TransactionOutput, TariKeyId, TariKeyId), String> {
+    let (spending_key_id, _, script_key_id, script_public_key) = key_manager
+        .get_next_spend_and_script_key_ids()
+        .await
+        .map_err(|e| e.to_string())?;
+    let (sender_offset_key_id, _) = key_manager
+        .get_next_key(TransactionKeyManagerBranch::SenderOffset.get_branch_key())
+        .await
+        .map_err(|e| e.to_string())?;
+    let (script, _) = sweep_output_script_and_size()?;
+    let features = OutputFeatures {
+        range_proof_type,
+        ..Default::default()
+    };
+    let minimum_value_promise =
+        MinimumValuePromisePolicy::default_for(range_proof_type).choose(value, range_proof_type)?;
+    let wallet_output = WalletOutputBuilder::new(value, spending_key_id.clone())
+        .with_features(features)
+        .with_script(script)
+        .encrypt_data_for_recovery(key_manager, None)
+        .await
+        .map_err(|e| e.to_string())?
+        .with_input_data(inputs!(script_public_key))
+        .with_covenant(Covenant::default())
+        .with_minimum_value_promise(minimum_value_promise)
+        .with_script_key(script_key_id)
+        .sign_as_sender_and_receiver(key_manager, &sender_offset_key_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .try_build(key_manager)
+        .await
+        .map_err(|e| e.to_string())?;
+    let output = wallet_output
+        .to_transaction_output(key_manager)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((output, spending_key_id, sender_offset_key_id))
+}
+
+/// Signs the kernel of a transaction spending and creating the given txos, returning the kernel and the total offset
+pub(crate) async fn sign_kernel(
+    key_manager: &MemoryDbKeyManager,
+    kernel_keys: &[(TariKeyId, TxoStage)],
+    fee: MicroMinotari,
+) -> Result<(TransactionKernel, PrivateKey), String> {
+    let mut nonce_ids = Vec::with_capacity(kernel_keys.len());
+    let mut total_nonce = PublicKey::default();
+    let mut total_excess = PublicKey::default();
+    let mut total_offset = PrivateKey::default();
+    for (spend_key_id, stage) in kernel_keys {
+        let (nonce_id, nonce) = key_manager
+            .get_next_key(TransactionKeyManagerBranch::KernelNonce.get_branch_key())
+            .await
+            .map_err(|e| e.to_string())?;
+        let excess = key_manager
+            .get_txo_kernel_signature_excess_with_offset(spend_key_id, &nonce_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        let offset = key_manager
+            .get_txo_private_kernel_offset(spend_key_id, &nonce_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        match stage {
+            TxoStage::Input => {
+                total_excess = total_excess - excess;
+                total_offset = total_offset - offset;
+            },
+            TxoStage::Output => {
+                total_excess = total_excess + excess;
+                total_offset = total_offset + offset;
+            },
+        }
+        total_nonce = total_nonce + nonce;
+        nonce_ids.push(nonce_id);
+    }
+
+    let version = TransactionKernelVersion::get_current_version();
+    let features = KernelFeatures::empty();
+    let message = TransactionKernel::build_kernel_signature_message(&version, fee, 0, &features, &None);
+    let mut signature = Signature::default();
+    for ((spend_key_id, stage), nonce_id) in kernel_keys.iter().zip(&nonce_ids) {
+        let partial = key_manager
+            .get_partial_txo_kernel_signature(
+                spend_key_id,
+                nonce_id,
+                &total_nonce,
+                &total_excess,
+                &version,
+                &message,
+                &features,
+                *stage,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        signature = signature + partial;
+    }
+
+    let kernel = KernelBuilder::new()
+        .with_fee(fee)
+        .with_features(features)
+        .with_lock_height(0)
+        .with_excess(&Commitment::from_public_key(&total_excess))
+        .with_signature(signature)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok((kernel, total_offset))
+}
+
+async fn collect_txos(
+    key_manager: &MemoryDbKeyManager,
+    inputs: &[SweepInput],
+    value: MicroMinotari,
+    range_proof_type: RangeProofType,
+) -> Result<SweepTxos, String> {
+    let mut transaction_inputs = Vec::with_capacity(inputs.len());
+    let mut kernel_keys = Vec::with_capacity(inputs.len() + 1);
+    let mut script_key_ids = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let input_data = spending_input_data(&input.output.script, &input.recovered.script_key)
+            .map_err(|e| format!("input {}: {e}", input.output.hash().to_hex()))?;
+        let (transaction_input, spending_key_id, script_key_id) = import_input(
+            key_manager,
+            &input.output,
+            input.recovered.value,
+            input.recovered.spending_key.clone(),
+            input.recovered.script_key.clone(),
+            input_data,
+        )
+        .await?;
+        transaction_inputs.push(transaction_input);
+        kernel_keys.push((spending_key_id, TxoStage::Input));
+        script_key_ids.push(script_key_id);
+    }
+    let (output, spending_key_id, sender_offset_key_id) =
+        build_sweep_output(key_manager, value, range_proof_type).await?;
+    kernel_keys.push((spending_key_id, TxoStage::Output));
+    Ok(SweepTxos {
+        inputs: transaction_inputs,
+        output,
+        kernel_keys,
+        script_key_ids,
+        sender_offset_key_id,
+    })
+}
+
+/// The total value of the sweep inputs, or an error if it overflows
+fn sweep_inputs_total(inputs: &[SweepInput]) -> Result<MicroMinotari, String> {
+    inputs
+        .iter()
+        .try_fold(MicroMinotari::from(0), |total, input| {
+            total.checked_add(input.recovered.value)
+        })
+        .ok_or_else(|| "The value of the inputs overflows".to_string())
+}
+
+/// Builds a transaction spending all `inputs` to a single output of the new seed, or `None` if the inputs are worth
+/// less than the fee
+async fn build_sweep_transaction(
+    key_manager: &MemoryDbKeyManager,
+    inputs: &[SweepInput],
+    fee_per_gram: u64,
+    range_proof_type: RangeProofType,
+) -> Result<Option<SweepTransaction>, String> {
+    let total = sweep_inputs_total(inputs)?;
+    let fee = sweep_fee(fee_per_gram, inputs.len())?;
+    let value = match total.checked_sub(fee) {
+        Some(value) if value > MicroMinotari::from(0) => value,
+        _ => return Ok(None),
+    };
+
+    let txos = collect_txos(key_manager, inputs, value, range_proof_type).await?;
+    let (kernel, offset) = sign_kernel(key_manager, &txos.kernel_keys, fee).await?;
+    let script_offset = key_manager
+        .get_script_offset(&txos.script_key_ids, &[txos.sender_offset_key_id])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut builder = TransactionBuilder::new();
+    builder
+        .add_inputs(txos.inputs)
+        .add_output(txos.output)
+        .with_kernel(kernel)
+        .add_offset(offset)
+        .add_script_offset(script_offset);
+    let transaction = builder.build().map_err(|e| e.to_string())?;
+    Ok(Some(SweepTransaction {
+        transaction,
+        value,
+        fee,
+    }))
+}
+
+/// Creates a key manager for the new seed that continues from the given branch indices, rather than handing out the
+/// keys from index 0 again, and the database through which its indices can be read back
+fn sweep_key_manager(
+    new_seed: &str,
+    key_indices: Option<String>,
+) -> Result<
+    (
+        MemoryDbKeyManager,
+        KeyManagerDatabase<KeyManagerMemoryDatabase<PublicKey>, PublicKey>,
+    ),
+    String,
+> {
+    let seed = cipher_seed_from_hex(new_seed).map_err(|e| format!("new_seed: {e}"))?;
+    let key_indices = match key_indices {
+        Some(json) => serde_json::from_str::<BTreeMap<String, u64>>(&json).map_err(|e| format!("key_indices: {e}"))?,
+        None => BTreeMap::new(),
+    };
+    let db = KeyManagerDatabase::new(KeyManagerMemoryDatabase::new());
+    // The key manager starts each branch from the index found in its database
+    for (branch, index) in key_indices {
+        db.set_key_manager_state(KeyManagerState {
+            branch_seed: branch,
+            primary_key_index: index,
+        })
+        .map_err(|e| format!("key_indices: {e}"))?;
+    }
+    let key_manager = MemoryDbKeyManager::new(seed, db.clone(), crypto_factories(), WalletType::default())
+        .map_err(|e| format!("new_seed: {e}"))?;
+    Ok((key_manager, db))
+}
+
+/// The index of the last key handed out on each sweep branch
+fn sweep_key_indices(
+    db: &KeyManagerDatabase<KeyManagerMemoryDatabase<PublicKey>, PublicKey>,
+) -> Result<BTreeMap<String, u64>, String> {
+    let mut key_indices = BTreeMap::new();
+    for branch in SWEEP_BRANCHES {
+        let branch = branch.get_branch_key();
+        if let Some(state) = db.get_key_manager_state(&branch).map_err(|e| e.to_string())? {
+            key_indices.insert(branch, state.primary_key_index);
+        }
+    }
+    Ok(key_indices)
+}
+
+/// Decodes and scans the supplied outputs, returning the wallet's outputs that are worth sweeping. Outputs worth no
+/// more than `dust_limit` are added to the result's `skipped`, and outputs that cannot be decoded or scanned to its
+/// `invalid`, so that a single malformed output does not fail the whole sweep.
+fn sweep_inputs(
+    keys: &ScanKeys,
+    outputs: &[String],
+    dust_limit: MicroMinotari,
+    result: &mut SweepResult,
+) -> Vec<SweepInput> {
+    let mut inputs = Vec::new();
+    for (index, output) in outputs.iter().enumerate() {
+        let output = match decode_output_str(output, OutputEncoding::Auto) {
+            Ok(val) => val,
+            Err(e) => {
+                result.invalid.push(SweepOutputError {
+                    index,
+                    error: e.to_string(),
+                });
+                continue;
+            },
+        };
+        match recover_output(keys, &output) {
+            // Spending an output worth less than its own share of the fee would only reduce the swept value
+            Ok(Some(recovered)) if recovered.value <= dust_limit => result.skipped.push(output.hash().to_hex()),
+            Ok(Some(recovered)) => inputs.push(SweepInput { output, recovered }),
+            Ok(None) => {},
+            Err(e) => result.invalid.push(SweepOutputError {
+                index,
+                error: e.to_string(),
+            }),
+        }
+    }
+    inputs
+}
+
+async fn sweep(
+    keys: &ScanKeys,
+    outputs: Vec<String>,
+    key_manager: &MemoryDbKeyManager,
+    fee_per_gram: u64,
+    max_inputs: usize,
+    range_proof_type: RangeProofType,
+) -> Result<SweepResult, String> {
+    let input_weight = TransactionWeight::latest().params().input_weight;
+    let dust_limit = MicroMinotari::from(fee_per_gram.saturating_mul(input_weight));
+    let mut result = SweepResult::default();
+    let inputs = sweep_inputs(keys, &outputs, dust_limit, &mut result);
+
+    let (mut swept_value, mut fees, mut input_count) = (MicroMinotari::from(0), MicroMinotari::from(0), 0);
+    for chunk in inputs.chunks(max_inputs) {
+        match build_sweep_transaction(key_manager, chunk, fee_per_gram, range_proof_type).await? {
+            Some(swept) => {
+                result
+                    .transactions
+                    .push(serde_json::to_string(&swept.transaction).map_err(|e| e.to_string())?);
+                swept_value = swept_value
+                    .checked_add(swept.value)
+                    .ok_or_else(|| "The swept value overflows".to_string())?;
+                fees = fees
+                    .checked_add(swept.fee)
+                    .ok_or_else(|| "The fees overflow".to_string())?;
+                input_count += chunk.len();
+            },
+            None => result
+                .skipped
+                .extend(chunk.iter().map(|input| input.output.hash().to_hex())),
+        }
+    }
+    result.swept_value = Some(swept_value.as_u64());
+    result.fees = Some(fees.as_u64());
+    result.input_count = Some(input_count);
+    Ok(result)
+}
+
+/// Moves all funds detected for an old wallet to a new seed, e.g. when rotating keys. The supplied outputs are scanned
+/// with the old wallet's keys (as for `scan_output_for_one_sided_payment`), and every one-sided payment found is spent
+/// to a single output of the new seed per transaction, without change. Only outputs that are mature and unspent at the
+/// current tip should be supplied.
+/// * `new_seed` - the new wallet's cipher seed (hex value), from which the swept outputs can be recovered
+/// * `fee_per_gram` - the fee per gram paid by each sweep transaction
+/// * `max_inputs_per_transaction` - the maximum number of outputs spent by one transaction, 500 if not provided
+/// * `range_proof_type` - the range proof type of the swept outputs, `bullet_proof_plus` if not provided, or
+///   `revealed_value` for sweeps that do not need to hide the swept value
+/// * `key_indices` - a JSON object of the index of the last key handed out per branch of the new seed, e.g. as returned
+///   by `PersistentKeyManager.key_index`, from which the sweep continues; keys are handed out from index 0 if not
+///   provided, so this must be supplied for a seed that has been used before. The indices after the sweep are returned
+///   in `key_indices` of the result.
+#[wasm_bindgen]
+pub async fn sweep_to_new_seed(
+    known_script_keys: Vec<String>,
+    wallet_sk: String,
+    outputs: Vec<String>,
+    new_seed: String,
+    fee_per_gram: u64,
+    max_inputs_per_transaction: Option<usize>,
+    range_proof_type: Option<String>,
+    key_indices: Option<String>,
+) -> SweepResult {
+    let keys = match ScanKeys::new(known_script_keys, wallet_sk) {
+        Ok(val) => val,
+        Err(e) => return sweep_error(&e.to_string()),
+    };
+    let (key_manager, db) = match sweep_key_manager(&new_seed, key_indices) {
+        Ok(val) => val,
+        Err(e) => return sweep_error(&e),
+    };
+    let max_inputs = max_inputs_per_transaction.unwrap_or(DEFAULT_MAX_SWEEP_INPUTS).max(1);
+    let range_proof_type = match range_proof_type.as_deref().map(parse_range_proof_type).transpose() {
+        Ok(val) => val.unwrap_or_default(),
+        Err(e) => return sweep_error(&e),
+    };
+
+    let mut result = match sweep(&keys, outputs, &key_manager, fee_per_gram, max_inputs, range_proof_type).await {
+        Ok(result) => result,
+        Err(e) => return sweep_error(&format!("Could not sweep outputs: {e}")),
+    };
+    match sweep_key_indices(&db) {
+        Ok(key_indices) => result.key_indices = key_indices,
+        Err(e) => return sweep_error(&format!("Could not read the key indices: {e}")),
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use tari_common_types::types::ComAndPubSignature;
+    use tari_comms::types::CommsDHKE;
+    use tari_core::{
+        one_sided::shared_secret_to_output_encryption_key,
+        transactions::transaction_components::EncryptedData,
+    };
+    use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::PublicKey as PublicKeyTrait};
+
+    use super::*;
+
+    const FEE_PER_GRAM: u64 = 5;
+
+    fn known_script_key() -> PrivateKey {
+        PrivateKey::from(3)
+    }
+
+    fn scan_keys() -> ScanKeys {
+        ScanKeys::new(vec![known_script_key().to_hex()], PrivateKey::from(5).to_hex()).unwrap()
+    }
+
+    fn dust_limit() -> MicroMinotari {
+        MicroMinotari::from(FEE_PER_GRAM * TransactionWeight::latest().params().input_weight)
+    }
+
+    // A one-sided payment of `value` to the known script key, as a sender would create it
+    fn one_sided_output(value: u64) -> TransactionOutput {
+        let sender_offset_sk = PrivateKey::from(11);
+        let known_script_pk = PublicKey::from_secret_key(&known_script_key());
+        let shared_secret = CommsDHKE::new(&sender_offset_sk, &known_script_pk);
+        let encryption_key = shared_secret_to_output_encryption_key(&shared_secret).unwrap();
+        let mask = PrivateKey::from(13);
+        let commitment = crypto_factories().commitment.commit_value(&mask, value);
+        let encrypted_data =
+            EncryptedData::encrypt_data(&encryption_key, &commitment, MicroMinotari::from(value), &mask).unwrap();
+        TransactionOutput::new_current_version(
+            OutputFeatures::default(),
+            commitment,
+            None,
+            script!(PushPubKey(Box::new(known_script_pk))),
+            PublicKey::from_secret_key(&sender_offset_sk),
+            ComAndPubSignature::default(),
+            Covenant::default(),
+            encrypted_data,
+            MicroMinotari::zero(),
+        )
+    }
+
+    fn output_hex(output: &TransactionOutput) -> String {
+        borsh::to_vec(output).unwrap().to_hex()
+    }
+
+    #[test]
+    fn malformed_outputs_are_reported_and_skipped() {
+        let output = one_sided_output(1_000_000);
+        let outputs = vec!["not an output".to_string(), output_hex(&output), "00ff".to_string()];
+        let mut result = SweepResult::default();
+        let inputs = sweep_inputs(&scan_keys(), &outputs, dust_limit(), &mut result);
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].output, output);
+        assert_eq!(inputs[0].recovered.value, MicroMinotari::from(1_000_000));
+        assert_eq!(
+            result.invalid.iter().map(|invalid| invalid.index).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert!(result.skipped.is_empty());
+    }
+
+    #[test]
+    fn dust_outputs_are_skipped() {
+        let dust = one_sided_output(dust_limit().as_u64());
+        let mut result = SweepResult::default();
+        let inputs = sweep_inputs(&scan_keys(), &[output_hex(&dust)], dust_limit(), &mut result);
+        assert!(inputs.is_empty());
+        assert_eq!(result.skipped, vec![dust.hash().to_hex()]);
+        assert!(result.invalid.is_empty());
+    }
+
+    #[test]
+    fn outputs_of_another_wallet_are_ignored() {
+        let output = one_sided_output(1_000_000);
+        let keys = ScanKeys::new(vec![PrivateKey::from(17).to_hex()], PrivateKey::from(5).to_hex()).unwrap();
+        let mut result = SweepResult::default();
+        let inputs = sweep_inputs(&keys, &[output_hex(&output)], dust_limit(), &mut result);
+        assert!(inputs.is_empty());
+        assert!(result.skipped.is_empty());
+        assert!(result.invalid.is_empty());
+    }
+
+    #[test]
+    fn input_total_overflow_is_an_error() {
+        let input = |value: u64| SweepInput {
+            output: one_sided_output(value),
+            recovered: RecoveredOutput {
+                output_source: "OneSided".to_string(),
+                value: MicroMinotari::from(value),
+                spending_key: PrivateKey::from(13),
+                script_key: known_script_key(),
+            },
+        };
+        let inputs = vec![input(u64::MAX), input(1)];
+        assert_eq!(sweep_inputs_total(&inputs[..1]), Ok(MicroMinotari::from(u64::MAX)));
+        assert_eq!(
+            sweep_inputs_total(&inputs),
+            Err("The value of the inputs overflows".to_string())
+        );
+    }
+
+    #[test]
+    fn sweep_fee_grows_with_the_inputs() {
+        let one = sweep_fee(FEE_PER_GRAM, 1).unwrap();
+        let two = sweep_fee(FEE_PER_GRAM, 2).unwrap();
+        assert!(one > MicroMinotari::from(0));
+        assert!(two > one);
+        assert_eq!(sweep_fee(0, 2), Ok(MicroMinotari::from(0)));
+    }
+}
@@ -0,0 +1,943 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! A chain sync state machine for browser light wallets. The state follows the chain block by block from the wallet's
+//! birthday height, scans each block's outputs for the wallet's and marks the recovered outputs its inputs spend, so
+//! that the recovered UTXO set stays current. The state can be serialized to a JS storage callback, e.g. one that
+//! writes to IndexedDB, and restored from it in a later session. The spending and script keys of the recovered
+//! outputs are encrypted with a key derived from the wallet secret key in the serialized state.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+};
+
+use blake2::Blake2b;
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, Payload},
+    Key,
+    KeyInit,
+    XChaCha20Poly1305,
+    XNonce,
+};
+use digest::consts::U32;
+use js_sys::{Function, Uint8Array};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use tari_common::configuration::Network;
+use tari_common_types::types::FixedHash;
+use tari_core::{blocks::BlockHeader, transactions::aggregated_body::AggregateBody};
+use tari_crypto::{
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    tari_utilities::{
+        hex::{from_hex, Hex},
+        ByteArray,
+    },
+};
+use tari_mmr::{Hash, MerkleMountainRange};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use zeroize::Zeroizing;
+
+use crate::{
+    compression::{compress_entry, decompress_entry, EntryContent},
+    custody_log::{CustodyEventKind, CustodyLog, CustodyLogExport},
+    deposit_tagging::DepositRules,
+    headers::header_from_borsh,
+    key_manager_storage::call_storage,
+    network::{on_network, parse_network},
+    scan_block::{block_body_from_borsh, scan_aggregate_body, BlockScannedOutput},
+    scan_outputs::ScanKeys,
+    session_guard::SessionGuard,
+    smt_proofs::verify_output_proof,
+    RecoveredOutputResult,
+};
+
+// The hash domain of the per-block input MMR, as used by base nodes
+hash_domain!(InputMmrHashDomain, "com.tari.base_layer.core.input_mmr", 1);
+hash_domain!(SyncStateHashDomain, "com.tari.transaction_services.sync_state", 1);
+
+type InputMmrHasherBlake256 = DomainSeparatedHasher<Blake2b<U32>, InputMmrHashDomain>;
+type SyncStateHasher = DomainSeparatedHasher<Blake2b<U32>, SyncStateHashDomain>;
+
+/// The version of the serialized sync state
+const SYNC_STATE_VERSION: u8 = 2;
+const SIZE_NONCE: usize = 24;
+/// The number of most recent block hashes kept, and so the deepest reorg the state can rewind with linkage checks
+const MAX_REORG_DEPTH: u64 = 1_000;
+
+/// A recovered output, with the height of the block it was mined in
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedOutput {
+    /// The height of the block the output was mined in
+    pub mined_height: u64,
+    /// The height of the block the output was spent in, if spent
+    pub spent_height: Option<u64>,
+    /// The recovered output
+    pub output: RecoveredOutputResult,
+    /// The spending and script keys of the output, encrypted with the wallet key (hex value); only set in the
+    /// serialized state, where the keys are removed from `output`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_keys: Option<String>,
+}
+
+/// The spending and script keys of a recovered output, as they are encrypted in the serialized state
+#[derive(Serialize, Deserialize)]
+struct OutputKeys {
+    spending_key: Option<String>,
+    script_key: Option<String>,
+}
+
+/// The part of a `WalletSyncState` that is persisted. The wallet keys are not part of it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncState {
+    version: u8,
+    birthday_height: u64,
+    last_scanned_height: Option<u64>,
+    // The hashes of the most recently accepted blocks, by height (hex values)
+    block_hashes: BTreeMap<u64, String>,
+    // The recovered outputs that are not spent, by output hash (hex value)
+    unspent: BTreeMap<String, SyncedOutput>,
+    // The recovered outputs that are spent, by output hash (hex value)
+    spent: BTreeMap<String, SyncedOutput>,
+    // The custody log, only set in the serialized state
+    #[serde(default)]
+    custody: CustodyLogExport,
+}
+
+/// A struct to hold the result of accepting a block
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SyncBlockResult {
+    /// The height of the accepted block
+    pub height: Option<u64>,
+    /// The outputs of the block found to belong to the wallet, and the outputs that could not be scanned
+    pub outputs: Vec<BlockScannedOutput>,
+    /// The hashes of the recovered outputs the block's inputs spend (hex values)
+    pub spent: Vec<String>,
+    /// An error message in case the block was not accepted
+    pub error: Option<String>,
+}
+
+fn sync_block_error(error: &str) -> SyncBlockResult {
+    SyncBlockResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// The sync state of a light wallet: its birthday height, the last height scanned, and its recovered and spent
+/// outputs. Blocks are accepted in ascending height order, each linking to the one before it, starting at the birthday
+/// height.
+#[wasm_bindgen]
+pub struct WalletSyncState {
+    keys: ScanKeys,
+    rules: DepositRules,
+    network: Option<Network>,
+    storage: Option<Function>,
+    state: RefCell<SyncState>,
+    custody: RefCell<CustodyLog>,
+    guard: SessionGuard,
+}
+
+impl SyncState {
+    /// The height of the next block to accept
+    fn next_height(&self) -> u64 {
+        self.last_scanned_height
+            .map_or(self.birthday_height, |height| height + 1)
+    }
+
+    /// Checks that a header is the next block the state expects
+    fn check_next_header(&self, header: &BlockHeader) -> Result<(), String> {
+        let next_height = self.next_height();
+        if header.height != next_height {
+            return Err(format!(
+                "Expected the block at height {next_height}, got height {}",
+                header.height
+            ));
+        }
+        let prev_hash = header.prev_hash.to_hex();
+        match self.tip_hash() {
+            Some(tip_hash) if *tip_hash != prev_hash => Err(format!(
+                "Block {} does not link to the block hash {tip_hash} at height {}, rewind past the reorg first",
+                header.height,
+                next_height - 1
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn tip_hash(&self) -> Option<&String> {
+        self.last_scanned_height
+            .and_then(|height| self.block_hashes.get(&height))
+    }
+
+    /// Records a block as the new tip, forgetting the hashes of blocks deeper than `MAX_REORG_DEPTH`
+    fn advance_tip(&mut self, header: &BlockHeader) {
+        self.last_scanned_height = Some(header.height);
+        self.block_hashes.insert(header.height, header.hash().to_hex());
+        let oldest = header.height.saturating_sub(MAX_REORG_DEPTH);
+        self.block_hashes = self.block_hashes.split_off(&oldest);
+    }
+}
+
+impl WalletSyncState {
+    /// Records a mutation of a recovered output (hash as hex value) in the custody log
+    fn record_custody(&self, kind: CustodyEventKind, hash: &str, output: &SyncedOutput, range: (u64, u64)) {
+        if let (Ok(hash), Some(value)) = (FixedHash::from_hex(hash), output.output.value) {
+            self.custody.borrow_mut().record(kind, &hash, value, Some(range));
+        }
+    }
+
+    /// Accepts the next block, on the state's network
+    fn accept_block_on_network(
+        &self,
+        header: &[u8],
+        block_bytes: &[u8],
+        output_proofs: Option<&str>,
+    ) -> Result<SyncBlockResult, String> {
+        let mut state = self.state.borrow_mut();
+        let header = header_from_borsh(header).map_err(|e| format!("header: {e}"))?;
+        state.check_next_header(&header)?;
+        let body = block_body_from_borsh(block_bytes).map_err(|e| format!("block_bytes: {e}"))?;
+        if block_input_mr(&body)? != header.input_mr {
+            return Err(format!(
+                "block_bytes: The inputs of block {} do not match the header's input_mr",
+                header.height
+            ));
+        }
+        let output_proofs = output_proofs
+            .map(serde_json::from_str::<HashMap<String, String>>)
+            .transpose()
+            .map_err(|e| format!("output_proofs: {e}"))?;
+
+        let scanned = scan_aggregate_body(&self.keys, &self.rules, &body, header.height);
+        if let Some(output_proofs) = &output_proofs {
+            let recovered = scanned
+                .outputs
+                .iter()
+                .filter_map(|scanned_output| scanned_output.result.hash.as_ref().map(|hash| (scanned_output, hash)));
+            for (scanned_output, hash) in recovered {
+                let proof = output_proofs
+                    .get(hash)
+                    .ok_or_else(|| format!("output_proofs: No inclusion proof for the recovered output {hash}"))
+                    .and_then(|proof| from_hex(proof).map_err(|e| format!("output_proofs: {hash}: {e}")))?;
+                let output = &body.outputs()[scanned_output.output_index as usize];
+                verify_output_proof(header.output_mr.as_slice(), &proof, output, header.height)
+                    .map_err(|e| format!("output_proofs: {hash}: {e}"))?;
+            }
+        }
+        for scanned_output in &scanned.outputs {
+            if let Some(hash) = &scanned_output.result.hash {
+                let output = SyncedOutput {
+                    mined_height: header.height,
+                    spent_height: None,
+                    output: scanned_output.result.clone(),
+                    encrypted_keys: None,
+                };
+                let range = (header.height, header.height);
+                self.record_custody(CustodyEventKind::OutputRecovered, hash, &output, range);
+                state.unspent.insert(hash.clone(), output);
+            }
+        }
+        let mut spent = Vec::new();
+        for input in body.inputs() {
+            let hash = input.output_hash().to_hex();
+            if let Some(mut output) = state.unspent.remove(&hash) {
+                output.spent_height = Some(header.height);
+                let range = (header.height, header.height);
+                self.record_custody(CustodyEventKind::OutputSpent, &hash, &output, range);
+                state.spent.insert(hash.clone(), output);
+                spent.push(hash);
+            }
+        }
+        state.advance_tip(&header);
+        Ok(SyncBlockResult {
+            height: Some(header.height),
+            outputs: scanned.outputs,
+            spent,
+            error: None,
+        })
+    }
+
+    /// The key the spending and script keys of the recovered outputs are encrypted with in the serialized state
+    fn state_key(&self) -> Key {
+        let (wallet_sk, _) = self.keys.wallet_keys();
+        let hash = SyncStateHasher::new_with_label("aead_key")
+            .chain(wallet_sk.as_bytes())
+            .finalize();
+        *Key::from_slice(hash.as_ref())
+    }
+
+    /// The state with the spending and script keys of every recovered output encrypted
+    fn encrypted_state(&self) -> Result<SyncState, String> {
+        let cipher = XChaCha20Poly1305::new(&self.state_key());
+        let mut state = self.state.borrow().clone();
+        state.custody = self.custody.borrow().export();
+        for (hash, synced) in state.unspent.iter_mut().chain(state.spent.iter_mut()) {
+            let keys = Zeroizing::new(
+                serde_json::to_vec(&OutputKeys {
+                    spending_key: synced.output.spending_key.take(),
+                    script_key: synced.output.script_key.take(),
+                })
+                .map_err(|e| e.to_string())?,
+            );
+            let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+            let ciphertext = cipher
+                .encrypt(&nonce, Payload {
+                    msg: &keys,
+                    aad: hash.as_bytes(),
+                })
+                .map_err(|e| format!("Could not encrypt the keys of {hash}: {e}"))?;
+            let mut encrypted = nonce.to_vec();
+            encrypted.extend_from_slice(&ciphertext);
+            synced.encrypted_keys = Some(encrypted.to_hex());
+        }
+        Ok(state)
+    }
+
+    /// Decrypts the spending and script keys of every recovered output of a restored state
+    fn decrypt_state(&self) -> Result<(), String> {
+        let cipher = XChaCha20Poly1305::new(&self.state_key());
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        for (hash, synced) in state.unspent.iter_mut().chain(state.spent.iter_mut()) {
+            let encrypted = match synced.encrypted_keys.take() {
+                Some(val) => val,
+                None => continue,
+            };
+            let encrypted = from_hex(&encrypted).map_err(|e| format!("The keys of {hash}: {e}"))?;
+            if encrypted.len() < SIZE_NONCE {
+                return Err(format!("The keys of {hash} are too short"));
+            }
+            let (nonce, ciphertext) = encrypted.split_at(SIZE_NONCE);
+            let keys = Zeroizing::new(
+                cipher
+                    .decrypt(XNonce::from_slice(nonce), Payload {
+                        msg: ciphertext,
+                        aad: hash.as_bytes(),
+                    })
+                    .map_err(|_| format!("Could not decrypt the keys of {hash}, the wallet key does not match"))?,
+            );
+            let keys: OutputKeys = serde_json::from_slice(&keys).map_err(|e| format!("The keys of {hash}: {e}"))?;
+            synced.output.spending_key = keys.spending_key;
+            synced.output.script_key = keys.script_key;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a serialized sync state, either a compressed entry as written by `persist` or the JSON of `to_json`
+fn decode_state(state: &[u8]) -> Result<SyncState, String> {
+    let json = match decompress_entry(state)? {
+        Some((EntryContent::Text, json)) => json,
+        Some((EntryContent::Bytes, _)) => return Err("The state entry is not JSON".to_string()),
+        None => state.to_vec(),
+    };
+    let state: SyncState = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+    if state.version != SYNC_STATE_VERSION {
+        return Err(format!(
+            "Unsupported version {}, expected {SYNC_STATE_VERSION}",
+            state.version
+        ));
+    }
+    Ok(state)
+}
+
+/// Computes the root of the input MMR of a block body, which a base node commits to as the header's `input_mr`
+fn block_input_mr(body: &AggregateBody) -> Result<FixedHash, String> {
+    let mut input_mmr = MerkleMountainRange::<InputMmrHasherBlake256, Vec<Hash>>::new(Vec::new());
+    for input in body.inputs() {
+        input_mmr
+            .push(input.canonical_hash().to_vec())
+            .map_err(|e| format!("Could not compute the input_mr: {e}"))?;
+    }
+    let root = input_mmr
+        .get_merkle_root()
+        .map_err(|e| format!("Could not compute the input_mr: {e}"))?;
+    FixedHash::try_from(root).map_err(|e| format!("Could not compute the input_mr: {e}"))
+}
+
+#[wasm_bindgen]
+impl WalletSyncState {
+    /// Creates the sync state of a wallet that scans from its birthday height, with the keys of
+    /// `scan_output_for_one_sided_payment`
+    /// * `deposit_rules` - a JSON array of `DepositRule`s to tag recovered outputs with, if provided
+    /// * `network` - the network the scanned outputs are hashed for, or the library's network if not provided
+    /// * `storage` - a callback that is called with the serialized state by `persist`, e.g. to write it to IndexedDB
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        known_script_keys: Vec<String>,
+        wallet_sk: String,
+        birthday_height: u64,
+        deposit_rules: Option<String>,
+        network: Option<String>,
+        storage: Option<Function>,
+    ) -> Result<WalletSyncState, JsValue> {
+        let keys = ScanKeys::new(known_script_keys, wallet_sk).map_err(JsValue::from)?;
+        let rules = DepositRules::from_optional_json(deposit_rules.as_deref()).map_err(JsValue::from)?;
+        let network = parse_network(network.as_deref()).map_err(|e| JsValue::from_str(&e))?;
+        let custody = CustodyLog::new(keys.wallet_keys().0);
+        Ok(Self {
+            keys,
+            rules,
+            network,
+            storage,
+            state: RefCell::new(SyncState {
+                version: SYNC_STATE_VERSION,
+                birthday_height,
+                ..Default::default()
+            }),
+            custody: RefCell::new(custody),
+            guard: SessionGuard::new(),
+        })
+    }
+
+    /// Restores a sync state serialized by `to_json`, with the same parameters as the constructor
+    pub fn restore(
+        state: &str,
+        known_script_keys: Vec<String>,
+        wallet_sk: String,
+        deposit_rules: Option<String>,
+        network: Option<String>,
+        storage: Option<Function>,
+    ) -> Result<WalletSyncState, JsValue> {
+        Self::restore_bytes(
+            state.as_bytes(),
+            known_script_keys,
+            wallet_sk,
+            deposit_rules,
+            network,
+            storage,
+        )
+    }
+
+    /// Restores a sync state serialized by `to_bytes` or passed to the storage callback, with the same parameters as
+    /// the constructor. The JSON of `to_json`, as passed to the storage callback before the state was compressed,
+    /// is read as well.
+    pub fn restore_bytes(
+        state: &[u8],
+        known_script_keys: Vec<String>,
+        wallet_sk: String,
+        deposit_rules: Option<String>,
+        network: Option<String>,
+        storage: Option<Function>,
+    ) -> Result<WalletSyncState, JsValue> {
+        let mut state = decode_state(state).map_err(|e| JsValue::from_str(&format!("state: {e}")))?;
+        let sync = Self::new(
+            known_script_keys,
+            wallet_sk,
+            state.birthday_height,
+            deposit_rules,
+            network,
+            storage,
+        )?;
+        let custody = CustodyLog::restore(sync.keys.wallet_keys().0, std::mem::take(&mut state.custody))
+            .map_err(|e| JsValue::from_str(&format!("state: custody log: {e}")))?;
+        *sync.custody.borrow_mut() = custody;
+        *sync.state.borrow_mut() = state;
+        sync.decrypt_state()
+            .map_err(|e| JsValue::from_str(&format!("state: {e}")))?;
+        Ok(sync)
+    }
+
+    /// Accepts the next block, scanning its outputs for the wallet's and marking the recovered outputs its inputs
+    /// spend. The body's inputs are checked against the header's `input_mr`. The header's `output_mr` is the root of
+    /// the whole UTXO set, which a single body cannot be checked against, so the recovered outputs are only checked
+    /// against it if `output_proofs` is provided. The proof of work is not validated, so blocks must come from a
+    /// trusted source. Fails with a `Busy` error while `persist` is in progress.
+    /// * `header` - the Borsh bytes of the block header, whose height must be `next_height()` and which must link to
+    ///   the last accepted block
+    /// * `block_bytes` - the Borsh bytes of the block's aggregate body
+    /// * `output_proofs` - a JSON object of Borsh encoded SMT membership proofs (hex values) by output hash, as served
+    ///   by the base node; if provided, every recovered output must have a proof that verifies against `output_mr`
+    pub fn accept_block(&self, header: &[u8], block_bytes: &[u8], output_proofs: Option<String>) -> SyncBlockResult {
+        let _permit = match self.guard.try_acquire("accept_block") {
+            Ok(val) => val,
+            Err(e) => return sync_block_error(&e.to_string()),
+        };
+        let network = self.network;
+        on_network(network, || {
+            self.accept_block_on_network(header, block_bytes, output_proofs.as_deref())
+        })
+        .unwrap_or_else(|e| sync_block_error(&e))
+    }
+
+    /// Accepts the header of the next block without scanning its body, for blocks known to contain neither outputs nor
+    /// inputs of the wallet, e.g. those left out by a filtering indexer. Fails with a `Busy` error while `persist` is
+    /// in progress.
+    pub fn accept_header(&self, header: &[u8]) -> SyncBlockResult {
+        let _permit = match self.guard.try_acquire("accept_header") {
+            Ok(val) => val,
+            Err(e) => return sync_block_error(&e.to_string()),
+        };
+        let network = self.network;
+        on_network(network, || -> Result<SyncBlockResult, String> {
+            let header = header_from_borsh(header).map_err(|e| format!("header: {e}"))?;
+            let mut state = self.state.borrow_mut();
+            state.check_next_header(&header)?;
+            state.advance_tip(&header);
+            Ok(SyncBlockResult {
+                height: Some(header.height),
+                ..Default::default()
+            })
+        })
+        .unwrap_or_else(|e| sync_block_error(&e))
+    }
+
+    /// Rewinds the state to `height` after a reorg, forgetting the outputs mined and the spends seen above it. The next
+    /// block accepted must link to the block at `height` if it is one of the last 1,000 blocks accepted. Every
+    /// forgotten spend and output is recorded in the custody log. Fails with a `Busy` error while `persist` is in
+    /// progress.
+    pub fn rewind_to_height(&self, height: u64) -> Result<(), JsValue> {
+        let _permit = self.guard.try_acquire("rewind_to_height")?;
+        let mut state = self.state.borrow_mut();
+        let last_scanned_height = match state.last_scanned_height {
+            Some(last) if height < last => last,
+            _ => return Ok(()),
+        };
+        let range = (height + 1, last_scanned_height);
+        let rewound_spends = state
+            .spent
+            .iter()
+            .filter(|(_, output)| output.spent_height.map_or(false, |spent_height| spent_height > height))
+            .map(|(hash, output)| (hash.clone(), output.clone()))
+            .collect::<Vec<_>>();
+        for (hash, output) in &rewound_spends {
+            self.record_custody(CustodyEventKind::SpendRewound, hash, output, range);
+        }
+        let rewound_outputs = state
+            .unspent
+            .iter()
+            .chain(state.spent.iter())
+            .filter(|(_, output)| output.mined_height > height)
+            .map(|(hash, output)| (hash.clone(), output.clone()))
+            .collect::<Vec<_>>();
+        for (hash, output) in &rewound_outputs {
+            self.record_custody(CustodyEventKind::RecoveryRewound, hash, output, range);
+        }
+
+        let birthday_height = state.birthday_height;
+        state.last_scanned_height = Some(height).filter(|height| *height >= birthday_height);
+        state.block_hashes.retain(|block_height, _| *block_height <= height);
+        state.unspent.retain(|_, output| output.mined_height <= height);
+        state.spent.retain(|_, output| output.mined_height <= height);
+        let unspent = state
+            .spent
+            .iter()
+            .filter(|(_, output)| output.spent_height.map_or(false, |spent_height| spent_height > height))
+            .map(|(hash, _)| hash.clone())
+            .collect::<Vec<_>>();
+        for hash in unspent {
+            if let Some(mut output) = state.spent.remove(&hash) {
+                output.spent_height = None;
+                state.unspent.insert(hash, output);
+            }
+        }
+        Ok(())
+    }
+
+    /// The height the wallet scans from
+    pub fn birthday_height(&self) -> u64 {
+        self.state.borrow().birthday_height
+    }
+
+    /// The height of the last block accepted, if any
+    pub fn last_scanned_height(&self) -> Option<u64> {
+        self.state.borrow().last_scanned_height
+    }
+
+    /// The height of the next block to accept
+    pub fn next_height(&self) -> u64 {
+        self.state.borrow().next_height()
+    }
+
+    /// The hash of the last block accepted (hex value), if known
+    pub fn tip_block_hash(&self) -> Option<String> {
+        self.state.borrow().tip_hash().cloned()
+    }
+
+    /// The recovered outputs that are not spent, as an array of `SyncedOutput`s in output hash order
+    pub fn unspent_outputs(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(
+            &self.state.borrow().unspent.values().collect::<Vec<_>>(),
+        )?)
+    }
+
+    /// The hashes of the recovered outputs that are spent (hex values)
+    pub fn spent_output_hashes(&self) -> Vec<String> {
+        self.state.borrow().spent.keys().cloned().collect()
+    }
+
+    /// The custody log of the state, as JSON: a hash-chained `CustodyLogExport` of every output recovered, spent or
+    /// forgotten by a rewind and the balance after it, with the blocks each mutation was made for. The log can be
+    /// checked with `verify_custody_log` and `custody_audit_key`.
+    pub fn custody_log(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.custody.borrow().export()).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// The key the entries of the custody log are keyed with (hex value), as per `ScanSession.custody_audit_key`
+    pub fn custody_audit_key(&self) -> String {
+        self.custody.borrow().audit_key()
+    }
+
+    /// Serializes the state, without the wallet keys and with the keys of the recovered outputs encrypted, as JSON
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        let state = self.encrypted_state().map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string(&state).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Serializes the state as per `to_json`, compressed in the versioned entry format of the storage callbacks
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsValue> {
+        Ok(compress_entry(EntryContent::Text, self.to_json()?.as_bytes()))
+    }
+
+    /// Calls the storage callback with the serialized state of `to_bytes` as a `Uint8Array`, resolving to what the
+    /// callback returns, once it resolves if it returns a promise, e.g. once the state is written. Calls to
+    /// `persist` are queued, so that the states are written in the order they were persisted, and blocks cannot be
+    /// accepted nor the state rewound until they resolve.
+    pub async fn persist(&self) -> Result<JsValue, JsValue> {
+        let _permit = self.guard.acquire("persist").await;
+        let storage = self
+            .storage
+            .as_ref()
+            .ok_or_else(|| JsValue::from_str("No storage callback was provided"))?;
+        call_storage(storage, &[&Uint8Array::from(self.to_bytes()?.as_slice()).into()]).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_common_types::types::{ComAndPubSignature, PrivateKey, PublicKey};
+    use tari_comms::types::CommsDHKE;
+    use tari_core::{
+        covenants::Covenant,
+        one_sided::shared_secret_to_output_encryption_key,
+        proof_of_work::ProofOfWork,
+        transactions::{
+            tari_amount::MicroMinotari,
+            transaction_components::{EncryptedData, OutputFeatures, TransactionInput, TransactionOutput},
+        },
+    };
+    use tari_crypto::{
+        commitment::HomomorphicCommitmentFactory,
+        keys::PublicKey as PublicKeyTrait,
+        tari_utilities::epoch_time::EpochTime,
+    };
+    use tari_script::{script, ExecutionStack};
+
+    use super::*;
+    use crate::factories::crypto_factories;
+
+    fn wallet_sync_state(birthday_height: u64) -> WalletSyncState {
+        let known_script_key = PrivateKey::from(3).to_hex();
+        let wallet_sk = PrivateKey::from(5).to_hex();
+        WalletSyncState::new(vec![known_script_key], wallet_sk, birthday_height, None, None, None).unwrap()
+    }
+
+    // A one-sided payment of `value` to the wallet's known script key, with a distinct commitment per `value`
+    fn one_sided_output(value: u64) -> TransactionOutput {
+        let sender_offset_sk = PrivateKey::from(11);
+        let known_script_pk = PublicKey::from_secret_key(&PrivateKey::from(3));
+        let shared_secret = CommsDHKE::new(&sender_offset_sk, &known_script_pk);
+        let encryption_key = shared_secret_to_output_encryption_key(&shared_secret).unwrap();
+        let mask = PrivateKey::from(value);
+        let commitment = crypto_factories().commitment.commit_value(&mask, value);
+        let encrypted_data =
+            EncryptedData::encrypt_data(&encryption_key, &commitment, MicroMinotari::from(value), &mask).unwrap();
+        TransactionOutput::new_current_version(
+            OutputFeatures::default(),
+            commitment,
+            None,
+            script!(PushPubKey(Box::new(known_script_pk))),
+            PublicKey::from_secret_key(&sender_offset_sk),
+            ComAndPubSignature::default(),
+            Covenant::default(),
+            encrypted_data,
+            MicroMinotari::zero(),
+        )
+    }
+
+    fn spend(output: &TransactionOutput) -> TransactionInput {
+        TransactionInput::new_with_output_hash(output.hash(), ExecutionStack::default(), ComAndPubSignature::default())
+    }
+
+    // A block at `height` on top of `prev`, with a header that commits to the body's inputs; `nonce` tells forks apart
+    fn block(
+        height: u64,
+        prev: Option<&BlockHeader>,
+        nonce: u64,
+        inputs: Vec<TransactionInput>,
+        outputs: Vec<TransactionOutput>,
+    ) -> (BlockHeader, AggregateBody) {
+        let body = AggregateBody::new(inputs, outputs, Vec::new());
+        let header = BlockHeader {
+            version: 1,
+            height,
+            prev_hash: prev.map_or_else(FixedHash::zero, |prev| prev.hash()),
+            timestamp: EpochTime::from(height),
+            output_mr: FixedHash::zero(),
+            output_smt_size: 0,
+            kernel_mr: FixedHash::zero(),
+            kernel_mmr_size: 0,
+            input_mr: block_input_mr(&body).unwrap(),
+            total_kernel_offset: PrivateKey::default(),
+            total_script_offset: PrivateKey::default(),
+            nonce,
+            pow: ProofOfWork::default(),
+            validator_node_mr: FixedHash::zero(),
+            validator_node_size: 0,
+        };
+        (header, body)
+    }
+
+    fn accept(sync: &WalletSyncState, (header, body): &(BlockHeader, AggregateBody)) -> SyncBlockResult {
+        sync.accept_block(&borsh::to_vec(header).unwrap(), &borsh::to_vec(body).unwrap(), None)
+    }
+
+    fn unspent_hashes(sync: &WalletSyncState) -> Vec<String> {
+        sync.state.borrow().unspent.keys().cloned().collect()
+    }
+
+    fn spent_heights(sync: &WalletSyncState) -> Vec<(String, Option<u64>)> {
+        sync.state
+            .borrow()
+            .spent
+            .iter()
+            .map(|(hash, output)| (hash.clone(), output.spent_height))
+            .collect()
+    }
+
+    #[test]
+    fn blocks_are_rejected_while_a_persist_is_pending() {
+        let sync = wallet_sync_state(0);
+        let persist = sync.guard.try_acquire("persist").unwrap();
+        let result = sync.accept_block(&[], &[], None);
+        let error = result.error.unwrap();
+        assert!(error.starts_with("Busy"), "{error}");
+        assert!(error.contains("persist"), "{error}");
+        let result = sync.accept_header(&[]);
+        assert!(result.error.unwrap().starts_with("Busy"));
+        drop(persist);
+        let result = sync.accept_block(&[], &[], None);
+        assert!(!result.error.unwrap().starts_with("Busy"));
+    }
+
+    #[test]
+    fn state_round_trips_compressed() {
+        let sync = wallet_sync_state(1_234);
+        let bytes = sync.to_bytes().unwrap();
+        let json = sync.to_json().unwrap();
+        let restored = WalletSyncState::restore_bytes(
+            &bytes,
+            vec![PrivateKey::from(3).to_hex()],
+            PrivateKey::from(5).to_hex(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(restored.birthday_height(), 1_234);
+        assert_eq!(restored.next_height(), 1_234);
+        assert_eq!(restored.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn state_written_before_compression_is_restored() {
+        let sync = wallet_sync_state(42);
+        let json = sync.to_json().unwrap();
+        let state = decode_state(json.as_bytes()).unwrap();
+        assert_eq!(state.birthday_height, 42);
+        let state = decode_state(&sync.to_bytes().unwrap()).unwrap();
+        assert_eq!(state.birthday_height, 42);
+        let restored = WalletSyncState::restore(
+            &json,
+            vec![PrivateKey::from(3).to_hex()],
+            PrivateKey::from(5).to_hex(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(restored.birthday_height(), 42);
+    }
+
+    #[test]
+    fn malformed_states_are_rejected() {
+        assert!(decode_state(&compress_entry(EntryContent::Bytes, b"{}")).is_err());
+        assert!(decode_state(b"not json").is_err());
+        let json = wallet_sync_state(0).to_json().unwrap();
+        let other_version = json.replacen(
+            &format!("\"version\":{SYNC_STATE_VERSION}"),
+            &format!("\"version\":{}", SYNC_STATE_VERSION + 1),
+            1,
+        );
+        assert_ne!(other_version, json);
+        let error = decode_state(&compress_entry(EntryContent::Text, other_version.as_bytes())).unwrap_err();
+        assert!(error.contains("Unsupported version"), "{error}");
+    }
+
+    #[test]
+    fn blocks_are_accepted_in_order() {
+        let sync = wallet_sync_state(10);
+        let output = one_sided_output(1_000);
+        let first = block(10, None, 0, vec![], vec![output.clone()]);
+        let result = accept(&sync, &first);
+        assert_eq!(result.error, None);
+        assert_eq!(result.height, Some(10));
+        assert_eq!(result.outputs.len(), 1);
+        assert_eq!(result.outputs[0].result.hash, Some(output.hash().to_hex()));
+        assert_eq!(result.outputs[0].result.value, Some(1_000));
+        assert!(result.spent.is_empty());
+        assert_eq!(sync.last_scanned_height(), Some(10));
+        assert_eq!(sync.next_height(), 11);
+        assert_eq!(sync.tip_block_hash(), Some(first.0.hash().to_hex()));
+        assert_eq!(unspent_hashes(&sync), vec![output.hash().to_hex()]);
+
+        // An output of another wallet is not recovered
+        let known_script_key = PrivateKey::from(17).to_hex();
+        let other = WalletSyncState::new(
+            vec![known_script_key],
+            PrivateKey::from(5).to_hex(),
+            10,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let result = accept(&other, &first);
+        assert_eq!(result.error, None);
+        assert!(result.outputs.iter().all(|output| output.result.hash.is_none()));
+        assert!(unspent_hashes(&other).is_empty());
+    }
+
+    #[test]
+    fn blocks_out_of_order_are_rejected() {
+        let sync = wallet_sync_state(10);
+        let first = block(10, None, 0, vec![], vec![]);
+
+        let error = accept(&sync, &block(11, Some(&first.0), 0, vec![], vec![]))
+            .error
+            .unwrap();
+        assert!(
+            error.contains("Expected the block at height 10, got height 11"),
+            "{error}"
+        );
+        assert_eq!(accept(&sync, &first).error, None);
+
+        // The next block must link to the last one
+        let error = accept(&sync, &block(11, None, 0, vec![], vec![])).error.unwrap();
+        assert!(error.contains("does not link"), "{error}");
+        let error = accept(&sync, &first).error.unwrap();
+        assert!(
+            error.contains("Expected the block at height 11, got height 10"),
+            "{error}"
+        );
+
+        // The inputs of the body must match the header's input_mr
+        let output = one_sided_output(1_000);
+        let (header, _) = block(11, Some(&first.0), 0, vec![], vec![]);
+        let (_, body) = block(11, Some(&first.0), 0, vec![spend(&output)], vec![]);
+        let error = accept(&sync, &(header, body)).error.unwrap();
+        assert!(error.contains("do not match the header's input_mr"), "{error}");
+        assert_eq!(sync.last_scanned_height(), Some(10));
+
+        let error = sync.accept_block(&[0u8; 3], &[], None).error.unwrap();
+        assert!(error.starts_with("header: "), "{error}");
+    }
+
+    #[test]
+    fn inputs_spend_recovered_outputs() {
+        let sync = wallet_sync_state(10);
+        let spent = one_sided_output(1_000);
+        let kept = one_sided_output(2_000);
+        let first = block(10, None, 0, vec![], vec![spent.clone(), kept.clone()]);
+        assert_eq!(accept(&sync, &first).error, None);
+
+        // An input spending an output of another wallet is not reported
+        let foreign = one_sided_output(3_000);
+        let second = block(11, Some(&first.0), 0, vec![spend(&spent), spend(&foreign)], vec![]);
+        let result = accept(&sync, &second);
+        assert_eq!(result.error, None);
+        assert_eq!(result.spent, vec![spent.hash().to_hex()]);
+        assert_eq!(unspent_hashes(&sync), vec![kept.hash().to_hex()]);
+        assert_eq!(spent_heights(&sync), vec![(spent.hash().to_hex(), Some(11))]);
+        assert_eq!(sync.spent_output_hashes(), vec![spent.hash().to_hex()]);
+    }
+
+    #[test]
+    fn rewind_undoes_spends_and_recoveries_across_a_reorg() {
+        let sync = wallet_sync_state(10);
+        let first_output = one_sided_output(1_000);
+        let second_output = one_sided_output(2_000);
+        let first = block(10, None, 0, vec![], vec![first_output.clone()]);
+        let second = block(11, Some(&first.0), 0, vec![spend(&first_output)], vec![
+            second_output.clone()
+        ]);
+        let third = block(12, Some(&second.0), 0, vec![], vec![]);
+        for block in [&first, &second, &third] {
+            assert_eq!(accept(&sync, block).error, None);
+        }
+        assert_eq!(unspent_hashes(&sync), vec![second_output.hash().to_hex()]);
+        assert_eq!(spent_heights(&sync), vec![(first_output.hash().to_hex(), Some(11))]);
+
+        // Blocks 11 and 12 are reorged out: the spend is undone and the output mined in block 11 is forgotten
+        sync.rewind_to_height(10).unwrap();
+        assert_eq!(sync.last_scanned_height(), Some(10));
+        assert_eq!(sync.next_height(), 11);
+        assert_eq!(sync.tip_block_hash(), Some(first.0.hash().to_hex()));
+        assert_eq!(unspent_hashes(&sync), vec![first_output.hash().to_hex()]);
+        assert!(spent_heights(&sync).is_empty());
+        assert_eq!(
+            sync.state.borrow().unspent[&first_output.hash().to_hex()].spent_height,
+            None
+        );
+
+        // The old chain no longer links, the fork does
+        let error = accept(&sync, &third).error.unwrap();
+        assert!(error.contains("Expected the block at height 11"), "{error}");
+        let fork_output = one_sided_output(3_000);
+        let fork = block(11, Some(&first.0), 1, vec![], vec![fork_output.clone()]);
+        assert_ne!(fork.0.hash(), second.0.hash());
+        assert_eq!(accept(&sync, &fork).error, None);
+        let error = accept(&sync, &block(12, Some(&second.0), 0, vec![], vec![]))
+            .error
+            .unwrap();
+        assert!(error.contains("does not link"), "{error}");
+        let mut unspent = vec![first_output.hash().to_hex(), fork_output.hash().to_hex()];
+        unspent.sort();
+        assert_eq!(unspent_hashes(&sync), unspent);
+
+        // The spend is seen again on the new chain
+        let spend_again = block(12, Some(&fork.0), 0, vec![spend(&first_output)], vec![]);
+        assert_eq!(accept(&sync, &spend_again).spent, vec![first_output.hash().to_hex()]);
+        assert_eq!(spent_heights(&sync), vec![(first_output.hash().to_hex(), Some(12))]);
+        assert_eq!(unspent_hashes(&sync), vec![fork_output.hash().to_hex()]);
+    }
+
+    #[test]
+    fn rewind_below_the_birthday_resets_the_state() {
+        let sync = wallet_sync_state(10);
+        let output = one_sided_output(1_000);
+        let first = block(10, None, 0, vec![], vec![output.clone()]);
+        assert_eq!(accept(&sync, &first).error, None);
+
+        // Rewinding to the tip or above it changes nothing
+        sync.rewind_to_height(10).unwrap();
+        sync.rewind_to_height(20).unwrap();
+        assert_eq!(sync.last_scanned_height(), Some(10));
+        assert_eq!(unspent_hashes(&sync), vec![output.hash().to_hex()]);
+
+        sync.rewind_to_height(9).unwrap();
+        assert_eq!(sync.last_scanned_height(), None);
+        assert_eq!(sync.next_height(), 10);
+        assert_eq!(sync.tip_block_hash(), None);
+        assert!(unspent_hashes(&sync).is_empty());
+        assert_eq!(accept(&sync, &block(10, None, 1, vec![], vec![])).error, None);
+    }
+}
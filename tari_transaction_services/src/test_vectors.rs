@@ -0,0 +1,272 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Deterministic fixtures for downstream test suites, with the `test_vectors` feature. The keys, the nonces of the
+//! outputs' metadata signatures and encrypted data, and so the outputs themselves, are derived from the seed alone, so
+//! the vectors are reproducible byte-for-byte. The outputs reveal their values instead of carrying a bulletproof, whose
+//! nonces cannot be seeded.
+
+use blake2::Blake2b;
+use digest::consts::U64;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_core::{
+    one_sided::diffie_hellman_stealth_domain_hasher,
+    transactions::{
+        tari_amount::MicroMinotari,
+        transaction_components::{OutputFeatures, RangeProofType, TransactionOutput, TransactionOutputVersion},
+    },
+};
+use tari_crypto::{
+    hash_domain,
+    hashing::DomainSeparatedHasher,
+    keys::{PublicKey as PK, SecretKey},
+    tari_utilities::hex::Hex,
+};
+use tari_script::script;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    create_output::{build_output_to_script_with_rng, build_stealth_output_with_rng, SenderKeys},
+    deposit_tagging::DepositRules,
+    scan_outputs::{scan_output, ScanKeys},
+    value_promise::MinimumValuePromisePolicy,
+    RecoveredOutputResult,
+};
+
+hash_domain!(TestVectorHashDomain, "com.tari.transaction_services.test_vectors", 1);
+
+type TestVectorHasher = DomainSeparatedHasher<Blake2b<U64>, TestVectorHashDomain>;
+
+/// The value of the simple one-sided output
+const SIMPLE_VALUE: u64 = 5_000_000;
+/// The value of the stealth one-sided output
+const STEALTH_VALUE: u64 = 7_000_000;
+
+/// An output generated for a test vector, with the result scanning it must produce
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputVector {
+    /// The output, serialized as JSON
+    pub output: String,
+    /// The result of scanning the output with the vector's wallet keys
+    pub expected: RecoveredOutputResult,
+}
+
+/// A struct to hold a deterministic one-sided payment test vector
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct OneSidedOutputVector {
+    /// The wallet secret key to scan with (hex value)
+    pub wallet_sk: Option<String>,
+    /// The wallet public key, which the stealth output is paid to (hex value)
+    pub wallet_pk: Option<String>,
+    /// The known script private key to scan with (hex value)
+    pub known_script_key: Option<String>,
+    /// The known script public key, which the simple output is paid to (hex value)
+    pub known_script_pk: Option<String>,
+    /// A one-sided output paid to the known script key
+    pub simple: Option<OutputVector>,
+    /// A one-sided output paid to the stealth address of the wallet key
+    pub stealth: Option<OutputVector>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn one_sided_output_vector_error(error: &str) -> OneSidedOutputVector {
+    OneSidedOutputVector {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Derives the private key labelled `label` from the seed
+fn vector_key(seed: &str, label: &'static str) -> PrivateKey {
+    let hash = TestVectorHasher::new_with_label(label)
+        .chain(seed.as_bytes())
+        .finalize();
+    PrivateKey::from_uniform_bytes(hash.as_ref()).expect("'DomainSeparatedHash<Blake2b<U64>>' has correct size")
+}
+
+/// Seeds the RNG labelled `label`, which draws the nonces of an output, from the seed
+fn vector_rng(seed: &str, label: &'static str) -> ChaCha20Rng {
+    let hash = TestVectorHasher::new_with_label(label)
+        .chain(seed.as_bytes())
+        .finalize();
+    let mut rng_seed = [0u8; 32];
+    rng_seed.copy_from_slice(&hash.as_ref()[..32]);
+    ChaCha20Rng::from_seed(rng_seed)
+}
+
+/// Scans the output and checks the result against the value and keys the output was generated with
+fn expect_recovered(
+    keys: &ScanKeys,
+    output: TransactionOutput,
+    value: u64,
+    spending_key: &PrivateKey,
+    script_key: &PrivateKey,
+) -> Result<OutputVector, String> {
+    let expected = scan_output(keys, &DepositRules::default(), &output)
+        .map_err(|e| e.message)?
+        .ok_or_else(|| "The generated output does not match the wallet keys".to_string())?;
+    if expected.value != Some(value) ||
+        expected.spending_key != Some(spending_key.to_hex()) ||
+        expected.script_key != Some(script_key.to_hex())
+    {
+        return Err("The recovered output does not match the generated value and keys".to_string());
+    }
+    let output = serde_json::to_string(&output).map_err(|e| format!("Could not serialize output: {e}"))?;
+    Ok(OutputVector { output, expected })
+}
+
+/// Generates a one-sided payment test vector from `seed`, any string: a wallet key set, a simple one-sided output paid
+/// to a known script key, a stealth one-sided output paid to the wallet key, and the `RecoveredOutputResult`s that
+/// scanning the outputs with `ScannerContext::new([knownScriptKey], walletSk)` produces.
+#[wasm_bindgen]
+pub async fn generate_one_sided_output_vector(seed: &str) -> OneSidedOutputVector {
+    let wallet_sk = vector_key(seed, "wallet");
+    let wallet_pk = PublicKey::from_secret_key(&wallet_sk);
+    let known_script_key = vector_key(seed, "known_script_key");
+    let known_script_pk = PublicKey::from_secret_key(&known_script_key);
+    let sender_wallet_pk = PublicKey::from_secret_key(&vector_key(seed, "sender_wallet"));
+    let keys = match ScanKeys::new(vec![known_script_key.to_hex()], wallet_sk.to_hex()) {
+        Ok(val) => val,
+        Err(e) => return one_sided_output_vector_error(&e.message),
+    };
+    let features = OutputFeatures {
+        range_proof_type: RangeProofType::RevealedValue,
+        ..Default::default()
+    };
+    let version = TransactionOutputVersion::get_current_version();
+
+    let simple = match build_output_to_script_with_rng(
+        MicroMinotari::from(SIMPLE_VALUE),
+        &sender_wallet_pk,
+        vector_key(seed, "simple_sender_offset"),
+        &known_script_pk,
+        script!(PushPubKey(Box::new(known_script_pk.clone()))),
+        features.clone(),
+        MinimumValuePromisePolicy::default_for(features.range_proof_type),
+        version,
+        &mut vector_rng(seed, "simple_rng"),
+    )
+    .await
+    .and_then(|(output, spending_key)| expect_recovered(&keys, output, SIMPLE_VALUE, &spending_key, &known_script_key))
+    {
+        Ok(val) => val,
+        Err(e) => return one_sided_output_vector_error(&format!("Simple output: {e}")),
+    };
+
+    let nonce_sk = vector_key(seed, "stealth_nonce");
+    let stealth_address_hasher = diffie_hellman_stealth_domain_hasher(&nonce_sk, &wallet_pk);
+    let stealth_address_offset = PrivateKey::from_uniform_bytes(stealth_address_hasher.as_ref())
+        .expect("'DomainSeparatedHash<Blake2b<U64>>' has correct size");
+    let stealth_script_key = wallet_sk.clone() + stealth_address_offset;
+    let sender_keys = SenderKeys {
+        wallet_pk: sender_wallet_pk.to_hex(),
+        sender_offset_sk: Some(vector_key(seed, "stealth_sender_offset").to_hex()),
+        nonce_sk: Some(nonce_sk.to_hex()),
+    };
+    let stealth = match build_stealth_output_with_rng(
+        MicroMinotari::from(STEALTH_VALUE),
+        sender_keys,
+        &wallet_pk,
+        &wallet_pk,
+        features.clone(),
+        MinimumValuePromisePolicy::default_for(features.range_proof_type),
+        version,
+        &mut vector_rng(seed, "stealth_rng"),
+    )
+    .await
+    .and_then(|(output, spending_key)| {
+        expect_recovered(&keys, output, STEALTH_VALUE, &spending_key, &stealth_script_key)
+    }) {
+        Ok(val) => val,
+        Err(e) => return one_sided_output_vector_error(&format!("Stealth output: {e}")),
+    };
+
+    OneSidedOutputVector {
+        wallet_sk: Some(wallet_sk.to_hex()),
+        wallet_pk: Some(wallet_pk.to_hex()),
+        known_script_key: Some(known_script_key.to_hex()),
+        known_script_pk: Some(known_script_pk.to_hex()),
+        simple: Some(simple),
+        stealth: Some(stealth),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SEED: &str = "tari_transaction_services";
+
+    /// The keys of the vector for `SEED`, which the outputs are built from
+    fn expected_keys() -> OneSidedOutputVector {
+        serde_json::from_str(include_str!("test_vectors/one_sided_output_vector_keys.json")).unwrap()
+    }
+
+    #[test]
+    fn vector_keys_match_the_committed_keys() {
+        let expected = expected_keys();
+        let wallet_sk = vector_key(SEED, "wallet");
+        let known_script_key = vector_key(SEED, "known_script_key");
+        assert_eq!(Some(wallet_sk.to_hex()), expected.wallet_sk);
+        assert_eq!(
+            Some(PublicKey::from_secret_key(&wallet_sk).to_hex()),
+            expected.wallet_pk
+        );
+        assert_eq!(Some(known_script_key.to_hex()), expected.known_script_key);
+        assert_eq!(
+            Some(PublicKey::from_secret_key(&known_script_key).to_hex()),
+            expected.known_script_pk
+        );
+    }
+
+    #[test]
+    fn vector_keys_and_rngs_are_separated_by_label() {
+        assert_eq!(vector_key(SEED, "wallet"), vector_key(SEED, "wallet"));
+        assert_ne!(vector_key(SEED, "wallet"), vector_key(SEED, "sender_wallet"));
+        assert_ne!(vector_key(SEED, "wallet"), vector_key("another seed", "wallet"));
+        assert_eq!(
+            vector_rng(SEED, "simple_rng").get_seed(),
+            vector_rng(SEED, "simple_rng").get_seed()
+        );
+        assert_ne!(
+            vector_rng(SEED, "simple_rng").get_seed(),
+            vector_rng(SEED, "stealth_rng").get_seed()
+        );
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    async fn one_sided_output_vector_is_deterministic() {
+        let vector = generate_one_sided_output_vector(SEED).await;
+        assert_eq!(vector.error, None);
+        let again = generate_one_sided_output_vector(SEED).await;
+        assert_eq!(
+            serde_json::to_string(&vector).unwrap(),
+            serde_json::to_string(&again).unwrap()
+        );
+
+        let expected = expected_keys();
+        assert_eq!(vector.wallet_sk, expected.wallet_sk);
+        assert_eq!(vector.wallet_pk, expected.wallet_pk);
+        assert_eq!(vector.known_script_key, expected.known_script_key);
+        assert_eq!(vector.known_script_pk, expected.known_script_pk);
+        let simple = vector.simple.unwrap();
+        assert_eq!(simple.expected.value, Some(SIMPLE_VALUE));
+        assert_eq!(simple.expected.script_key, expected.known_script_key);
+        assert_eq!(vector.stealth.unwrap().expected.value, Some(STEALTH_VALUE));
+
+        let other = generate_one_sided_output_vector("another seed").await;
+        assert_eq!(other.error, None);
+        assert_ne!(other.wallet_sk, vector.wallet_sk);
+        assert_ne!(other.simple.unwrap().output, simple.output);
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::PrivateKey;
+use tari_core::transactions::{
+    key_manager::create_memory_db_key_manager,
+    transaction_components::{SpentOutput, TransactionInput},
+};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, tari_utilities::hex::Hex};
+use tari_key_manager::cipher_seed::CipherSeed;
+use tari_script::ExecutionStack;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    factories::crypto_factories,
+    output_encoding::{decode_output, OutputEncoding},
+    sweep::import_input,
+};
+
+/// A struct to hold a signed transaction input
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct TransactionInputResult {
+    /// The input, serialized as JSON
+    pub input: Option<String>,
+    /// The hash of the spent output (hex value)
+    pub output_hash: Option<String>,
+    /// The version of the input
+    pub version: Option<u8>,
+    /// Whether the input is compact, i.e. it carries only the hash of the spent output instead of its data
+    pub compact: Option<bool>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn transaction_input_error(error: &str) -> TransactionInputResult {
+    TransactionInputResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Creates an input spending a recovered output, with a script signature for the current transaction input version,
+/// for callers that assemble the rest of the transaction elsewhere. The spending key and value must open the output's
+/// commitment.
+/// * `value` - the value of the output, as recovered by the scanner
+/// * `script_key` - the script private key of the output (hex value)
+/// * `spending_key` - the commitment mask of the output (hex value)
+/// * `input_data` - the serialized execution stack the script is run with (hex value), empty if not provided
+/// * `encoding` - the encoding of `output` (see `OutputEncoding`), detected if not provided
+/// * `compact` - whether to return the compact input, which carries only the output hash, as in compact blocks
+#[wasm_bindgen]
+pub async fn build_transaction_input(
+    output: JsValue,
+    value: u64,
+    script_key: String,
+    spending_key: String,
+    input_data: Option<String>,
+    encoding: Option<OutputEncoding>,
+    compact: Option<bool>,
+) -> TransactionInputResult {
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => return transaction_input_error(&format!("output: {e}")),
+    };
+    let script_key = match PrivateKey::from_hex(&script_key) {
+        Ok(val) => val,
+        Err(e) => return transaction_input_error(&format!("script_key: {e}")),
+    };
+    let spending_key = match PrivateKey::from_hex(&spending_key) {
+        Ok(val) => val,
+        Err(e) => return transaction_input_error(&format!("spending_key: {e}")),
+    };
+    let input_data = match input_data.as_deref().map(ExecutionStack::from_hex).transpose() {
+        Ok(val) => val.unwrap_or_default(),
+        Err(e) => return transaction_input_error(&format!("input_data: {e}")),
+    };
+    if !crypto_factories()
+        .commitment
+        .open_value(&spending_key, value, &output.commitment)
+    {
+        return transaction_input_error("The spending key and value do not open the output's commitment");
+    }
+
+    let key_manager = match create_memory_db_key_manager(CipherSeed::new(), crypto_factories()) {
+        Ok(val) => val,
+        Err(e) => return transaction_input_error(&format!("Could not create key manager: {e}")),
+    };
+    let input = match import_input(
+        &key_manager,
+        &output,
+        value.into(),
+        spending_key,
+        script_key,
+        input_data,
+    )
+    .await
+    {
+        Ok((input, _, _)) if compact.unwrap_or(false) => input.to_compact(),
+        Ok((input, _, _)) => input,
+        Err(e) => return transaction_input_error(&format!("Could not build input: {e}")),
+    };
+    input_result(&input)
+}
+
+fn input_result(input: &TransactionInput) -> TransactionInputResult {
+    match serde_json::to_string(input) {
+        Ok(json) => TransactionInputResult {
+            input: Some(json),
+            output_hash: Some(input.output_hash().to_hex()),
+            version: Some(input.version.as_u8()),
+            compact: Some(input.is_compact()),
+            error: None,
+        },
+        Err(e) => transaction_input_error(&format!("Could not serialize input: {e}")),
+    }
+}
+
+/// Converts a transaction input (JSON) to its compact form, which carries only the hash of the spent output, as the
+/// inputs of compact blocks do. A compact input is returned unchanged.
+#[wasm_bindgen]
+pub fn compact_transaction_input(input: &str) -> TransactionInputResult {
+    match serde_json::from_str::<TransactionInput>(input) {
+        Ok(input) => input_result(&input.to_compact()),
+        Err(e) => transaction_input_error(&format!("input: {e}")),
+    }
+}
+
+/// Upgrades a compact transaction input (JSON) to a full input by adding the data of the output it spends, e.g. one
+/// recovered by the scanner or fetched from a base node, as a base node does when it reconstructs a compact block. The
+/// output's hash must be the one the input references.
+/// * `encoding` - the encoding of `output` (see `OutputEncoding`), detected if not provided
+#[wasm_bindgen]
+pub fn expand_transaction_input(
+    input: &str,
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+) -> TransactionInputResult {
+    let mut input = match serde_json::from_str::<TransactionInput>(input) {
+        Ok(val) => val,
+        Err(e) => return transaction_input_error(&format!("input: {e}")),
+    };
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => return transaction_input_error(&format!("output: {e}")),
+    };
+    if input.output_hash() != output.hash() {
+        return transaction_input_error(&format!(
+            "The input spends output {}, not output {}",
+            input.output_hash().to_hex(),
+            output.hash().to_hex()
+        ));
+    }
+    input.spent_output = SpentOutput::create_from_output(output);
+    input_result(&input)
+}
@@ -0,0 +1,201 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_core::transactions::{
+    transaction_components::{transaction_output::batch_verify_range_proofs, Transaction, TransactionOutput},
+    CryptoFactories,
+};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::PublicKey as PK};
+use tari_script::ScriptContext;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    factories::crypto_factories,
+    network::{on_network, parse_network},
+};
+
+/// The outcome of a single check of `validate_transaction`
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationCheck {
+    /// The check, one of `balance`, `kernelSignatures`, `metadataSignatures`, `scriptSignatures`, `rangeProofs` or
+    /// `covenants`
+    pub check: String,
+    /// Whether the check passed
+    pub passed: bool,
+    /// Why the check failed
+    pub error: Option<String>,
+}
+
+/// A struct to hold the per-check report of a transaction validation
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct TransactionValidationResult {
+    /// Whether all checks passed
+    pub valid: Option<bool>,
+    /// The block height the scripts and covenants were evaluated at
+    pub block_height: Option<u64>,
+    /// The outcome of every check, in the order they were run
+    pub checks: Vec<ValidationCheck>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn transaction_validation_error(error: &str) -> TransactionValidationResult {
+    TransactionValidationResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Checks that the outputs less the inputs, plus the fee, commit to the kernel excesses plus the offset, i.e. that no
+/// value is created or destroyed
+fn check_balance(transaction: &Transaction, factories: &CryptoFactories) -> Result<(), String> {
+    let body = &transaction.body;
+    let fee = body.get_total_fee().map_err(|e| e.to_string())?;
+    let mut excess = factories.commitment.commit_value(&transaction.offset, 0);
+    for kernel in body.kernels() {
+        excess = &excess + &kernel.excess;
+    }
+    let mut sum_io = factories.commitment.commit_value(&PrivateKey::default(), fee.as_u64());
+    for output in body.outputs() {
+        sum_io = &sum_io + &output.commitment;
+    }
+    for (i, input) in body.inputs().iter().enumerate() {
+        let commitment = input.commitment().map_err(|e| format!("Input {i}: {e}"))?;
+        sum_io = &sum_io - commitment;
+    }
+    if excess != sum_io {
+        return Err(
+            "The transaction does not balance: the outputs less the inputs and fee do not match the kernel excesses \
+             and offset"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+fn check_metadata_signatures(outputs: &[TransactionOutput]) -> Result<(), String> {
+    for (i, output) in outputs.iter().enumerate() {
+        output
+            .verify_metadata_signature()
+            .map_err(|e| format!("Output {i}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Runs the script of every input, verifies its script signature, and checks that the script offset balances the
+/// script keys against the sender offset public keys of the outputs
+fn check_script_signatures(
+    transaction: &Transaction,
+    block_height: u64,
+    factories: &CryptoFactories,
+) -> Result<(), String> {
+    let body = &transaction.body;
+    let mut input_keys = PublicKey::default();
+    for (i, input) in body.inputs().iter().enumerate() {
+        let commitment = input.commitment().map_err(|e| format!("Input {i}: {e}"))?;
+        let context = ScriptContext::new(block_height, &[0u8; 32], commitment);
+        let script_key = input
+            .run_and_verify_script(&factories.commitment, Some(context))
+            .map_err(|e| format!("Input {i}: {e}"))?;
+        input_keys = input_keys + script_key;
+    }
+    let output_keys = body.outputs().iter().fold(PublicKey::default(), |keys, output| {
+        keys + &output.sender_offset_public_key
+    });
+    if input_keys - output_keys != PublicKey::from_secret_key(&transaction.script_offset) {
+        return Err(
+            "The script offset does not balance the input script keys and output sender offset keys".to_string(),
+        );
+    }
+    Ok(())
+}
+
+fn check_covenants(transaction: &Transaction, block_height: u64) -> Result<(), String> {
+    let outputs = transaction.body.outputs();
+    for (i, input) in transaction.body.inputs().iter().enumerate() {
+        input
+            .covenant()
+            .map_err(|e| e.to_string())
+            .and_then(|covenant| {
+                covenant
+                    .execute(block_height, input, outputs)
+                    .map_err(|e| e.to_string())
+            })
+            .map_err(|e| format!("Input {i}: {e}"))?;
+    }
+    Ok(())
+}
+
+fn validation_check(check: &str, outcome: Result<(), String>) -> ValidationCheck {
+    ValidationCheck {
+        check: check.to_string(),
+        passed: outcome.is_ok(),
+        error: outcome.err(),
+    }
+}
+
+/// Validates a transaction, as JSON, the way a base node validates its aggregated body: the commitments balance
+/// against the kernels with the offset, and all kernel signatures, output metadata signatures, input script
+/// signatures with the script offset, output range proofs (verified in a batch) and input covenants are valid. All
+/// checks are run, so that the report shows every check that failed. The inputs must not be compact.
+/// * `block_height` - the height to evaluate the input scripts and covenants at; if not provided, the lowest height the
+///   transaction can be mined at is used
+/// * `network` - the network the transaction is validated for, e.g. `mainnet`; the library's network if not provided
+#[wasm_bindgen]
+pub fn validate_transaction(
+    tx_json: &str,
+    block_height: Option<u64>,
+    network: Option<String>,
+) -> TransactionValidationResult {
+    let network = match parse_network(network.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return transaction_validation_error(&e),
+    };
+    on_network(network, || validate(tx_json, block_height))
+}
+
+fn validate(tx_json: &str, block_height: Option<u64>) -> TransactionValidationResult {
+    let transaction: Transaction = match serde_json::from_str(tx_json) {
+        Ok(val) => val,
+        Err(e) => return transaction_validation_error(&format!("tx_json: {e}")),
+    };
+    let block_height = match block_height {
+        Some(height) => height,
+        None => match transaction.min_spendable_height() {
+            Ok(val) => val,
+            Err(e) => return transaction_validation_error(&format!("Could not determine the block height: {e}")),
+        },
+    };
+    let factories = crypto_factories();
+    let outputs = transaction.body.outputs();
+    let checks = vec![
+        validation_check("balance", check_balance(&transaction, &factories)),
+        validation_check(
+            "kernelSignatures",
+            transaction.body.verify_kernel_signatures().map_err(|e| e.to_string()),
+        ),
+        validation_check("metadataSignatures", check_metadata_signatures(outputs)),
+        validation_check(
+            "scriptSignatures",
+            check_script_signatures(&transaction, block_height, &factories),
+        ),
+        validation_check(
+            "rangeProofs",
+            batch_verify_range_proofs(&factories.range_proof, &outputs.iter().collect::<Vec<_>>())
+                .map_err(|e| e.to_string()),
+        ),
+        validation_check("covenants", check_covenants(&transaction, block_height)),
+    ];
+    TransactionValidationResult {
+        valid: Some(checks.iter().all(|check| check.passed)),
+        block_height: Some(block_height),
+        checks,
+        error: None,
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::{epoch::VnEpoch, types::FixedHash};
+use tari_crypto::tari_utilities::hex::{from_hex, to_hex, Hex};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::output_encoding::{decode_output, OutputEncoding};
+
+/// A struct to hold the inspection of a validator node registration
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct ValidatorNodeRegistrationResult {
+    /// Whether the output registers a validator node
+    pub is_registration: bool,
+    /// The hash of the output (hex value)
+    pub output_hash: Option<String>,
+    /// The validator node public key (hex value)
+    pub public_key: Option<String>,
+    /// The public nonce of the registration signature (hex value)
+    pub signature_public_nonce: Option<String>,
+    /// The scalar of the registration signature (hex value)
+    pub signature_u: Option<String>,
+    /// Whether the registration signature is valid
+    pub signature_valid: Option<bool>,
+    /// The validator node's shard key, if an epoch, epoch interval and block hash were given (hex value)
+    pub shard_key: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn registration_error(error: &str) -> ValidatorNodeRegistrationResult {
+    ValidatorNodeRegistrationResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn parse_prev_shard_key(prev_shard_key: Option<&str>) -> Result<Option<[u8; 32]>, String> {
+    prev_shard_key
+        .map(|key| {
+            let bytes = from_hex(key.trim()).map_err(|e| e.to_string())?;
+            <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| format!("expected 32 bytes, got {}", bytes.len()))
+        })
+        .transpose()
+}
+
+/// Inspects a validator node registration output: its validator node public key, whether the registration signature
+/// is valid, as checked by base nodes, and optionally the shard key the validator node is assigned. Outputs without a
+/// registration only report `isRegistration` as false. The output is passed in any of the encodings accepted by
+/// `scan_output_for_one_sided_payment`.
+/// * `epoch`, `interval` - the epoch to derive the shard key for and the number of epochs between shard key changes
+/// * `block_hash` - the hash of the block the shard key is derived from (hex value)
+/// * `prev_shard_key` - the validator node's shard key in the previous epoch, which is kept if the validator node is
+///   not due a new one in `epoch` (hex value)
+#[wasm_bindgen]
+pub fn inspect_validator_node_registration(
+    output: JsValue,
+    epoch: Option<u64>,
+    interval: Option<u64>,
+    block_hash: Option<String>,
+    prev_shard_key: Option<String>,
+    encoding: Option<OutputEncoding>,
+) -> ValidatorNodeRegistrationResult {
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => return registration_error(&e.to_string()),
+    };
+    let block_hash = match block_hash.as_deref().map(FixedHash::from_hex).transpose() {
+        Ok(val) => val,
+        Err(e) => return registration_error(&format!("block_hash: {e}")),
+    };
+    let prev_shard_key = match parse_prev_shard_key(prev_shard_key.as_deref()) {
+        Ok(val) => val,
+        Err(e) => return registration_error(&format!("prev_shard_key: {e}")),
+    };
+    let registration = match output
+        .features
+        .sidechain_feature
+        .as_ref()
+        .and_then(|feature| feature.validator_node_registration())
+    {
+        Some(registration) => registration,
+        None => return ValidatorNodeRegistrationResult::default(),
+    };
+    let shard_key = match (epoch, interval, block_hash) {
+        (Some(epoch), Some(interval), Some(block_hash)) => {
+            if interval == 0 {
+                return registration_error("interval must be greater than 0");
+            }
+            Some(registration.derive_shard_key(prev_shard_key, VnEpoch(epoch), VnEpoch(interval), &block_hash))
+        },
+        (None, None, None) => None,
+        _ => return registration_error("epoch, interval and block_hash must be given together"),
+    };
+    ValidatorNodeRegistrationResult {
+        is_registration: true,
+        output_hash: Some(output.hash().to_hex()),
+        public_key: Some(registration.public_key().to_hex()),
+        signature_public_nonce: Some(registration.signature().get_public_nonce().to_hex()),
+        signature_u: Some(registration.signature().get_signature().to_hex()),
+        signature_valid: Some(output.verify_validator_node_signature().is_ok()),
+        shard_key: shard_key.map(|key| to_hex(&key)),
+        error: None,
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::{tari_amount::MicroMinotari, transaction_components::RangeProofType};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// How much of an output's value the sender promises as its minimum value. The range proof proves that the value is
+/// at least the promise, so a promise above the value makes the proof fail.
+/// * `"zero"` - promise nothing, which reveals nothing about the value
+/// * `"exact"` - promise the exact value, as required for `RevealedValue` outputs
+/// * `{ "floor": <amount> }` - promise a floor, partially revealing the value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(from_wasm_abi)]
+pub enum MinimumValuePromisePolicy {
+    Zero,
+    Exact,
+    Floor(u64),
+}
+
+impl MinimumValuePromisePolicy {
+    /// The policy to use when the sender has not chosen one
+    pub fn default_for(range_proof_type: RangeProofType) -> Self {
+        match range_proof_type {
+            RangeProofType::BulletProofPlus => MinimumValuePromisePolicy::Zero,
+            RangeProofType::RevealedValue => MinimumValuePromisePolicy::Exact,
+        }
+    }
+
+    /// Chooses the minimum value promise for an output of `value` with the given range proof type, or an error if the
+    /// policy would produce an output whose range proof cannot be verified
+    pub fn choose(self, value: MicroMinotari, range_proof_type: RangeProofType) -> Result<MicroMinotari, String> {
+        match (range_proof_type, self) {
+            (RangeProofType::RevealedValue, MinimumValuePromisePolicy::Exact) => Ok(value),
+            (RangeProofType::RevealedValue, _) => Err(format!(
+                "A RevealedValue output must promise its exact value ({value}), use the `exact` policy"
+            )),
+            (RangeProofType::BulletProofPlus, MinimumValuePromisePolicy::Zero) => Ok(MicroMinotari::from(0)),
+            (RangeProofType::BulletProofPlus, MinimumValuePromisePolicy::Exact) => Ok(value),
+            (RangeProofType::BulletProofPlus, MinimumValuePromisePolicy::Floor(floor)) => {
+                let floor = MicroMinotari::from(floor);
+                if floor > value {
+                    Err(format!(
+                        "The promised floor ({floor}) exceeds the output value ({value})"
+                    ))
+                } else {
+                    Ok(floor)
+                }
+            },
+        }
+    }
+}
+
+/// A struct to hold a chosen minimum value promise
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct MinimumValuePromiseResult {
+    /// The minimum value promise to use for the output
+    pub minimum_value_promise: Option<u64>,
+    /// The range proof type the promise was chosen for
+    pub range_proof_type: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn minimum_value_promise_error(error: &str) -> MinimumValuePromiseResult {
+    MinimumValuePromiseResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Parses a range proof type as named in output features JSON (`bullet_proof_plus` or `revealed_value`)
+pub(crate) fn parse_range_proof_type(range_proof_type: &str) -> Result<RangeProofType, String> {
+    serde_json::from_value(serde_json::Value::String(range_proof_type.to_string()))
+        .map_err(|e| format!("range_proof_type: {e}"))
+}
+
+/// Chooses the minimum value promise for an output of `value` according to `policy`, checking it against the output's
+/// range proof type (`bullet_proof_plus` if not provided, or `revealed_value`), so that senders building outputs do
+/// not produce range proofs that fail to verify.
+#[wasm_bindgen]
+pub fn choose_minimum_value_promise(
+    value: u64,
+    policy: Option<MinimumValuePromisePolicy>,
+    range_proof_type: Option<String>,
+) -> MinimumValuePromiseResult {
+    let range_proof_type = match range_proof_type.as_deref().map(parse_range_proof_type).transpose() {
+        Ok(val) => val.unwrap_or_default(),
+        Err(e) => return minimum_value_promise_error(&e),
+    };
+    let policy = policy.unwrap_or_else(|| MinimumValuePromisePolicy::default_for(range_proof_type));
+    match policy.choose(value.into(), range_proof_type) {
+        Ok(promise) => MinimumValuePromiseResult {
+            minimum_value_promise: Some(promise.as_u64()),
+            range_proof_type: serde_json::to_value(range_proof_type)
+                .ok()
+                .and_then(|name| name.as_str().map(String::from)),
+            error: None,
+        },
+        Err(e) => minimum_value_promise_error(&e),
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use js_sys::{Function, Promise, Reflect};
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::{
+    transaction_components::{Transaction, TransactionOutput},
+    CryptoFactories,
+};
+use tari_crypto::tari_utilities::hex::Hex;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
+
+use crate::{
+    factories::crypto_factories,
+    output_encoding::{decode_output_str, OutputEncoding},
+    session_guard::SessionGuard,
+};
+
+const DEFAULT_CHUNK_SIZE: usize = 25;
+
+/// The result of verifying an item of a `VerificationQueue`, passed to its result callback
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationQueueResult {
+    /// The id returned when the item was enqueued
+    pub id: u32,
+    /// The kind of item, `output` or `transaction`
+    pub kind: String,
+    /// The hash of the output (hex value), for an output
+    pub hash: Option<String>,
+    /// Whether the item passed verification
+    pub valid: bool,
+    /// An error message in case of an error or failed verification
+    pub error: Option<String>,
+}
+
+enum QueueItem {
+    Output(String),
+    Transaction(String),
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: VecDeque<(u32, QueueItem)>,
+    next_id: u32,
+}
+
+struct QueueInner {
+    state: RefCell<QueueState>,
+    on_result: Function,
+    chunk_size: usize,
+    factories: CryptoFactories,
+    guard: SessionGuard,
+}
+
+fn verify_output(output: &TransactionOutput, factories: &CryptoFactories) -> Result<(), String> {
+    output
+        .verify_range_proof(&factories.range_proof)
+        .map_err(|e| format!("Range proof verification failed: {e}"))?;
+    output
+        .verify_metadata_signature()
+        .map_err(|e| format!("Metadata signature verification failed: {e}"))
+}
+
+fn verify_transaction(transaction: &Transaction, factories: &CryptoFactories) -> Result<(), String> {
+    for output in transaction.body.outputs() {
+        verify_output(output, factories).map_err(|e| format!("Output {}: {e}", output.hash().to_hex()))?;
+    }
+    transaction
+        .body
+        .verify_kernel_signatures()
+        .map_err(|e| format!("Kernel signature verification failed: {e}"))
+}
+
+fn verify_item(id: u32, item: &QueueItem, factories: &CryptoFactories) -> VerificationQueueResult {
+    let (kind, hash, verified) = match item {
+        QueueItem::Output(output) => match decode_output_str(output, OutputEncoding::Auto) {
+            Ok(output) => (
+                "output",
+                Some(output.hash().to_hex()),
+                verify_output(&output, factories),
+            ),
+            Err(e) => ("output", None, Err(e.to_string())),
+        },
+        QueueItem::Transaction(transaction) => match serde_json::from_str::<Transaction>(transaction) {
+            Ok(transaction) => ("transaction", None, verify_transaction(&transaction, factories)),
+            Err(e) => ("transaction", None, Err(format!("transaction: {e}"))),
+        },
+    };
+    VerificationQueueResult {
+        id,
+        kind: kind.to_string(),
+        hash,
+        valid: verified.is_ok(),
+        error: verified.err(),
+    }
+}
+
+impl QueueInner {
+    // Verifies up to one chunk of pending items, passing each result to the callback, and returns the number verified.
+    // An item is only taken off the queue once its callback has returned, so that if the callback throws, the item and
+    // those behind it stay queued.
+    fn verify_chunk(&self) -> Result<usize, JsValue> {
+        let mut verified = 0;
+        while verified < self.chunk_size {
+            let (id, result) = {
+                let state = self.state.borrow();
+                match state.pending.front() {
+                    Some((id, item)) => (*id, verify_item(*id, item, &self.factories)),
+                    None => break,
+                }
+            };
+            let result = serde_wasm_bindgen::to_value(&result)?;
+            self.on_result.call1(&JsValue::NULL, &result)?;
+            let mut state = self.state.borrow_mut();
+            // The callback may have cleared the queue
+            if state.pending.front().map(|(front, _)| *front) == Some(id) {
+                state.pending.pop_front();
+            }
+            verified += 1;
+        }
+        Ok(verified)
+    }
+}
+
+/// Yields to the event loop, so that the page can render between chunks
+async fn yield_to_event_loop() -> Result<(), JsValue> {
+    let promise = Promise::new(&mut |resolve, _reject| {
+        let set_timeout = Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .ok()
+            .and_then(|set_timeout| set_timeout.dyn_into::<Function>().ok());
+        let scheduled = set_timeout.map_or(false, |set_timeout| {
+            set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(0)).is_ok()
+        });
+        if !scheduled {
+            let _ = resolve.call0(&JsValue::NULL);
+        }
+    });
+    JsFuture::from(promise).await.map(|_| ())
+}
+
+/// A queue of outputs and transactions to verify without blocking the UI thread. Items are verified in chunks, with
+/// each `VerificationQueueResult` passed to the result callback as soon as it is available, so that explorers can
+/// render verification badges progressively. Outputs have their range proof and metadata signature verified, and
+/// transactions in addition the signatures of their kernels.
+#[wasm_bindgen]
+pub struct VerificationQueue {
+    inner: Rc<QueueInner>,
+}
+
+impl VerificationQueue {
+    fn enqueue(&self, item: QueueItem) -> u32 {
+        let mut state = self.inner.state.borrow_mut();
+        let id = state.next_id;
+        state.next_id = state.next_id.wrapping_add(1);
+        state.pending.push_back((id, item));
+        id
+    }
+}
+
+#[wasm_bindgen]
+impl VerificationQueue {
+    /// Creates a verification queue that calls `on_result` with a `VerificationQueueResult` for every verified item,
+    /// verifying `chunk_size` items (25 if not provided) between yields to the event loop
+    #[wasm_bindgen(constructor)]
+    pub fn new(on_result: Function, chunk_size: Option<usize>) -> VerificationQueue {
+        Self {
+            inner: Rc::new(QueueInner {
+                state: RefCell::new(QueueState::default()),
+                on_result,
+                chunk_size: chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE).max(1),
+                factories: crypto_factories(),
+                guard: SessionGuard::new(),
+            }),
+        }
+    }
+
+    /// Queues a transaction output, in any of the string encodings accepted by `scan_output_for_one_sided_payment`,
+    /// and returns the id its result will carry
+    pub fn enqueue_output(&self, output: String) -> u32 {
+        self.enqueue(QueueItem::Output(output))
+    }
+
+    /// Queues a transaction, as JSON, and returns the id its result will carry
+    pub fn enqueue_transaction(&self, transaction: String) -> u32 {
+        self.enqueue(QueueItem::Transaction(transaction))
+    }
+
+    /// The number of items waiting to be verified
+    pub fn pending(&self) -> usize {
+        self.inner.state.borrow().pending.len()
+    }
+
+    /// Drops all items waiting to be verified
+    pub fn clear(&self) {
+        self.inner.state.borrow_mut().pending.clear();
+    }
+
+    /// Verifies one chunk of pending items synchronously, for callers that schedule the work themselves, e.g. from
+    /// `requestIdleCallback`. Returns the number of items verified. If the result callback throws, the item it was
+    /// called for stays queued and is verified again by the next call.
+    pub fn verify_chunk(&self) -> Result<usize, JsValue> {
+        let _permit = self.inner.guard.try_acquire("verify_chunk").map_err(JsValue::from)?;
+        self.inner.verify_chunk()
+    }
+
+    /// Verifies all pending items, including items enqueued while running, one chunk at a time and yielding to the
+    /// event loop between chunks. Returns a promise of the number of items verified.
+    pub fn run(&self) -> Promise {
+        let inner = self.inner.clone();
+        future_to_promise(async move {
+            let _permit = inner.guard.acquire("run").await;
+            let mut verified = 0;
+            loop {
+                let count = inner.verify_chunk()?;
+                if count == 0 {
+                    break;
+                }
+                verified += count;
+                yield_to_event_loop().await?;
+            }
+            Ok(JsValue::from(verified))
+        })
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{CommitmentFactory, PrivateKey, PublicKey};
+use tari_core::transactions::transaction_components::TransactionOutput;
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{PublicKey as PK, SecretKey},
+    tari_utilities::hex::{to_hex, Hex},
+};
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    factories::crypto_factories,
+    output_encoding::{decode_output, OutputEncoding},
+    parallel::verify_range_proofs,
+};
+
+/// A struct to hold the result of a transaction output verification
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct OutputVerificationResult {
+    /// The hash of the output (hex value)
+    pub hash: Option<String>,
+    /// Whether the output passed verification
+    pub valid: bool,
+    /// An error message in case of an error or failed verification
+    pub error: Option<String>,
+}
+
+/// A struct to hold the result of a batch of transaction output verifications
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct BatchVerificationResult {
+    /// The number of outputs that were verified
+    pub count: usize,
+    /// Whether all outputs in the batch passed verification
+    pub valid: bool,
+    /// The hashes of the outputs that failed verification (hex values)
+    pub invalid: Vec<String>,
+    /// An error message in case of an error or failed verification
+    pub error: Option<String>,
+}
+
+/// A struct to hold the result of a metadata signature verification
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct MetadataSignatureVerificationResult {
+    /// The hash of the output (hex value)
+    pub hash: Option<String>,
+    /// Whether the metadata signature is valid
+    pub valid: bool,
+    /// The metadata signature challenge (hex value)
+    pub challenge: Option<String>,
+    /// The components of the signature that failed verification, `commitment` and/or `sender_offset_public_key`
+    pub failed_components: Vec<String>,
+    /// An error message in case of an error or failed verification
+    pub error: Option<String>,
+}
+
+/// Returns a verification error message
+fn verification_error(hash: Option<String>, error: &str) -> OutputVerificationResult {
+    OutputVerificationResult {
+        hash,
+        valid: false,
+        error: Some(error.to_string()),
+    }
+}
+
+/// Verifies the range proof of a single transaction output. The output is deserialized from its JSON representation
+/// and the range proof is verified with the default range proof service, without needing a base node.
+#[wasm_bindgen]
+pub fn verify_output_range_proof(output: &str) -> OutputVerificationResult {
+    let output: TransactionOutput = match serde_json::from_str(output) {
+        Ok(val) => val,
+        Err(e) => return verification_error(None, &format!("output: {e}")),
+    };
+    let hash = output.hash().to_hex();
+
+    let crypto_factories = crypto_factories();
+    match output.verify_range_proof(&crypto_factories.range_proof) {
+        Ok(()) => OutputVerificationResult {
+            hash: Some(hash),
+            valid: true,
+            error: None,
+        },
+        Err(e) => verification_error(Some(hash), &format!("Range proof verification failed: {e}")),
+    }
+}
+
+/// Verifies the range proofs of a batch of transaction outputs, supplied as a JSON array. Bulletproof+ range proofs
+/// are verified together with the batched verifier, which is considerably faster than verifying each output on its
+/// own. With the `threads` feature, chunks of the batch are verified in parallel. If the batch fails, the outputs are
+/// verified individually to identify the offending outputs.
+#[wasm_bindgen]
+pub fn batch_verify_outputs(outputs: &str) -> BatchVerificationResult {
+    let outputs: Vec<TransactionOutput> = match serde_json::from_str(outputs) {
+        Ok(val) => val,
+        Err(e) => {
+            return BatchVerificationResult {
+                error: Some(format!("outputs: {e}")),
+                ..Default::default()
+            }
+        },
+    };
+
+    let crypto_factories = crypto_factories();
+    match verify_range_proofs(&crypto_factories.range_proof, &outputs) {
+        Ok(()) => BatchVerificationResult {
+            count: outputs.len(),
+            valid: true,
+            invalid: vec![],
+            error: None,
+        },
+        Err(e) => BatchVerificationResult {
+            count: outputs.len(),
+            valid: false,
+            invalid: outputs
+                .iter()
+                .filter(|output| output.verify_range_proof(&crypto_factories.range_proof).is_err())
+                .map(|output| output.hash().to_hex())
+                .collect(),
+            error: Some(format!("Batch range proof verification failed: {e}")),
+        },
+    }
+}
+
+fn metadata_signature_challenge(output: &TransactionOutput) -> [u8; 64] {
+    TransactionOutput::build_metadata_signature_challenge(
+        &output.version,
+        &output.script,
+        &output.features,
+        &output.sender_offset_public_key,
+        output.metadata_signature.ephemeral_commitment(),
+        output.metadata_signature.ephemeral_pubkey(),
+        &output.commitment,
+        &output.covenant,
+        &output.encrypted_data,
+        output.minimum_value_promise,
+    )
+}
+
+/// Checks each half of the metadata signature on its own, to report which one is at fault: the commitment half, signed
+/// by the receiver with the spending key and value, and the public key half, signed by the sender with the sender
+/// offset key
+fn failed_metadata_signature_components(output: &TransactionOutput, challenge: &[u8; 64]) -> Vec<String> {
+    let e = match PrivateKey::from_uniform_bytes(challenge) {
+        Ok(e) => e,
+        Err(_) => return vec!["challenge".to_string()],
+    };
+    let signature = &output.metadata_signature;
+    let mut failed = Vec::new();
+    let commitment_lhs = CommitmentFactory::default().commit(signature.u_x(), signature.u_a());
+    let commitment_rhs = signature.ephemeral_commitment().as_public_key() + &e * output.commitment.as_public_key();
+    if commitment_lhs.as_public_key() != &commitment_rhs {
+        failed.push("commitment".to_string());
+    }
+    let pubkey_lhs = PublicKey::from_secret_key(signature.u_y());
+    let pubkey_rhs = signature.ephemeral_pubkey() + &e * &output.sender_offset_public_key;
+    if pubkey_lhs != pubkey_rhs {
+        failed.push("sender_offset_public_key".to_string());
+    }
+    failed
+}
+
+/// Verifies the metadata signature of a transaction output, e.g. one signed off-device by a hardware wallet, before it
+/// is broadcast. The output is passed in any of the encodings accepted by `scan_output_for_one_sided_payment`. The
+/// result contains the signature challenge and, if verification fails, which half of the signature is invalid.
+#[wasm_bindgen]
+pub fn verify_output_metadata_signature(
+    output: JsValue,
+    encoding: Option<OutputEncoding>,
+) -> MetadataSignatureVerificationResult {
+    let output = match decode_output(&output, encoding) {
+        Ok(val) => val,
+        Err(e) => {
+            return MetadataSignatureVerificationResult {
+                error: Some(e.to_string()),
+                ..Default::default()
+            }
+        },
+    };
+    let challenge = metadata_signature_challenge(&output);
+    let mut result = MetadataSignatureVerificationResult {
+        hash: Some(output.hash().to_hex()),
+        valid: true,
+        challenge: Some(to_hex(&challenge)),
+        failed_components: vec![],
+        error: None,
+    };
+    if let Err(e) = output.verify_metadata_signature() {
+        result.valid = false;
+        result.failed_components = failed_metadata_signature_components(&output, &challenge);
+        result.error = Some(format!("Metadata signature verification failed: {e}"));
+    }
+    result
+}
@@ -0,0 +1,317 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::convert::TryFrom;
+
+use borsh::BorshDeserialize;
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::transaction_components::{
+    OutputFeaturesVersion,
+    TransactionInputVersion,
+    TransactionKernelVersion,
+    TransactionOutput,
+    TransactionOutputVersion,
+};
+use tari_crypto::tari_utilities::hex::{to_hex, Hex};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{output_encoding::MAX_OUTPUT_BYTES, size_budget::check_output_components};
+
+/// The networks that versions can be validated for
+const NETWORKS: [&str; 6] = ["mainnet", "stagenet", "nextnet", "esmeralda", "igor", "localnet"];
+
+/// The highest version number of a versioned type supported by this library
+fn max_version<T: TryFrom<u8>>() -> u8 {
+    (0..=u8::MAX)
+        .take_while(|version| T::try_from(*version).is_ok())
+        .last()
+        .unwrap_or_default()
+}
+
+/// A struct to hold the versions supported by this library
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SupportedVersions {
+    /// The highest transaction output version this library can build
+    pub max_output_version: u8,
+    /// The highest transaction kernel version this library can build
+    pub max_kernel_version: u8,
+    /// The highest transaction input version this library can build
+    pub max_input_version: u8,
+    /// The highest output features version this library can build
+    pub max_output_features_version: u8,
+    /// The transaction output version built by default
+    pub current_output_version: u8,
+    /// The transaction kernel version built by default
+    pub current_kernel_version: u8,
+}
+
+/// Returns the highest versions of transaction outputs, kernels, inputs and output features that this library can
+/// build, as well as the versions built by default
+#[wasm_bindgen]
+pub fn max_supported_versions() -> SupportedVersions {
+    SupportedVersions {
+        max_output_version: max_version::<TransactionOutputVersion>(),
+        max_kernel_version: max_version::<TransactionKernelVersion>(),
+        max_input_version: max_version::<TransactionInputVersion>(),
+        max_output_features_version: max_version::<OutputFeaturesVersion>(),
+        current_output_version: TransactionOutputVersion::get_current_version().as_u8(),
+        current_kernel_version: TransactionKernelVersion::get_current_version().as_u8(),
+    }
+}
+
+/// The version numbers of a versioned type supported by this library
+fn versions<T: TryFrom<u8>>() -> Vec<u8> {
+    (0..=u8::MAX).filter(|version| T::try_from(*version).is_ok()).collect()
+}
+
+/// A struct to hold every version this library can deserialize
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct SupportedVersionSets {
+    /// The transaction output versions
+    pub output_versions: Vec<u8>,
+    /// The transaction kernel versions
+    pub kernel_versions: Vec<u8>,
+    /// The transaction input versions
+    pub input_versions: Vec<u8>,
+    /// The output features versions
+    pub output_features_versions: Vec<u8>,
+}
+
+/// Returns every version of transaction outputs, kernels, inputs and output features that this library can
+/// deserialize, so that a deployed bundle can tell whether it needs updating before a new version activates
+#[wasm_bindgen]
+pub fn get_supported_versions() -> SupportedVersionSets {
+    SupportedVersionSets {
+        output_versions: versions::<TransactionOutputVersion>(),
+        kernel_versions: versions::<TransactionKernelVersion>(),
+        input_versions: versions::<TransactionInputVersion>(),
+        output_features_versions: versions::<OutputFeaturesVersion>(),
+    }
+}
+
+/// A struct to hold an output deserialized in the version-tolerant mode
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct TolerantOutputResult {
+    /// Whether the output's versions are supported, so that it was deserialized
+    pub supported: bool,
+    /// The transaction output version
+    pub version: Option<u8>,
+    /// The output features version, if the output version is supported
+    pub features_version: Option<u8>,
+    /// The hash of the output (hex value), if it was deserialized
+    pub hash: Option<String>,
+    /// The output, serialized as JSON, if it was deserialized
+    pub output: Option<String>,
+    /// The bytes this library cannot interpret (hex value): those following the fields it knows, e.g. fields appended
+    /// by a future version, or, for an unsupported version, everything following the version byte
+    pub opaque_tail: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn tolerant_output_error(error: &str) -> TolerantOutputResult {
+    TolerantOutputResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Deserializes a transaction output from its Borsh bytes without failing on versions or fields this library does
+/// not know, so that a deployed bundle keeps working when a new output version activates. An output or output
+/// features version that is not supported is reported with the bytes following the output version byte; bytes
+/// following a supported output are returned as its opaque tail instead of being rejected.
+#[wasm_bindgen]
+pub fn deserialize_output_tolerant(bytes: &[u8]) -> TolerantOutputResult {
+    if bytes.len() > MAX_OUTPUT_BYTES {
+        return tolerant_output_error(&format!(
+            "The output is {} bytes, exceeding the maximum of {MAX_OUTPUT_BYTES}",
+            bytes.len()
+        ));
+    }
+    let (version, features_version) = match bytes {
+        [version, features_version, ..] => (*version, *features_version),
+        _ => return tolerant_output_error("The output is truncated"),
+    };
+    let unsupported = TransactionOutputVersion::try_from(version).is_err();
+    let features_unsupported = OutputFeaturesVersion::try_from(features_version).is_err();
+    if unsupported || features_unsupported {
+        return TolerantOutputResult {
+            supported: false,
+            version: Some(version),
+            features_version: if unsupported { None } else { Some(features_version) },
+            opaque_tail: Some(to_hex(&bytes[1..])),
+            ..Default::default()
+        };
+    }
+
+    let mut remaining = bytes;
+    let output = match TransactionOutput::deserialize(&mut remaining) {
+        Ok(val) => val,
+        Err(e) => return tolerant_output_error(&format!("Borsh: {e}")),
+    };
+    if let Err(e) = check_output_components(&output.script, &output.covenant, &output.features) {
+        return tolerant_output_error(&e);
+    }
+    match serde_json::to_string(&output) {
+        Ok(json) => TolerantOutputResult {
+            supported: true,
+            version: Some(version),
+            features_version: Some(features_version),
+            hash: Some(output.hash().to_hex()),
+            output: Some(json),
+            opaque_tail: if remaining.is_empty() {
+                None
+            } else {
+                Some(to_hex(remaining))
+            },
+            error: None,
+        },
+        Err(e) => tolerant_output_error(&format!("Could not serialize output: {e}")),
+    }
+}
+
+/// Parses a transaction output version, or returns the current version if not provided
+pub(crate) fn output_version(version: Option<u8>) -> Result<TransactionOutputVersion, String> {
+    version.map_or_else(
+        || Ok(TransactionOutputVersion::get_current_version()),
+        TransactionOutputVersion::try_from,
+    )
+}
+
+/// A versioned transaction component
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub enum VersionedComponent {
+    Output,
+    Kernel,
+}
+
+/// The height from which a network accepts a version of a transaction component
+#[derive(Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionActivation {
+    /// The component, `output` or `kernel`
+    pub component: VersionedComponent,
+    /// The version number
+    pub version: u8,
+    /// The network, e.g. `mainnet`, or all networks if not provided
+    pub network: Option<String>,
+    /// The first height at which the version is accepted
+    pub from_height: u64,
+}
+
+/// The versions accepted by the networks as of this library's consensus rules: version 0 of outputs and kernels, from
+/// the genesis block on
+fn default_activations() -> Vec<VersionActivation> {
+    [VersionedComponent::Output, VersionedComponent::Kernel]
+        .iter()
+        .map(|component| VersionActivation {
+            component: *component,
+            version: 0,
+            network: None,
+            from_height: 0,
+        })
+        .collect()
+}
+
+fn is_accepted(
+    activations: &[VersionActivation],
+    component: VersionedComponent,
+    version: u8,
+    network: &str,
+    height: u64,
+) -> bool {
+    activations.iter().any(|activation| {
+        activation.component == component &&
+            activation.version == version &&
+            activation
+                .network
+                .as_deref()
+                .map_or(true, |name| name.eq_ignore_ascii_case(network)) &&
+            height >= activation.from_height
+    })
+}
+
+/// A struct to hold the result of validating chosen versions
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct VersionValidationResult {
+    /// Whether both versions are accepted
+    pub accepted: bool,
+    /// Whether the transaction output version is accepted
+    pub output_version_accepted: bool,
+    /// Whether the transaction kernel version is accepted
+    pub kernel_version_accepted: bool,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn version_validation_error(error: &str) -> VersionValidationResult {
+    VersionValidationResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Validates that a transaction output and kernel version, as chosen for a transaction, are supported by this library
+/// and accepted on `network` at block `height`.
+/// * `network` - the network name, e.g. `mainnet` or `esmeralda`
+/// * `activations` - a JSON array of `VersionActivation`s, e.g. `[{ "component": "output", "version": 1, "network":
+///   "nextnet", "fromHeight": 100000 }]`, to validate against when a consensus version bump is scheduled; if not
+///   provided, the versions accepted as of this library's consensus rules are used
+#[wasm_bindgen]
+pub fn validate_versions(
+    output_version: u8,
+    kernel_version: u8,
+    network: &str,
+    height: u64,
+    activations: Option<String>,
+) -> VersionValidationResult {
+    if !NETWORKS.iter().any(|name| name.eq_ignore_ascii_case(network)) {
+        return version_validation_error(&format!(
+            "Unknown network `{network}`, expected one of {}",
+            NETWORKS.join(", ")
+        ));
+    }
+    if let Err(e) = TransactionOutputVersion::try_from(output_version) {
+        return version_validation_error(&format!("output_version: {e}"));
+    }
+    if let Err(e) = TransactionKernelVersion::try_from(kernel_version) {
+        return version_validation_error(&format!("kernel_version: {e}"));
+    }
+    let activations = match activations {
+        Some(activations) => match serde_json::from_str::<Vec<VersionActivation>>(&activations) {
+            Ok(val) => val,
+            Err(e) => return version_validation_error(&format!("activations: {e}")),
+        },
+        None => default_activations(),
+    };
+    let output_version_accepted = is_accepted(
+        &activations,
+        VersionedComponent::Output,
+        output_version,
+        network,
+        height,
+    );
+    let kernel_version_accepted = is_accepted(
+        &activations,
+        VersionedComponent::Kernel,
+        kernel_version,
+        network,
+        height,
+    );
+    VersionValidationResult {
+        accepted: output_version_accepted && kernel_version_accepted,
+        output_version_accepted,
+        kernel_version_accepted,
+        error: None,
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! The cipher the console wallet encrypts its database with, so that a browser recovery tool can read the seed of an
+//! uploaded wallet file. The wallet derives a secondary key from the passphrase with Argon2id and the stored salt, and
+//! uses it to decrypt the main key, which in turn decrypts the database values. Both are XChaCha20-Poly1305 with the
+//! nonce prepended to the ciphertext and a domain label as associated data. Reading the values out of the SQLite file
+//! is left to the caller, e.g. with sql.js.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, Payload},
+    Key,
+    KeyInit,
+    XChaCha20Poly1305,
+    XNonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tari_crypto::tari_utilities::hex::{from_hex, Hex};
+use tari_key_manager::cipher_seed::CipherSeed;
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+use zeroize::Zeroizing;
+
+use crate::{
+    cipher_seed_from_hex,
+    mnemonic::{cipher_seed_result, CipherSeedResult},
+};
+
+/// The version of the wallet's key encryption, the only one the console wallet has used
+const WALLET_KEY_VERSION: u8 = 1;
+/// The associated data of the main key, followed by the key version
+const MAIN_KEY_AAD_PREFIX: &str = "wallet_main_key_encryption_v";
+/// The associated data of the master seed
+const MASTER_SEED_AAD: &[u8] = b"wallet_setting_master_seed";
+const SIZE_KEY: usize = 32;
+const SIZE_NONCE: usize = 24;
+const SIZE_TAG: usize = 16;
+const SIZE_SALT: usize = 16;
+
+/// A struct to hold the wallet database values that protect the seed
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct WalletFileCipherResult {
+    /// The salt of the secondary key, the `SecondaryKeySalt` setting
+    pub secondary_key_salt: Option<String>,
+    /// The main key encrypted with the secondary key, the `EncryptedMainKey` setting (hex value)
+    pub encrypted_main_key: Option<String>,
+    /// The seed encrypted with the main key, the `MasterSeed` setting (hex value)
+    pub encrypted_master_seed: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn wallet_file_cipher_error(error: &str) -> WalletFileCipherResult {
+    WalletFileCipherResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn cipher_seed_error(error: &str) -> CipherSeedResult {
+    CipherSeedResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Derives the secondary key from the passphrase with Argon2id, with the console wallet's parameters for key version 1
+fn secondary_key(passphrase: &str, salt: &str) -> Result<Zeroizing<[u8; SIZE_KEY]>, String> {
+    let params = Params::new(
+        46 * 1024, // m-cost is in KiB, so 46 MiB
+        1,         // t-cost
+        1,         // p-cost
+        Some(SIZE_KEY),
+    )
+    .map_err(|e| format!("Invalid Argon2 parameters: {e}"))?;
+    let mut key = Zeroizing::new([0u8; SIZE_KEY]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt.as_bytes(), key.as_mut())
+        .map_err(|e| format!("Could not derive the secondary key: {e}"))?;
+    Ok(key)
+}
+
+fn main_key_aad() -> Vec<u8> {
+    format!("{MAIN_KEY_AAD_PREFIX}{WALLET_KEY_VERSION}").into_bytes()
+}
+
+/// Encrypts `value` as the wallet does, as `nonce || ciphertext`
+fn encrypt_value(key: &[u8], aad: &[u8], value: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(&nonce, Payload { msg: value, aad })
+        .map_err(|e| format!("Could not encrypt value: {e}"))?;
+    let mut encrypted = Vec::with_capacity(SIZE_NONCE + ciphertext.len());
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+/// Decrypts a value encrypted as `nonce || ciphertext`
+fn decrypt_value(key: &[u8], aad: &[u8], encrypted: &[u8]) -> Result<Zeroizing<Vec<u8>>, String> {
+    if encrypted.len() < SIZE_NONCE + SIZE_TAG {
+        return Err("The encrypted value is too short".to_string());
+    }
+    let (nonce, ciphertext) = encrypted.split_at(SIZE_NONCE);
+    XChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map(Zeroizing::new)
+        .map_err(|_| {
+            "Could not decrypt the value, the passphrase is wrong or the value has been tampered with".to_string()
+        })
+}
+
+/// Decrypts the seed of a console wallet database with its passphrase, returning it as `create_cipher_seed` does, to
+/// scan or recover the wallet with
+/// * `secondary_key_salt` - the `SecondaryKeySalt` setting of the database
+/// * `encrypted_main_key` - the `EncryptedMainKey` setting of the database (hex value)
+/// * `encrypted_master_seed` - the `MasterSeed` setting of the database (hex value)
+#[wasm_bindgen]
+pub fn decrypt_wallet_seed(
+    passphrase: &str,
+    secondary_key_salt: &str,
+    encrypted_main_key: &str,
+    encrypted_master_seed: &str,
+) -> CipherSeedResult {
+    let encrypted_main_key = match from_hex(encrypted_main_key) {
+        Ok(val) => val,
+        Err(e) => return cipher_seed_error(&format!("encrypted_main_key: {e}")),
+    };
+    let encrypted_master_seed = match from_hex(encrypted_master_seed) {
+        Ok(val) => val,
+        Err(e) => return cipher_seed_error(&format!("encrypted_master_seed: {e}")),
+    };
+    let secondary_key = match secondary_key(passphrase, secondary_key_salt) {
+        Ok(val) => val,
+        Err(e) => return cipher_seed_error(&e),
+    };
+    let main_key = match decrypt_value(secondary_key.as_ref(), &main_key_aad(), &encrypted_main_key) {
+        Ok(val) if val.len() == SIZE_KEY => val,
+        Ok(_) => return cipher_seed_error("encrypted_main_key: The main key has the wrong length"),
+        Err(e) => return cipher_seed_error(&format!("encrypted_main_key: {e}")),
+    };
+    let seed = match decrypt_value(&main_key, MASTER_SEED_AAD, &encrypted_master_seed) {
+        Ok(val) => val,
+        Err(e) => return cipher_seed_error(&format!("encrypted_master_seed: {e}")),
+    };
+    match CipherSeed::from_enciphered_bytes(&seed, None) {
+        Ok(seed) => cipher_seed_result(&seed),
+        Err(e) => cipher_seed_error(&format!("encrypted_master_seed: {e}")),
+    }
+}
+
+/// Encrypts a cipher seed (hex value) with a passphrase as the console wallet stores it in its database, with a fresh
+/// salt and main key, e.g. to prepare a wallet file for a seed recovered in the browser
+#[wasm_bindgen]
+pub fn encrypt_wallet_seed(passphrase: &str, seed: &str) -> WalletFileCipherResult {
+    let seed = match cipher_seed_from_hex(seed) {
+        Ok(val) => val,
+        Err(e) => return wallet_file_cipher_error(&e),
+    };
+    let seed = match seed.encipher(None) {
+        Ok(val) => Zeroizing::new(val),
+        Err(e) => return wallet_file_cipher_error(&format!("seed: {e}")),
+    };
+    let mut salt = [0u8; SIZE_SALT];
+    OsRng.fill_bytes(&mut salt);
+    let salt = STANDARD_NO_PAD.encode(salt);
+    let mut main_key = Zeroizing::new([0u8; SIZE_KEY]);
+    OsRng.fill_bytes(main_key.as_mut());
+
+    let encrypted = secondary_key(passphrase, &salt).and_then(|secondary_key| {
+        let encrypted_main_key = encrypt_value(secondary_key.as_ref(), &main_key_aad(), main_key.as_ref())?;
+        let encrypted_master_seed = encrypt_value(main_key.as_ref(), MASTER_SEED_AAD, &seed)?;
+        Ok((encrypted_main_key, encrypted_master_seed))
+    });
+    match encrypted {
+        Ok((encrypted_main_key, encrypted_master_seed)) => WalletFileCipherResult {
+            secondary_key_salt: Some(salt),
+            encrypted_main_key: Some(encrypted_main_key.to_hex()),
+            encrypted_master_seed: Some(encrypted_master_seed.to_hex()),
+            error: None,
+        },
+        Err(e) => wallet_file_cipher_error(&e),
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use tari_common::configuration::Network;
+use tari_common_types::{
+    tari_address::{TariAddress, TariAddressFeatures},
+    types::{PrivateKey, PublicKey},
+};
+use tari_core::{
+    consensus::consensus_network,
+    transactions::key_manager::{derive_labelled_key, TransactionKeyManagerBranch, TransactionKeyManagerLabel},
+};
+use tari_crypto::{keys::PublicKey as PK, tari_utilities::hex::Hex};
+use tari_key_manager::{cipher_seed::CipherSeed, key_manager::KeyManager};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::cipher_seed_from_hex;
+
+/// The branch of the wallet's node identity key, which is also the spend key of its address
+const COMMS_BRANCH: &str = "comms";
+/// The maximum number of keys derived per call
+const MAX_DERIVED_KEYS: u64 = 10_000;
+
+/// A struct to hold a key derived from the wallet seed
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct DerivedKeyResult {
+    /// The key branch
+    pub branch: Option<String>,
+    /// The key index
+    pub index: Option<u64>,
+    /// The derived private key (hex value)
+    pub private_key: Option<String>,
+    /// The derived public key (hex value)
+    pub public_key: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn derived_key_error(error: &str) -> DerivedKeyResult {
+    DerivedKeyResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Deterministically derives the private and public key at `index` on `branch` (e.g. `script key` or `commitment
+/// mask`) from the wallet cipher seed, as returned by `create_cipher_seed` or `mnemonic_to_cipher_seed`.
+#[wasm_bindgen]
+pub fn derive_wallet_key(seed: &str, branch: &str, index: u64) -> DerivedKeyResult {
+    derive_key_result(seed, branch, index)
+}
+
+/// Deterministically derives the private and public key at `index` for a second layer (DAN) identity from the wallet
+/// cipher seed, so that one seed backs both base layer and sidechain identities. `key_type` is one of
+/// `validator_node`, `template_author` or `confidential_output`.
+#[wasm_bindgen]
+pub fn derive_sidechain_key(seed: &str, key_type: &str, index: u64) -> DerivedKeyResult {
+    let branch = match key_type {
+        "validator_node" => TransactionKeyManagerBranch::ValidatorNode,
+        "template_author" => TransactionKeyManagerBranch::TemplateAuthor,
+        "confidential_output" => TransactionKeyManagerBranch::ConfidentialOutput,
+        _ => return derived_key_error(&format!("Unknown sidechain key type `{key_type}`")),
+    };
+    derive_key_result(seed, &branch.get_branch_key(), index)
+}
+
+fn derive_key_result(seed: &str, branch: &str, index: u64) -> DerivedKeyResult {
+    let seed = match cipher_seed_from_hex(seed) {
+        Ok(val) => val,
+        Err(e) => return derived_key_error(&e),
+    };
+
+    let key_manager = KeyManager::<PublicKey>::from(seed, branch.to_string(), 0);
+    match key_manager.derive_key(index) {
+        Ok(derived_key) => DerivedKeyResult {
+            branch: Some(branch.to_string()),
+            index: Some(index),
+            private_key: Some(derived_key.key.to_hex()),
+            public_key: Some(PublicKey::from_secret_key(&derived_key.key).to_hex()),
+            error: None,
+        },
+        Err(e) => derived_key_error(&format!("Could not derive key: {e}")),
+    }
+}
+
+/// A struct to hold the keys and address of a wallet derived from its seed
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct WalletAddressesResult {
+    /// The private view key of the console wallet's dual-key address (hex value)
+    pub view_key: Option<String>,
+    /// The public view key (hex value)
+    pub view_pk: Option<String>,
+    /// The private spend key (hex value)
+    pub spend_key: Option<String>,
+    /// The public spend key (hex value)
+    pub spend_pk: Option<String>,
+    /// The private key of the wallet's node identity (hex value)
+    pub comms_key: Option<String>,
+    /// The public key of the wallet's node identity (hex value)
+    pub comms_pk: Option<String>,
+    /// The network of the address, e.g. `mainnet`
+    pub network: Option<String>,
+    /// The dual-key address of the wallet in base58
+    pub base58: Option<String>,
+    /// The dual-key address of the wallet in emoji
+    pub emoji: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn wallet_addresses_error(error: &str) -> WalletAddressesResult {
+    WalletAddressesResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Derives the key at index 0 of `branch`
+fn derive_branch_key(seed: &CipherSeed, branch: &str) -> Result<PrivateKey, String> {
+    KeyManager::<PublicKey>::from(seed.clone(), branch.to_string(), 0)
+        .derive_key(0)
+        .map(|derived_key| derived_key.key)
+        .map_err(|e| format!("Could not derive {branch} key: {e}"))
+}
+
+/// Derives the view, spend and node identity keys of a wallet from the wallet cipher seed, as the console wallet does:
+/// the view key is the first key of the `data encryption` branch, and the spend key is the node identity key, the first
+/// key of the `comms` branch. The address is the wallet's dual-key address of the view and spend keys, as the console
+/// wallet shows it.
+/// * `network` - the network of the address, e.g. `mainnet`; the library's network (see `set_network`) if not provided
+#[wasm_bindgen]
+pub fn derive_wallet_addresses(seed: &str, network: Option<String>) -> WalletAddressesResult {
+    let seed = match cipher_seed_from_hex(seed) {
+        Ok(val) => val,
+        Err(e) => return wallet_addresses_error(&e),
+    };
+    let network = match network.as_deref().map(Network::from_str).transpose() {
+        Ok(val) => val.unwrap_or_else(consensus_network),
+        Err(e) => return wallet_addresses_error(&format!("network: {e}")),
+    };
+    let view_key = match derive_branch_key(&seed, &TransactionKeyManagerBranch::DataEncryption.get_branch_key()) {
+        Ok(val) => val,
+        Err(e) => return wallet_addresses_error(&e),
+    };
+    let comms_key = match derive_branch_key(&seed, COMMS_BRANCH) {
+        Ok(val) => val,
+        Err(e) => return wallet_addresses_error(&e),
+    };
+    let view_pk = PublicKey::from_secret_key(&view_key);
+    let comms_pk = PublicKey::from_secret_key(&comms_key);
+    let address = TariAddress::new_dual_address(
+        view_pk.clone(),
+        comms_pk.clone(),
+        network,
+        TariAddressFeatures::default(),
+    );
+    WalletAddressesResult {
+        view_key: Some(view_key.to_hex()),
+        view_pk: Some(view_pk.to_hex()),
+        spend_key: Some(comms_key.to_hex()),
+        spend_pk: Some(comms_pk.to_hex()),
+        comms_key: Some(comms_key.to_hex()),
+        comms_pk: Some(comms_pk.to_hex()),
+        network: Some(network.to_string()),
+        base58: Some(address.to_base58()),
+        emoji: Some(address.to_emoji_string()),
+        error: None,
+    }
+}
+
+/// A key at an index of a key manager branch
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchKey {
+    /// The key index
+    pub index: u64,
+    /// The derived private key (hex value)
+    pub private_key: String,
+    /// The derived public key (hex value)
+    pub public_key: String,
+}
+
+/// A struct to hold a range of keys derived from the wallet seed
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct DerivedKeysResult {
+    /// The key branch
+    pub branch: Option<String>,
+    /// Whether the branch holds keys for second layer (DAN) identities, e.g. `validator node`, which must not be used
+    /// for base layer transactions
+    pub sidechain: Option<bool>,
+    /// The derived keys, in index order
+    pub keys: Vec<BranchKey>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn derived_keys_error(error: &str) -> DerivedKeysResult {
+    DerivedKeysResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Returns the names of the key manager branches `derive_keys_for_branch` derives keys on: the transaction key
+/// manager's branches, `comms` for the node identity key, and `script key` for the script keys of the wallet's
+/// outputs, which are derived from the `commitment mask` keys at the same index
+#[wasm_bindgen]
+pub fn get_key_branches() -> Vec<String> {
+    TransactionKeyManagerBranch::iter()
+        .map(|branch| branch.get_branch_key())
+        .chain([
+            COMMS_BRANCH.to_string(),
+            TransactionKeyManagerLabel::ScriptKey.get_branch_key(),
+        ])
+        .collect()
+}
+
+/// Deterministically derives the private and public keys at `count` consecutive indexes from `start_index` on
+/// `branch` (see `get_key_branches`) from the wallet cipher seed, e.g. to regenerate the known script keys to scan with
+/// from the `script key` branch. At most 10,000 keys are derived per call.
+#[wasm_bindgen]
+pub async fn derive_keys_for_branch(seed: &str, branch: &str, start_index: u64, count: u64) -> DerivedKeysResult {
+    let seed = match cipher_seed_from_hex(seed) {
+        Ok(val) => val,
+        Err(e) => return derived_keys_error(&e),
+    };
+    if count > MAX_DERIVED_KEYS {
+        return derived_keys_error(&format!(
+            "count: at most {MAX_DERIVED_KEYS} keys can be derived per call"
+        ));
+    }
+    let end_index = match start_index.checked_add(count) {
+        Some(val) => val,
+        None => return derived_keys_error("count: the index range overflows"),
+    };
+    let transaction_branch = TransactionKeyManagerBranch::iter().find(|b| b.get_branch_key() == branch);
+    let keys = if branch == TransactionKeyManagerLabel::ScriptKey.get_branch_key() {
+        let key_manager = commitment_mask_key_manager(seed);
+        (start_index..end_index)
+            .map(|index| derive_script_key(&key_manager, index).map(|key| (index, key)))
+            .collect()
+    } else if branch == COMMS_BRANCH || transaction_branch.is_some() {
+        let key_manager = KeyManager::<PublicKey>::from(seed, branch.to_string(), 0);
+        (start_index..end_index)
+            .map(|index| {
+                key_manager
+                    .derive_key(index)
+                    .map(|derived_key| (index, derived_key.key))
+                    .map_err(|e| format!("Could not derive key: {e}"))
+            })
+            .collect()
+    } else {
+        Err(format!("Unknown key branch `{branch}`"))
+    };
+    match keys {
+        Ok(keys) => DerivedKeysResult {
+            branch: Some(branch.to_string()),
+            sidechain: Some(transaction_branch.map_or(false, |b| b.is_sidechain_branch())),
+            keys: keys
+                .into_iter()
+                .map(|(index, key)| BranchKey {
+                    index,
+                    private_key: key.to_hex(),
+                    public_key: PublicKey::from_secret_key(&key).to_hex(),
+                })
+                .collect(),
+            error: None,
+        },
+        Err(e) => derived_keys_error(&e),
+    }
+}
+
+/// Derives the script key of the wallet's output at `index` from the `commitment mask` key manager, as the key manager
+/// derives it from the commitment mask at the same index
+pub(crate) fn derive_script_key(
+    commitment_mask_key_manager: &KeyManager<PublicKey>,
+    index: u64,
+) -> Result<PrivateKey, String> {
+    let commitment_mask = commitment_mask_key_manager
+        .derive_key(index)
+        .map_err(|e| format!("Could not derive key: {e}"))?
+        .key;
+    derive_labelled_key(
+        &TransactionKeyManagerLabel::ScriptKey.get_branch_key(),
+        &commitment_mask,
+    )
+    .map_err(|e| format!("Could not derive key: {e}"))
+}
+
+/// The key manager of the `commitment mask` branch, which the script keys are derived from
+pub(crate) fn commitment_mask_key_manager(seed: CipherSeed) -> KeyManager<PublicKey> {
+    KeyManager::<PublicKey>::from(seed, TransactionKeyManagerBranch::CommitmentMask.get_branch_key(), 0)
+}
@@ -0,0 +1,257 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Import and export of recovered outputs as `UnblindedOutput`s, the JSON schema the console wallet exports UTXOs in
+//! and imports them from, so that outputs recovered in the browser can be spent from a desktop wallet and vice versa.
+//! The schema carries no range proof, so only an output with a revealed value can be rebuilt from it exactly; other
+//! outputs are identified by their commitment.
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::PrivateKey;
+use tari_core::{
+    limited_reader::LimitedBytesReader,
+    transactions::transaction_components::{RangeProofType, TransactionOutput, UnblindedOutput},
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    tari_utilities::hex::{from_hex, Hex},
+};
+use tari_script::ExecutionStack;
+use tsify::Tsify;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    factories::crypto_factories,
+    lock_heights,
+    output_encoding::{decode_output, OutputEncoding, MAX_OUTPUT_BYTES},
+    size_budget::check_output_components,
+};
+
+/// The maximum size of the Borsh bytes of an exported output, an output with its keys and input data
+const MAX_WALLET_OUTPUT_BYTES: usize = 2 * MAX_OUTPUT_BYTES;
+
+/// A struct to hold an exported output
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct WalletOutputExportResult {
+    /// The exported output, as JSON for `wallet_output_to_json` and as hex-encoded Borsh bytes for
+    /// `wallet_output_to_borsh`
+    pub data: Option<String>,
+    /// The hash of the output (hex value)
+    pub hash: Option<String>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn wallet_output_export_error(error: &str) -> WalletOutputExportResult {
+    WalletOutputExportResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// A struct to hold an imported output
+#[derive(Debug, Default, Serialize, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(into_wasm_abi)]
+pub struct WalletOutputImportResult {
+    /// The output, serialized as JSON, if it has a revealed value and so no range proof
+    pub output: Option<String>,
+    /// The hash of the output (hex value), if it has a revealed value
+    pub hash: Option<String>,
+    /// The commitment of the output (hex value)
+    pub commitment: Option<String>,
+    /// The output value
+    pub value: Option<u64>,
+    /// The output spending private key (hex value)
+    pub spending_key: Option<String>,
+    /// The script private key (hex value)
+    pub script_key: Option<String>,
+    /// The serialized execution stack the script is run with (hex value)
+    pub input_data: Option<String>,
+    /// The height lock of the output's script
+    pub script_lock_height: Option<u64>,
+    /// An error message in case of an error
+    pub error: Option<String>,
+}
+
+fn wallet_output_import_error(error: &str) -> WalletOutputImportResult {
+    WalletOutputImportResult {
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Combines a recovered output with its value and keys, checking that they open its commitment
+fn unblinded_output(
+    output: &JsValue,
+    encoding: Option<OutputEncoding>,
+    value: u64,
+    spending_key: &str,
+    script_key: &str,
+    input_data: Option<&str>,
+) -> Result<(UnblindedOutput, TransactionOutput), String> {
+    let output = decode_output(output, encoding).map_err(|e| format!("output: {e}"))?;
+    let spending_key = PrivateKey::from_hex(spending_key).map_err(|e| format!("spending_key: {e}"))?;
+    let script_key = PrivateKey::from_hex(script_key).map_err(|e| format!("script_key: {e}"))?;
+    let input_data = input_data
+        .map(ExecutionStack::from_hex)
+        .transpose()
+        .map_err(|e| format!("input_data: {e}"))?
+        .unwrap_or_default();
+    if !crypto_factories()
+        .commitment
+        .open_value(&spending_key, value, &output.commitment)
+    {
+        return Err("The spending key and value do not open the output's commitment".to_string());
+    }
+    let (_, script_lock_height, _) = lock_heights(&output);
+    let unblinded = UnblindedOutput::new(
+        output.version,
+        value.into(),
+        spending_key,
+        output.features.clone(),
+        output.script.clone(),
+        input_data,
+        script_key,
+        output.sender_offset_public_key.clone(),
+        output.metadata_signature.clone(),
+        script_lock_height.unwrap_or(0),
+        output.covenant.clone(),
+        output.encrypted_data.clone(),
+        output.minimum_value_promise,
+    );
+    Ok((unblinded, output))
+}
+
+/// Exports a recovered output as the JSON the console wallet imports UTXOs from (`import-utxos`)
+/// * `value` - the value of the output, as recovered by the scanner
+/// * `spending_key` - the commitment mask of the output (hex value)
+/// * `script_key` - the script private key of the output (hex value)
+/// * `input_data` - the serialized execution stack the script is run with (hex value), empty if not provided
+/// * `encoding` - the encoding of `output` (see `OutputEncoding`), detected if not provided
+#[wasm_bindgen]
+pub fn wallet_output_to_json(
+    output: JsValue,
+    value: u64,
+    spending_key: String,
+    script_key: String,
+    input_data: Option<String>,
+    encoding: Option<OutputEncoding>,
+) -> WalletOutputExportResult {
+    let (unblinded, output) = match unblinded_output(
+        &output,
+        encoding,
+        value,
+        &spending_key,
+        &script_key,
+        input_data.as_deref(),
+    ) {
+        Ok(val) => val,
+        Err(e) => return wallet_output_export_error(&e),
+    };
+    match serde_json::to_string(&unblinded) {
+        Ok(json) => WalletOutputExportResult {
+            data: Some(json),
+            hash: Some(output.hash().to_hex()),
+            error: None,
+        },
+        Err(e) => wallet_output_export_error(&format!("Could not serialize output: {e}")),
+    }
+}
+
+/// Exports a recovered output as the Borsh bytes of an `UnblindedOutput` (hex value), a compact form of the JSON
+/// returned by `wallet_output_to_json`. The parameters are those of `wallet_output_to_json`.
+#[wasm_bindgen]
+pub fn wallet_output_to_borsh(
+    output: JsValue,
+    value: u64,
+    spending_key: String,
+    script_key: String,
+    input_data: Option<String>,
+    encoding: Option<OutputEncoding>,
+) -> WalletOutputExportResult {
+    let (unblinded, output) = match unblinded_output(
+        &output,
+        encoding,
+        value,
+        &spending_key,
+        &script_key,
+        input_data.as_deref(),
+    ) {
+        Ok(val) => val,
+        Err(e) => return wallet_output_export_error(&e),
+    };
+    match borsh::to_vec(&unblinded) {
+        Ok(bytes) => WalletOutputExportResult {
+            data: Some(bytes.to_hex()),
+            hash: Some(output.hash().to_hex()),
+            error: None,
+        },
+        Err(e) => wallet_output_export_error(&format!("Could not serialize output: {e}")),
+    }
+}
+
+/// Returns the value, keys and commitment of an imported output, and the output itself if it has a revealed value
+fn import_result(unblinded: UnblindedOutput) -> WalletOutputImportResult {
+    if let Err(e) = check_output_components(&unblinded.script, &unblinded.covenant, &unblinded.features) {
+        return wallet_output_import_error(&e);
+    }
+    let commitment = crypto_factories()
+        .commitment
+        .commit_value(&unblinded.spending_key, unblinded.value.as_u64());
+    let output = (unblinded.features.range_proof_type == RangeProofType::RevealedValue).then(|| {
+        TransactionOutput::new(
+            unblinded.version,
+            unblinded.features.clone(),
+            commitment.clone(),
+            None,
+            unblinded.script.clone(),
+            unblinded.sender_offset_public_key.clone(),
+            unblinded.metadata_signature.clone(),
+            unblinded.covenant.clone(),
+            unblinded.encrypted_data,
+            unblinded.minimum_value_promise,
+        )
+    });
+    let json = match output.as_ref().map(serde_json::to_string).transpose() {
+        Ok(val) => val,
+        Err(e) => return wallet_output_import_error(&format!("Could not serialize output: {e}")),
+    };
+    WalletOutputImportResult {
+        output: json,
+        hash: output.map(|output| output.hash().to_hex()),
+        commitment: Some(commitment.to_hex()),
+        value: Some(unblinded.value.as_u64()),
+        spending_key: Some(unblinded.spending_key.to_hex()),
+        script_key: Some(unblinded.script_private_key.to_hex()),
+        input_data: Some(unblinded.input_data.to_hex()),
+        script_lock_height: Some(unblinded.script_lock_height),
+        error: None,
+    }
+}
+
+/// Parses an output exported by the console wallet (`export-utxos`) or by `wallet_output_to_json`. An output with a
+/// range proof cannot be rebuilt, so only its commitment is returned, to look it up with.
+#[wasm_bindgen]
+pub fn parse_wallet_output_json(json: &str) -> WalletOutputImportResult {
+    match serde_json::from_str::<UnblindedOutput>(json) {
+        Ok(unblinded) => import_result(unblinded),
+        Err(e) => wallet_output_import_error(&format!("json: {e}")),
+    }
+}
+
+/// Parses an output exported by `wallet_output_to_borsh` (hex value), as `parse_wallet_output_json` does
+#[wasm_bindgen]
+pub fn parse_wallet_output_borsh(bytes: &str) -> WalletOutputImportResult {
+    let bytes = match from_hex(bytes) {
+        Ok(val) => val,
+        Err(e) => return wallet_output_import_error(&format!("bytes: {e}")),
+    };
+    let mut reader = LimitedBytesReader::new(MAX_WALLET_OUTPUT_BYTES, &bytes);
+    match borsh::from_reader::<_, UnblindedOutput>(&mut reader) {
+        Ok(unblinded) => import_result(unblinded),
+        Err(e) => wallet_output_import_error(&format!("bytes: Borsh: {e}")),
+    }
+}
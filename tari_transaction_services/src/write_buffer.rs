@@ -0,0 +1,231 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Write-behind buffering of storage writes.
+//!
+//! Writing every recovered output in its own IndexedDB transaction makes scanning I/O bound in real browsers. A
+//! [`WriteBuffer`] coalesces the updates per key and hands them to a JavaScript callback in batches, at most once per
+//! flush interval unless a batch fills up. The scan position is recorded with [`WriteBuffer::set_checkpoint`] and
+//! passed along with the last batch of a flush, so that the callback can store it in the same transaction: after a
+//! crash the store then holds every update up to its stored checkpoint, and the scan resumes from there. Updates must
+//! be idempotent, as updates made after the checkpoint may have been stored as well and are made again when resuming.
+//!
+//! If compression is enabled, string and `Uint8Array` values are written as compressed entries in a versioned format,
+//! which [`WriteBuffer::decode_value`] reads back, along with values written before compression was enabled.
+
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use js_sys::{Array, Date, Function, Promise, Uint8Array};
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
+
+use crate::{
+    compression::{compress_entry, decompress_entry, EntryContent},
+    session_guard::SessionGuard,
+};
+
+#[derive(Default)]
+struct BufferState {
+    // The latest update per key, not yet written
+    pending: BTreeMap<String, JsValue>,
+    // The checkpoint set since the last flush, if any
+    checkpoint: Option<JsValue>,
+    // The checkpoint written by the last successful flush
+    committed_checkpoint: Option<JsValue>,
+    last_flush_ms: f64,
+}
+
+impl BufferState {
+    // Puts back the updates and checkpoint of a failed flush, unless they have been superseded in the meantime
+    fn requeue(&mut self, entries: impl Iterator<Item = (String, JsValue)>, checkpoint: Option<JsValue>) {
+        for (key, value) in entries {
+            self.pending.entry(key).or_insert(value);
+        }
+        if self.checkpoint.is_none() {
+            self.checkpoint = checkpoint;
+        }
+    }
+}
+
+/// Calls the write callback with a batch of `[key, value]` pairs and the checkpoint, or `undefined` if the batch is
+/// not the last of a flush, and awaits the result if the callback returns a promise
+async fn write_batch(
+    callback: &Function,
+    entries: &[(String, JsValue)],
+    checkpoint: Option<&JsValue>,
+) -> Result<(), JsValue> {
+    let batch = entries
+        .iter()
+        .map(|(key, value)| Array::of2(&JsValue::from_str(key), value))
+        .collect::<Array>();
+    let checkpoint = checkpoint.cloned().unwrap_or(JsValue::UNDEFINED);
+    let result = callback.call2(&JsValue::NULL, &batch, &checkpoint)?;
+    if let Some(promise) = result.dyn_ref::<Promise>() {
+        JsFuture::from(promise.clone()).await?;
+    }
+    Ok(())
+}
+
+/// Compresses a string or `Uint8Array` value into a `Uint8Array` entry, other values are written as is
+fn encode_value(value: JsValue) -> JsValue {
+    if let Some(text) = value.as_string() {
+        return Uint8Array::from(compress_entry(EntryContent::Text, text.as_bytes()).as_slice()).into();
+    }
+    if let Some(bytes) = value.dyn_ref::<Uint8Array>() {
+        return Uint8Array::from(compress_entry(EntryContent::Bytes, &bytes.to_vec()).as_slice()).into();
+    }
+    value
+}
+
+/// Coalesces per-key storage updates, e.g. of recovered outputs by output hash, into batched writes, see the module
+/// documentation. The `write` callback is called with an array of `[key, value]` pairs and the checkpoint to store
+/// along with them, and may return a promise; it should write both in a single IndexedDB transaction.
+#[wasm_bindgen]
+pub struct WriteBuffer {
+    state: Rc<RefCell<BufferState>>,
+    write: Function,
+    max_batch_size: usize,
+    flush_interval_ms: f64,
+    compress: bool,
+    guard: SessionGuard,
+}
+
+impl WriteBuffer {
+    // Writes all pending updates in batches of at most `max_batch_size`, with the checkpoint passed along with the
+    // last batch, returning the number of updates written
+    async fn flush_pending(
+        state: Rc<RefCell<BufferState>>,
+        write: Function,
+        max_batch_size: usize,
+        guard: SessionGuard,
+    ) -> Result<usize, JsValue> {
+        // Flushes are written one after the other, so that checkpoints are stored in order
+        let _permit = guard.acquire("flush").await;
+        let (mut entries, checkpoint) = {
+            let mut state = state.borrow_mut();
+            state.last_flush_ms = Date::now();
+            let entries = std::mem::take(&mut state.pending).into_iter().collect::<Vec<_>>();
+            (entries, state.checkpoint.take())
+        };
+        if entries.is_empty() && checkpoint.is_none() {
+            return Ok(0);
+        }
+        let mut written = 0;
+        loop {
+            let end = (written + max_batch_size).min(entries.len());
+            let last = end == entries.len();
+            let batch_checkpoint = if last { checkpoint.as_ref() } else { None };
+            let result = write_batch(&write, &entries[written..end], batch_checkpoint).await;
+            if let Err(e) = result {
+                state.borrow_mut().requeue(entries.drain(written..), checkpoint);
+                return Err(e);
+            }
+            written = end;
+            if last {
+                break;
+            }
+        }
+        if checkpoint.is_some() {
+            state.borrow_mut().committed_checkpoint = checkpoint;
+        }
+        Ok(written)
+    }
+
+    fn flush_promise(&self) -> Promise {
+        let state = self.state.clone();
+        let write = self.write.clone();
+        let max_batch_size = self.max_batch_size;
+        let guard = self.guard.clone();
+        future_to_promise(async move {
+            let written = Self::flush_pending(state, write, max_batch_size, guard).await?;
+            Ok(JsValue::from(written))
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl WriteBuffer {
+    /// Creates a write buffer that writes batches of at most `max_batch_size` updates through the `write` callback,
+    /// flushing at most once every `flush_interval_ms` milliseconds unless a batch fills up. If `compress` is set,
+    /// string and `Uint8Array` values are compressed, see `decode_value`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(write: Function, max_batch_size: usize, flush_interval_ms: f64, compress: Option<bool>) -> WriteBuffer {
+        Self {
+            state: Rc::new(RefCell::new(BufferState {
+                last_flush_ms: Date::now(),
+                ..Default::default()
+            })),
+            write,
+            max_batch_size: max_batch_size.max(1),
+            flush_interval_ms,
+            compress: compress.unwrap_or(false),
+            guard: SessionGuard::new(),
+        }
+    }
+
+    /// Queues an update of `key`, replacing any pending update of the same key. Returns whether a flush is due.
+    pub fn put(&self, key: String, value: JsValue) -> bool {
+        let value = if self.compress { encode_value(value) } else { value };
+        self.state.borrow_mut().pending.insert(key, value);
+        self.is_flush_due()
+    }
+
+    /// Reads back a value written by a buffer with compression enabled, as the string or `Uint8Array` that was put.
+    /// Values that are not compressed entries, e.g. as written before compression was enabled, are returned as is.
+    pub fn decode_value(value: JsValue) -> Result<JsValue, JsValue> {
+        let bytes = match value.dyn_ref::<Uint8Array>() {
+            Some(val) => val.to_vec(),
+            None => return Ok(value),
+        };
+        match decompress_entry(&bytes).map_err(|e| JsValue::from_str(&e))? {
+            Some((EntryContent::Text, data)) => String::from_utf8(data)
+                .map(JsValue::from)
+                .map_err(|e| JsValue::from_str(&format!("The entry is not valid text: {e}"))),
+            Some((EntryContent::Bytes, data)) => Ok(Uint8Array::from(data.as_slice()).into()),
+            None => Ok(value),
+        }
+    }
+
+    /// Records the scan position reached, e.g. a block height, once all updates up to it have been queued. The
+    /// checkpoint is written with the next flush.
+    pub fn set_checkpoint(&self, checkpoint: JsValue) {
+        self.state.borrow_mut().checkpoint = Some(checkpoint);
+    }
+
+    /// The checkpoint written by the last successful flush, from which a scan can resume after a crash
+    pub fn committed_checkpoint(&self) -> JsValue {
+        self.state
+            .borrow()
+            .committed_checkpoint
+            .clone()
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+
+    /// The number of updates not yet written
+    pub fn pending(&self) -> usize {
+        self.state.borrow().pending.len()
+    }
+
+    /// Whether a batch has filled up, or updates have been pending for at least the flush interval
+    pub fn is_flush_due(&self) -> bool {
+        let state = self.state.borrow();
+        !state.pending.is_empty() &&
+            (state.pending.len() >= self.max_batch_size ||
+                Date::now() - state.last_flush_ms >= self.flush_interval_ms)
+    }
+
+    /// Writes all pending updates and the checkpoint. Returns a promise of the number of updates written; if the
+    /// callback fails, the updates not written are queued again and the promise is rejected with the callback's error.
+    pub fn flush(&self) -> Promise {
+        self.flush_promise()
+    }
+
+    /// Flushes as per `flush` if a flush is due, otherwise resolves to 0 without writing
+    pub fn flush_if_due(&self) -> Promise {
+        if self.is_flush_due() {
+            self.flush_promise()
+        } else {
+            Promise::resolve(&JsValue::from(0))
+        }
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use once_cell::sync::Lazy;
+use tari_crypto::tari_utilities::ByteArray;
+use thiserror::Error;
+
+use crate::{
+    dammsum::{compute_checksum, validate_checksum},
+    types::PublicKey,
+};
+
+/// The number of emoji in the emoji set, one per byte value
+pub const DICT_SIZE: usize = 256;
+
+/// The emoji set used to display bytes, e.g. of addresses and public keys, as emoji. Each byte value maps to the emoji
+/// at its index. This must match upstream Tari's set exactly, or emoji IDs and addresses will not interoperate with
+/// other Tari wallets.
+pub const EMOJI: [char; DICT_SIZE] = [
+    '🌀', '🌂', '🌈', '🌊', '🌋', '🌍', '🌙', '🌝', '🌞', '🌟', '🌠', '🌰', '🌴', '🌵', '🌷', '🌸', '🌹', '🌻', '🌽',
+    '🍀', '🍁', '🍄', '🥑', '🍆', '🍇', '🍈', '🍉', '🍊', '🍋', '🍌', '🍍', '🍎', '🍐', '🍑', '🍒', '🍓', '🍔', '🍕',
+    '🍗', '🍚', '🍞', '🍟', '🥝', '🍣', '🍦', '🍩', '🍪', '🍫', '🍬', '🍭', '🍯', '🥐', '🍳', '🥄', '🍵', '🍶', '🍷',
+    '🍸', '🍾', '🍺', '🍼', '🎀', '🎁', '🎂', '🎃', '🤖', '🎈', '🎉', '🎒', '🎓', '🎠', '🎡', '🎢', '🎣', '🎤', '🎥',
+    '🎧', '🎨', '🎩', '🎪', '🎬', '🎭', '🎮', '🎰', '🎱', '🎲', '🎳', '🎵', '🎷', '🎸', '🎹', '🎺', '🎻', '🎼', '🎽',
+    '🎾', '🎿', '🏀', '🏁', '🏆', '🏈', '⚽', '🏠', '🏥', '🏦', '🏭', '🏰', '🐀', '🐉', '🐊', '🐌', '🐍', '🦁', '🐐',
+    '🐑', '🐔', '🙈', '🐗', '🐘', '🐙', '🐚', '🐛', '🐜', '🐝', '🐞', '🦋', '🐣', '🐨', '🦀', '🐪', '🐬', '🐭', '🐮',
+    '🐯', '🐰', '🦆', '🦂', '🐴', '🐵', '🐶', '🐷', '🐸', '🐺', '🐻', '🐼', '🐽', '🐾', '👀', '👅', '👑', '👒', '🧢',
+    '💅', '👕', '👖', '👗', '👘', '👙', '💃', '👛', '👞', '👟', '👠', '🥊', '👢', '👣', '🤡', '👻', '👽', '👾', '🤠',
+    '👃', '💄', '💈', '💉', '💊', '💋', '👂', '💍', '💎', '💐', '💔', '🔒', '🧩', '💡', '💣', '💤', '💦', '💨', '💩',
+    '➕', '💯', '💰', '💳', '💵', '💺', '💻', '💼', '📈', '📜', '📌', '📎', '📖', '📿', '📡', '⏰', '📱', '📷', '🔋',
+    '🔌', '🚰', '🔑', '🔔', '🔥', '🔦', '🔧', '🔨', '🔩', '🔪', '🔫', '🔬', '🔭', '🔮', '🔱', '🗽', '😂', '😇', '😈',
+    '🤑', '😍', '😎', '😱', '😷', '🤢', '👍', '👶', '🚀', '🚁', '🚂', '🚚', '🚑', '🚒', '🚓', '🛵', '🚗', '🚜', '🚢',
+    '🚦', '🚧', '🚨', '🚪', '🚫', '🚲', '🚽', '🚿', '🧲',
+];
+
+/// The reverse lookup of the emoji set, from emoji to byte value
+pub static REVERSE_EMOJI: Lazy<HashMap<char, u8>> = Lazy::new(|| {
+    EMOJI
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (*c, u8::try_from(i).expect("the emoji set has 256 entries")))
+        .collect()
+});
+
+/// Encodes bytes as a string of emoji, one per byte
+pub fn bytes_to_emoji_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| EMOJI[usize::from(*b)]).collect()
+}
+
+/// Decodes a string of emoji into bytes, one per emoji, returning `None` if the string contains a character that is
+/// not in the emoji set
+pub fn emoji_string_to_bytes(emoji: &str) -> Option<Vec<u8>> {
+    emoji.chars().map(|c| REVERSE_EMOJI.get(&c).copied()).collect()
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EmojiIdError {
+    #[error("Invalid size")]
+    InvalidSize,
+    #[error("Invalid emoji character")]
+    InvalidEmoji,
+    #[error("Invalid checksum")]
+    InvalidChecksum,
+    #[error("Cannot recover public key")]
+    CannotRecoverPublicKey,
+}
+
+/// The emoji ID of a public key: one emoji per byte of the key, followed by the emoji of a DammSum checksum byte, so
+/// that typos are detected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmojiId(PublicKey);
+
+impl EmojiId {
+    /// The number of emoji in an emoji ID
+    pub const LEN: usize = 32 + 1;
+
+    pub fn from_public_key(public_key: &PublicKey) -> Self {
+        Self(public_key.clone())
+    }
+
+    pub fn as_public_key(&self) -> &PublicKey {
+        &self.0
+    }
+
+    pub fn to_emoji_string(&self) -> String {
+        let mut bytes = self.0.as_bytes().to_vec();
+        bytes.push(compute_checksum(&bytes));
+        bytes_to_emoji_string(&bytes)
+    }
+}
+
+impl FromStr for EmojiId {
+    type Err = EmojiIdError;
+
+    fn from_str(emoji: &str) -> Result<Self, Self::Err> {
+        let emoji = emoji.trim();
+        if emoji.chars().count() != Self::LEN {
+            return Err(EmojiIdError::InvalidSize);
+        }
+        let bytes = emoji_string_to_bytes(emoji).ok_or(EmojiIdError::InvalidEmoji)?;
+        validate_checksum(&bytes).map_err(|_| EmojiIdError::InvalidChecksum)?;
+        PublicKey::from_canonical_bytes(&bytes[0..Self::LEN - 1])
+            .map(Self)
+            .map_err(|_| EmojiIdError::CannotRecoverPublicKey)
+    }
+}
+
+impl Display for EmojiId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_emoji_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use tari_crypto::tari_utilities::hex::Hex;
+
+    use super::*;
+
+    #[test]
+    fn emoji_set_is_unique() {
+        assert_eq!(EMOJI.iter().collect::<HashSet<_>>().len(), DICT_SIZE);
+        assert_eq!(REVERSE_EMOJI.len(), DICT_SIZE);
+    }
+
+    #[test]
+    fn emoji_set_matches_upstream() {
+        assert_eq!(
+            bytes_to_emoji_string(&[0x00, 0x01, 0x16, 0x41, 0x65, 0xbe, 0xe4, 0xff]),
+            "🌀🌂🥑🤖⚽➕🤑🧲"
+        );
+        assert_eq!(
+            emoji_string_to_bytes("🌀🌂🥑🤖⚽➕🤑🧲"),
+            Some(vec![0x00, 0x01, 0x16, 0x41, 0x65, 0xbe, 0xe4, 0xff])
+        );
+        assert_eq!(emoji_string_to_bytes("🌀a"), None);
+    }
+
+    #[test]
+    fn emoji_id_known_vector() {
+        // The Ristretto base point
+        let public_key =
+            PublicKey::from_hex("e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76").unwrap();
+        let emoji = "😇🚓💉🌠🏰💨🎩🐐👽🐮👾🏀💼🌀🎭🎾🎷😈🌰🏰👣🐬🔭🐸🔒🤡🎸🎓🗽🐸🍩🐘🏰";
+        assert_eq!(EmojiId::from_public_key(&public_key).to_emoji_string(), emoji);
+        assert_eq!(EmojiId::from_str(emoji).unwrap().as_public_key(), &public_key);
+        // A substituted emoji fails the checksum
+        let typo = emoji.replacen('🏰', '🏠', 1);
+        assert_eq!(EmojiId::from_str(&typo), Err(EmojiIdError::InvalidChecksum));
+    }
+}
@@ -0,0 +1,364 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use tari_common::configuration::Network;
+use tari_crypto::tari_utilities::ByteArray;
+use thiserror::Error;
+
+use crate::{
+    dammsum::{compute_checksum, validate_checksum},
+    emoji::{bytes_to_emoji_string, emoji_string_to_bytes},
+    types::PublicKey,
+};
+
+/// The size of a single address in bytes: network, features, spend key and checksum
+pub const TARI_ADDRESS_INTERNAL_SINGLE_SIZE: usize = 2 + 32 + 1;
+/// The size of a dual address without a payment ID in bytes: network, features, view key, spend key and checksum
+pub const TARI_ADDRESS_INTERNAL_DUAL_SIZE: usize = 2 + 32 + 32 + 1;
+/// The largest payment ID a dual address can carry
+pub const MAX_PAYMENT_ID_SIZE: usize = 256;
+
+/// The payment types an address accepts, and whether it carries a payment ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TariAddressFeatures(u8);
+
+impl TariAddressFeatures {
+    pub const INTERACTIVE: u8 = 0b0000_0010;
+    pub const ONE_SIDED: u8 = 0b0000_0001;
+    pub const PAYMENT_ID: u8 = 0b0000_0100;
+
+    pub fn create_interactive_only() -> Self {
+        Self(Self::INTERACTIVE)
+    }
+
+    pub fn create_one_sided_only() -> Self {
+        Self(Self::ONE_SIDED)
+    }
+
+    pub fn create_interactive_and_one_sided() -> Self {
+        Self(Self::INTERACTIVE | Self::ONE_SIDED)
+    }
+
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        if bits & !(Self::INTERACTIVE | Self::ONE_SIDED | Self::PAYMENT_ID) == 0 {
+            Some(Self(bits))
+        } else {
+            None
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(&self, feature: u8) -> bool {
+        self.0 & feature == feature
+    }
+
+    fn with_payment_id(self, has_payment_id: bool) -> Self {
+        if has_payment_id {
+            Self(self.0 | Self::PAYMENT_ID)
+        } else {
+            Self(self.0 & !Self::PAYMENT_ID)
+        }
+    }
+}
+
+impl Default for TariAddressFeatures {
+    fn default() -> Self {
+        Self::create_interactive_and_one_sided()
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TariAddressError {
+    #[error("Invalid size")]
+    InvalidSize,
+    #[error("Invalid network: {0}")]
+    InvalidNetwork(u8),
+    #[error("Invalid features: {0}")]
+    InvalidFeatures(u8),
+    #[error("Invalid checksum")]
+    InvalidChecksum,
+    #[error("Invalid emoji character")]
+    InvalidEmoji,
+    #[error("Cannot recover public key")]
+    CannotRecoverPublicKey,
+    #[error("Cannot recover network")]
+    CannotRecoverNetwork,
+    #[error("Cannot recover features")]
+    CannotRecoverFeatures,
+    #[error("Invalid payment ID")]
+    InvalidPaymentId,
+}
+
+/// A Tari address for a network. A dual address has a view key, used to find payments, and a spend key, used to spend
+/// them, and may carry a payment ID; a single address has only the spend key, which is used for both.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TariAddress {
+    network: Network,
+    features: TariAddressFeatures,
+    public_view_key: Option<PublicKey>,
+    public_spend_key: PublicKey,
+    payment_id_user_data: Vec<u8>,
+}
+
+impl TariAddress {
+    /// Creates a new dual address from the view and spend keys
+    pub fn new_dual_address(
+        public_view_key: PublicKey,
+        public_spend_key: PublicKey,
+        network: Network,
+        features: TariAddressFeatures,
+    ) -> Self {
+        Self {
+            network,
+            features: features.with_payment_id(false),
+            public_view_key: Some(public_view_key),
+            public_spend_key,
+            payment_id_user_data: Vec::new(),
+        }
+    }
+
+    /// Creates a new dual address whose view and spend keys are both `public_key`
+    pub fn new_dual_address_with_default_features(public_key: PublicKey, network: Network) -> Self {
+        Self::new_dual_address(public_key.clone(), public_key, network, TariAddressFeatures::default())
+    }
+
+    /// Creates a new single address from the spend key
+    pub fn new_single_address(public_spend_key: PublicKey, network: Network, features: TariAddressFeatures) -> Self {
+        Self {
+            network,
+            features: features.with_payment_id(false),
+            public_view_key: None,
+            public_spend_key,
+            payment_id_user_data: Vec::new(),
+        }
+    }
+
+    /// Returns the dual address with the payment ID, or without one if it is empty. Single addresses cannot carry a
+    /// payment ID.
+    pub fn with_payment_id(self, payment_id_user_data: Vec<u8>) -> Result<Self, TariAddressError> {
+        if (self.public_view_key.is_none() && !payment_id_user_data.is_empty()) ||
+            payment_id_user_data.len() > MAX_PAYMENT_ID_SIZE
+        {
+            return Err(TariAddressError::InvalidPaymentId);
+        }
+        Ok(Self {
+            features: self.features.with_payment_id(!payment_id_user_data.is_empty()),
+            payment_id_user_data,
+            ..self
+        })
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn features(&self) -> TariAddressFeatures {
+        self.features
+    }
+
+    /// The view key of a dual address
+    pub fn public_view_key(&self) -> Option<&PublicKey> {
+        self.public_view_key.as_ref()
+    }
+
+    pub fn public_spend_key(&self) -> &PublicKey {
+        &self.public_spend_key
+    }
+
+    /// The payment ID of a dual address, empty if it has none
+    pub fn payment_id_user_data(&self) -> &[u8] {
+        &self.payment_id_user_data
+    }
+
+    /// Serializes a dual address as `network | features | view key | spend key | payment ID | checksum`, and a single
+    /// address as `network | features | spend key | checksum`
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(TARI_ADDRESS_INTERNAL_DUAL_SIZE + self.payment_id_user_data.len());
+        bytes.push(self.network.as_byte());
+        bytes.push(self.features.as_u8());
+        if let Some(public_view_key) = &self.public_view_key {
+            bytes.extend_from_slice(public_view_key.as_bytes());
+        }
+        bytes.extend_from_slice(self.public_spend_key.as_bytes());
+        bytes.extend_from_slice(&self.payment_id_user_data);
+        bytes.push(compute_checksum(&bytes));
+        bytes
+    }
+
+    /// Deserializes a single or dual address, validating its size, checksum, network, features and keys
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TariAddressError> {
+        if bytes.len() != TARI_ADDRESS_INTERNAL_SINGLE_SIZE &&
+            !(TARI_ADDRESS_INTERNAL_DUAL_SIZE..=TARI_ADDRESS_INTERNAL_DUAL_SIZE + MAX_PAYMENT_ID_SIZE)
+                .contains(&bytes.len())
+        {
+            return Err(TariAddressError::InvalidSize);
+        }
+        if validate_checksum(bytes).is_err() {
+            return Err(TariAddressError::InvalidChecksum);
+        }
+        let network = Network::try_from(bytes[0]).map_err(|_| TariAddressError::InvalidNetwork(bytes[0]))?;
+        let features = TariAddressFeatures::from_bits(bytes[1]).ok_or(TariAddressError::InvalidFeatures(bytes[1]))?;
+        let public_key =
+            |bytes: &[u8]| PublicKey::from_canonical_bytes(bytes).map_err(|_| TariAddressError::CannotRecoverPublicKey);
+        if bytes.len() == TARI_ADDRESS_INTERNAL_SINGLE_SIZE {
+            if features.contains(TariAddressFeatures::PAYMENT_ID) {
+                return Err(TariAddressError::InvalidFeatures(bytes[1]));
+            }
+            return Ok(Self::new_single_address(public_key(&bytes[2..34])?, network, features));
+        }
+        let payment_id_user_data = bytes[66..bytes.len() - 1].to_vec();
+        if features.contains(TariAddressFeatures::PAYMENT_ID) == payment_id_user_data.is_empty() {
+            return Err(TariAddressError::InvalidFeatures(bytes[1]));
+        }
+        Self::new_dual_address(
+            public_key(&bytes[2..34])?,
+            public_key(&bytes[34..66])?,
+            network,
+            features,
+        )
+        .with_payment_id(payment_id_user_data)
+    }
+
+    /// Encodes the address as emoji, one per byte
+    pub fn to_emoji_string(&self) -> String {
+        bytes_to_emoji_string(&self.to_vec())
+    }
+
+    /// Decodes an address from emoji, one per byte
+    pub fn from_emoji_string(emoji: &str) -> Result<Self, TariAddressError> {
+        let bytes = emoji_string_to_bytes(emoji.trim()).ok_or(TariAddressError::InvalidEmoji)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Encodes the address in base58, with the network and features bytes encoded separately, so that the first two
+    /// characters identify the network and features
+    pub fn to_base58(&self) -> String {
+        let bytes = self.to_vec();
+        let mut base58 = bs58::encode(&bytes[0..1]).into_string();
+        base58.push_str(&bs58::encode(&bytes[1..2]).into_string());
+        base58.push_str(&bs58::encode(&bytes[2..]).into_string());
+        base58
+    }
+
+    /// Decodes an address from base58
+    pub fn from_base58(base58: &str) -> Result<Self, TariAddressError> {
+        let base58 = base58.trim();
+        if base58.len() < 3 || !base58.is_char_boundary(2) {
+            return Err(TariAddressError::InvalidSize);
+        }
+        let network = bs58::decode(&base58[0..1])
+            .into_vec()
+            .map_err(|_| TariAddressError::CannotRecoverNetwork)?;
+        let features = bs58::decode(&base58[1..2])
+            .into_vec()
+            .map_err(|_| TariAddressError::CannotRecoverFeatures)?;
+        let rest = bs58::decode(&base58[2..])
+            .into_vec()
+            .map_err(|_| TariAddressError::CannotRecoverPublicKey)?;
+        let mut bytes = Vec::with_capacity(2 + rest.len());
+        bytes.extend(network);
+        bytes.extend(features);
+        bytes.extend(rest);
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl FromStr for TariAddress {
+    type Err = TariAddressError;
+
+    /// Parses an address in base58 or emoji
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        match Self::from_base58(address) {
+            Ok(address) => Ok(address),
+            Err(_) => Self::from_emoji_string(address),
+        }
+    }
+}
+
+impl Display for TariAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_base58())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_crypto::tari_utilities::hex::Hex;
+
+    use super::*;
+
+    fn base_point() -> PublicKey {
+        PublicKey::from_hex("e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76").unwrap()
+    }
+
+    #[test]
+    fn dual_address_known_vector() {
+        let address = TariAddress::new_dual_address_with_default_features(base_point(), Network::MainNet);
+        let emoji = "🌀🌊😇🚓💉🌠🏰💨🎩🐐👽🐮👾🏀💼🌀🎭🎾🎷😈🌰🏰👣🐬🔭🐸🔒🤡🎸🎓🗽🐸🍩🐘😇🚓💉🌠🏰💨🎩🐐👽🐮👾🏀💼🌀🎭🎾🎷😈🌰🏰👣🐬🔭🐸🔒🤡🎸🎓🗽🐸🍩🐘🔧";
+        assert_eq!(address.to_emoji_string(), emoji);
+        assert_eq!(TariAddress::from_emoji_string(emoji).unwrap(), address);
+        assert_eq!(TariAddress::from_base58(&address.to_base58()).unwrap(), address);
+    }
+
+    #[test]
+    fn single_address_known_vector() {
+        let address = TariAddress::new_single_address(
+            base_point(),
+            Network::MainNet,
+            TariAddressFeatures::create_one_sided_only(),
+        );
+        let emoji = "🌀🌂😇🚓💉🌠🏰💨🎩🐐👽🐮👾🏀💼🌀🎭🎾🎷😈🌰🏰👣🐬🔭🐸🔒🤡🎸🎓🗽🐸🍩🐘💣";
+        assert_eq!(address.to_emoji_string(), emoji);
+        let parsed = TariAddress::from_str(emoji).unwrap();
+        assert_eq!(parsed, address);
+        assert_eq!(parsed.public_view_key(), None);
+        assert_eq!(TariAddress::from_str(&address.to_base58()).unwrap(), address);
+    }
+
+    #[test]
+    fn payment_id_address_round_trip() {
+        let address = TariAddress::new_dual_address_with_default_features(base_point(), Network::MainNet)
+            .with_payment_id(b"invoice 42".to_vec())
+            .unwrap();
+        assert!(address.features().contains(TariAddressFeatures::PAYMENT_ID));
+        assert_eq!(address.to_vec().len(), TARI_ADDRESS_INTERNAL_DUAL_SIZE + 10);
+        let parsed = TariAddress::from_str(&address.to_base58()).unwrap();
+        assert_eq!(parsed.payment_id_user_data(), b"invoice 42");
+        assert_eq!(
+            TariAddress::from_emoji_string(&address.to_emoji_string()).unwrap(),
+            address
+        );
+
+        let single = TariAddress::new_single_address(base_point(), Network::MainNet, TariAddressFeatures::default());
+        assert_eq!(single.with_payment_id(vec![1]), Err(TariAddressError::InvalidPaymentId));
+    }
+}
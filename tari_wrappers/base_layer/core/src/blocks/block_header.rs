@@ -0,0 +1,134 @@
+// Copyright 2018. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use blake2::Blake2b;
+use borsh::{BorshDeserialize, BorshSerialize};
+use digest::consts::U32;
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::{BlockHash, FixedHash, PrivateKey};
+use tari_utilities::epoch_time::EpochTime;
+use thiserror::Error;
+
+use crate::{blocks::BlocksHashDomain, consensus::DomainSeparatedConsensusHasher, proof_of_work::ProofOfWork};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BlockHeaderValidationError {
+    #[error(
+        "Header at height {height} does not link to the previous header: expected prev_hash {expected}, got {actual}"
+    )]
+    InvalidPrevHash {
+        height: u64,
+        expected: BlockHash,
+        actual: BlockHash,
+    },
+    #[error("Header height {actual} does not follow the previous header height {previous}")]
+    InvalidHeight { previous: u64, actual: u64 },
+}
+
+/// The BlockHeader contains all the metadata for the block, including proof of work, a link to the previous block
+/// and the transaction kernels.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct BlockHeader {
+    /// Version of the block
+    pub version: u16,
+    /// Height of this block since the genesis block (height 0)
+    pub height: u64,
+    /// Hash of the block previous to this in the chain.
+    pub prev_hash: BlockHash,
+    /// Timestamp at which the block was built.
+    pub timestamp: EpochTime,
+    /// This is the UTXO merkle root of the outputs in the blockchain
+    pub output_mr: FixedHash,
+    /// The size (number of leaves) of the output sparse merkle tree
+    pub output_smt_size: u64,
+    /// This is the MMR root of the kernels
+    pub kernel_mr: FixedHash,
+    /// The number of MMR leaves in the kernel MMR
+    pub kernel_mmr_size: u64,
+    /// This is the Merkle root of the inputs in this block
+    pub input_mr: FixedHash,
+    /// Sum of kernel offsets for all kernels in this block.
+    pub total_kernel_offset: PrivateKey,
+    /// Sum of script offsets for all kernels in this block.
+    pub total_script_offset: PrivateKey,
+    /// Nonce increment used to mine this block.
+    pub nonce: u64,
+    /// Proof of work summary
+    pub pow: ProofOfWork,
+    /// Merkle root of all active validator node.
+    pub validator_node_mr: FixedHash,
+    /// The number of validator nodes in the validator node merkle tree
+    pub validator_node_size: u64,
+}
+
+impl BlockHeader {
+    /// Provides a mining hash of the header, used for the mining. This differs from the normal hash by not hashing the
+    /// nonce and kernel pow.
+    pub fn mining_hash(&self) -> FixedHash {
+        DomainSeparatedConsensusHasher::<BlocksHashDomain, Blake2b<U32>>::new("block_header")
+            .chain(&self.version)
+            .chain(&self.height)
+            .chain(&self.prev_hash)
+            .chain(&self.timestamp)
+            .chain(&self.input_mr)
+            .chain(&self.output_mr)
+            .chain(&self.output_smt_size)
+            .chain(&self.kernel_mr)
+            .chain(&self.kernel_mmr_size)
+            .chain(&self.total_kernel_offset)
+            .chain(&self.total_script_offset)
+            .chain(&self.validator_node_mr)
+            .chain(&self.validator_node_size)
+            .finalize()
+            .into()
+    }
+
+    /// The hash of the block header, which commits to the mining hash, the proof of work and the nonce
+    pub fn hash(&self) -> FixedHash {
+        DomainSeparatedConsensusHasher::<BlocksHashDomain, Blake2b<U32>>::new("block_header")
+            .chain(&self.mining_hash())
+            .chain(&self.pow)
+            .chain(&self.nonce)
+            .finalize()
+            .into()
+    }
+
+    /// Checks that this header directly follows `previous`: it must link to the previous header's hash and be exactly
+    /// one block higher. The proof of work is not validated.
+    pub fn validate_linkage(&self, previous: &BlockHeader) -> Result<(), BlockHeaderValidationError> {
+        if previous.height.checked_add(1) != Some(self.height) {
+            return Err(BlockHeaderValidationError::InvalidHeight {
+                previous: previous.height,
+                actual: self.height,
+            });
+        }
+        let expected = previous.hash();
+        if self.prev_hash != expected {
+            return Err(BlockHeaderValidationError::InvalidPrevHash {
+                height: self.height,
+                expected,
+                actual: self.prev_hash,
+            });
+        }
+        Ok(())
+    }
+}
@@ -28,6 +28,17 @@ pub struct LimitedBytesReader<R> {
     inner: R,
 }
 
+impl<R> LimitedBytesReader<R> {
+    /// Wraps `inner`, failing any read that takes the total number of bytes read past `byte_limit`
+    pub fn new(byte_limit: usize, inner: R) -> Self {
+        Self {
+            byte_limit,
+            num_read: 0,
+            inner,
+        }
+    }
+}
+
 impl<R: Read> Read for LimitedBytesReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let read = self.inner.read(buf)?;
@@ -24,7 +24,7 @@ mod bytes;
 mod hashing;
 mod string;
 
-pub use hashing::DomainSeparatedConsensusHasher;
+pub use hashing::{consensus_network, set_consensus_network, with_consensus_network, DomainSeparatedConsensusHasher};
 pub use string::MaxSizeString;
 
 pub use self::bytes::MaxSizeBytes;
@@ -20,12 +20,59 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::{cell::Cell, sync::RwLock};
+
 use borsh::BorshSerialize;
 use digest::Digest;
 use tari_common::configuration::Network;
 use tari_crypto::hashing::DomainSeparation;
 use tari_hashing::DomainSeparatedBorshHasher;
 
+/// The network that consensus hashes are separated by, when set, instead of the global network setting
+static CONSENSUS_NETWORK: RwLock<Option<Network>> = RwLock::new(None);
+
+thread_local! {
+    /// The network set by `with_consensus_network` for the duration of a call on this thread, which takes precedence
+    /// over `CONSENSUS_NETWORK`. It is per thread so that concurrent calls for different networks do not interfere.
+    static NETWORK_OVERRIDE: Cell<Option<Network>> = Cell::new(None);
+}
+
+/// Sets the network that consensus hashes are separated by, overriding the global network setting, which can only be
+/// set once per process. `None` reverts to the global setting.
+pub fn set_consensus_network(network: Option<Network>) {
+    let mut current = CONSENSUS_NETWORK.write().unwrap_or_else(|e| e.into_inner());
+    *current = network;
+}
+
+/// The network that consensus hashes are separated by on this thread
+pub fn consensus_network() -> Network {
+    NETWORK_OVERRIDE
+        .with(Cell::get)
+        .or_else(|| *CONSENSUS_NETWORK.read().unwrap_or_else(|e| e.into_inner()))
+        .unwrap_or_else(Network::get_current_or_user_setting_or_default)
+}
+
+/// Restores the network override of this thread when dropped, including when unwinding
+struct NetworkOverrideGuard {
+    previous: Option<Network>,
+}
+
+impl Drop for NetworkOverrideGuard {
+    fn drop(&mut self) {
+        NETWORK_OVERRIDE.with(|current| current.set(self.previous));
+    }
+}
+
+/// Runs `f` with consensus hashes separated by `network`, restoring the previous network afterwards. The network only
+/// applies to hashes computed on the calling thread, so work spread across threads must pass the network on, e.g. by
+/// calling this function again on each worker with the value of `consensus_network()`.
+pub fn with_consensus_network<R, F: FnOnce() -> R>(network: Network, f: F) -> R {
+    let _guard = NetworkOverrideGuard {
+        previous: NETWORK_OVERRIDE.with(|current| current.replace(Some(network))),
+    };
+    f()
+}
+
 /// Domain separated consensus encoding hasher.
 /// This is a thin wrapper around the domain-separated Borsh hasher but adds the network byte in its constructor
 /// functions
@@ -37,7 +84,7 @@ impl<M: DomainSeparation, D: Digest> DomainSeparatedConsensusHasher<M, D>
 where D: Default
 {
     pub fn new(label: &'static str) -> Self {
-        Self::new_with_network(label, Network::get_current_or_user_setting_or_default())
+        Self::new_with_network(label, consensus_network())
     }
 
     pub fn new_with_network(label: &'static str, network: Network) -> Self {
@@ -21,4 +21,11 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 mod consensus_encoding;
-pub use consensus_encoding::{DomainSeparatedConsensusHasher, MaxSizeBytes, MaxSizeString};
+pub use consensus_encoding::{
+    consensus_network,
+    set_consensus_network,
+    with_consensus_network,
+    DomainSeparatedConsensusHasher,
+    MaxSizeBytes,
+    MaxSizeString,
+};
@@ -128,9 +128,25 @@ impl Covenant {
         input: &TransactionInput,
         outputs: &[TransactionOutput],
     ) -> Result<usize, CovenantError> {
+        let matching = self.execute_matching(block_height, input, outputs)?;
+        if matching.is_empty() {
+            return Err(CovenantError::NoMatchingOutputs);
+        }
+
+        Ok(matching.len())
+    }
+
+    /// It executes the covenant on the transaction input being spent, and returns the indexes of the transaction
+    /// outputs that match, which may be none. An empty covenant is an identity and matches all outputs.
+    pub fn execute_matching(
+        &self,
+        block_height: u64,
+        input: &TransactionInput,
+        outputs: &[TransactionOutput],
+    ) -> Result<Vec<usize>, CovenantError> {
         if self.tokens.is_empty() {
             // Empty covenants always pass
-            return Ok(outputs.len());
+            return Ok((0..outputs.len()).collect());
         }
 
         let tokens = CovenantTokenCollection::from_iter(self.tokens.clone());
@@ -141,11 +157,8 @@ impl Covenant {
         if cx.has_more_tokens() {
             return Err(CovenantError::RemainingTokens);
         }
-        if output_set.is_empty() {
-            return Err(CovenantError::NoMatchingOutputs);
-        }
 
-        Ok(output_set.len())
+        Ok(output_set.indexes())
     }
 
     /// Adds a new `CovenantToken` to the current `tokens` vector field.
@@ -162,6 +175,11 @@ impl Covenant {
     pub fn is_empty(&self) -> bool {
         self.tokens.is_empty()
     }
+
+    /// Outputs the tokens of the covenant, in prefix order.
+    pub fn tokens(&self) -> &[CovenantToken] {
+        &self.tokens
+    }
 }
 
 impl FromIterator<CovenantToken> for Covenant {
@@ -39,11 +39,12 @@ mod output_set;
 mod serde;
 mod token;
 
+pub use arguments::CovenantArg;
 pub use covenant::Covenant;
+pub use decoder::CovenantDecodeError;
 pub use error::CovenantError;
-// Used in macro
-#[allow(unused_imports)]
-pub(crate) use fields::OutputField;
+pub use fields::{OutputField, OutputFields};
+pub use filters::CovenantFilter;
 pub use token::CovenantToken;
 
 #[macro_use]
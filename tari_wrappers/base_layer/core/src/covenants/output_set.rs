@@ -109,6 +109,11 @@ impl<'a> OutputSet<'a> {
     pub fn clear(&mut self) {
         self.0.clear();
     }
+
+    /// Returns the indexes of the outputs in the set, in ascending order.
+    pub fn indexes(&self) -> Vec<usize> {
+        self.0.iter().map(|output| output.index).collect()
+    }
 }
 
 impl<'a> FromIterator<(usize, &'a TransactionOutput)> for OutputSet<'a> {
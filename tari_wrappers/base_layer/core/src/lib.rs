@@ -24,9 +24,11 @@
 #[macro_use]
 extern crate bitflags;
 
+pub mod blocks;
 pub mod consensus;
 #[macro_use]
 pub mod covenants;
 mod common;
+pub mod proof_of_work;
 pub mod transactions;
-pub use common::{borsh, one_sided, ConfidentialOutputHasher};
+pub use common::{borsh, byte_counter, limited_reader, one_sided, ConfidentialOutputHasher};
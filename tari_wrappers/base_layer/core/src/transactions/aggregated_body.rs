@@ -24,25 +24,31 @@ use std::{
     fmt::{Display, Error, Formatter},
 };
 
+use blake2::Blake2b;
 use borsh::{BorshDeserialize, BorshSerialize};
+use digest::consts::U32;
 use log::*;
 use serde::{Deserialize, Serialize};
-use tari_common_types::types::{Commitment, PrivateKey};
+use tari_common_types::types::{Commitment, FixedHash, PrivateKey};
 use tari_crypto::commitment::HomomorphicCommitmentFactory;
-
-use crate::transactions::{
-    crypto_factories::CryptoFactories,
-    tari_amount::MicroMinotari,
-    transaction_components::{
-        KernelFeatures,
-        OutputType,
-        Transaction,
-        TransactionError,
-        TransactionInput,
-        TransactionKernel,
-        TransactionOutput,
+use tari_hashing::TransactionHashDomain;
+
+use crate::{
+    consensus::DomainSeparatedConsensusHasher,
+    transactions::{
+        crypto_factories::CryptoFactories,
+        tari_amount::MicroMinotari,
+        transaction_components::{
+            KernelFeatures,
+            OutputType,
+            Transaction,
+            TransactionError,
+            TransactionInput,
+            TransactionKernel,
+            TransactionOutput,
+        },
+        weight::TransactionWeight,
     },
-    weight::TransactionWeight,
 };
 
 pub const LOG_TARGET: &str = "c::tx::aggregated_body";
@@ -195,6 +201,42 @@ impl AggregateBody {
         false
     }
 
+    /// Returns true if an output of the body is spent by an input of the same body, i.e. the body has not been cut
+    /// through
+    pub fn contains_cut_through(&self) -> bool {
+        self.inputs()
+            .iter()
+            .map(|input| input.output_hash())
+            .any(|spent| self.outputs().iter().any(|output| output.hash() == spent))
+    }
+
+    /// Check that no output of the body is spent by an input of the same body
+    pub fn check_cut_through(&self) -> Result<(), TransactionError> {
+        if self.contains_cut_through() {
+            warn!(target: LOG_TARGET, "Body contains an output spent in the same body");
+            return Err(TransactionError::InvalidCutThrough);
+        }
+        Ok(())
+    }
+
+    /// Returns the hash of the body, committing to the hashes of its inputs, outputs and kernels. The component
+    /// hashes are sorted before hashing, so the hash does not depend on whether the body is sorted, and compact inputs
+    /// hash the same as the full inputs they were made from.
+    pub fn hash(&self) -> FixedHash {
+        let mut input_hashes = self.inputs.iter().map(|i| i.output_hash()).collect::<Vec<_>>();
+        let mut output_hashes = self.outputs.iter().map(|o| o.hash()).collect::<Vec<_>>();
+        let mut kernel_hashes = self.kernels.iter().map(|k| k.hash()).collect::<Vec<_>>();
+        input_hashes.sort();
+        output_hashes.sort();
+        kernel_hashes.sort();
+        DomainSeparatedConsensusHasher::<TransactionHashDomain, Blake2b<U32>>::new("aggregate_body")
+            .chain(&input_hashes)
+            .chain(&output_hashes)
+            .chain(&kernel_hashes)
+            .finalize()
+            .into()
+    }
+
     /// Sort the component lists of the aggregate body
     pub fn sort(&mut self) {
         if self.sorted {
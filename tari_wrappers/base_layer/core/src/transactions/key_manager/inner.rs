@@ -0,0 +1,719 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::collections::HashMap;
+
+use blake2::Blake2b;
+use digest::consts::U64;
+use log::*;
+use rand::rngs::OsRng;
+use strum::IntoEnumIterator;
+use tari_common_types::types::{ComAndPubSignature, Commitment, PrivateKey, PublicKey, RangeProof, Signature};
+use tari_comms::types::CommsDHKE;
+use tari_crypto::{
+    commitment::{ExtensionDegree, HomomorphicCommitmentFactory},
+    extended_range_proof::ExtendedRangeProofService,
+    hash_domain,
+    hashing::{DomainSeparatedHash, DomainSeparatedHasher},
+    keys::{PublicKey as PKtrait, SecretKey as SKtrait},
+    range_proof::RangeProofService as RPService,
+    ristretto::{
+        bulletproofs_plus::{RistrettoExtendedMask, RistrettoExtendedWitness},
+        RistrettoComSig,
+    },
+};
+use tari_key_manager::{
+    cipher_seed::CipherSeed,
+    interface::AddResult,
+    key_manager::KeyManager,
+    key_manager_service::{
+        storage::database::{KeyManagerBackend, KeyManagerDatabase, KeyManagerState},
+        KeyManagerServiceError,
+    },
+    KeyId,
+};
+use tari_utilities::{hex::Hex, ByteArray, ByteArrayError};
+use tokio::sync::RwLock;
+
+use crate::{
+    one_sided::diffie_hellman_stealth_domain_hasher,
+    transactions::{
+        key_manager::{
+            interface::{TransactionKeyManagerBranch, TransactionKeyManagerLabel, TxoStage},
+            TariKeyId,
+        },
+        tari_amount::MicroMinotari,
+        transaction_components::{
+            create_burn_commitment_proof,
+            EncryptedData,
+            KernelFeatures,
+            RangeProofType,
+            TransactionError,
+            TransactionInput,
+            TransactionInputVersion,
+            TransactionKernel,
+            TransactionKernelVersion,
+            TransactionOutput,
+            TransactionOutputVersion,
+        },
+        CryptoFactories,
+    },
+};
+
+const LOG_TARGET: &str = "c::bn::key_manager::key_manager_service";
+const KEY_MANAGER_MAX_SEARCH_DEPTH: u64 = 1_000_000;
+
+hash_domain!(
+    KeyManagerHashDomain,
+    "com.tari.base_layer.core.transactions.key_manager",
+    1
+);
+
+/// Derives the key labelled `label` from a key of a branch, as the key of a `KeyId::Derived` is derived, e.g. the
+/// script key of an output from its commitment mask
+pub fn derive_labelled_key(label: &str, branch_key: &PrivateKey) -> Result<PrivateKey, ByteArrayError> {
+    let derived_key = DomainSeparatedHasher::<Blake2b<U64>, KeyManagerHashDomain>::new_with_label(label)
+        .chain(branch_key.as_bytes())
+        .finalize();
+    PrivateKey::from_uniform_bytes(derived_key.as_ref())
+}
+
+pub struct TransactionKeyManagerInner<TBackend> {
+    key_managers: HashMap<String, RwLock<KeyManager<PublicKey>>>,
+    db: KeyManagerDatabase<TBackend, PublicKey>,
+    master_seed: CipherSeed,
+    crypto_factories: CryptoFactories,
+}
+
+impl<TBackend> TransactionKeyManagerInner<TBackend>
+where TBackend: KeyManagerBackend<PublicKey> + 'static
+{
+    /// Creates a new key manager and registers all of the standard transaction branches.
+    pub fn new(
+        master_seed: CipherSeed,
+        db: KeyManagerDatabase<TBackend, PublicKey>,
+        crypto_factories: CryptoFactories,
+    ) -> Result<Self, KeyManagerServiceError> {
+        let mut km = TransactionKeyManagerInner {
+            key_managers: HashMap::new(),
+            db,
+            master_seed,
+            crypto_factories,
+        };
+        for branch in TransactionKeyManagerBranch::iter() {
+            km.add_key_manager_branch(&branch.get_branch_key())?;
+        }
+        Ok(km)
+    }
+
+    pub fn add_key_manager_branch(&mut self, branch: &str) -> Result<AddResult, KeyManagerServiceError> {
+        if self.key_managers.contains_key(branch) {
+            return Ok(AddResult::AlreadyExists);
+        }
+        let state = match self.db.get_key_manager_state(branch)? {
+            Some(state) => state,
+            None => {
+                let state = KeyManagerState {
+                    branch_seed: branch.to_string(),
+                    primary_key_index: 0,
+                };
+                self.db.set_key_manager_state(state.clone())?;
+                state
+            },
+        };
+        self.key_managers.insert(
+            branch.to_string(),
+            RwLock::new(KeyManager::from(
+                self.master_seed.clone(),
+                state.branch_seed,
+                state.primary_key_index,
+            )),
+        );
+        Ok(AddResult::NewEntry)
+    }
+
+    fn get_key_manager(&self, branch: &str) -> Result<&RwLock<KeyManager<PublicKey>>, KeyManagerServiceError> {
+        self.key_managers
+            .get(branch)
+            .ok_or(KeyManagerServiceError::UnknownKeyBranch)
+    }
+
+    pub async fn get_next_key(&self, branch: &str) -> Result<(TariKeyId, PublicKey), KeyManagerServiceError> {
+        let mut km = self.get_key_manager(branch)?.write().await;
+        self.db.increment_key_index(branch)?;
+        let key = km.next_key()?;
+        Ok((
+            KeyId::Managed {
+                branch: branch.to_string(),
+                index: key.key_index,
+            },
+            PublicKey::from_secret_key(&key.key),
+        ))
+    }
+
+    pub fn get_static_key(&self, branch: &str) -> Result<TariKeyId, KeyManagerServiceError> {
+        self.get_key_manager(branch)?;
+        Ok(KeyId::Managed {
+            branch: branch.to_string(),
+            index: 0,
+        })
+    }
+
+    pub async fn get_public_key_at_key_id(&self, key_id: &TariKeyId) -> Result<PublicKey, KeyManagerServiceError> {
+        match key_id {
+            KeyId::Managed { branch, index } => {
+                let km = self.get_key_manager(branch)?.read().await;
+                Ok(km.derive_public_key(*index)?.key)
+            },
+            KeyId::Derived { .. } => {
+                let private_key = self.get_private_key(key_id).await?;
+                Ok(PublicKey::from_secret_key(&private_key))
+            },
+            KeyId::Imported { key } => Ok(key.clone()),
+            KeyId::Zero => Ok(PublicKey::default()),
+        }
+    }
+
+    /// Searches the branch backwards from its current index, as the key is most likely to be a recently used one.
+    pub async fn find_key_index(&self, branch: &str, key: &PublicKey) -> Result<u64, KeyManagerServiceError> {
+        let km = self.get_key_manager(branch)?.read().await;
+        let current_index = km.key_index();
+        let lowest_index = current_index.saturating_sub(KEY_MANAGER_MAX_SEARCH_DEPTH);
+        for index in (lowest_index..=current_index).rev() {
+            if km.derive_public_key(index)?.key == *key {
+                trace!(target: LOG_TARGET, "Key found in {} key manager at index {}", branch, index);
+                return Ok(index);
+            }
+        }
+        Err(KeyManagerServiceError::KeyNotFoundInKeyChain)
+    }
+
+    /// Searches the branch backwards from its current index, as the key is most likely to be a recently used one.
+    pub async fn find_private_key_index(&self, branch: &str, key: &PrivateKey) -> Result<u64, KeyManagerServiceError> {
+        let km = self.get_key_manager(branch)?.read().await;
+        let current_index = km.key_index();
+        let lowest_index = current_index.saturating_sub(KEY_MANAGER_MAX_SEARCH_DEPTH);
+        for index in (lowest_index..=current_index).rev() {
+            if km.get_private_key(index)? == *key {
+                trace!(target: LOG_TARGET, "Private key found in {} key manager at index {}", branch, index);
+                return Ok(index);
+            }
+        }
+        Err(KeyManagerServiceError::KeyNotFoundInKeyChain)
+    }
+
+    pub async fn update_current_key_index_if_higher(
+        &self,
+        branch: &str,
+        index: u64,
+    ) -> Result<(), KeyManagerServiceError> {
+        let mut km = self.get_key_manager(branch)?.write().await;
+        if index > km.key_index() {
+            km.update_key_index(index);
+            self.db.set_key_index(branch, index)?;
+        }
+        Ok(())
+    }
+
+    pub fn import_key(&self, private_key: PrivateKey) -> Result<TariKeyId, KeyManagerServiceError> {
+        let public_key = PublicKey::from_secret_key(&private_key);
+        trace!(target: LOG_TARGET, "Importing key {}", public_key.to_hex());
+        self.db.insert_imported_key(public_key.clone(), private_key)?;
+        Ok(KeyId::Imported { key: public_key })
+    }
+
+    pub async fn get_private_key(&self, key_id: &TariKeyId) -> Result<PrivateKey, KeyManagerServiceError> {
+        match key_id {
+            KeyId::Managed { branch, index } => {
+                let km = self.get_key_manager(branch)?.read().await;
+                Ok(km.get_private_key(*index)?)
+            },
+            KeyId::Derived { branch, label, index } => {
+                let km = self.get_key_manager(branch)?.read().await;
+                let branch_key = km.get_private_key(*index)?;
+                Ok(derive_labelled_key(label, &branch_key)?)
+            },
+            KeyId::Imported { key } => Ok(self.db.get_imported_key(key)?),
+            KeyId::Zero => Ok(PrivateKey::default()),
+        }
+    }
+
+    // -----------------------------------------------------------------------------------------------------------------
+    // Transaction specific methods
+    // -----------------------------------------------------------------------------------------------------------------
+
+    pub async fn get_commitment(
+        &self,
+        spend_key_id: &TariKeyId,
+        value: &PrivateKey,
+    ) -> Result<Commitment, KeyManagerServiceError> {
+        let spend_key = self.get_private_key(spend_key_id).await?;
+        Ok(self.crypto_factories.commitment.commit(&spend_key, value))
+    }
+
+    pub async fn verify_mask(
+        &self,
+        commitment: &Commitment,
+        spend_key_id: &TariKeyId,
+        value: u64,
+    ) -> Result<bool, KeyManagerServiceError> {
+        let spend_key = self.get_private_key(spend_key_id).await?;
+        Ok(self
+            .crypto_factories
+            .range_proof
+            .verify_mask(commitment, &spend_key, value)?)
+    }
+
+    pub fn get_recovery_key_id(&self) -> Result<TariKeyId, KeyManagerServiceError> {
+        self.get_static_key(&TransactionKeyManagerBranch::DataEncryption.get_branch_key())
+    }
+
+    pub async fn get_next_spend_and_script_key_ids(
+        &self,
+    ) -> Result<(TariKeyId, PublicKey, TariKeyId, PublicKey), KeyManagerServiceError> {
+        let (spend_key_id, spend_public_key) = self
+            .get_next_key(&TransactionKeyManagerBranch::CommitmentMask.get_branch_key())
+            .await?;
+        let index = spend_key_id
+            .managed_index()
+            .ok_or(KeyManagerServiceError::KyeIdWithoutIndex)?;
+        let script_key_id = KeyId::Derived {
+            branch: TransactionKeyManagerBranch::CommitmentMask.get_branch_key(),
+            label: TransactionKeyManagerLabel::ScriptKey.get_branch_key(),
+            index,
+        };
+        let script_public_key = self.get_public_key_at_key_id(&script_key_id).await?;
+        Ok((spend_key_id, spend_public_key, script_key_id, script_public_key))
+    }
+
+    pub async fn find_script_key_id_from_spend_key_id(
+        &self,
+        spend_key_id: &TariKeyId,
+        public_script_key: Option<&PublicKey>,
+    ) -> Result<Option<TariKeyId>, KeyManagerServiceError> {
+        let index = match spend_key_id {
+            KeyId::Managed { index, .. } => *index,
+            KeyId::Derived { .. } | KeyId::Imported { .. } | KeyId::Zero => return Ok(None),
+        };
+        let script_key_id = KeyId::Derived {
+            branch: TransactionKeyManagerBranch::CommitmentMask.get_branch_key(),
+            label: TransactionKeyManagerLabel::ScriptKey.get_branch_key(),
+            index,
+        };
+        if let Some(key) = public_script_key {
+            if *key != self.get_public_key_at_key_id(&script_key_id).await? {
+                return Ok(None);
+            }
+        }
+        Ok(Some(script_key_id))
+    }
+
+    pub async fn get_diffie_hellman_shared_secret(
+        &self,
+        secret_key_id: &TariKeyId,
+        public_key: &PublicKey,
+    ) -> Result<CommsDHKE, TransactionError> {
+        let secret_key = self.get_private_key(secret_key_id).await?;
+        Ok(CommsDHKE::new(&secret_key, public_key))
+    }
+
+    pub async fn get_diffie_hellman_stealth_domain_hasher(
+        &self,
+        secret_key_id: &TariKeyId,
+        public_key: &PublicKey,
+    ) -> Result<DomainSeparatedHash<Blake2b<U64>>, TransactionError> {
+        let secret_key = self.get_private_key(secret_key_id).await?;
+        Ok(diffie_hellman_stealth_domain_hasher(&secret_key, public_key))
+    }
+
+    pub async fn import_add_offset_to_private_key(
+        &self,
+        secret_key_id: &TariKeyId,
+        offset: PrivateKey,
+    ) -> Result<TariKeyId, KeyManagerServiceError> {
+        let secret_key = self.get_private_key(secret_key_id).await?;
+        self.import_key(secret_key + offset)
+    }
+
+    pub async fn get_spending_key_id(&self, public_spending_key: &PublicKey) -> Result<TariKeyId, TransactionError> {
+        let branch = TransactionKeyManagerBranch::CommitmentMask.get_branch_key();
+        match self.find_key_index(&branch, public_spending_key).await {
+            Ok(index) => Ok(KeyId::Managed { branch, index }),
+            Err(_) => {
+                self.db.get_imported_key(public_spending_key).map_err(|_| {
+                    TransactionError::KeyManagerError(KeyManagerServiceError::KeyNotFoundInKeyChain.to_string())
+                })?;
+                Ok(KeyId::Imported {
+                    key: public_spending_key.clone(),
+                })
+            },
+        }
+    }
+
+    pub async fn construct_range_proof(
+        &self,
+        spend_key_id: &TariKeyId,
+        value: u64,
+        min_value: u64,
+    ) -> Result<RangeProof, TransactionError> {
+        let spend_private_key = self.get_private_key(spend_key_id).await?;
+        let proof_bytes = if min_value == 0 {
+            self.crypto_factories
+                .range_proof
+                .construct_proof(&spend_private_key, value)?
+        } else {
+            let extended_mask =
+                RistrettoExtendedMask::assign(ExtensionDegree::DefaultPedersen, vec![spend_private_key])?;
+            let extended_witness = RistrettoExtendedWitness {
+                mask: extended_mask,
+                value,
+                minimum_value_promise: min_value,
+            };
+            self.crypto_factories
+                .range_proof
+                .construct_extended_proof(vec![extended_witness], None)?
+        };
+        Ok(RangeProof::from(proof_bytes))
+    }
+
+    pub async fn get_script_signature(
+        &self,
+        script_key_id: &TariKeyId,
+        spend_key_id: &TariKeyId,
+        value: &PrivateKey,
+        txi_version: &TransactionInputVersion,
+        script_message: &[u8; 32],
+    ) -> Result<ComAndPubSignature, TransactionError> {
+        let spend_private_key = self.get_private_key(spend_key_id).await?;
+        let script_private_key = self.get_private_key(script_key_id).await?;
+        let commitment = self.crypto_factories.commitment.commit(&spend_private_key, value);
+
+        let r_a = PrivateKey::random(&mut OsRng);
+        let r_x = PrivateKey::random(&mut OsRng);
+        let r_y = PrivateKey::random(&mut OsRng);
+        let ephemeral_commitment = self.crypto_factories.commitment.commit(&r_x, &r_a);
+        let ephemeral_pubkey = PublicKey::from_secret_key(&r_y);
+        let script_public_key = PublicKey::from_secret_key(&script_private_key);
+
+        let challenge = TransactionInput::finalize_script_signature_challenge(
+            txi_version,
+            &ephemeral_commitment,
+            &ephemeral_pubkey,
+            &script_public_key,
+            &commitment,
+            script_message,
+        );
+        Ok(ComAndPubSignature::sign(
+            value,
+            &spend_private_key,
+            &script_private_key,
+            &r_a,
+            &r_x,
+            &r_y,
+            &challenge,
+            &*self.crypto_factories.commitment,
+        )?)
+    }
+
+    pub async fn get_partial_txo_kernel_signature(
+        &self,
+        spend_key_id: &TariKeyId,
+        nonce_id: &TariKeyId,
+        total_nonce: &PublicKey,
+        total_excess: &PublicKey,
+        kernel_version: &TransactionKernelVersion,
+        kernel_message: &[u8; 32],
+        kernel_features: &KernelFeatures,
+        txo_type: TxoStage,
+    ) -> Result<Signature, TransactionError> {
+        let private_key = self.get_private_key(spend_key_id).await?;
+        // Coinbase kernels do not carry an offset, as it would not be known to the miner or the mempool
+        let private_signing_key = if kernel_features.is_coinbase() {
+            private_key
+        } else {
+            private_key - &self.get_txo_private_kernel_offset(spend_key_id, nonce_id).await?
+        };
+        let private_signing_key = match txo_type {
+            TxoStage::Input => PrivateKey::default() - &private_signing_key,
+            TxoStage::Output => private_signing_key,
+        };
+        let private_nonce = self.get_private_key(nonce_id).await?;
+        let challenge = TransactionKernel::finalize_kernel_signature_challenge(
+            kernel_version,
+            total_nonce,
+            total_excess,
+            kernel_message,
+        );
+        Ok(Signature::sign_raw_uniform(
+            &private_signing_key,
+            private_nonce,
+            &challenge,
+        )?)
+    }
+
+    pub async fn get_txo_kernel_signature_excess_with_offset(
+        &self,
+        spend_key_id: &TariKeyId,
+        nonce_id: &TariKeyId,
+    ) -> Result<PublicKey, TransactionError> {
+        let private_key = self.get_private_key(spend_key_id).await?;
+        let offset = self.get_txo_private_kernel_offset(spend_key_id, nonce_id).await?;
+        Ok(PublicKey::from_secret_key(&(private_key - &offset)))
+    }
+
+    pub async fn get_txo_private_kernel_offset(
+        &self,
+        spend_key_id: &TariKeyId,
+        nonce_id: &TariKeyId,
+    ) -> Result<PrivateKey, TransactionError> {
+        let spend_private_key = self.get_private_key(spend_key_id).await?;
+        let nonce_private_key = self.get_private_key(nonce_id).await?;
+        hash_to_private_key("kernel_excess_offset", &[
+            spend_private_key.as_bytes(),
+            nonce_private_key.as_bytes(),
+        ])
+    }
+
+    async fn get_recovery_key(
+        &self,
+        custom_recovery_key_id: Option<&TariKeyId>,
+    ) -> Result<PrivateKey, TransactionError> {
+        let recovery_key_id = match custom_recovery_key_id {
+            Some(key_id) => key_id.clone(),
+            None => self.get_recovery_key_id()?,
+        };
+        Ok(self.get_private_key(&recovery_key_id).await?)
+    }
+
+    pub async fn encrypt_data_for_recovery(
+        &self,
+        spend_key_id: &TariKeyId,
+        custom_recovery_key_id: Option<&TariKeyId>,
+        value: u64,
+    ) -> Result<EncryptedData, TransactionError> {
+        let recovery_key = self.get_recovery_key(custom_recovery_key_id).await?;
+        let spend_key = self.get_private_key(spend_key_id).await?;
+        let commitment = self
+            .crypto_factories
+            .commitment
+            .commit(&spend_key, &PrivateKey::from(value));
+        Ok(EncryptedData::encrypt_data(
+            &recovery_key,
+            &commitment,
+            MicroMinotari::from(value),
+            &spend_key,
+        )?)
+    }
+
+    pub async fn try_output_key_recovery(
+        &self,
+        output: &TransactionOutput,
+        custom_recovery_key_id: Option<&TariKeyId>,
+    ) -> Result<(TariKeyId, MicroMinotari), TransactionError> {
+        let recovery_key = self.get_recovery_key(custom_recovery_key_id).await?;
+        let (value, spend_key) =
+            EncryptedData::decrypt_data(&recovery_key, &output.commitment, &output.encrypted_data)?;
+        if !self
+            .crypto_factories
+            .range_proof
+            .verify_mask(&output.commitment, &spend_key, value.as_u64())?
+        {
+            return Err(TransactionError::RangeProofError(
+                "Recovered mask does not open the output commitment".to_string(),
+            ));
+        }
+
+        let branch = TransactionKeyManagerBranch::CommitmentMask.get_branch_key();
+        let key_id = match self.find_private_key_index(&branch, &spend_key).await {
+            Ok(index) => {
+                self.update_current_key_index_if_higher(&branch, index).await?;
+                KeyId::Managed { branch, index }
+            },
+            Err(_) => self.import_key(spend_key)?,
+        };
+        Ok((key_id, value))
+    }
+
+    pub async fn get_script_offset(
+        &self,
+        script_key_ids: &[TariKeyId],
+        sender_offset_key_ids: &[TariKeyId],
+    ) -> Result<PrivateKey, TransactionError> {
+        let mut total_script_private_key = PrivateKey::default();
+        for script_key_id in script_key_ids {
+            total_script_private_key = total_script_private_key + self.get_private_key(script_key_id).await?;
+        }
+        let mut total_sender_offset_private_key = PrivateKey::default();
+        for sender_offset_key_id in sender_offset_key_ids {
+            total_sender_offset_private_key =
+                total_sender_offset_private_key + self.get_private_key(sender_offset_key_id).await?;
+        }
+        Ok(total_script_private_key - total_sender_offset_private_key)
+    }
+
+    /// Returns the ephemeral nonce pair `(r_a, r_x)` for a metadata signature. For revealed value outputs `r_a` must be
+    /// zero so that the value part of the signature reveals the value to the base node.
+    async fn get_metadata_signature_ephemeral_private_key_pair(
+        &self,
+        nonce_id: &TariKeyId,
+        range_proof_type: RangeProofType,
+    ) -> Result<(PrivateKey, PrivateKey), TransactionError> {
+        let nonce_private_key = self.get_private_key(nonce_id).await?;
+        let nonce_a = match range_proof_type {
+            RangeProofType::BulletProofPlus => {
+                hash_to_private_key("metadata_signature_ephemeral_nonce_a", &[nonce_private_key.as_bytes()])?
+            },
+            RangeProofType::RevealedValue => PrivateKey::default(),
+        };
+        let nonce_x = hash_to_private_key("metadata_signature_ephemeral_nonce_x", &[nonce_private_key.as_bytes()])?;
+        Ok((nonce_a, nonce_x))
+    }
+
+    pub async fn get_metadata_signature_ephemeral_commitment(
+        &self,
+        nonce_id: &TariKeyId,
+        range_proof_type: RangeProofType,
+    ) -> Result<Commitment, TransactionError> {
+        let (r_a, r_x) = self
+            .get_metadata_signature_ephemeral_private_key_pair(nonce_id, range_proof_type)
+            .await?;
+        Ok(self.crypto_factories.commitment.commit(&r_x, &r_a))
+    }
+
+    pub async fn get_metadata_signature(
+        &self,
+        spending_key_id: &TariKeyId,
+        value_as_private_key: &PrivateKey,
+        sender_offset_key_id: &TariKeyId,
+        txo_version: &TransactionOutputVersion,
+        metadata_signature_message: &[u8; 32],
+        range_proof_type: RangeProofType,
+    ) -> Result<ComAndPubSignature, TransactionError> {
+        let (ephemeral_private_nonce_id, ephemeral_pubkey) = self
+            .get_next_key(&TransactionKeyManagerBranch::MetadataEphemeralNonce.get_branch_key())
+            .await?;
+        let sender_offset_public_key = self.get_public_key_at_key_id(sender_offset_key_id).await?;
+        let receiver_partial_metadata_signature = self
+            .get_receiver_partial_metadata_signature(
+                spending_key_id,
+                value_as_private_key,
+                &sender_offset_public_key,
+                &ephemeral_pubkey,
+                txo_version,
+                metadata_signature_message,
+                range_proof_type,
+            )
+            .await?;
+        let commitment = self.get_commitment(spending_key_id, value_as_private_key).await?;
+        let sender_partial_metadata_signature = self
+            .get_sender_partial_metadata_signature(
+                &ephemeral_private_nonce_id,
+                sender_offset_key_id,
+                &commitment,
+                receiver_partial_metadata_signature.ephemeral_commitment(),
+                txo_version,
+                metadata_signature_message,
+            )
+            .await?;
+        Ok(&receiver_partial_metadata_signature + &sender_partial_metadata_signature)
+    }
+
+    pub async fn get_receiver_partial_metadata_signature(
+        &self,
+        spend_key_id: &TariKeyId,
+        value: &PrivateKey,
+        sender_offset_public_key: &PublicKey,
+        ephemeral_pubkey: &PublicKey,
+        txo_version: &TransactionOutputVersion,
+        metadata_signature_message: &[u8; 32],
+        range_proof_type: RangeProofType,
+    ) -> Result<ComAndPubSignature, TransactionError> {
+        let spend_private_key = self.get_private_key(spend_key_id).await?;
+        let (ephemeral_commitment_nonce_id, _) = self
+            .get_next_key(&TransactionKeyManagerBranch::MetadataEphemeralNonce.get_branch_key())
+            .await?;
+        let (r_a, r_x) = self
+            .get_metadata_signature_ephemeral_private_key_pair(&ephemeral_commitment_nonce_id, range_proof_type)
+            .await?;
+        let ephemeral_commitment = self.crypto_factories.commitment.commit(&r_x, &r_a);
+        let commitment = self.crypto_factories.commitment.commit(&spend_private_key, value);
+        let challenge = TransactionOutput::finalize_metadata_signature_challenge(
+            txo_version,
+            sender_offset_public_key,
+            &ephemeral_commitment,
+            ephemeral_pubkey,
+            &commitment,
+            metadata_signature_message,
+        );
+        Ok(ComAndPubSignature::sign(
+            value,
+            &spend_private_key,
+            &PrivateKey::default(),
+            &r_a,
+            &r_x,
+            &PrivateKey::default(),
+            &challenge,
+            &*self.crypto_factories.commitment,
+        )?)
+    }
+
+    pub async fn get_sender_partial_metadata_signature(
+        &self,
+        ephemeral_private_nonce_id: &TariKeyId,
+        sender_offset_key_id: &TariKeyId,
+        commitment: &Commitment,
+        ephemeral_commitment: &Commitment,
+        txo_version: &TransactionOutputVersion,
+        metadata_signature_message: &[u8; 32],
+    ) -> Result<ComAndPubSignature, TransactionError> {
+        let sender_offset_private_key = self.get_private_key(sender_offset_key_id).await?;
+        let sender_offset_public_key = PublicKey::from_secret_key(&sender_offset_private_key);
+        let ephemeral_private_key = self.get_private_key(ephemeral_private_nonce_id).await?;
+        let ephemeral_pubkey = PublicKey::from_secret_key(&ephemeral_private_key);
+        let challenge = TransactionOutput::finalize_metadata_signature_challenge(
+            txo_version,
+            &sender_offset_public_key,
+            ephemeral_commitment,
+            &ephemeral_pubkey,
+            commitment,
+            metadata_signature_message,
+        );
+        Ok(ComAndPubSignature::sign(
+            &PrivateKey::default(),
+            &PrivateKey::default(),
+            &sender_offset_private_key,
+            &PrivateKey::default(),
+            &PrivateKey::default(),
+            &ephemeral_private_key,
+            &challenge,
+            &*self.crypto_factories.commitment,
+        )?)
+    }
+
+    pub async fn generate_burn_proof(
+        &self,
+        spending_key: &TariKeyId,
+        amount: &PrivateKey,
+        claim_public_key: &PublicKey,
+    ) -> Result<RistrettoComSig, TransactionError> {
+        let spend_private_key = self.get_private_key(spending_key).await?;
+        create_burn_commitment_proof(
+            &self.crypto_factories.commitment,
+            &spend_private_key,
+            amount,
+            claim_public_key,
+        )
+    }
+}
+
+/// Derives a uniformly random private key from the domain separated hash of the provided data
+fn hash_to_private_key(label: &'static str, data: &[&[u8]]) -> Result<PrivateKey, TransactionError> {
+    let hash = data
+        .iter()
+        .fold(
+            DomainSeparatedHasher::<Blake2b<U64>, KeyManagerHashDomain>::new_with_label(label),
+            |hasher, bytes| hasher.chain(bytes),
+        )
+        .finalize();
+    PrivateKey::from_uniform_bytes(hash.as_ref()).map_err(|e| TransactionError::KeyManagerError(e.to_string()))
+}
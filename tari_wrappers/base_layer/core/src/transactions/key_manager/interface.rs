@@ -62,6 +62,9 @@ pub enum TransactionKeyManagerBranch {
     Nonce = 0x04,
     KernelNonce = 0x05,
     SenderOffset = 0x06,
+    ValidatorNode = 0x07,
+    TemplateAuthor = 0x08,
+    ConfidentialOutput = 0x09,
 }
 
 impl TransactionKeyManagerBranch {
@@ -76,6 +79,9 @@ impl TransactionKeyManagerBranch {
             TransactionKeyManagerBranch::MetadataEphemeralNonce => "metadata ephemeral nonce".to_string(),
             TransactionKeyManagerBranch::KernelNonce => "kernel nonce".to_string(),
             TransactionKeyManagerBranch::SenderOffset => "sender offset".to_string(),
+            TransactionKeyManagerBranch::ValidatorNode => "validator node".to_string(),
+            TransactionKeyManagerBranch::TemplateAuthor => "template author".to_string(),
+            TransactionKeyManagerBranch::ConfidentialOutput => "confidential output".to_string(),
         }
     }
 
@@ -87,6 +93,9 @@ impl TransactionKeyManagerBranch {
             "metadata ephemeral nonce" => TransactionKeyManagerBranch::MetadataEphemeralNonce,
             "kernel nonce" => TransactionKeyManagerBranch::KernelNonce,
             "sender offset" => TransactionKeyManagerBranch::SenderOffset,
+            "validator node" => TransactionKeyManagerBranch::ValidatorNode,
+            "template author" => TransactionKeyManagerBranch::TemplateAuthor,
+            "confidential output" => TransactionKeyManagerBranch::ConfidentialOutput,
             "nonce" => TransactionKeyManagerBranch::Nonce,
             _ => TransactionKeyManagerBranch::Nonce,
         }
@@ -95,6 +104,17 @@ impl TransactionKeyManagerBranch {
     pub fn as_byte(self) -> u8 {
         self as u8
     }
+
+    /// Whether the branch holds keys for second layer (DAN) identities rather than base layer transactions. The same
+    /// cipher seed backs both, but sidechain keys are never used to sign base layer transaction components.
+    pub fn is_sidechain_branch(self) -> bool {
+        matches!(
+            self,
+            TransactionKeyManagerBranch::ValidatorNode |
+                TransactionKeyManagerBranch::TemplateAuthor |
+                TransactionKeyManagerBranch::ConfidentialOutput
+        )
+    }
 }
 
 #[derive(Clone, Copy, EnumIter)]
@@ -0,0 +1,31 @@
+// Copyright 2023 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use tari_common_types::{types::PublicKey, wallet_types::WalletType};
+use tari_key_manager::{
+    cipher_seed::CipherSeed,
+    key_manager_service::{
+        storage::{database::KeyManagerDatabase, memory_db::KeyManagerMemoryDatabase},
+        KeyManagerServiceError,
+    },
+};
+
+use crate::transactions::{key_manager::TransactionKeyManagerWrapper, CryptoFactories};
+
+/// A transaction key manager that keeps all of its branch state and imported keys in memory.
+pub type MemoryDbKeyManager = TransactionKeyManagerWrapper<KeyManagerMemoryDatabase<PublicKey>>;
+
+/// Creates a [MemoryDbKeyManager] for the provided seed, using the provided factories so that callers can share one
+/// set of range proof generators. All key indices start at zero, so wallets that have been used before will have their
+/// indices advanced as their outputs are recovered.
+pub fn create_memory_db_key_manager(
+    seed: CipherSeed,
+    factories: CryptoFactories,
+) -> Result<MemoryDbKeyManager, KeyManagerServiceError> {
+    TransactionKeyManagerWrapper::new(
+        seed,
+        KeyManagerDatabase::new(KeyManagerMemoryDatabase::new()),
+        factories,
+        WalletType::default(),
+    )
+}
@@ -21,6 +21,9 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 #![allow(clippy::too_many_arguments)]
+mod inner;
+pub use inner::derive_labelled_key;
+
 mod wrapper;
 pub use wrapper::TransactionKeyManagerWrapper;
 
@@ -35,5 +38,8 @@ pub use interface::{
 };
 
 /// This is a memory database implementation of the `TransactionKeyManager` trait.
+mod memory_db_key_manager;
+pub use memory_db_key_manager::{create_memory_db_key_manager, MemoryDbKeyManager};
+
 mod error;
 pub use error::CoreKeyManagerError;
@@ -20,7 +20,7 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::marker::PhantomData;
+use std::sync::Arc;
 
 use blake2::Blake2b;
 use digest::consts::U64;
@@ -33,12 +33,18 @@ use tari_crypto::{hashing::DomainSeparatedHash, ristretto::RistrettoComSig};
 use tari_key_manager::{
     cipher_seed::CipherSeed,
     interface::AddResult,
-    key_manager_service::{storage::database::KeyManagerBackend, KeyManagerInterface, KeyManagerServiceError},
+    key_manager_service::{
+        storage::database::{KeyManagerBackend, KeyManagerDatabase},
+        KeyManagerInterface,
+        KeyManagerServiceError,
+    },
     KeyId,
 };
+use tokio::sync::RwLock;
 
 use crate::transactions::{
     key_manager::{
+        inner::TransactionKeyManagerInner,
         interface::{SecretTransactionKeyManagerInterface, TxoStage},
         TariKeyId,
         TransactionKeyManagerInterface,
@@ -64,11 +70,7 @@ use crate::transactions::{
 /// This handle can be cloned cheaply and safely shared across multiple threads.
 #[derive(Clone)]
 pub struct TransactionKeyManagerWrapper<TBackend> {
-    transaction_key_manager_inner: PhantomData<TBackend>,
-}
-
-pub struct KeyManagerDatabase<TBackend> {
-    db: PhantomData<TBackend>,
+    transaction_key_manager_inner: Arc<RwLock<TransactionKeyManagerInner<TBackend>>>,
 }
 
 impl<TBackend> TransactionKeyManagerWrapper<TBackend>
@@ -77,13 +79,20 @@ where TBackend: KeyManagerBackend<PublicKey> + 'static
     /// Creates a new key manager.
     /// * `master_seed` is the primary seed that will be used to derive all unique branch keys with their indexes
     /// * `db` implements `KeyManagerBackend` and is used for persistent storage of branches and indices.
+    /// * `wallet_type` only software wallets are supported, so the keys are always derived from `master_seed`.
     pub fn new(
-        _master_seed: CipherSeed,
-        _db: KeyManagerDatabase<TBackend>,
-        _crypto_factories: CryptoFactories,
+        master_seed: CipherSeed,
+        db: KeyManagerDatabase<TBackend, PublicKey>,
+        crypto_factories: CryptoFactories,
         _wallet_type: WalletType,
     ) -> Result<Self, KeyManagerServiceError> {
-        unimplemented!("new")
+        Ok(TransactionKeyManagerWrapper {
+            transaction_key_manager_inner: Arc::new(RwLock::new(TransactionKeyManagerInner::new(
+                master_seed,
+                db,
+                crypto_factories,
+            )?)),
+        })
     }
 }
 
@@ -91,46 +100,68 @@ where TBackend: KeyManagerBackend<PublicKey> + 'static
 impl<TBackend> KeyManagerInterface<PublicKey> for TransactionKeyManagerWrapper<TBackend>
 where TBackend: KeyManagerBackend<PublicKey> + 'static
 {
-    async fn add_new_branch<T: Into<String> + Send>(&self, _branch: T) -> Result<AddResult, KeyManagerServiceError> {
-        unimplemented!("add_new_branch")
+    async fn add_new_branch<T: Into<String> + Send>(&self, branch: T) -> Result<AddResult, KeyManagerServiceError> {
+        self.transaction_key_manager_inner
+            .write()
+            .await
+            .add_key_manager_branch(&branch.into())
     }
 
     async fn get_next_key<T: Into<String> + Send>(
         &self,
-        _branch: T,
+        branch: T,
     ) -> Result<(KeyId<PublicKey>, PublicKey), KeyManagerServiceError> {
-        unimplemented!("get_next_key")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_next_key(&branch.into())
+            .await
     }
 
     async fn get_static_key<T: Into<String> + Send>(
         &self,
-        _branch: T,
+        branch: T,
     ) -> Result<KeyId<PublicKey>, KeyManagerServiceError> {
-        unimplemented!("get_static_key")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_static_key(&branch.into())
     }
 
-    async fn get_public_key_at_key_id(&self, _key_id: &KeyId<PublicKey>) -> Result<PublicKey, KeyManagerServiceError> {
-        unimplemented!("get_public_key_at_key_id")
+    async fn get_public_key_at_key_id(&self, key_id: &KeyId<PublicKey>) -> Result<PublicKey, KeyManagerServiceError> {
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_public_key_at_key_id(key_id)
+            .await
     }
 
     async fn find_key_index<T: Into<String> + Send>(
         &self,
-        _branch: T,
-        _key: &PublicKey,
+        branch: T,
+        key: &PublicKey,
     ) -> Result<u64, KeyManagerServiceError> {
-        unimplemented!("find_key_index")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .find_key_index(&branch.into(), key)
+            .await
     }
 
     async fn update_current_key_index_if_higher<T: Into<String> + Send>(
         &self,
-        _branch: T,
-        _index: u64,
+        branch: T,
+        index: u64,
     ) -> Result<(), KeyManagerServiceError> {
-        unimplemented!("update_current_key_index_if_higher")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .update_current_key_index_if_higher(&branch.into(), index)
+            .await
     }
 
-    async fn import_key(&self, _private_key: PrivateKey) -> Result<TariKeyId, KeyManagerServiceError> {
-        unimplemented!("import_key")
+    async fn import_key(&self, private_key: PrivateKey) -> Result<TariKeyId, KeyManagerServiceError> {
+        self.transaction_key_manager_inner.read().await.import_key(private_key)
     }
 }
 
@@ -140,194 +171,309 @@ where TBackend: KeyManagerBackend<PublicKey> + 'static
 {
     async fn get_commitment(
         &self,
-        _spend_key_id: &TariKeyId,
-        _value: &PrivateKey,
+        spend_key_id: &TariKeyId,
+        value: &PrivateKey,
     ) -> Result<Commitment, KeyManagerServiceError> {
-        unimplemented!("get_commitment")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_commitment(spend_key_id, value)
+            .await
     }
 
     async fn verify_mask(
         &self,
-        _commitment: &Commitment,
-        _spending_key_id: &TariKeyId,
-        _value: u64,
+        commitment: &Commitment,
+        spending_key_id: &TariKeyId,
+        value: u64,
     ) -> Result<bool, KeyManagerServiceError> {
-        unimplemented!("verify_mask")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .verify_mask(commitment, spending_key_id, value)
+            .await
     }
 
     async fn get_recovery_key_id(&self) -> Result<TariKeyId, KeyManagerServiceError> {
-        unimplemented!("get_recovery_key_id")
+        self.transaction_key_manager_inner.read().await.get_recovery_key_id()
     }
 
     async fn get_next_spend_and_script_key_ids(
         &self,
     ) -> Result<(TariKeyId, PublicKey, TariKeyId, PublicKey), KeyManagerServiceError> {
-        unimplemented!("get_next_spend_and_script_key_ids")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_next_spend_and_script_key_ids()
+            .await
     }
 
     async fn find_script_key_id_from_spend_key_id(
         &self,
-        _spend_key_id: &TariKeyId,
-        _public_script_key: Option<&PublicKey>,
+        spend_key_id: &TariKeyId,
+        public_script_key: Option<&PublicKey>,
     ) -> Result<Option<TariKeyId>, KeyManagerServiceError> {
-        unimplemented!("find_script_key_id_from_spend_key_id")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .find_script_key_id_from_spend_key_id(spend_key_id, public_script_key)
+            .await
     }
 
     async fn get_diffie_hellman_shared_secret(
         &self,
-        _secret_key_id: &TariKeyId,
-        _public_key: &PublicKey,
+        secret_key_id: &TariKeyId,
+        public_key: &PublicKey,
     ) -> Result<CommsDHKE, TransactionError> {
-        unimplemented!("get_diffie_hellman_shared_secret")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_diffie_hellman_shared_secret(secret_key_id, public_key)
+            .await
     }
 
     async fn get_diffie_hellman_stealth_domain_hasher(
         &self,
-        _secret_key_id: &TariKeyId,
-        _public_key: &PublicKey,
+        secret_key_id: &TariKeyId,
+        public_key: &PublicKey,
     ) -> Result<DomainSeparatedHash<Blake2b<U64>>, TransactionError> {
-        unimplemented!("get_diffie_hellman_stealth_domain_hasher")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_diffie_hellman_stealth_domain_hasher(secret_key_id, public_key)
+            .await
     }
 
     async fn import_add_offset_to_private_key(
         &self,
-        _secret_key_id: &TariKeyId,
-        _offset: PrivateKey,
+        secret_key_id: &TariKeyId,
+        offset: PrivateKey,
     ) -> Result<TariKeyId, KeyManagerServiceError> {
-        unimplemented!("import_add_offset_to_private_key")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .import_add_offset_to_private_key(secret_key_id, offset)
+            .await
     }
 
-    async fn get_spending_key_id(&self, _public_spending_key: &PublicKey) -> Result<TariKeyId, TransactionError> {
-        unimplemented!("get_spending_key_id")
+    async fn get_spending_key_id(&self, public_spending_key: &PublicKey) -> Result<TariKeyId, TransactionError> {
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_spending_key_id(public_spending_key)
+            .await
     }
 
     async fn construct_range_proof(
         &self,
-        _spend_key_id: &TariKeyId,
-        _value: u64,
-        _min_value: u64,
+        spend_key_id: &TariKeyId,
+        value: u64,
+        min_value: u64,
     ) -> Result<RangeProof, TransactionError> {
-        unimplemented!("construct_range_proof")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .construct_range_proof(spend_key_id, value, min_value)
+            .await
     }
 
     async fn get_script_signature(
         &self,
-        _script_key_id: &TariKeyId,
-        _spend_key_id: &TariKeyId,
-        _value: &PrivateKey,
-        _txi_version: &TransactionInputVersion,
-        _script_message: &[u8; 32],
+        script_key_id: &TariKeyId,
+        spend_key_id: &TariKeyId,
+        value: &PrivateKey,
+        txi_version: &TransactionInputVersion,
+        script_message: &[u8; 32],
     ) -> Result<ComAndPubSignature, TransactionError> {
-        unimplemented!("get_script_signature")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_script_signature(script_key_id, spend_key_id, value, txi_version, script_message)
+            .await
     }
 
     async fn get_partial_txo_kernel_signature(
         &self,
-        _spend_key_id: &TariKeyId,
-        _nonce_id: &TariKeyId,
-        _total_nonce: &PublicKey,
-        _total_excess: &PublicKey,
-        _kernel_version: &TransactionKernelVersion,
-        _kernel_message: &[u8; 32],
-        _kernel_features: &KernelFeatures,
-        _txo_type: TxoStage,
+        spend_key_id: &TariKeyId,
+        nonce_id: &TariKeyId,
+        total_nonce: &PublicKey,
+        total_excess: &PublicKey,
+        kernel_version: &TransactionKernelVersion,
+        kernel_message: &[u8; 32],
+        kernel_features: &KernelFeatures,
+        txo_type: TxoStage,
     ) -> Result<Signature, TransactionError> {
-        unimplemented!("get_partial_txo_kernel_signature")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_partial_txo_kernel_signature(
+                spend_key_id,
+                nonce_id,
+                total_nonce,
+                total_excess,
+                kernel_version,
+                kernel_message,
+                kernel_features,
+                txo_type,
+            )
+            .await
     }
 
     async fn get_txo_kernel_signature_excess_with_offset(
         &self,
-        _spend_key_id: &TariKeyId,
-        _nonce_id: &TariKeyId,
+        spend_key_id: &TariKeyId,
+        nonce_id: &TariKeyId,
     ) -> Result<PublicKey, TransactionError> {
-        unimplemented!("get_txo_kernel_signature_excess_with_offset")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_txo_kernel_signature_excess_with_offset(spend_key_id, nonce_id)
+            .await
     }
 
     async fn get_txo_private_kernel_offset(
         &self,
-        _spend_key_id: &TariKeyId,
-        _nonce_id: &TariKeyId,
+        spend_key_id: &TariKeyId,
+        nonce_id: &TariKeyId,
     ) -> Result<PrivateKey, TransactionError> {
-        unimplemented!("get_txo_private_kernel_offset")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_txo_private_kernel_offset(spend_key_id, nonce_id)
+            .await
     }
 
     async fn encrypt_data_for_recovery(
         &self,
-        _spend_key_id: &TariKeyId,
-        _custom_recovery_key_id: Option<&TariKeyId>,
-        _value: u64,
+        spend_key_id: &TariKeyId,
+        custom_recovery_key_id: Option<&TariKeyId>,
+        value: u64,
     ) -> Result<EncryptedData, TransactionError> {
-        unimplemented!("encrypt_data_for_recovery")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .encrypt_data_for_recovery(spend_key_id, custom_recovery_key_id, value)
+            .await
     }
 
     async fn try_output_key_recovery(
         &self,
-        _output: &TransactionOutput,
-        _custom_recovery_key_id: Option<&TariKeyId>,
+        output: &TransactionOutput,
+        custom_recovery_key_id: Option<&TariKeyId>,
     ) -> Result<(TariKeyId, MicroMinotari), TransactionError> {
-        unimplemented!("try_output_key_recovery")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .try_output_key_recovery(output, custom_recovery_key_id)
+            .await
     }
 
     async fn get_script_offset(
         &self,
-        _script_key_ids: &[TariKeyId],
-        _sender_offset_key_ids: &[TariKeyId],
+        script_key_ids: &[TariKeyId],
+        sender_offset_key_ids: &[TariKeyId],
     ) -> Result<PrivateKey, TransactionError> {
-        unimplemented!("get_script_offset")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_script_offset(script_key_ids, sender_offset_key_ids)
+            .await
     }
 
     async fn get_metadata_signature_ephemeral_commitment(
         &self,
-        _nonce_id: &TariKeyId,
-        _range_proof_type: RangeProofType,
+        nonce_id: &TariKeyId,
+        range_proof_type: RangeProofType,
     ) -> Result<Commitment, TransactionError> {
-        unimplemented!("get_metadata_signature_ephemeral_commitment")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_metadata_signature_ephemeral_commitment(nonce_id, range_proof_type)
+            .await
     }
 
     async fn get_metadata_signature(
         &self,
-        _spending_key_id: &TariKeyId,
-        _value_as_private_key: &PrivateKey,
-        _sender_offset_key_id: &TariKeyId,
-        _txo_version: &TransactionOutputVersion,
-        _metadata_signature_message: &[u8; 32],
-        _range_proof_type: RangeProofType,
+        spending_key_id: &TariKeyId,
+        value_as_private_key: &PrivateKey,
+        sender_offset_key_id: &TariKeyId,
+        txo_version: &TransactionOutputVersion,
+        metadata_signature_message: &[u8; 32],
+        range_proof_type: RangeProofType,
     ) -> Result<ComAndPubSignature, TransactionError> {
-        unimplemented!("get_metadata_signature")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_metadata_signature(
+                spending_key_id,
+                value_as_private_key,
+                sender_offset_key_id,
+                txo_version,
+                metadata_signature_message,
+                range_proof_type,
+            )
+            .await
     }
 
     async fn get_receiver_partial_metadata_signature(
         &self,
-        _spend_key_id: &TariKeyId,
-        _value: &PrivateKey,
-        _sender_offset_public_key: &PublicKey,
-        _ephemeral_pubkey: &PublicKey,
-        _txo_version: &TransactionOutputVersion,
-        _metadata_signature_message: &[u8; 32],
-        _range_proof_type: RangeProofType,
+        spend_key_id: &TariKeyId,
+        value: &PrivateKey,
+        sender_offset_public_key: &PublicKey,
+        ephemeral_pubkey: &PublicKey,
+        txo_version: &TransactionOutputVersion,
+        metadata_signature_message: &[u8; 32],
+        range_proof_type: RangeProofType,
     ) -> Result<ComAndPubSignature, TransactionError> {
-        unimplemented!("get_receiver_partial_metadata_signature")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_receiver_partial_metadata_signature(
+                spend_key_id,
+                value,
+                sender_offset_public_key,
+                ephemeral_pubkey,
+                txo_version,
+                metadata_signature_message,
+                range_proof_type,
+            )
+            .await
     }
 
     async fn get_sender_partial_metadata_signature(
         &self,
-        _ephemeral_private_nonce_id: &TariKeyId,
-        _sender_offset_key_id: &TariKeyId,
-        _commitment: &Commitment,
-        _ephemeral_commitment: &Commitment,
-        _txo_version: &TransactionOutputVersion,
-        _metadata_signature_message: &[u8; 32],
+        ephemeral_private_nonce_id: &TariKeyId,
+        sender_offset_key_id: &TariKeyId,
+        commitment: &Commitment,
+        ephemeral_commitment: &Commitment,
+        txo_version: &TransactionOutputVersion,
+        metadata_signature_message: &[u8; 32],
     ) -> Result<ComAndPubSignature, TransactionError> {
-        unimplemented!("get_sender_partial_metadata_signature")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_sender_partial_metadata_signature(
+                ephemeral_private_nonce_id,
+                sender_offset_key_id,
+                commitment,
+                ephemeral_commitment,
+                txo_version,
+                metadata_signature_message,
+            )
+            .await
     }
 
     async fn generate_burn_proof(
         &self,
-        _spending_key: &TariKeyId,
-        _amount: &PrivateKey,
-        _claim_public_key: &PublicKey,
+        spending_key: &TariKeyId,
+        amount: &PrivateKey,
+        claim_public_key: &PublicKey,
     ) -> Result<RistrettoComSig, TransactionError> {
-        unimplemented!("generate_burn_proof")
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .generate_burn_proof(spending_key, amount, claim_public_key)
+            .await
     }
 }
 
@@ -335,7 +481,11 @@ where TBackend: KeyManagerBackend<PublicKey> + 'static
 impl<TBackend> SecretTransactionKeyManagerInterface for TransactionKeyManagerWrapper<TBackend>
 where TBackend: KeyManagerBackend<PublicKey> + 'static
 {
-    async fn get_private_key(&self, _key_id: &TariKeyId) -> Result<PrivateKey, KeyManagerServiceError> {
-        unimplemented!("get_private_key")
+    async fn get_private_key(&self, key_id: &TariKeyId) -> Result<PrivateKey, KeyManagerServiceError> {
+        self.transaction_key_manager_inner
+            .read()
+            .await
+            .get_private_key(key_id)
+            .await
     }
 }
@@ -8,6 +8,7 @@ pub use crypto_factories::CryptoFactories;
 mod format_currency;
 pub use format_currency::format_currency;
 pub mod key_manager;
+pub mod output_audit;
 pub mod tari_amount;
 pub mod transaction_components;
 pub mod transaction_protocol;
@@ -0,0 +1,114 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::RangeProofService;
+use tari_crypto::tari_utilities::hex::Hex;
+
+use crate::transactions::{
+    key_manager::{TariKeyId, TransactionKeyManagerInterface},
+    transaction_components::WalletOutput,
+    CryptoFactories,
+};
+
+/// A discrepancy found while auditing a stored wallet output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputAuditIssue {
+    /// The commitment could not be recomputed from the spending key and value
+    Commitment(String),
+    /// The metadata signature does not verify against the recomputed output
+    MetadataSignature(String),
+    /// The encrypted data decrypts to a different value or spending key than the one stored
+    EncryptedData(String),
+}
+
+/// The discrepancies found for a single wallet output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputAuditReport {
+    /// The position of the output in the audited list
+    pub index: usize,
+    /// The discrepancies found for this output
+    pub issues: Vec<OutputAuditIssue>,
+}
+
+/// Audits persisted wallet outputs for corruption or tampering before they are spent. For every output the commitment
+/// is recomputed from the spending key and value and checked against the range proof, the metadata signature is
+/// verified against the recomputed output, and the encrypted data is decrypted and compared to the stored value and
+/// spending key. Only outputs with discrepancies are reported; encrypted data that cannot be decrypted is one.
+///
+/// Each output is passed with the custom recovery key its encrypted data was produced with, if any (e.g. for one-sided
+/// payments), as it cannot be decrypted with the wallet's default recovery key. The range proofs are verified with
+/// `factories`, so that callers can share one set of range proof generators.
+pub async fn audit_outputs<KM: TransactionKeyManagerInterface>(
+    wallet_outputs: &[(WalletOutput, Option<TariKeyId>)],
+    key_manager: &KM,
+    factories: &CryptoFactories,
+) -> Vec<OutputAuditReport> {
+    let mut reports = Vec::new();
+    for (index, (wallet_output, custom_recovery_key_id)) in wallet_outputs.iter().enumerate() {
+        let issues = audit_output(
+            wallet_output,
+            custom_recovery_key_id.as_ref(),
+            key_manager,
+            &factories.range_proof,
+        )
+        .await;
+        if !issues.is_empty() {
+            reports.push(OutputAuditReport { index, issues });
+        }
+    }
+    reports
+}
+
+async fn audit_output<KM: TransactionKeyManagerInterface>(
+    wallet_output: &WalletOutput,
+    custom_recovery_key_id: Option<&TariKeyId>,
+    key_manager: &KM,
+    range_proof: &RangeProofService,
+) -> Vec<OutputAuditIssue> {
+    // The commitment is recomputed from the stored spending key and value
+    let output = match wallet_output.to_transaction_output(key_manager).await {
+        Ok(output) => output,
+        Err(e) => {
+            return vec![OutputAuditIssue::Commitment(format!(
+                "Could not recompute the commitment: {e}"
+            ))]
+        },
+    };
+
+    let mut issues = Vec::new();
+    // The range proof commits to the commitment the output was created with, so it only verifies against the
+    // recomputed commitment if the spending key and value are intact
+    if let Err(e) = output.verify_range_proof(range_proof) {
+        issues.push(OutputAuditIssue::Commitment(format!(
+            "The recomputed commitment {} does not match the range proof: {e}",
+            output.commitment.to_hex()
+        )));
+    }
+    if let Err(e) = output.verify_metadata_signature() {
+        issues.push(OutputAuditIssue::MetadataSignature(e.to_string()));
+    }
+    match key_manager
+        .try_output_key_recovery(&output, custom_recovery_key_id)
+        .await
+    {
+        Ok((spending_key_id, value)) => {
+            if value != wallet_output.value {
+                issues.push(OutputAuditIssue::EncryptedData(format!(
+                    "Decrypted value {} does not match stored value {}",
+                    value, wallet_output.value
+                )));
+            }
+            if spending_key_id != wallet_output.spending_key_id {
+                issues.push(OutputAuditIssue::EncryptedData(format!(
+                    "Decrypted spending key `{}` does not match stored spending key `{}`",
+                    spending_key_id, wallet_output.spending_key_id
+                )));
+            }
+        },
+        Err(e) => issues.push(OutputAuditIssue::EncryptedData(format!(
+            "Could not decrypt the encrypted data: {e}"
+        ))),
+    }
+    issues
+}
@@ -37,6 +37,7 @@ use chacha20poly1305::{
     XNonce,
 };
 use digest::{consts::U32, generic_array::GenericArray, FixedOutput};
+use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use tari_common_types::types::{Commitment, PrivateKey};
 use tari_crypto::{hashing::DomainSeparatedHasher, keys::SecretKey};
@@ -85,6 +86,19 @@ impl EncryptedData {
         commitment: &Commitment,
         value: MicroMinotari,
         mask: &PrivateKey,
+    ) -> Result<EncryptedData, EncryptedDataError> {
+        EncryptedData::encrypt_data_with_rng(encryption_key, commitment, value, mask, &mut OsRng)
+    }
+
+    /// Encrypt the value and mask as with `encrypt_data`, drawing the nonce from `rng`
+    /// Note: The nonce must never be reused for the same key and commitment, so `rng` must only be a seeded RNG where
+    ///       the ciphertext has to be reproducible, e.g. for test vectors
+    pub fn encrypt_data_with_rng<R: RngCore + CryptoRng>(
+        encryption_key: &PrivateKey,
+        commitment: &Commitment,
+        value: MicroMinotari,
+        mask: &PrivateKey,
+        rng: &mut R,
     ) -> Result<EncryptedData, EncryptedDataError> {
         // Encode the value and mask
         let mut bytes = Zeroizing::new([0u8; SIZE_VALUE + SIZE_MASK]);
@@ -92,7 +106,7 @@ impl EncryptedData {
         bytes[SIZE_VALUE..].clone_from_slice(mask.as_bytes());
 
         // Produce a secure random nonce
-        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let nonce = XChaCha20Poly1305::generate_nonce(rng);
 
         // Set up the AEAD
         let aead_key = kdf_aead(encryption_key, commitment);
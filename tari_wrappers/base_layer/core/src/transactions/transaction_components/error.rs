@@ -75,6 +75,8 @@ pub enum TransactionError {
     EncryptedDataError(String),
     #[error("Transaction has a zero weight, not possible")]
     ZeroWeight,
+    #[error("Body contains an output that is spent by an input in the same body")]
+    InvalidCutThrough,
 }
 
 impl From<KeyManagerServiceError> for TransactionError {
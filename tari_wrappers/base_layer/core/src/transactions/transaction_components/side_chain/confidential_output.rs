@@ -21,10 +21,35 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use borsh::{BorshDeserialize, BorshSerialize};
+use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
-use tari_common_types::types::PublicKey;
+use tari_common_types::types::{CommitmentFactory, PrivateKey, PublicKey};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey, ristretto::RistrettoComSig};
+
+use crate::{transactions::transaction_components::TransactionError, ConfidentialOutputHasher};
 
 #[derive(Debug, Clone, Hash, PartialEq, Deserialize, Serialize, Eq, BorshSerialize, BorshDeserialize)]
 pub struct ConfidentialOutputData {
     pub claim_public_key: PublicKey,
 }
+
+/// Signs the commitment of a burned output, proving knowledge of its opening `(spending_key, amount)` and binding it to
+/// the claim public key, as required to claim the burned funds as a confidential output on the second layer
+pub fn create_burn_commitment_proof(
+    factory: &CommitmentFactory,
+    spending_key: &PrivateKey,
+    amount: &PrivateKey,
+    claim_public_key: &PublicKey,
+) -> Result<RistrettoComSig, TransactionError> {
+    let commitment = factory.commit(spending_key, amount);
+    let nonce_a = PrivateKey::random(&mut OsRng);
+    let nonce_x = PrivateKey::random(&mut OsRng);
+    let public_nonce = factory.commit(&nonce_x, &nonce_a);
+    let challenge = ConfidentialOutputHasher::new("commitment_signature")
+        .chain(&public_nonce)
+        .chain(&commitment)
+        .chain(claim_public_key)
+        .finalize();
+    RistrettoComSig::sign(amount, spending_key, &nonce_a, &nonce_x, challenge.as_ref(), factory)
+        .map_err(|e| TransactionError::InvalidSignatureError(e.to_string()))
+}
@@ -29,7 +29,7 @@ mod validator_node_registration;
 mod validator_node_signature;
 
 use blake2::Blake2b;
-pub use confidential_output::ConfidentialOutputData;
+pub use confidential_output::{create_burn_commitment_proof, ConfidentialOutputData};
 use digest::consts::U32;
 use tari_crypto::{hash_domain, hashing::DomainSeparatedHasher};
 pub use template_registration::{BuildInfo, CodeTemplateRegistration, TemplateType};
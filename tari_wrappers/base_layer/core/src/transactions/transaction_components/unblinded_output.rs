@@ -28,6 +28,7 @@ use std::{
     fmt::{Debug, Formatter},
 };
 
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use tari_common_types::types::{ComAndPubSignature, PrivateKey, PublicKey};
 use tari_script::{ExecutionStack, TariScript};
@@ -44,8 +45,8 @@ use crate::{
 
 /// An unblinded output is one where the value and spending key (blinding factor) are known. This can be used to
 /// build both inputs and outputs (every input comes from an output). This is only used for import and export where
-/// serialization is important.
-#[derive(Clone, Serialize, Deserialize)]
+/// serialization is important. The JSON form is the one the console wallet exports and imports UTXOs in.
+#[derive(Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct UnblindedOutput {
     pub version: TransactionOutputVersion,
     pub value: MicroMinotari,
@@ -118,6 +118,11 @@ impl WalletOutputBuilder {
         Ok(self)
     }
 
+    pub fn with_encrypted_data(mut self, encrypted_data: EncryptedData) -> Self {
+        self.encrypted_data = encrypted_data;
+        self
+    }
+
     pub fn with_script_key(mut self, script_key_id: TariKeyId) -> Self {
         self.script_key_id = Some(script_key_id);
         self
@@ -47,6 +47,8 @@ pub enum KeyManagerError {
     SliceError(String),
     #[error("Key ID not valid")]
     InvalidKeyID,
+    #[error("The key index overflows")]
+    KeyIndexOverflow,
 }
 
 impl From<ByteArrayError> for KeyManagerError {
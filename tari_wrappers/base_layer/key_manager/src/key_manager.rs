@@ -20,12 +20,16 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::marker::PhantomData;
+
+use blake2::Blake2b;
 use derivative::Derivative;
+use digest::consts::U64;
 use serde::{Deserialize, Serialize};
 use tari_crypto::{keys::PublicKey, tari_utilities::byte_array::ByteArrayError};
 use zeroize::Zeroize;
 
-use crate::cipher_seed::CipherSeed;
+use crate::{cipher_seed::CipherSeed, error::KeyManagerError, mac_domain_hasher, LABEL_DERIVE_KEY};
 
 #[derive(Clone, Derivative, Serialize, Deserialize, Zeroize)]
 #[derivative(Debug)]
@@ -52,9 +56,11 @@ where PK: PublicKey
 #[derive(Clone, Derivative, PartialEq, Serialize, Deserialize, Zeroize)]
 #[derivative(Debug)]
 pub struct KeyManager<PK: PublicKey> {
+    #[derivative(Debug = "ignore")]
+    seed: CipherSeed,
     pub branch_seed: String,
     primary_key_index: u64,
-    key: Option<PK>,
+    key_type: PhantomData<PK>,
 }
 
 impl<PK> KeyManager<PK>
@@ -63,49 +69,75 @@ where PK: PublicKey
     /// Creates a new KeyManager with a new randomly selected entropy
     pub fn new() -> KeyManager<PK> {
         KeyManager {
+            seed: CipherSeed::new(),
             branch_seed: "".to_string(),
             primary_key_index: 0,
-            key: None,
+            key_type: PhantomData,
         }
     }
 
     /// Constructs a KeyManager from known parts
-    pub fn from(branch_seed: String, primary_key_index: u64) -> KeyManager<PK> {
+    pub fn from(seed: CipherSeed, branch_seed: String, primary_key_index: u64) -> KeyManager<PK> {
         KeyManager {
+            seed,
             branch_seed,
             primary_key_index,
-            key: None,
+            key_type: PhantomData,
         }
     }
 
     /// Derive a new private key from master key: derived_key=H(master_key||branch_seed||index), for some
     /// hash function H which is Length attack resistant, such as Blake2b.
-    pub fn derive_key(&self, _key_index: u64) -> Result<DerivedKey<PK>, ByteArrayError> {
-        unimplemented!("derive_key")
+    pub fn derive_key(&self, key_index: u64) -> Result<DerivedKey<PK>, ByteArrayError> {
+        // Apply domain separation to generate the derived key. Under the hood, the hashing api prepends the length of
+        // each piece of data for concatenation, reducing the risk of collisions due to redundancy of variable length
+        // input
+        let derived_key = mac_domain_hasher::<Blake2b<U64>>(LABEL_DERIVE_KEY)
+            .chain(self.seed.entropy())
+            .chain(self.branch_seed.as_bytes())
+            .chain(key_index.to_le_bytes())
+            .finalize();
+        let key = PK::K::from_uniform_bytes(derived_key.as_ref())?;
+        Ok(DerivedKey { key, key_index })
     }
 
     /// Derive a new public key from master key: derived_key=H(master_key||branch_seed||index), for some
     /// hash function H which is Length attack resistant, such as Blake2b.
-    pub fn derive_public_key(&self, _key_index: u64) -> Result<DerivedPublicKey<PK>, ByteArrayError> {
-        unimplemented!("derive_public_key")
+    pub fn derive_public_key(&self, key_index: u64) -> Result<DerivedPublicKey<PK>, ByteArrayError> {
+        let derived_key = self.derive_key(key_index)?;
+        Ok(DerivedPublicKey {
+            key: PK::from_secret_key(&derived_key.key),
+            key_index,
+        })
     }
 
-    pub fn get_private_key(&self, _key_index: u64) -> Result<PK::K, ByteArrayError> {
-        unimplemented!("get_private_key")
+    pub fn get_private_key(&self, key_index: u64) -> Result<PK::K, ByteArrayError> {
+        Ok(self.derive_key(key_index)?.key)
     }
 
-    /// Generate next deterministic private key derived from master key
-    pub fn next_key(&mut self) -> Result<DerivedKey<PK>, ByteArrayError> {
-        unimplemented!("next_key")
+    /// Generate next deterministic private key derived from master key. The key index is left unchanged if it would
+    /// overflow.
+    pub fn next_key(&mut self) -> Result<DerivedKey<PK>, KeyManagerError> {
+        let key_index = self
+            .primary_key_index
+            .checked_add(1)
+            .ok_or(KeyManagerError::KeyIndexOverflow)?;
+        let key = self.derive_key(key_index)?;
+        self.primary_key_index = key_index;
+        Ok(key)
     }
 
-    /// Generate next deterministic private key derived from master key
-    pub fn increment_key_index(&mut self, _increment: u64) -> u64 {
-        unimplemented!("increment_key_index")
+    /// Increment the key index by the provided amount and return the new key index, or an error if it would overflow
+    pub fn increment_key_index(&mut self, increment: u64) -> Result<u64, KeyManagerError> {
+        self.primary_key_index = self
+            .primary_key_index
+            .checked_add(increment)
+            .ok_or(KeyManagerError::KeyIndexOverflow)?;
+        Ok(self.primary_key_index)
     }
 
     pub fn cipher_seed(&self) -> &CipherSeed {
-        unimplemented!("cipher_seed")
+        &self.seed
     }
 
     pub fn key_index(&self) -> u64 {
@@ -124,3 +156,43 @@ where K: PublicKey
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use tari_crypto::ristretto::RistrettoPublicKey;
+
+    use super::*;
+
+    #[test]
+    fn next_key_advances_the_index() {
+        let mut key_manager = KeyManager::<RistrettoPublicKey>::from(CipherSeed::new(), "branch".to_string(), 0);
+        let key = key_manager.next_key().unwrap();
+        assert_eq!(key.key_index, 1);
+        assert_eq!(key_manager.key_index(), 1);
+        assert_eq!(key.key, key_manager.derive_key(1).unwrap().key);
+        assert_eq!(key_manager.increment_key_index(5), Ok(6));
+        assert_eq!(key_manager.next_key().unwrap().key_index, 7);
+    }
+
+    #[test]
+    fn key_index_overflow_is_an_error() {
+        let mut key_manager =
+            KeyManager::<RistrettoPublicKey>::from(CipherSeed::new(), "branch".to_string(), u64::MAX - 1);
+        assert_eq!(key_manager.next_key().unwrap().key_index, u64::MAX);
+        assert_eq!(key_manager.next_key().err(), Some(KeyManagerError::KeyIndexOverflow));
+        assert_eq!(key_manager.key_index(), u64::MAX);
+        assert_eq!(
+            key_manager.increment_key_index(1),
+            Err(KeyManagerError::KeyIndexOverflow)
+        );
+        assert_eq!(key_manager.key_index(), u64::MAX);
+        assert_eq!(key_manager.increment_key_index(0), Ok(u64::MAX));
+
+        key_manager.update_key_index(1);
+        assert_eq!(
+            key_manager.increment_key_index(u64::MAX),
+            Err(KeyManagerError::KeyIndexOverflow)
+        );
+        assert_eq!(key_manager.key_index(), 1);
+    }
+}
@@ -20,47 +20,293 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::{mem::size_of, time::Duration};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use blake2::Blake2b;
+use chacha20::{
+    cipher::{KeyIvInit, StreamCipher},
+    ChaCha20,
+    Key,
+    Nonce,
+};
+use digest::consts::{U32, U64};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
 use tari_utilities::SafePassword;
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::{
+    error::KeyManagerError,
+    mac_domain_hasher,
+    LABEL_ARGON_ENCODING,
+    LABEL_CHACHA20_ENCODING,
+    LABEL_MAC_GENERATION,
+};
 
-use crate::error::KeyManagerError;
+// The current recommended cipher seed version
+const CIPHER_SEED_VERSION: u8 = 2u8;
+/// The genesis date for seed birthdays, 2022-01-01 00:00:00 UTC
+pub const BIRTHDAY_GENESIS_FROM_UNIX_EPOCH: u64 = 1640995200;
+/// The passphrase used when none is provided
+pub const DEFAULT_CIPHER_SEED_PASSPHRASE: &str = "TARI_CIPHER_SEED";
+const ARGON2_SALT_BYTES: usize = 16;
+/// The number of bytes used to encode the seed birthday
+pub const CIPHER_SEED_BIRTHDAY_BYTES: usize = 2;
+/// The number of bytes of seed entropy
+pub const CIPHER_SEED_ENTROPY_BYTES: usize = 16;
+/// The number of bytes of the main salt
+pub const CIPHER_SEED_MAIN_SALT_BYTES: usize = 5;
+const CIPHER_SEED_ENCRYPTION_KEY_BYTES: usize = 32;
+const CIPHER_SEED_MAC_KEY_BYTES: usize = 32;
+const CIPHER_SEED_CHECKSUM_BYTES: usize = 4;
+/// The number of bytes of the MAC
+pub const CIPHER_SEED_MAC_BYTES: usize = 5;
+/// The length of an enciphered seed: version, birthday, entropy, MAC, main salt and checksum
+pub const CIPHER_SEED_ENCIPHERED_BYTES: usize = 1 +
+    CIPHER_SEED_BIRTHDAY_BYTES +
+    CIPHER_SEED_ENTROPY_BYTES +
+    CIPHER_SEED_MAC_BYTES +
+    CIPHER_SEED_MAIN_SALT_BYTES +
+    CIPHER_SEED_CHECKSUM_BYTES;
 
-/// This is a non-implementation of a Cipher Seed.
+type CipherSeedEncryptionKey = Zeroizing<[u8; CIPHER_SEED_ENCRYPTION_KEY_BYTES]>;
+type CipherSeedMacKey = Zeroizing<[u8; CIPHER_SEED_MAC_KEY_BYTES]>;
 
+/// A `CipherSeed` is a versioned, encrypted wallet seed with a birthday, following the approach of
+/// [aezeed](https://github.com/lightningnetwork/lnd/tree/master/aezeed).
+///
+/// The enciphered form is assembled as follows:
+/// - the version byte
+/// - the birthday (days since `BIRTHDAY_GENESIS_FROM_UNIX_EPOCH`), entropy and MAC, encrypted with ChaCha20
+/// - the main salt
+/// - a CRC32 checksum over all of the above
+///
+/// The encryption and MAC keys are derived from the passphrase and main salt using Argon2. The ChaCha20 nonce and
+/// Argon2 salt are in turn derived from the main salt using domain separated hashing. The MAC is verified in
+/// constant time on decryption, so that an incorrect passphrase or tampered data is rejected.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub struct CipherSeed {
     version: u8,
     birthday: u16,
+    entropy: Box<[u8; CIPHER_SEED_ENTROPY_BYTES]>,
+    salt: [u8; CIPHER_SEED_MAIN_SALT_BYTES],
 }
 
 impl CipherSeed {
-    /// Generate a new seed
+    /// Generate a new seed with the current date as birthday
     pub fn new() -> Self {
-        unimplemented!("CipherSeed::new is not implemented")
+        const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+        let days = current_unix_time_secs().saturating_sub(BIRTHDAY_GENESIS_FROM_UNIX_EPOCH) / SECONDS_PER_DAY;
+        let birthday = u16::try_from(days).unwrap_or(0u16);
+        CipherSeed::new_with_birthday(birthday)
+    }
+
+    /// Generate a new seed with the provided birthday
+    pub fn new_with_birthday(birthday: u16) -> Self {
+        CipherSeed::new_with_rng(birthday, &mut OsRng)
+    }
+
+    /// Generate a new seed with the provided birthday, drawing the entropy and salt from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(birthday: u16, rng: &mut R) -> Self {
+        let mut entropy = Box::new([0u8; CIPHER_SEED_ENTROPY_BYTES]);
+        rng.fill_bytes(entropy.as_mut());
+        let mut salt = [0u8; CIPHER_SEED_MAIN_SALT_BYTES];
+        rng.fill_bytes(&mut salt);
+
+        Self {
+            version: CIPHER_SEED_VERSION,
+            birthday,
+            entropy,
+            salt,
+        }
     }
 
     /// Generate an encrypted seed from a passphrase
-    pub fn encipher(&self, _passphrase: Option<SafePassword>) -> Result<Vec<u8>, KeyManagerError> {
-        unimplemented!("CipherSeed::encipher is not implemented")
+    pub fn encipher(&self, passphrase: Option<SafePassword>) -> Result<Vec<u8>, KeyManagerError> {
+        // Derive encryption and MAC keys from the passphrase and main salt
+        let passphrase = passphrase.unwrap_or_else(default_passphrase);
+        let (encryption_key, mac_key) = Self::derive_keys(&passphrase, &self.salt)?;
+
+        // Generate the MAC
+        let mac = Self::generate_mac(
+            &self.birthday.to_le_bytes(),
+            self.entropy(),
+            self.version,
+            &self.salt,
+            mac_key.as_ref(),
+        )?;
+
+        // Assemble the secret data to be encrypted: birthday, entropy, MAC
+        let mut secret_data = Zeroizing::new(Vec::<u8>::with_capacity(
+            CIPHER_SEED_BIRTHDAY_BYTES + CIPHER_SEED_ENTROPY_BYTES + CIPHER_SEED_MAC_BYTES,
+        ));
+        secret_data.extend(&self.birthday.to_le_bytes());
+        secret_data.extend(self.entropy.iter());
+        secret_data.extend(&mac);
+
+        // Encrypt the secret data
+        Self::apply_stream_cipher(&mut secret_data, encryption_key.as_ref(), &self.salt);
+
+        // Assemble the final seed: version, secret data, main salt, checksum
+        let mut encrypted_seed = Vec::<u8>::with_capacity(CIPHER_SEED_ENCIPHERED_BYTES);
+        encrypted_seed.push(self.version);
+        encrypted_seed.extend(secret_data.iter());
+        encrypted_seed.extend(&self.salt);
+
+        let mut crc_hasher = crc32fast::Hasher::new();
+        crc_hasher.update(encrypted_seed.as_slice());
+        let checksum = crc_hasher.finalize().to_le_bytes();
+        encrypted_seed.extend(&checksum);
+
+        Ok(encrypted_seed)
     }
 
     /// Recover a seed from encrypted data and a passphrase
     pub fn from_enciphered_bytes(
-        _encrypted_seed: &[u8],
-        _passphrase: Option<SafePassword>,
+        encrypted_seed: &[u8],
+        passphrase: Option<SafePassword>,
     ) -> Result<Self, KeyManagerError> {
-        unimplemented!("CipherSeed::from_enciphered_bytes is not implemented")
+        // Check the length: version, birthday, entropy, MAC, salt, checksum
+        if encrypted_seed.len() != CIPHER_SEED_ENCIPHERED_BYTES {
+            return Err(KeyManagerError::InvalidData);
+        }
+
+        // We only support one version right now
+        let version = encrypted_seed[0];
+        if version != CIPHER_SEED_VERSION {
+            return Err(KeyManagerError::VersionMismatch);
+        }
+
+        // Verify the checksum first, to detect obvious errors
+        let (encrypted_seed, checksum) =
+            encrypted_seed.split_at(CIPHER_SEED_ENCIPHERED_BYTES - CIPHER_SEED_CHECKSUM_BYTES);
+        let mut crc_hasher = crc32fast::Hasher::new();
+        crc_hasher.update(encrypted_seed);
+        let expected_checksum = crc_hasher.finalize().to_le_bytes();
+        if checksum != expected_checksum {
+            return Err(KeyManagerError::CrcError);
+        }
+
+        // Derive encryption and MAC keys from the passphrase and main salt
+        let (encrypted_secret_data, salt) = encrypted_seed.split_at(encrypted_seed.len() - CIPHER_SEED_MAIN_SALT_BYTES);
+        let salt: [u8; CIPHER_SEED_MAIN_SALT_BYTES] = salt.try_into().map_err(|_| KeyManagerError::InvalidData)?;
+        let passphrase = passphrase.unwrap_or_else(default_passphrase);
+        let (encryption_key, mac_key) = Self::derive_keys(&passphrase, &salt)?;
+
+        // Decrypt the secret data: birthday, entropy, MAC
+        let mut secret_data = Zeroizing::new(encrypted_secret_data[1..].to_vec());
+        Self::apply_stream_cipher(&mut secret_data, encryption_key.as_ref(), &salt);
+
+        // Parse the secret data
+        let (birthday_bytes, remainder) = secret_data.split_at(CIPHER_SEED_BIRTHDAY_BYTES);
+        let (entropy, mac) = remainder.split_at(CIPHER_SEED_ENTROPY_BYTES);
+        let birthday_bytes: [u8; CIPHER_SEED_BIRTHDAY_BYTES] =
+            birthday_bytes.try_into().map_err(|_| KeyManagerError::InvalidData)?;
+        let birthday = u16::from_le_bytes(birthday_bytes);
+        let entropy: Box<[u8; CIPHER_SEED_ENTROPY_BYTES]> =
+            Box::new(entropy.try_into().map_err(|_| KeyManagerError::InvalidData)?);
+
+        // Generate the MAC and verify it in constant time to avoid leaking data
+        let expected_mac = Self::generate_mac(&birthday_bytes, entropy.as_ref(), version, &salt, mac_key.as_ref())?;
+        if mac.ct_eq(&expected_mac).unwrap_u8() == 0 {
+            return Err(KeyManagerError::DecryptionFailed);
+        }
+
+        Ok(Self {
+            version,
+            birthday,
+            entropy,
+            salt,
+        })
     }
 
     /// Get a reference to the seed entropy
     pub fn entropy(&self) -> &[u8] {
-        unimplemented!("CipherSeed::entropy is not implemented")
+        self.entropy.as_ref()
     }
 
     /// Get the seed birthday
     pub fn birthday(&self) -> u16 {
-        unimplemented!("CipherSeed::birthday is not implemented")
+        self.birthday
+    }
+
+    /// Encrypt or decrypt data using ChaCha20
+    fn apply_stream_cipher(data: &mut [u8], encryption_key: &[u8], salt: &[u8]) {
+        // The ChaCha20 nonce is derived from the main salt
+        let encryption_nonce = mac_domain_hasher::<Blake2b<U64>>(LABEL_CHACHA20_ENCODING)
+            .chain(salt)
+            .finalize();
+        let encryption_nonce = &encryption_nonce.as_ref()[..size_of::<Nonce>()];
+
+        let mut cipher = ChaCha20::new(Key::from_slice(encryption_key), Nonce::from_slice(encryption_nonce));
+        cipher.apply_keystream(data);
+    }
+
+    /// Generate a MAC over the seed data using the MAC key
+    fn generate_mac(
+        birthday: &[u8],
+        entropy: &[u8],
+        cipher_seed_version: u8,
+        salt: &[u8],
+        mac_key: &[u8],
+    ) -> Result<Vec<u8>, KeyManagerError> {
+        if birthday.len() != CIPHER_SEED_BIRTHDAY_BYTES ||
+            entropy.len() != CIPHER_SEED_ENTROPY_BYTES ||
+            salt.len() != CIPHER_SEED_MAIN_SALT_BYTES ||
+            mac_key.len() != CIPHER_SEED_MAC_KEY_BYTES
+        {
+            return Err(KeyManagerError::InvalidData);
+        }
+
+        Ok(mac_domain_hasher::<Blake2b<U32>>(LABEL_MAC_GENERATION)
+            .chain([cipher_seed_version])
+            .chain(birthday)
+            .chain(entropy)
+            .chain(salt)
+            .chain(mac_key)
+            .finalize()
+            .as_ref()[..CIPHER_SEED_MAC_BYTES]
+            .to_vec())
+    }
+
+    /// Derive the encryption and MAC keys from a passphrase and main salt using Argon2
+    fn derive_keys(
+        passphrase: &SafePassword,
+        salt: &[u8],
+    ) -> Result<(CipherSeedEncryptionKey, CipherSeedMacKey), KeyManagerError> {
+        // The Argon2 salt is derived from the main salt
+        let argon2_salt = mac_domain_hasher::<Blake2b<U32>>(LABEL_ARGON_ENCODING)
+            .chain(salt)
+            .finalize();
+        let argon2_salt = &argon2_salt.as_ref()[..ARGON2_SALT_BYTES];
+
+        // Run Argon2 with enough output to accommodate both keys, so we only run it once.
+        // We use the recommended OWASP parameters for this:
+        // https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#argon2id
+        let params = Params::new(
+            46 * 1024, // m-cost is in KiB, so 46 MiB
+            1,         // t-cost
+            1,         // p-cost
+            Some(CIPHER_SEED_ENCRYPTION_KEY_BYTES + CIPHER_SEED_MAC_KEY_BYTES),
+        )
+        .map_err(|_| KeyManagerError::CryptographicError("Problem generating Argon2 parameters".to_string()))?;
+
+        // Derive the main key from the passphrase in place
+        let mut main_key = Zeroizing::new([0u8; CIPHER_SEED_ENCRYPTION_KEY_BYTES + CIPHER_SEED_MAC_KEY_BYTES]);
+        let hasher = Argon2::new(Algorithm::Argon2d, Version::V0x13, params);
+        hasher
+            .hash_password_into(passphrase.reveal(), argon2_salt, main_key.as_mut())
+            .map_err(|_| KeyManagerError::CryptographicError("Problem generating Argon2 password hash".to_string()))?;
+
+        // Split off the keys
+        let mut encryption_key = Zeroizing::new([0u8; CIPHER_SEED_ENCRYPTION_KEY_BYTES]);
+        encryption_key.copy_from_slice(&main_key[..CIPHER_SEED_ENCRYPTION_KEY_BYTES]);
+        let mut mac_key = Zeroizing::new([0u8; CIPHER_SEED_MAC_KEY_BYTES]);
+        mac_key.copy_from_slice(&main_key[CIPHER_SEED_ENCRYPTION_KEY_BYTES..]);
+
+        Ok((encryption_key, mac_key))
     }
 }
 
@@ -69,3 +315,150 @@ impl Default for CipherSeed {
         Self::new()
     }
 }
+
+fn default_passphrase() -> SafePassword {
+    SafePassword::from(DEFAULT_CIPHER_SEED_PASSPHRASE)
+}
+
+// `SystemTime::now()` is not available in the browser, so we ask the JavaScript runtime for the time instead
+#[cfg(target_arch = "wasm32")]
+fn current_unix_time_secs() -> u64 {
+    Duration::from_secs_f64(js_sys::Date::now() / 1000.0).as_secs()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn current_unix_time_secs() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
+
+#[cfg(test)]
+mod test {
+    use tari_utilities::hex::{from_hex, Hex};
+
+    use super::*;
+
+    // A seed with a fixed birthday, entropy and salt, and its enciphered forms, computed independently of this crate
+    const KNOWN_PASSPHRASE: &str = "test passphrase";
+    const KNOWN_ENCIPHERED: &str = "02f6f6a330dc4e77208a69e382dc857c4c7f1f1569264defa5a5a5a5a5d27ef98b";
+    const KNOWN_ENCIPHERED_DEFAULT_PASSPHRASE: &str =
+        "02ed7bf7359f6603514b5806b4da79d1d1da9bfe9063f3d3a5a5a5a5a5d3d9fa04";
+    const MAC_OFFSET: usize = 1 + CIPHER_SEED_BIRTHDAY_BYTES + CIPHER_SEED_ENTROPY_BYTES;
+
+    fn known_seed() -> CipherSeed {
+        CipherSeed {
+            version: CIPHER_SEED_VERSION,
+            birthday: 1_000,
+            entropy: Box::new([0x5a; CIPHER_SEED_ENTROPY_BYTES]),
+            salt: [0xa5; CIPHER_SEED_MAIN_SALT_BYTES],
+        }
+    }
+
+    fn passphrase(passphrase: &str) -> Option<SafePassword> {
+        Some(SafePassword::from(passphrase))
+    }
+
+    // Replaces the checksum of an enciphered seed, so that tampering with the data is not caught by the checksum
+    fn with_checksum(mut enciphered: Vec<u8>) -> Vec<u8> {
+        let data_len = CIPHER_SEED_ENCIPHERED_BYTES - CIPHER_SEED_CHECKSUM_BYTES;
+        let mut crc_hasher = crc32fast::Hasher::new();
+        crc_hasher.update(&enciphered[..data_len]);
+        enciphered[data_len..].copy_from_slice(&crc_hasher.finalize().to_le_bytes());
+        enciphered
+    }
+
+    #[test]
+    fn known_answer_with_passphrase() {
+        let enciphered = known_seed().encipher(passphrase(KNOWN_PASSPHRASE)).unwrap();
+        assert_eq!(enciphered.len(), CIPHER_SEED_ENCIPHERED_BYTES);
+        assert_eq!(enciphered.to_hex(), KNOWN_ENCIPHERED);
+
+        let seed = CipherSeed::from_enciphered_bytes(&enciphered, passphrase(KNOWN_PASSPHRASE)).unwrap();
+        assert_eq!(seed, known_seed());
+        assert_eq!(seed.birthday(), 1_000);
+        assert_eq!(seed.entropy(), &[0x5a; CIPHER_SEED_ENTROPY_BYTES]);
+    }
+
+    #[test]
+    fn known_answer_with_default_passphrase() {
+        let enciphered = known_seed().encipher(None).unwrap();
+        assert_eq!(enciphered.to_hex(), KNOWN_ENCIPHERED_DEFAULT_PASSPHRASE);
+        let enciphered = known_seed()
+            .encipher(passphrase(DEFAULT_CIPHER_SEED_PASSPHRASE))
+            .unwrap();
+        assert_eq!(enciphered.to_hex(), KNOWN_ENCIPHERED_DEFAULT_PASSPHRASE);
+
+        let seed = CipherSeed::from_enciphered_bytes(&enciphered, None).unwrap();
+        assert_eq!(seed, known_seed());
+    }
+
+    #[test]
+    fn seeds_round_trip() {
+        let seed = CipherSeed::new_with_birthday(123);
+        let enciphered = seed.encipher(passphrase("another passphrase")).unwrap();
+        let deciphered = CipherSeed::from_enciphered_bytes(&enciphered, passphrase("another passphrase")).unwrap();
+        assert_eq!(deciphered, seed);
+        assert_eq!(deciphered.birthday(), 123);
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let enciphered = from_hex(KNOWN_ENCIPHERED).unwrap();
+        assert_eq!(
+            CipherSeed::from_enciphered_bytes(&enciphered, passphrase("wrong passphrase")),
+            Err(KeyManagerError::DecryptionFailed)
+        );
+        assert_eq!(
+            CipherSeed::from_enciphered_bytes(&enciphered, None),
+            Err(KeyManagerError::DecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn tampered_mac_is_rejected() {
+        for i in MAC_OFFSET..MAC_OFFSET + CIPHER_SEED_MAC_BYTES {
+            let mut enciphered = from_hex(KNOWN_ENCIPHERED).unwrap();
+            enciphered[i] ^= 0x01;
+            assert_eq!(
+                CipherSeed::from_enciphered_bytes(&enciphered, passphrase(KNOWN_PASSPHRASE)),
+                Err(KeyManagerError::CrcError)
+            );
+            assert_eq!(
+                CipherSeed::from_enciphered_bytes(&with_checksum(enciphered), passphrase(KNOWN_PASSPHRASE)),
+                Err(KeyManagerError::DecryptionFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn tampered_data_is_rejected() {
+        // The encrypted birthday and entropy, and the salt, are covered by the MAC as well
+        for i in [1, MAC_OFFSET - 1, MAC_OFFSET + CIPHER_SEED_MAC_BYTES] {
+            let mut enciphered = from_hex(KNOWN_ENCIPHERED).unwrap();
+            enciphered[i] ^= 0x01;
+            assert_eq!(
+                CipherSeed::from_enciphered_bytes(&with_checksum(enciphered), passphrase(KNOWN_PASSPHRASE)),
+                Err(KeyManagerError::DecryptionFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_seeds_are_rejected() {
+        let enciphered = from_hex(KNOWN_ENCIPHERED).unwrap();
+        assert_eq!(
+            CipherSeed::from_enciphered_bytes(&enciphered[1..], None),
+            Err(KeyManagerError::InvalidData)
+        );
+        let mut other_version = enciphered;
+        other_version[0] = CIPHER_SEED_VERSION + 1;
+        assert_eq!(
+            CipherSeed::from_enciphered_bytes(&with_checksum(other_version), None),
+            Err(KeyManagerError::VersionMismatch)
+        );
+    }
+}
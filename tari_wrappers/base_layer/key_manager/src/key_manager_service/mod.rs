@@ -4,7 +4,7 @@
 pub mod cipher_seed;
 
 mod error;
-pub use error::KeyManagerServiceError;
+pub use error::{KeyManagerServiceError, KeyManagerStorageError};
 
 pub mod interface;
 pub mod storage;
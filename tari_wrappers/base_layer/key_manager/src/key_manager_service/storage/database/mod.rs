@@ -20,6 +20,12 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::{marker::PhantomData, sync::Arc};
+
+use tari_crypto::keys::PublicKey;
+
+use crate::key_manager_service::error::KeyManagerStorageError;
+
 mod backend;
 
 pub use backend::KeyManagerBackend;
@@ -30,3 +36,58 @@ pub struct KeyManagerState {
     pub branch_seed: String,
     pub primary_key_index: u64,
 }
+
+/// This structure holds an inner type that implements the `KeyManagerBackend` trait and contains the more complex
+/// data access logic required by the module built onto the functionality defined by the trait
+#[derive(Clone)]
+pub struct KeyManagerDatabase<T, PK> {
+    db: Arc<T>,
+    public_key_type: PhantomData<PK>,
+}
+
+impl<T, PK> KeyManagerDatabase<T, PK>
+where
+    T: KeyManagerBackend<PK> + 'static,
+    PK: PublicKey,
+{
+    /// Creates a new [KeyManagerDatabase] linked to the provided KeyManagerBackend
+    pub fn new(db: T) -> Self {
+        Self {
+            db: Arc::new(db),
+            public_key_type: PhantomData,
+        }
+    }
+
+    /// Retrieves the key manager state of the provided branch
+    /// Returns None if the request branch does not exist.
+    pub fn get_key_manager_state(&self, branch: &str) -> Result<Option<KeyManagerState>, KeyManagerStorageError> {
+        self.db.get_key_manager(branch)
+    }
+
+    /// Saves the specified key manager state to the backend database.
+    pub fn set_key_manager_state(&self, state: KeyManagerState) -> Result<(), KeyManagerStorageError> {
+        self.db.add_key_manager(state)
+    }
+
+    /// Increment the key index of the provided branch of the key manager.
+    /// Will error if the branch does not exist.
+    pub fn increment_key_index(&self, branch: &str) -> Result<(), KeyManagerStorageError> {
+        self.db.increment_key_index(branch)
+    }
+
+    /// Sets the key index of the provided branch of the key manager.
+    /// Will error if the branch does not exist.
+    pub fn set_key_index(&self, branch: &str, index: u64) -> Result<(), KeyManagerStorageError> {
+        self.db.set_key_index(branch, index)
+    }
+
+    /// This will insert an imported key pair into the database
+    pub fn insert_imported_key(&self, public_key: PK, private_key: PK::K) -> Result<(), KeyManagerStorageError> {
+        self.db.insert_imported_key(public_key, private_key)
+    }
+
+    /// This will retrieve the private key of an imported key pair from the database
+    pub fn get_imported_key(&self, public_key: &PK) -> Result<PK::K, KeyManagerStorageError> {
+        self.db.get_imported_key(public_key)
+    }
+}
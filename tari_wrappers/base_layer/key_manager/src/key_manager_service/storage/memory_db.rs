@@ -0,0 +1,108 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+};
+
+use tari_crypto::keys::PublicKey;
+
+use crate::key_manager_service::{
+    error::KeyManagerStorageError,
+    storage::database::{KeyManagerBackend, KeyManagerState},
+};
+
+struct MemoryDatabaseState<PK: PublicKey> {
+    key_managers: HashMap<String, KeyManagerState>,
+    imported_keys: BTreeMap<PK, PK::K>,
+}
+
+/// An in-memory implementation of `KeyManagerBackend`, for use where no persistent storage is available (e.g. in the
+/// browser) or required. All branches, key indices and imported keys are lost when the database is dropped.
+///
+/// This handle can be cloned cheaply, with all clones sharing the same underlying state.
+pub struct KeyManagerMemoryDatabase<PK: PublicKey> {
+    state: Arc<RwLock<MemoryDatabaseState<PK>>>,
+}
+
+impl<PK: PublicKey> KeyManagerMemoryDatabase<PK> {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(RwLock::new(MemoryDatabaseState {
+                key_managers: HashMap::new(),
+                imported_keys: BTreeMap::new(),
+            })),
+        }
+    }
+}
+
+impl<PK: PublicKey> Default for KeyManagerMemoryDatabase<PK> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<PK: PublicKey> Clone for KeyManagerMemoryDatabase<PK> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+fn lock_error<E: ToString>(e: E) -> KeyManagerStorageError {
+    KeyManagerStorageError::UnexpectedResult(format!("Memory database lock poisoned: {}", e.to_string()))
+}
+
+impl<PK> KeyManagerBackend<PK> for KeyManagerMemoryDatabase<PK>
+where
+    PK: PublicKey + Send + Sync,
+    PK::K: Send + Sync,
+{
+    fn get_key_manager(&self, branch: &str) -> Result<Option<KeyManagerState>, KeyManagerStorageError> {
+        let state = self.state.read().map_err(lock_error)?;
+        Ok(state.key_managers.get(branch).cloned())
+    }
+
+    fn add_key_manager(&self, key_manager: KeyManagerState) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        state.key_managers.insert(key_manager.branch_seed.clone(), key_manager);
+        Ok(())
+    }
+
+    fn increment_key_index(&self, branch: &str) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        let key_manager = state
+            .key_managers
+            .get_mut(branch)
+            .ok_or(KeyManagerStorageError::KeyManagerNotInitialized)?;
+        key_manager.primary_key_index += 1;
+        Ok(())
+    }
+
+    fn set_key_index(&self, branch: &str, index: u64) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        let key_manager = state
+            .key_managers
+            .get_mut(branch)
+            .ok_or(KeyManagerStorageError::KeyManagerNotInitialized)?;
+        key_manager.primary_key_index = index;
+        Ok(())
+    }
+
+    fn insert_imported_key(&self, public_key: PK, private_key: PK::K) -> Result<(), KeyManagerStorageError> {
+        let mut state = self.state.write().map_err(lock_error)?;
+        state.imported_keys.insert(public_key, private_key);
+        Ok(())
+    }
+
+    fn get_imported_key(&self, public_key: &PK) -> Result<PK::K, KeyManagerStorageError> {
+        let state = self.state.read().map_err(lock_error)?;
+        state
+            .imported_keys
+            .get(public_key)
+            .cloned()
+            .ok_or(KeyManagerStorageError::ValueNotFound)
+    }
+}
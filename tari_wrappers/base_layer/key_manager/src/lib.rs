@@ -6,3 +6,23 @@ pub use key_manager_service::{cipher_seed, interface, KeyId, KeyManagerInterface
 
 pub mod error;
 pub mod key_manager;
+pub mod mnemonic;
+
+use digest::Digest;
+use tari_crypto::{
+    hash_domain,
+    hashing::{DomainSeparatedHasher, LengthExtensionAttackResistant},
+};
+
+hash_domain!(KeyManagerDomain, "com.tari.base_layer.key_manager", 1);
+
+const LABEL_ARGON_ENCODING: &str = "argon2_encoding";
+const LABEL_CHACHA20_ENCODING: &str = "chacha20_encoding";
+const LABEL_DERIVE_KEY: &str = "derive_key";
+const LABEL_MAC_GENERATION: &str = "mac_generation";
+
+pub(crate) fn mac_domain_hasher<D: Digest + LengthExtensionAttackResistant>(
+    label: &'static str,
+) -> DomainSeparatedHasher<D, KeyManagerDomain> {
+    DomainSeparatedHasher::<D, KeyManagerDomain>::new_with_label(label)
+}
@@ -0,0 +1,320 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{fmt, str::FromStr};
+
+use bip39::Language;
+use tari_utilities::SafePassword;
+
+use crate::{
+    cipher_seed::CipherSeed,
+    error::{KeyManagerError, MnemonicError},
+};
+
+/// The number of bits encoded by a single mnemonic word
+const BITS_PER_WORD: usize = 11;
+
+/// The natural languages for which mnemonic word lists are defined. These are the standard BIP39 word lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MnemonicLanguage {
+    ChineseSimplified,
+    ChineseTraditional,
+    English,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Spanish,
+}
+
+impl MnemonicLanguage {
+    /// Returns all the supported languages
+    pub fn all() -> [MnemonicLanguage; 8] {
+        [
+            MnemonicLanguage::ChineseSimplified,
+            MnemonicLanguage::ChineseTraditional,
+            MnemonicLanguage::English,
+            MnemonicLanguage::French,
+            MnemonicLanguage::Italian,
+            MnemonicLanguage::Japanese,
+            MnemonicLanguage::Korean,
+            MnemonicLanguage::Spanish,
+        ]
+    }
+
+    /// Detects the language of a mnemonic sequence, i.e. the first language whose word list contains every word
+    pub fn detect_language(mnemonic_seq: &[String]) -> Result<MnemonicLanguage, MnemonicError> {
+        MnemonicLanguage::all()
+            .into_iter()
+            .find(|language| {
+                mnemonic_seq
+                    .iter()
+                    .all(|word| language.word_list_language().find_word(word).is_some())
+            })
+            .ok_or(MnemonicError::UnknownLanguage)
+    }
+
+    /// Returns the mnemonic word list for this language
+    pub fn word_list(self) -> &'static [&'static str; 2048] {
+        self.word_list_language().word_list()
+    }
+
+    fn word_list_language(self) -> Language {
+        match self {
+            MnemonicLanguage::ChineseSimplified => Language::SimplifiedChinese,
+            MnemonicLanguage::ChineseTraditional => Language::TraditionalChinese,
+            MnemonicLanguage::English => Language::English,
+            MnemonicLanguage::French => Language::French,
+            MnemonicLanguage::Italian => Language::Italian,
+            MnemonicLanguage::Japanese => Language::Japanese,
+            MnemonicLanguage::Korean => Language::Korean,
+            MnemonicLanguage::Spanish => Language::Spanish,
+        }
+    }
+}
+
+impl FromStr for MnemonicLanguage {
+    type Err = MnemonicError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace([' ', '_', '-'], "").as_str() {
+            "chinesesimplified" => Ok(MnemonicLanguage::ChineseSimplified),
+            "chinesetraditional" => Ok(MnemonicLanguage::ChineseTraditional),
+            "english" => Ok(MnemonicLanguage::English),
+            "french" => Ok(MnemonicLanguage::French),
+            "italian" => Ok(MnemonicLanguage::Italian),
+            "japanese" => Ok(MnemonicLanguage::Japanese),
+            "korean" => Ok(MnemonicLanguage::Korean),
+            "spanish" => Ok(MnemonicLanguage::Spanish),
+            _ => Err(MnemonicError::UnknownLanguage),
+        }
+    }
+}
+
+impl fmt::Display for MnemonicLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Finds and returns the mnemonic word for the provided index in the word list of the specified language
+pub fn find_mnemonic_word_from_index(index: usize, language: MnemonicLanguage) -> Result<String, MnemonicError> {
+    language
+        .word_list()
+        .get(index)
+        .map(|word| (*word).to_string())
+        .ok_or(MnemonicError::IndexOutOfBounds)
+}
+
+/// Finds and returns the index of the provided mnemonic word in the word list of the specified language
+pub fn find_mnemonic_index_from_word(word: &str, language: MnemonicLanguage) -> Result<usize, MnemonicError> {
+    language
+        .word_list_language()
+        .find_word(word)
+        .map(usize::from)
+        .ok_or_else(|| MnemonicError::WordNotFound(word.to_string()))
+}
+
+/// Converts a byte vector into a mnemonic word sequence. Each word encodes 11 bits, with the bits of every byte taken
+/// least significant first, and the final word is padded with zero bits.
+pub fn from_bytes(bytes: &[u8], language: MnemonicLanguage) -> Result<Vec<String>, MnemonicError> {
+    let mut bits = bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+        .collect::<Vec<bool>>();
+    let padded_size = bits.len().div_ceil(BITS_PER_WORD) * BITS_PER_WORD;
+    bits.resize(padded_size, false);
+
+    bits.chunks(BITS_PER_WORD)
+        .map(|chunk| {
+            let index = chunk
+                .iter()
+                .enumerate()
+                .fold(0usize, |acc, (i, bit)| acc | (usize::from(*bit) << i));
+            find_mnemonic_word_from_index(index, language)
+        })
+        .collect()
+}
+
+/// Converts a mnemonic word sequence into a byte vector, detecting the language of the words. Any trailing padding
+/// bits that do not make up a full byte are discarded.
+pub fn to_bytes(mnemonic_seq: &[String]) -> Result<Vec<u8>, MnemonicError> {
+    let language = MnemonicLanguage::detect_language(mnemonic_seq)?;
+    to_bytes_with_language(mnemonic_seq, language)
+}
+
+/// Converts a mnemonic word sequence in the specified language into a byte vector. Any trailing padding bits that do
+/// not make up a full byte are discarded.
+pub fn to_bytes_with_language(mnemonic_seq: &[String], language: MnemonicLanguage) -> Result<Vec<u8>, MnemonicError> {
+    let mut bits = Vec::with_capacity(mnemonic_seq.len() * BITS_PER_WORD);
+    for word in mnemonic_seq {
+        let index = find_mnemonic_index_from_word(word, language)?;
+        bits.extend((0..BITS_PER_WORD).map(|i| (index >> i) & 1 == 1));
+    }
+
+    Ok(bits
+        .chunks_exact(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |acc, (i, bit)| acc | (u8::from(*bit) << i))
+        })
+        .collect())
+}
+
+/// Encoding and decoding of a type to and from a mnemonic word sequence
+pub trait Mnemonic<T> {
+    /// Recovers the type from a mnemonic word sequence, detecting the language of the words
+    fn from_mnemonic(mnemonic_seq: &[String], passphrase: Option<SafePassword>) -> Result<T, KeyManagerError>;
+
+    /// Recovers the type from a mnemonic word sequence in the specified language
+    fn from_mnemonic_with_language(
+        mnemonic_seq: &[String],
+        language: MnemonicLanguage,
+        passphrase: Option<SafePassword>,
+    ) -> Result<T, KeyManagerError>;
+
+    /// Encodes the type as a mnemonic word sequence in the specified language
+    fn to_mnemonic(
+        &self,
+        language: MnemonicLanguage,
+        passphrase: Option<SafePassword>,
+    ) -> Result<Vec<String>, KeyManagerError>;
+}
+
+impl Mnemonic<CipherSeed> for CipherSeed {
+    fn from_mnemonic(mnemonic_seq: &[String], passphrase: Option<SafePassword>) -> Result<CipherSeed, KeyManagerError> {
+        let bytes = to_bytes(mnemonic_seq)?;
+        CipherSeed::from_enciphered_bytes(&bytes, passphrase)
+    }
+
+    fn from_mnemonic_with_language(
+        mnemonic_seq: &[String],
+        language: MnemonicLanguage,
+        passphrase: Option<SafePassword>,
+    ) -> Result<CipherSeed, KeyManagerError> {
+        let bytes = to_bytes_with_language(mnemonic_seq, language)?;
+        CipherSeed::from_enciphered_bytes(&bytes, passphrase)
+    }
+
+    fn to_mnemonic(
+        &self,
+        language: MnemonicLanguage,
+        passphrase: Option<SafePassword>,
+    ) -> Result<Vec<String>, KeyManagerError> {
+        Ok(from_bytes(&self.encipher(passphrase)?, language)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tari_utilities::hex::from_hex;
+
+    use super::*;
+
+    // The seed of the cipher seed known-answer tests, enciphered with the passphrase, and its English mnemonic as word
+    // list indices, computed independently of this crate
+    const KNOWN_PASSPHRASE: &str = "test passphrase";
+    const KNOWN_ENCIPHERED: &str = "02f6f6a330dc4e77208a69e382dc857c4c7f1f1569264defa5a5a5a5a5d27ef98b";
+    const KNOWN_MNEMONIC_INDICES: [usize; 24] = [
+        1538, 1758, 655, 1560, 1261, 238, 648, 844, 739, 912, 535, 1598, 2036, 574, 581, 307, 1869, 1213, 1686, 722,
+        602, 1445, 1631, 1119,
+    ];
+
+    fn passphrase() -> Option<SafePassword> {
+        Some(SafePassword::from(KNOWN_PASSPHRASE))
+    }
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| (*word).to_string()).collect()
+    }
+
+    #[test]
+    fn bytes_encode_to_known_words() {
+        let zeros = from_bytes(&[0u8; 33], MnemonicLanguage::English).unwrap();
+        assert_eq!(zeros, words(&["abandon"; 24]));
+        let ones = from_bytes(&[0xffu8; 33], MnemonicLanguage::English).unwrap();
+        assert_eq!(ones, words(&["zoo"; 24]));
+
+        // The bits of every byte are taken least significant first
+        let mut bytes = [0u8; 33];
+        bytes[0] = 0x03;
+        let mnemonic = from_bytes(&bytes, MnemonicLanguage::English).unwrap();
+        assert_eq!(mnemonic[0], "about");
+        assert_eq!(to_bytes(&mnemonic).unwrap(), bytes.to_vec());
+    }
+
+    #[test]
+    fn cipher_seed_encodes_to_known_mnemonic() {
+        let enciphered = from_hex(KNOWN_ENCIPHERED).unwrap();
+        let seed = CipherSeed::from_enciphered_bytes(&enciphered, passphrase()).unwrap();
+        let mnemonic = seed.to_mnemonic(MnemonicLanguage::English, passphrase()).unwrap();
+        let indices = mnemonic
+            .iter()
+            .map(|word| find_mnemonic_index_from_word(word, MnemonicLanguage::English).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(indices, KNOWN_MNEMONIC_INDICES);
+        assert_eq!(to_bytes(&mnemonic).unwrap(), enciphered);
+
+        let restored = CipherSeed::from_mnemonic(&mnemonic, passphrase()).unwrap();
+        assert_eq!(restored, seed);
+        let restored =
+            CipherSeed::from_mnemonic_with_language(&mnemonic, MnemonicLanguage::English, passphrase()).unwrap();
+        assert_eq!(restored, seed);
+    }
+
+    #[test]
+    fn mnemonics_round_trip_in_every_language() {
+        let seed = CipherSeed::new_with_birthday(42);
+        for language in MnemonicLanguage::all() {
+            let mnemonic = seed.to_mnemonic(language, None).unwrap();
+            assert_eq!(mnemonic.len(), 24);
+            // The Chinese word lists share words, so the language is not detected here
+            let restored = CipherSeed::from_mnemonic_with_language(&mnemonic, language, None).unwrap();
+            assert_eq!(restored, seed, "{language}");
+        }
+    }
+
+    #[test]
+    fn mnemonic_with_wrong_passphrase_is_rejected() {
+        let seed = CipherSeed::from_enciphered_bytes(&from_hex(KNOWN_ENCIPHERED).unwrap(), passphrase()).unwrap();
+        let mnemonic = seed.to_mnemonic(MnemonicLanguage::English, passphrase()).unwrap();
+        assert_eq!(
+            CipherSeed::from_mnemonic(&mnemonic, Some(SafePassword::from("wrong passphrase"))),
+            Err(KeyManagerError::DecryptionFailed)
+        );
+        assert_eq!(
+            CipherSeed::from_mnemonic(&mnemonic, None),
+            Err(KeyManagerError::DecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn tampered_mnemonic_is_rejected() {
+        let seed = CipherSeed::from_enciphered_bytes(&from_hex(KNOWN_ENCIPHERED).unwrap(), passphrase()).unwrap();
+        let mut mnemonic = seed.to_mnemonic(MnemonicLanguage::English, passphrase()).unwrap();
+        let index = find_mnemonic_index_from_word(&mnemonic[5], MnemonicLanguage::English).unwrap();
+        mnemonic[5] = find_mnemonic_word_from_index(index ^ 1, MnemonicLanguage::English).unwrap();
+        assert!(CipherSeed::from_mnemonic(&mnemonic, passphrase()).is_err());
+    }
+
+    #[test]
+    fn unknown_words_are_rejected() {
+        let mut mnemonic = words(&["abandon"; 24]);
+        mnemonic[3] = "notaword".to_string();
+        assert_eq!(to_bytes(&mnemonic), Err(MnemonicError::UnknownLanguage));
+        assert_eq!(
+            to_bytes_with_language(&mnemonic, MnemonicLanguage::English),
+            Err(MnemonicError::WordNotFound("notaword".to_string()))
+        );
+        assert_eq!(
+            find_mnemonic_word_from_index(2048, MnemonicLanguage::English),
+            Err(MnemonicError::IndexOutOfBounds)
+        );
+        assert_eq!(
+            "klingon".parse::<MnemonicLanguage>(),
+            Err(MnemonicError::UnknownLanguage)
+        );
+    }
+}